@@ -29,3 +29,64 @@ export interface AnyNode {
   // biome-ignore lint/suspicious/noExplicitAny: for extensibility
   [key: string]: any;
 }
+
+export interface LayoutRect {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+export interface LayoutGlyph {
+  id: number;
+  x: number;
+  y: number;
+  advance: number;
+}
+
+export interface LayoutGlyphRun {
+  text: string;
+  fontSize: number;
+  rect: LayoutRect;
+  baseline: number;
+  glyphs: LayoutGlyph[];
+}
+
+export interface LayoutLine {
+  text: string;
+  rect: LayoutRect;
+  baseline: number;
+  runs: LayoutGlyphRun[];
+  inlineBoxes: LayoutRect[];
+}
+
+export interface LayoutNode {
+  /**
+   * The boxes are in the local coordinate space of the node, where the border box starts at the origin.
+   */
+  borderBox: LayoutRect;
+  paddingBox: LayoutRect;
+  contentBox: LayoutRect;
+  /**
+   * Maps the local coordinate space of the node to the output.
+   */
+  transform: {
+    a: number;
+    b: number;
+    c: number;
+    d: number;
+    x: number;
+    y: number;
+  };
+  /**
+   * The lines of text laid out in the content box, if the node contains text.
+   */
+  lines?: LayoutLine[];
+  children: LayoutNode[];
+}
+
+export interface LayoutTree {
+  width: number;
+  height: number;
+  root: LayoutNode;
+}
@@ -1,10 +1,13 @@
+use std::io::Cursor;
+
 use napi::bindgen_prelude::*;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use takumi::{
   GlobalContext,
   layout::{Viewport, node::NodeKind},
   rendering::{
-    AnimationFrame, RenderOptionsBuilder, encode_animated_png, encode_animated_webp, render,
+    AnimationFrame, GifEncodeOptions, ImageMetadata, PngAnimationEncoder, RenderOptionsBuilder,
+    WebPAnimationEncoder, WebPAnimationOptions, encode_animated_gif, render,
   },
 };
 
@@ -15,9 +18,57 @@ pub struct RenderAnimationTask<'g> {
   pub context: &'g GlobalContext,
   pub viewport: Viewport,
   pub format: AnimationOutputFormat,
+  /// The quality of lossy WebP frames, lossless when `None`.
+  pub quality: Option<u8>,
+  pub metadata: Option<ImageMetadata>,
   pub draw_debug_border: bool,
 }
 
+impl RenderAnimationTask<'_> {
+  fn render_frame(&self, node: NodeKind, duration_ms: u32) -> AnimationFrame {
+    AnimationFrame::new(
+      render(
+        RenderOptionsBuilder::default()
+          .viewport(self.viewport)
+          .node(node)
+          .global(self.context)
+          .draw_debug_border(self.draw_debug_border)
+          .build()
+          .unwrap(),
+      )
+      .unwrap(),
+      duration_ms,
+    )
+  }
+
+  /// Renders a batch of frames at a time in parallel and pushes them in order,
+  /// so only one batch of frames is kept in memory.
+  fn render_in_batches(
+    &self,
+    nodes: Vec<(NodeKind, u32)>,
+    mut push_frame: impl FnMut(AnimationFrame) -> takumi::Result<()>,
+  ) -> Result<()> {
+    let mut nodes = nodes.into_iter();
+
+    loop {
+      let batch: Vec<_> = nodes.by_ref().take(rayon::current_num_threads()).collect();
+
+      if batch.is_empty() {
+        return Ok(());
+      }
+
+      let frames: Vec<_> = batch
+        .into_par_iter()
+        .map(|(node, duration_ms)| self.render_frame(node, duration_ms))
+        .collect();
+
+      for frame in frames {
+        push_frame(frame).map_err(|e| napi::Error::from_reason(e.to_string()))?;
+      }
+    }
+  }
+}
+
 impl Task for RenderAnimationTask<'_> {
   type Output = Vec<u8>;
   type JsValue = Buffer;
@@ -25,39 +76,70 @@ impl Task for RenderAnimationTask<'_> {
   fn compute(&mut self) -> Result<Self::Output> {
     let nodes = self.nodes.take().unwrap();
 
-    let frames: Vec<_> = nodes
-      .into_par_iter()
-      .map(|(node, duration_ms)| {
-        AnimationFrame::new(
-          render(
-            RenderOptionsBuilder::default()
-              .viewport(self.viewport)
-              .node(node)
-              .global(self.context)
-              .draw_debug_border(self.draw_debug_border)
-              .build()
-              .unwrap(),
-          )
-          .unwrap(),
-          duration_ms,
-        )
-      })
-      .collect();
+    let (width, height) = (
+      self.viewport.width.unwrap_or_default(),
+      self.viewport.height.unwrap_or_default(),
+    );
 
-    let mut buffer = Vec::new();
+    let mut buffer = Cursor::new(Vec::new());
 
     match self.format {
       AnimationOutputFormat::webp => {
-        encode_animated_webp(&frames, &mut buffer, true, false, None)
+        let mut encoder = WebPAnimationEncoder::new(
+          &mut buffer,
+          width,
+          height,
+          WebPAnimationOptions {
+            blend: true,
+            quality: self.quality,
+            ..Default::default()
+          },
+          self.metadata.as_ref(),
+        )
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+
+        self.render_in_batches(nodes, |frame| encoder.push_frame(frame))?;
+
+        encoder
+          .finish()
           .map_err(|e| napi::Error::from_reason(e.to_string()))?;
       }
       AnimationOutputFormat::apng => {
-        encode_animated_png(&frames, &mut buffer, None)
+        let mut encoder = PngAnimationEncoder::new(
+          &mut buffer,
+          width,
+          height,
+          nodes.len() as u32,
+          None,
+          self.metadata.as_ref(),
+        )
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+
+        self.render_in_batches(nodes, |frame| encoder.push_frame(frame))?;
+
+        encoder
+          .finish()
           .map_err(|e| napi::Error::from_reason(e.to_string()))?;
       }
+      AnimationOutputFormat::gif => {
+        // The palette is built from all the frames, so they have to be rendered first.
+        let frames: Vec<_> = nodes
+          .into_par_iter()
+          .map(|(node, duration_ms)| self.render_frame(node, duration_ms))
+          .collect();
+
+        encode_animated_gif(
+          &frames,
+          &mut buffer,
+          None,
+          GifEncodeOptions::default(),
+          self.metadata.as_ref(),
+        )
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+      }
     }
 
-    Ok(buffer)
+    Ok(buffer.into_inner())
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
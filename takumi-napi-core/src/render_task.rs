@@ -10,7 +10,11 @@ use takumi::{
     DEFAULT_DEVICE_PIXEL_RATIO, DEFAULT_FONT_SIZE, Viewport,
     node::{Node, NodeKind},
   },
-  rendering::{RenderOptionsBuilder, render, write_image},
+  rendering::{
+    ImageEncodeOptions, ImageMetadata, PngBitDepth, PngEncodeOptions, RenderOptionsBuilder,
+    WebPCompression, render, render_rgba16, render_svg, write_image_with_options, write_png,
+    write_png_rgba16,
+  },
   resources::{
     image::{ImageSource, load_image_source_from_bytes},
     task::{FetchTask, FetchTaskCollection},
@@ -29,6 +33,9 @@ pub struct RenderTask<'g> {
   pub viewport: Viewport,
   pub format: OutputFormat,
   pub quality: Option<u8>,
+  pub webp_compression: WebPCompression,
+  pub metadata: Option<ImageMetadata>,
+  pub png: PngEncodeOptions,
   pub(crate) resource_cache: ResourceCache,
   pub(crate) tasks_rx: Receiver<(FetchTask, MaybeInitialized<Buffer, Arc<ImageSource>>)>,
 }
@@ -97,6 +104,8 @@ impl<'g> RenderTask<'g> {
       })?;
     }
 
+    let format = options.format.unwrap_or(OutputFormat::png);
+
     Ok(RenderTask {
       node: Some(node),
       global,
@@ -109,8 +118,19 @@ impl<'g> RenderTask<'g> {
           .map(|ratio| ratio as f32)
           .unwrap_or(DEFAULT_DEVICE_PIXEL_RATIO),
       },
-      format: options.format.unwrap_or(OutputFormat::png),
+      format,
       quality: options.quality,
+      webp_compression: if options.lossless.unwrap_or(true) {
+        WebPCompression::Lossless
+      } else {
+        WebPCompression::Lossy
+      },
+      metadata: options.metadata.map(TryInto::try_into).transpose()?,
+      png: options
+        .png
+        .map(TryInto::try_into)
+        .transpose()?
+        .unwrap_or_default(),
       draw_debug_border: options.draw_debug_border.unwrap_or_default(),
       tasks_rx: rx,
       resource_cache: resources_cache.clone(),
@@ -150,17 +170,35 @@ impl Task for RenderTask<'_> {
       })
       .collect();
 
-    let image = render(
-      RenderOptionsBuilder::default()
-        .viewport(self.viewport)
-        .fetched_resources(fetched_resources)
-        .node(node)
-        .global(self.global)
-        .draw_debug_border(self.draw_debug_border)
-        .build()
-        .unwrap(),
-    )
-    .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+    let options = RenderOptionsBuilder::default()
+      .viewport(self.viewport)
+      .fetched_resources(fetched_resources)
+      .node(node)
+      .global(self.global)
+      .draw_debug_border(self.draw_debug_border)
+      .build()
+      .unwrap();
+
+    if self.format == OutputFormat::svg {
+      return render_svg(options)
+        .map(String::into_bytes)
+        .map_err(|e| napi::Error::from_reason(e.to_string()));
+    }
+
+    let is_png = matches!(self.format, OutputFormat::png | OutputFormat::Png);
+
+    // 16-bit output is rendered with 16 bits per channel instead of scaling 8-bit samples up.
+    if is_png && self.png.bit_depth == PngBitDepth::Sixteen {
+      let image = render_rgba16(options).map_err(|e| napi::Error::from_reason(e.to_string()))?;
+      let mut buffer = Vec::new();
+
+      write_png_rgba16(&image, &mut buffer, self.png, self.metadata.as_ref())
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+
+      return Ok(buffer);
+    }
+
+    let image = render(options).map_err(|e| napi::Error::from_reason(e.to_string()))?;
 
     if self.format == OutputFormat::raw {
       return Ok(image.into_raw());
@@ -168,8 +206,24 @@ impl Task for RenderTask<'_> {
 
     let mut buffer = Vec::new();
 
-    write_image(&image, &mut buffer, self.format.into(), self.quality)
-      .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+    if is_png {
+      write_png(&image, &mut buffer, self.png, self.metadata.as_ref())
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+
+      return Ok(buffer);
+    }
+
+    write_image_with_options(
+      &image,
+      &mut buffer,
+      self.format.into(),
+      ImageEncodeOptions {
+        quality: self.quality,
+        webp_compression: self.webp_compression,
+        metadata: self.metadata.as_ref(),
+      },
+    )
+    .map_err(|e| napi::Error::from_reason(e.to_string()))?;
 
     Ok(buffer)
   }
@@ -4,9 +4,12 @@ use napi_derive::napi;
 use serde::de::DeserializeOwned;
 use takumi::{
   GlobalContext,
-  layout::node::NodeKind,
+  layout::{DEFAULT_DEVICE_PIXEL_RATIO, DEFAULT_FONT_SIZE, Viewport, node::NodeKind},
   parley::{FontWeight, GenericFamily, fontique::FontInfoOverride},
-  rendering::ImageOutputFormat,
+  rendering::{
+    IccProfile, ImageOutputFormat, PngBitDepth, PngCompression, PngFilter, RenderOptionsBuilder,
+    compute_layout,
+  },
   resources::{
     image::{ImageSource, load_image_source_from_bytes},
     task::FetchTask,
@@ -19,6 +22,7 @@ use crate::{
   render_task::RenderTask,
 };
 use std::{
+  collections::HashMap,
   num::NonZeroUsize,
   ops::Deref,
   sync::{Arc, Mutex},
@@ -41,8 +45,11 @@ pub struct RenderOptions<'env> {
   pub height: Option<u32>,
   /// The format of the image.
   pub format: Option<OutputFormat>,
-  /// The quality of JPEG format (0-100).
+  /// The quality of JPEG and lossy WebP format (0-100).
   pub quality: Option<u8>,
+  /// Whether WebP output is lossless, otherwise it is encoded with `quality`.
+  /// @default true
+  pub lossless: Option<bool>,
   /// Whether to draw debug borders.
   pub draw_debug_border: Option<bool>,
   /// The fetch function to use to fetch resources.
@@ -51,6 +58,141 @@ pub struct RenderOptions<'env> {
   /// The device pixel ratio.
   /// @default 1.0
   pub device_pixel_ratio: Option<f64>,
+  /// Metadata to embed in the image, ignored by `raw` and `svg` formats.
+  pub metadata: Option<ImageMetadata>,
+  /// Options of the PNG encoder, only used with `png` format.
+  pub png: Option<PngOptions>,
+}
+
+#[napi(object)]
+pub struct PngOptions {
+  /// How much effort is spent compressing.
+  /// @default "fast"
+  pub compression: Option<PngCompressionLevel>,
+  /// The row filter, picked from the color type and compression if not provided.
+  pub filter: Option<PngRowFilter>,
+  /// Reduces the image to at most this many colors (2-256), so it is written with a palette.
+  pub max_colors: Option<u16>,
+  /// Whether to apply dithering when `maxColors` reduces the colors.
+  /// @default false
+  pub dither: Option<bool>,
+  /// Bits per channel. With 16, the image is also rendered with 16 bits per channel.
+  /// @default 8
+  #[napi(ts_type = "8 | 16")]
+  pub bit_depth: Option<u8>,
+}
+
+#[napi(string_enum)]
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PngCompressionLevel {
+  fastest,
+  fast,
+  balanced,
+  high,
+}
+
+impl From<PngCompressionLevel> for PngCompression {
+  fn from(level: PngCompressionLevel) -> Self {
+    match level {
+      PngCompressionLevel::fastest => PngCompression::Fastest,
+      PngCompressionLevel::fast => PngCompression::Fast,
+      PngCompressionLevel::balanced => PngCompression::Balanced,
+      PngCompressionLevel::high => PngCompression::High,
+    }
+  }
+}
+
+#[napi(string_enum)]
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PngRowFilter {
+  none,
+  sub,
+  up,
+  average,
+  paeth,
+  adaptive,
+}
+
+impl From<PngRowFilter> for PngFilter {
+  fn from(filter: PngRowFilter) -> Self {
+    match filter {
+      PngRowFilter::none => PngFilter::None,
+      PngRowFilter::sub => PngFilter::Sub,
+      PngRowFilter::up => PngFilter::Up,
+      PngRowFilter::average => PngFilter::Average,
+      PngRowFilter::paeth => PngFilter::Paeth,
+      PngRowFilter::adaptive => PngFilter::Adaptive,
+    }
+  }
+}
+
+impl TryFrom<PngOptions> for takumi::rendering::PngEncodeOptions {
+  type Error = Error;
+
+  fn try_from(options: PngOptions) -> Result<Self> {
+    let bit_depth = match options.bit_depth {
+      None | Some(8) => PngBitDepth::Eight,
+      Some(16) => PngBitDepth::Sixteen,
+      Some(bit_depth) => {
+        return Err(Error::from_reason(format!(
+          "Unsupported PNG bit depth {bit_depth}, expected 8 or 16"
+        )));
+      }
+    };
+
+    Ok(Self {
+      compression: options.compression.map(Into::into).unwrap_or_default(),
+      filter: options.filter.map(Into::into),
+      max_colors: options.max_colors,
+      dither: options.dither.unwrap_or(false),
+      bit_depth,
+    })
+  }
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct LayoutOptions {
+  /// The width of the viewport. If not provided, the width will be automatically calculated based on the content.
+  pub width: Option<u32>,
+  /// The height of the viewport. If not provided, the height will be automatically calculated based on the content.
+  pub height: Option<u32>,
+  /// The device pixel ratio.
+  /// @default 1.0
+  pub device_pixel_ratio: Option<f64>,
+}
+
+#[napi(object)]
+pub struct ImageMetadata {
+  /// Text entries to embed, like `Author`, `Copyright` or `Software`.
+  pub text: Option<HashMap<String, String>>,
+  /// The color profile to embed, either `"srgb"` or the bytes of an ICC profile.
+  #[napi(ts_type = "'srgb' | Uint8Array")]
+  pub icc_profile: Option<Either<String, Uint8Array>>,
+}
+
+impl TryFrom<ImageMetadata> for takumi::rendering::ImageMetadata {
+  type Error = Error;
+
+  fn try_from(metadata: ImageMetadata) -> Result<Self> {
+    let mut text: Vec<_> = metadata.text.unwrap_or_default().into_iter().collect();
+    text.sort_unstable();
+
+    let icc_profile = match metadata.icc_profile {
+      None => None,
+      Some(Either::A(name)) if name.eq_ignore_ascii_case("srgb") => Some(IccProfile::Srgb),
+      Some(Either::A(name)) => {
+        return Err(Error::from_reason(format!(
+          "Unknown color profile \"{name}\", expected \"srgb\" or ICC profile bytes"
+        )));
+      }
+      Some(Either::B(bytes)) => Some(IccProfile::Custom(bytes.to_vec())),
+    };
+
+    Ok(Self { text, icc_profile })
+  }
 }
 
 #[napi(object)]
@@ -66,6 +208,13 @@ pub struct RenderAnimationOptions {
   pub width: u32,
   pub height: u32,
   pub format: Option<AnimationOutputFormat>,
+  /// The quality of lossy WebP frames (0-100).
+  pub quality: Option<u8>,
+  /// Whether WebP frames are lossless, otherwise they are encoded with `quality`.
+  /// @default true
+  pub lossless: Option<bool>,
+  /// Metadata to embed in the animation.
+  pub metadata: Option<ImageMetadata>,
 }
 
 #[napi(string_enum)]
@@ -74,6 +223,7 @@ pub struct RenderAnimationOptions {
 pub enum AnimationOutputFormat {
   webp,
   apng,
+  gif,
 }
 
 #[napi(string_enum)]
@@ -90,6 +240,7 @@ pub enum OutputFormat {
   /// @deprecated Use lowercase `png` instead, may be removed in the future
   Png,
   raw,
+  svg,
 }
 
 impl From<OutputFormat> for ImageOutputFormat {
@@ -99,7 +250,7 @@ impl From<OutputFormat> for ImageOutputFormat {
       OutputFormat::Jpeg | OutputFormat::jpeg => ImageOutputFormat::Jpeg,
       OutputFormat::Png | OutputFormat::png => ImageOutputFormat::Png,
       // SAFETY: It's handled in the render task
-      OutputFormat::raw => unreachable!(),
+      OutputFormat::raw | OutputFormat::svg => unreachable!(),
     }
   }
 }
@@ -379,6 +530,42 @@ impl Renderer {
     ))
   }
 
+  /// Computes the layout of the node without rendering it.
+  ///
+  /// Images are not fetched, only the ones in the persistent image store are used for sizing.
+  #[napi(
+    ts_args_type = "source: AnyNode, options?: LayoutOptions",
+    ts_return_type = "LayoutTree"
+  )]
+  pub fn compute_layout(
+    &self,
+    source: Object,
+    options: Option<LayoutOptions>,
+  ) -> Result<serde_json::Value> {
+    let node: NodeKind = deserialize_with_tracing(source)?;
+    let options = options.unwrap_or_default();
+
+    let layout = compute_layout(
+      RenderOptionsBuilder::default()
+        .viewport(Viewport {
+          width: options.width,
+          height: options.height,
+          font_size: DEFAULT_FONT_SIZE,
+          device_pixel_ratio: options
+            .device_pixel_ratio
+            .map(|ratio| ratio as f32)
+            .unwrap_or(DEFAULT_DEVICE_PIXEL_RATIO),
+        })
+        .node(node)
+        .global(&self.global)
+        .build()
+        .unwrap(),
+    )
+    .map_err(|e| Error::from_reason(e.to_string()))?;
+
+    serde_json::to_value(layout).map_err(|e| Error::from_reason(e.to_string()))
+  }
+
   /// @deprecated Use `render` instead (to align with the naming convention for sync/async functions).
   #[napi(
     ts_args_type = "source: AnyNode, options?: RenderOptions, signal?: AbortSignal",
@@ -420,6 +607,8 @@ impl Renderer {
         context: &self.global,
         viewport: (options.width, options.height).into(),
         format: options.format.unwrap_or(AnimationOutputFormat::webp),
+        quality: (!options.lossless.unwrap_or(true)).then(|| options.quality.unwrap_or(75)),
+        metadata: options.metadata.map(TryInto::try_into).transpose()?,
         draw_debug_border: options.draw_debug_border.unwrap_or_default(),
       },
       signal,
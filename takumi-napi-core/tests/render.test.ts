@@ -121,6 +121,18 @@ describe("render", () => {
     expect(result).toBeInstanceOf(Buffer);
   });
 
+  test("lossy webp", async () => {
+    const result = await renderer.render(node, {
+      ...options,
+      format: "webp",
+      quality: 80,
+      lossless: false,
+    });
+
+    expect(result).toBeInstanceOf(Buffer);
+    expect(result.includes("VP8 ")).toBe(true);
+  });
+
   test("png", async () => {
     const result = await renderer.render(node, {
       ...options,
@@ -130,6 +142,48 @@ describe("render", () => {
     expect(result).toBeInstanceOf(Buffer);
   });
 
+  test("png with metadata", async () => {
+    const result = await renderer.render(node, {
+      ...options,
+      format: "png",
+      metadata: {
+        text: { Author: "Takumi" },
+        iccProfile: "srgb",
+      },
+    });
+
+    expect(result).toBeInstanceOf(Buffer);
+    expect(result.includes("tEXtAuthor\0Takumi")).toBe(true);
+    expect(result.includes("sRGB")).toBe(true);
+  });
+
+  test("png with reduced colors", async () => {
+    const result = await renderer.render(node, {
+      ...options,
+      format: "png",
+      png: {
+        compression: "high",
+        maxColors: 16,
+        dither: true,
+      },
+    });
+
+    expect(result).toBeInstanceOf(Buffer);
+    // Color type 3 (indexed) in the IHDR chunk.
+    expect(result[25]).toBe(3);
+  });
+
+  test("png 16-bit", async () => {
+    const result = await renderer.render(node, {
+      ...options,
+      format: "png",
+      png: { bitDepth: 16 },
+    });
+
+    expect(result).toBeInstanceOf(Buffer);
+    expect(result[24]).toBe(16);
+  });
+
   test("jpeg 75% Quality", async () => {
     const result = await renderer.render(node, {
       ...options,
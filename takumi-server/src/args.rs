@@ -1,4 +1,6 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+
+use crate::OutputFormat;
 
 /// Command line arguments for the image generator server.
 ///
@@ -18,8 +20,27 @@ pub struct Args {
   #[arg(short, long)]
   pub font_glob: Option<String>,
 
+  /// Raster formats to choose from with the `Accept` header when the query has no `format`, best first.
+  ///
+  /// Defaults to webp, png, jpeg and avif (with the `avif` feature), list avif first to prefer it.
+  #[arg(long, value_delimiter = ',', value_parser = parse_raster_format)]
+  pub format_preference: Vec<OutputFormat>,
+
   /// The HMAC key for integrity checking. Can be any valid UTF-8 string.
   #[cfg_attr(feature = "hmac_verify", arg(long))]
   #[cfg(feature = "hmac_verify")]
   pub hmac_key: Option<String>,
 }
+
+/// Parses a format of `--format-preference`, which only accepts raster formats.
+///
+/// A client accepting `image/*` expects a raster image, so SVG can only be requested with the `format` query.
+fn parse_raster_format(value: &str) -> Result<OutputFormat, String> {
+  let format = OutputFormat::from_str(value, true)?;
+
+  if !format.is_raster() {
+    return Err(format!("{value} is not a raster format"));
+  }
+
+  Ok(format)
+}
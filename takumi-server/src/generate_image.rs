@@ -1,22 +1,163 @@
 use axum::{
   extract::{Query, State},
-  http::StatusCode,
+  http::{
+    HeaderMap, HeaderValue, StatusCode,
+    header::{ACCEPT, VARY},
+  },
   response::{IntoResponse, Response},
 };
+use clap::ValueEnum;
 use serde::Deserialize;
 use serde_json::from_str;
 use takumi::{
   layout::{Viewport, node::NodeKind},
-  rendering::{ImageOutputFormat, RenderOptionsBuilder, render, write_image},
+  rendering::{
+    ImageEncodeOptions, ImageOutputFormat, PngBitDepth, PngCompression, PngEncodeOptions,
+    PngFilter, RenderOptionsBuilder, WebPCompression, render, render_rgba16, render_svg,
+    write_image_with_options, write_png, write_png_rgba16,
+  },
 };
 use tokio::task::spawn_blocking;
 
+#[cfg(feature = "avif")]
+use takumi::rendering::{AvifEncodeOptions, write_avif};
+
 use crate::{AxumResult, AxumState};
 
+/// Output format accepted by the `format` query parameter.
+#[derive(Deserialize, ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+  #[value(name = "webp")]
+  WebP,
+  Png,
+  Jpeg,
+  #[cfg(feature = "avif")]
+  Avif,
+  Svg,
+}
+
+impl OutputFormat {
+  pub fn content_type(&self) -> &'static str {
+    match self {
+      OutputFormat::WebP => ImageOutputFormat::WebP.content_type(),
+      OutputFormat::Png => ImageOutputFormat::Png.content_type(),
+      OutputFormat::Jpeg => ImageOutputFormat::Jpeg.content_type(),
+      #[cfg(feature = "avif")]
+      OutputFormat::Avif => ImageOutputFormat::Avif.content_type(),
+      OutputFormat::Svg => "image/svg+xml",
+    }
+  }
+
+  /// Whether the format is a raster image, which every format but SVG is.
+  pub fn is_raster(&self) -> bool {
+    *self != OutputFormat::Svg
+  }
+}
+
+/// Formats negotiated from the `Accept` header when no preference is configured, best first.
+///
+/// AVIF encodes much slower than the others, so it is only picked for clients not accepting them.
+pub const DEFAULT_FORMAT_PREFERENCE: &[OutputFormat] = &[
+  OutputFormat::WebP,
+  OutputFormat::Png,
+  OutputFormat::Jpeg,
+  #[cfg(feature = "avif")]
+  OutputFormat::Avif,
+];
+
+/// Picks the format of `preference` that the `Accept` header gives the highest quality.
+///
+/// Ties are broken by the order of `preference`. Without an `Accept` header,
+/// or when none of the formats is acceptable, the first preferred format is used.
+pub fn negotiate_format(accept: Option<&str>, preference: &[OutputFormat]) -> OutputFormat {
+  let fallback = preference.first().copied().unwrap_or(OutputFormat::WebP);
+
+  let Some(accept) = accept else {
+    return fallback;
+  };
+
+  let ranges: Vec<(String, f32)> = accept.split(',').filter_map(parse_media_range).collect();
+
+  preference
+    .iter()
+    .filter_map(|&format| {
+      let quality = accept_quality(&ranges, format.content_type())?;
+
+      (quality > 0.0).then_some((format, quality))
+    })
+    .fold(
+      None,
+      |best: Option<(OutputFormat, f32)>, candidate| match best {
+        Some(best) if best.1 >= candidate.1 => Some(best),
+        _ => Some(candidate),
+      },
+    )
+    .map_or(fallback, |(format, _)| format)
+}
+
+/// Parses a media range of the `Accept` header into its lowercased type and quality.
+fn parse_media_range(range: &str) -> Option<(String, f32)> {
+  let mut parts = range.split(';');
+
+  let media_type = parts.next()?.trim().to_ascii_lowercase();
+
+  if media_type.is_empty() {
+    return None;
+  }
+
+  let quality = match parts
+    .filter_map(|param| param.split_once('='))
+    .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+  {
+    Some((_, value)) => value.trim().parse::<f32>().ok()?,
+    None => 1.0,
+  };
+
+  Some((media_type, quality.clamp(0.0, 1.0)))
+}
+
+/// Returns the quality of the most specific media range matching `content_type`.
+fn accept_quality(ranges: &[(String, f32)], content_type: &str) -> Option<f32> {
+  let (main_type, _) = content_type.split_once('/')?;
+
+  ranges
+    .iter()
+    .filter_map(|(range, quality)| {
+      let specificity = if range == content_type {
+        2
+      } else if range.strip_suffix("/*") == Some(main_type) {
+        1
+      } else if range == "*/*" {
+        0
+      } else {
+        return None;
+      };
+
+      Some((specificity, *quality))
+    })
+    .max_by_key(|(specificity, _)| *specificity)
+    .map(|(_, quality)| quality)
+}
+
 #[derive(Deserialize)]
 pub struct GenerateImageQuery {
-  pub format: Option<ImageOutputFormat>,
+  pub format: Option<OutputFormat>,
   pub quality: Option<u8>,
+  /// Whether WebP output is lossless, defaults to lossless unless `quality` is given.
+  pub lossless: Option<bool>,
+  /// Encoder speed of AVIF output (1-10), only used with the `avif` feature.
+  pub speed: Option<u8>,
+  /// Compression level of PNG output.
+  pub compression: Option<PngCompression>,
+  /// Row filter of PNG output.
+  pub filter: Option<PngFilter>,
+  /// Reduces PNG output to at most this many colors (2-256).
+  pub colors: Option<u16>,
+  /// Whether to dither PNG output when `colors` is given.
+  pub dither: Option<bool>,
+  /// Bits per channel of PNG output, either `8` or `16`.
+  pub bit_depth: Option<PngBitDepth>,
   pub payload: String,
   pub draw_debug_border: Option<bool>,
   pub width: Option<u32>,
@@ -26,6 +167,7 @@ pub struct GenerateImageQuery {
 pub async fn generate_image_handler(
   Query(query): Query<GenerateImageQuery>,
   State(state): AxumState,
+  headers: HeaderMap,
 ) -> AxumResult<Response> {
   let root_node: NodeKind = from_str(&query.payload).map_err(|err| {
     (
@@ -34,7 +176,14 @@ pub async fn generate_image_handler(
     )
   })?;
 
-  let format = query.format.unwrap_or(ImageOutputFormat::WebP);
+  let negotiated = query.format.is_none();
+
+  let format = query.format.unwrap_or_else(|| {
+    negotiate_format(
+      headers.get(ACCEPT).and_then(|value| value.to_str().ok()),
+      &state.format_preference,
+    )
+  });
 
   let buffer = spawn_blocking(move || -> AxumResult<Vec<u8>> {
     let viewport = Viewport::new(query.width, query.height);
@@ -46,6 +195,51 @@ pub async fn generate_image_handler(
       .build()
       .unwrap();
 
+    let image_format = match format {
+      OutputFormat::Svg => {
+        return render_svg(options).map(String::into_bytes).map_err(|_| {
+          (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to render image.".to_string(),
+          )
+        });
+      }
+      OutputFormat::WebP => ImageOutputFormat::WebP,
+      OutputFormat::Png => ImageOutputFormat::Png,
+      OutputFormat::Jpeg => ImageOutputFormat::Jpeg,
+      #[cfg(feature = "avif")]
+      OutputFormat::Avif => ImageOutputFormat::Avif,
+    };
+
+    let png_options = PngEncodeOptions {
+      compression: query.compression.unwrap_or_default(),
+      filter: query.filter,
+      max_colors: query.colors,
+      dither: query.dither.unwrap_or(false),
+      bit_depth: query.bit_depth.unwrap_or_default(),
+    };
+
+    let mut buffer = Vec::new();
+
+    // 16-bit output is rendered with 16 bits per channel instead of scaling 8-bit samples up.
+    if image_format == ImageOutputFormat::Png && png_options.bit_depth == PngBitDepth::Sixteen {
+      let image = render_rgba16(options).map_err(|_| {
+        (
+          StatusCode::INTERNAL_SERVER_ERROR,
+          "Failed to render image.".to_string(),
+        )
+      })?;
+
+      write_png_rgba16(&image, &mut buffer, png_options, None).map_err(|_| {
+        (
+          StatusCode::INTERNAL_SERVER_ERROR,
+          "Failed to write image.".to_string(),
+        )
+      })?;
+
+      return Ok(buffer);
+    }
+
     let image = render(options).map_err(|_| {
       (
         StatusCode::INTERNAL_SERVER_ERROR,
@@ -53,9 +247,48 @@ pub async fn generate_image_handler(
       )
     })?;
 
-    let mut buffer = Vec::new();
+    #[cfg(feature = "avif")]
+    if image_format == ImageOutputFormat::Avif {
+      let defaults = AvifEncodeOptions::default();
+
+      let options = AvifEncodeOptions {
+        quality: query.quality.unwrap_or(defaults.quality),
+        speed: query.speed.unwrap_or(defaults.speed),
+      };
+
+      write_avif(&image, &mut buffer, options).map_err(|_| {
+        (
+          StatusCode::INTERNAL_SERVER_ERROR,
+          "Failed to write image.".to_string(),
+        )
+      })?;
+
+      return Ok(buffer);
+    }
 
-    write_image(&image, &mut buffer, format, query.quality).map_err(|_| {
+    if image_format == ImageOutputFormat::Png {
+      write_png(&image, &mut buffer, png_options, None).map_err(|_| {
+        (
+          StatusCode::INTERNAL_SERVER_ERROR,
+          "Failed to write image.".to_string(),
+        )
+      })?;
+
+      return Ok(buffer);
+    }
+
+    let options = ImageEncodeOptions {
+      quality: query.quality,
+      // A quality is only used by lossy WebP, so it is lossless by default only without one.
+      webp_compression: if query.lossless.unwrap_or(query.quality.is_none()) {
+        WebPCompression::Lossless
+      } else {
+        WebPCompression::Lossy
+      },
+      metadata: None,
+    };
+
+    write_image_with_options(&image, &mut buffer, image_format, options).map_err(|_| {
       (
         StatusCode::INTERNAL_SERVER_ERROR,
         "Failed to write image.".to_string(),
@@ -72,5 +305,13 @@ pub async fn generate_image_handler(
     )
   })??;
 
-  Ok(([("content-type", format.content_type())], buffer).into_response())
+  let mut response = ([("content-type", format.content_type())], buffer).into_response();
+
+  if negotiated {
+    response
+      .headers_mut()
+      .insert(VARY, HeaderValue::from_static("Accept"));
+  }
+
+  Ok(response)
 }
@@ -18,13 +18,27 @@ pub type AxumResult<T = Response> = Result<T, (StatusCode, String)>;
 
 pub struct AxumStateInner {
   pub context: GlobalContext,
+  /// Raster formats negotiated from the `Accept` header, best first.
+  pub format_preference: Vec<OutputFormat>,
   #[cfg(feature = "hmac_verify")]
   pub hmac_key: Option<Vec<u8>>,
 }
 
 pub fn create_state(args: Args, context: GlobalContext) -> AxumState {
+  let format_preference = if args.format_preference.is_empty() {
+    DEFAULT_FORMAT_PREFERENCE.to_vec()
+  } else {
+    args.format_preference
+  };
+
+  assert!(
+    format_preference.iter().all(OutputFormat::is_raster),
+    "The format preference can only contain raster formats"
+  );
+
   let state = Arc::new(AxumStateInner {
     context,
+    format_preference,
     #[cfg(feature = "hmac_verify")]
     hmac_key: args.hmac_key.map(|key| {
       use sha2::{Digest, Sha256};
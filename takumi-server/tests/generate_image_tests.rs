@@ -1,7 +1,20 @@
-use axum::extract::Query;
-use takumi::GlobalContext;
+use axum::{
+  body::to_bytes,
+  extract::Query,
+  http::{
+    HeaderMap, HeaderValue, Uri,
+    header::{ACCEPT, VARY},
+  },
+};
+use takumi::{
+  GlobalContext,
+  rendering::{PngBitDepth, PngCompression, PngFilter},
+};
 
-use takumi_server::{GenerateImageQuery, args::Args, create_state, generate_image_handler};
+use takumi_server::{
+  DEFAULT_FORMAT_PREFERENCE, GenerateImageQuery, OutputFormat, args::Args, create_state,
+  generate_image_handler, negotiate_format,
+};
 
 #[tokio::test]
 async fn test_generate_image_handler() {
@@ -15,14 +28,217 @@ async fn test_generate_image_handler() {
     Query(GenerateImageQuery {
       format: None,
       quality: None,
+      lossless: None,
+      speed: None,
+      compression: None,
+      filter: None,
+      colors: None,
+      dither: None,
+      bit_depth: None,
       payload: NODE.to_owned(),
       draw_debug_border: Some(false),
       width: Some(1200),
       height: Some(630),
     }),
     state,
+    HeaderMap::new(),
   )
   .await
   .unwrap();
   assert_eq!(response.status(), 200);
 }
+
+#[tokio::test]
+async fn test_generate_svg_handler() {
+  const NODE: &str = r#"{
+    "type": "container",
+    "tw": "w-100 h-100 bg-red-500"
+  }"#;
+
+  let state = create_state(Args::default(), GlobalContext::default());
+  let response = generate_image_handler(
+    Query(GenerateImageQuery {
+      format: Some(OutputFormat::Svg),
+      quality: None,
+      lossless: None,
+      speed: None,
+      compression: None,
+      filter: None,
+      colors: None,
+      dither: None,
+      bit_depth: None,
+      payload: NODE.to_owned(),
+      draw_debug_border: Some(false),
+      width: Some(1200),
+      height: Some(630),
+    }),
+    state,
+    HeaderMap::new(),
+  )
+  .await
+  .unwrap();
+  assert_eq!(response.status(), 200);
+  assert_eq!(response.headers()["content-type"], "image/svg+xml");
+}
+
+#[cfg(feature = "avif")]
+#[tokio::test]
+async fn test_generate_avif_handler() {
+  const NODE: &str = r#"{
+    "type": "container",
+    "tw": "w-100 h-100 bg-red-500/50"
+  }"#;
+
+  let state = create_state(Args::default(), GlobalContext::default());
+  let response = generate_image_handler(
+    Query(GenerateImageQuery {
+      format: Some(OutputFormat::Avif),
+      quality: Some(60),
+      lossless: None,
+      speed: Some(10),
+      compression: None,
+      filter: None,
+      colors: None,
+      dither: None,
+      bit_depth: None,
+      payload: NODE.to_owned(),
+      draw_debug_border: Some(false),
+      width: Some(1200),
+      height: Some(630),
+    }),
+    state,
+    HeaderMap::new(),
+  )
+  .await
+  .unwrap();
+  assert_eq!(response.status(), 200);
+  assert_eq!(response.headers()["content-type"], "image/avif");
+}
+
+#[test]
+fn test_negotiate_format() {
+  const PREFERENCE: &[OutputFormat] = &[OutputFormat::WebP, OutputFormat::Png, OutputFormat::Jpeg];
+
+  assert_eq!(negotiate_format(None, PREFERENCE), OutputFormat::WebP);
+  assert_eq!(
+    negotiate_format(Some("*/*"), PREFERENCE),
+    OutputFormat::WebP
+  );
+  assert_eq!(
+    negotiate_format(Some("image/png,image/*;q=0.8"), PREFERENCE),
+    OutputFormat::Png
+  );
+  assert_eq!(
+    negotiate_format(Some("image/webp;q=0, image/jpeg"), PREFERENCE),
+    OutputFormat::Jpeg
+  );
+  assert_eq!(
+    negotiate_format(
+      Some("image/avif,image/webp,image/apng,image/svg+xml,image/*,*/*;q=0.8"),
+      PREFERENCE
+    ),
+    OutputFormat::WebP
+  );
+  assert_eq!(
+    negotiate_format(Some("IMAGE/JPEG; Q=0.9, image/png; q=0.5"), PREFERENCE),
+    OutputFormat::Jpeg
+  );
+  assert_eq!(
+    negotiate_format(Some("text/html"), PREFERENCE),
+    OutputFormat::WebP
+  );
+  assert_eq!(
+    negotiate_format(
+      Some("image/webp"),
+      &[OutputFormat::Jpeg, OutputFormat::WebP]
+    ),
+    OutputFormat::WebP
+  );
+  assert_eq!(
+    negotiate_format(
+      Some("image/avif,image/webp,image/apng,image/svg+xml,image/*,*/*;q=0.8"),
+      DEFAULT_FORMAT_PREFERENCE
+    ),
+    OutputFormat::WebP
+  );
+}
+
+#[tokio::test]
+async fn test_generate_image_handler_negotiates_accept() {
+  const NODE: &str = r#"{
+    "type": "container",
+    "tw": "w-100 h-100"
+  }"#;
+
+  let mut headers = HeaderMap::new();
+  headers.insert(ACCEPT, HeaderValue::from_static("image/png,image/*;q=0.5"));
+
+  let state = create_state(Args::default(), GlobalContext::default());
+  let response = generate_image_handler(
+    Query(GenerateImageQuery {
+      format: None,
+      quality: None,
+      lossless: None,
+      speed: None,
+      compression: None,
+      filter: None,
+      colors: None,
+      dither: None,
+      bit_depth: None,
+      payload: NODE.to_owned(),
+      draw_debug_border: Some(false),
+      width: Some(1200),
+      height: Some(630),
+    }),
+    state,
+    headers,
+  )
+  .await
+  .unwrap();
+  assert_eq!(response.status(), 200);
+  assert_eq!(response.headers()["content-type"], "image/png");
+  assert_eq!(response.headers()[VARY], "Accept");
+}
+
+#[tokio::test]
+async fn test_generate_png_handler_with_options() {
+  let uri: Uri = "/image?format=png&compression=high&filter=paeth&colors=16&dither=true&bit_depth=8&payload=%7B%22type%22%3A%22container%22%2C%22tw%22%3A%22w-full%20h-full%20bg-gradient-to-r%20from-red-500%20to-blue-500%22%7D&width=64&height=64"
+    .parse()
+    .unwrap();
+  let Query(query) = Query::<GenerateImageQuery>::try_from_uri(&uri).unwrap();
+
+  assert_eq!(query.compression, Some(PngCompression::High));
+  assert_eq!(query.filter, Some(PngFilter::Paeth));
+  assert_eq!(query.colors, Some(16));
+  assert_eq!(query.dither, Some(true));
+  assert_eq!(query.bit_depth, Some(PngBitDepth::Eight));
+
+  let state = create_state(Args::default(), GlobalContext::default());
+  let response = generate_image_handler(Query(query), state, HeaderMap::new())
+    .await
+    .unwrap();
+  assert_eq!(response.status(), 200);
+  assert_eq!(response.headers()["content-type"], "image/png");
+}
+
+#[tokio::test]
+async fn test_generate_webp_handler_with_quality_is_lossy() {
+  let uri: Uri = "/image?format=webp&quality=80&payload=%7B%22type%22%3A%22container%22%2C%22tw%22%3A%22w-full%20h-full%20bg-gradient-to-r%20from-red-500%20to-blue-500%22%7D&width=64&height=64"
+    .parse()
+    .unwrap();
+  let Query(query) = Query::<GenerateImageQuery>::try_from_uri(&uri).unwrap();
+
+  assert_eq!(query.lossless, None);
+
+  let state = create_state(Args::default(), GlobalContext::default());
+  let response = generate_image_handler(Query(query), state, HeaderMap::new())
+    .await
+    .unwrap();
+  assert_eq!(response.status(), 200);
+  assert_eq!(response.headers()["content-type"], "image/webp");
+
+  let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+  assert!(body.windows(4).any(|chunk| chunk == b"VP8 "));
+  assert!(!body.windows(4).any(|chunk| chunk == b"VP8L"));
+}
@@ -1,8 +1,9 @@
 use axum::http::Request;
+use clap::Parser;
 use tower::ServiceExt;
 
 use takumi::GlobalContext;
-use takumi_server::{Args, create_app, create_state};
+use takumi_server::{Args, OutputFormat, create_app, create_state};
 
 #[test]
 fn test_create_state() {
@@ -11,6 +12,17 @@ fn test_create_state() {
   assert!(state.hmac_key.is_none());
 }
 
+#[test]
+fn test_format_preference_only_accepts_raster_formats() {
+  let args = Args::try_parse_from(["takumi-server", "--format-preference", "png,jpeg"]).unwrap();
+  assert_eq!(
+    args.format_preference,
+    [OutputFormat::Png, OutputFormat::Jpeg]
+  );
+
+  assert!(Args::try_parse_from(["takumi-server", "--format-preference", "webp,svg"]).is_err());
+}
+
 #[tokio::test]
 #[cfg(feature = "hmac_verify")]
 async fn test_generate_image_handler_with_hmac_verify() {
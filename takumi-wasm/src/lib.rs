@@ -1,12 +1,13 @@
 use std::{
-  collections::HashMap,
+  collections::{BTreeMap, HashMap},
+  io::Cursor,
   sync::{Arc, Once},
 };
 
 use base64::{Engine, prelude::BASE64_STANDARD};
 use serde::Deserialize;
 use serde_bytes::ByteBuf;
-use serde_wasm_bindgen::from_value;
+use serde_wasm_bindgen::{from_value, to_value};
 use takumi::{
   GlobalContext,
   image::load_from_memory,
@@ -16,8 +17,10 @@ use takumi::{
   },
   parley::{FontWeight, fontique::FontInfoOverride},
   rendering::{
-    AnimationFrame, ImageOutputFormat, RenderOptionsBuilder, encode_animated_png,
-    encode_animated_webp, render, write_image,
+    AnimationFrame, GifEncodeOptions, IccProfile, ImageEncodeOptions, ImageOutputFormat,
+    PngAnimationEncoder, RenderOptionsBuilder, WebPAnimationEncoder, WebPAnimationOptions,
+    WebPCompression, compute_layout, encode_animated_gif, render, render_svg,
+    write_image_with_options,
   },
   resources::{
     image::{ImageSource, load_image_source_from_bytes},
@@ -32,6 +35,17 @@ export type AnyNode = { type: string; [key: string]: any };
 
 export type ByteBuf = Uint8Array | ArrayBuffer | Buffer;
 
+export type ImageMetadata = {
+  /**
+   * Text entries to embed, like `Author`, `Copyright` or `Software`.
+   */
+  text?: Record<string, string>,
+  /**
+   * The color profile to embed, either `"srgb"` or the bytes of an ICC profile.
+   */
+  iccProfile?: "srgb" | ByteBuf,
+};
+
 export type RenderOptions = {
   /**
    * The width of the image. If not provided, the width will be automatically calculated based on the content.
@@ -45,11 +59,16 @@ export type RenderOptions = {
    * The format of the image.
    * @default "png"
    */
-  format?: "png" | "jpeg" | "webp",
+  format?: "png" | "jpeg" | "webp" | "svg",
   /**
-   * The quality of JPEG format (0-100).
+   * The quality of JPEG and lossy WebP format (0-100).
    */
   quality?: number,
+  /**
+   * Whether WebP output is lossless, otherwise it is encoded with `quality`.
+   * @default true
+   */
+  lossless?: boolean,
   /**
    * The resources fetched externally. You should collect the fetch tasks first using `collectNodeFetchTasks` and then pass the resources here.
    */
@@ -63,15 +82,86 @@ export type RenderOptions = {
    * @default 1.0
    */
   devicePixelRatio?: number,
+  /**
+   * Metadata to embed in the image, ignored by the `svg` format.
+   */
+  metadata?: ImageMetadata,
 };
 
 export type RenderAnimationOptions = {
   width: number,
   height: number,
-  format?: "webp" | "apng",
+  format?: "webp" | "apng" | "gif",
+  /**
+   * The quality of lossy WebP frames (0-100).
+   */
+  quality?: number,
+  /**
+   * Whether WebP frames are lossless, otherwise they are encoded with `quality`.
+   * @default true
+   */
+  lossless?: boolean,
+  /**
+   * Metadata to embed in the animation.
+   */
+  metadata?: ImageMetadata,
   drawDebugBorder?: boolean,
 };
 
+export type LayoutRect = {
+  x: number,
+  y: number,
+  width: number,
+  height: number,
+};
+
+export type LayoutGlyph = {
+  id: number,
+  x: number,
+  y: number,
+  advance: number,
+};
+
+export type LayoutGlyphRun = {
+  text: string,
+  fontSize: number,
+  rect: LayoutRect,
+  baseline: number,
+  glyphs: LayoutGlyph[],
+};
+
+export type LayoutLine = {
+  text: string,
+  rect: LayoutRect,
+  baseline: number,
+  runs: LayoutGlyphRun[],
+  inlineBoxes: LayoutRect[],
+};
+
+export type LayoutNode = {
+  /**
+   * The boxes are in the local coordinate space of the node, where the border box starts at the origin.
+   */
+  borderBox: LayoutRect,
+  paddingBox: LayoutRect,
+  contentBox: LayoutRect,
+  /**
+   * Maps the local coordinate space of the node to the output.
+   */
+  transform: { a: number, b: number, c: number, d: number, x: number, y: number },
+  /**
+   * The lines of text laid out in the content box, if the node contains text.
+   */
+  lines?: LayoutLine[],
+  children: LayoutNode[],
+};
+
+export type LayoutTree = {
+  width: number,
+  height: number,
+  root: LayoutNode,
+};
+
 export type FontDetails = {
   name?: string,
   data: ByteBuf,
@@ -94,6 +184,9 @@ extern "C" {
   #[wasm_bindgen(typescript_type = "RenderAnimationOptions")]
   pub type RenderAnimationOptionsType;
 
+  #[wasm_bindgen(typescript_type = "LayoutTree")]
+  pub type LayoutTreeType;
+
   #[wasm_bindgen(typescript_type = "FontDetails")]
   pub type FontDetailsType;
 
@@ -109,11 +202,13 @@ extern "C" {
 struct RenderOptions {
   width: Option<u32>,
   height: Option<u32>,
-  format: Option<ImageOutputFormat>,
+  format: Option<OutputFormat>,
   quality: Option<u8>,
+  lossless: Option<bool>,
   fetched_resources: Option<HashMap<Arc<str>, ByteBuf>>,
   draw_debug_border: Option<bool>,
   device_pixel_ratio: Option<f32>,
+  metadata: Option<ImageMetadata>,
 }
 
 #[derive(Deserialize)]
@@ -122,9 +217,44 @@ struct RenderAnimationOptions {
   width: u32,
   height: u32,
   format: Option<AnimationOutputFormat>,
+  quality: Option<u8>,
+  lossless: Option<bool>,
+  metadata: Option<ImageMetadata>,
   draw_debug_border: Option<bool>,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImageMetadata {
+  text: Option<BTreeMap<String, String>>,
+  icc_profile: Option<IccProfileInput>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IccProfileInput {
+  Name(IccProfileName),
+  Bytes(ByteBuf),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum IccProfileName {
+  Srgb,
+}
+
+impl From<ImageMetadata> for takumi::rendering::ImageMetadata {
+  fn from(metadata: ImageMetadata) -> Self {
+    Self {
+      text: metadata.text.unwrap_or_default().into_iter().collect(),
+      icc_profile: metadata.icc_profile.map(|profile| match profile {
+        IccProfileInput::Name(IccProfileName::Srgb) => IccProfile::Srgb,
+        IccProfileInput::Bytes(bytes) => IccProfile::Custom(bytes.into_vec()),
+      }),
+    }
+  }
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct FontDetails {
@@ -141,11 +271,32 @@ enum Font {
   Buffer(ByteBuf),
 }
 
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum OutputFormat {
+  Png,
+  Jpeg,
+  WebP,
+  Svg,
+}
+
+impl OutputFormat {
+  fn content_type(self) -> &'static str {
+    match self {
+      OutputFormat::Png => ImageOutputFormat::Png.content_type(),
+      OutputFormat::Jpeg => ImageOutputFormat::Jpeg.content_type(),
+      OutputFormat::WebP => ImageOutputFormat::WebP.content_type(),
+      OutputFormat::Svg => "image/svg+xml",
+    }
+  }
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "lowercase")]
 enum AnimationOutputFormat {
   APng,
   WebP,
+  Gif,
 }
 
 #[derive(Deserialize)]
@@ -262,9 +413,31 @@ impl Renderer {
     self.render_internal(node, options)
   }
 
-  fn render_internal(&self, node: NodeKind, options: RenderOptions) -> Vec<u8> {
+  /// Computes the layout of the node without rendering it.
+  #[wasm_bindgen(js_name = computeLayout)]
+  pub fn compute_layout(
+    &self,
+    node: AnyNode,
+    options: Option<RenderOptionsType>,
+  ) -> LayoutTreeType {
+    let node: NodeKind = from_value(node.into()).unwrap();
+    let mut options: RenderOptions = options
+      .map(|options| from_value(options.into()).unwrap())
+      .unwrap_or_default();
+
+    let layout = compute_layout(self.create_render_options(node, &mut options)).unwrap();
+
+    to_value(&layout).unwrap().unchecked_into()
+  }
+
+  fn create_render_options(
+    &self,
+    node: NodeKind,
+    options: &mut RenderOptions,
+  ) -> takumi::rendering::RenderOptions<'_, NodeKind> {
     let fetched_resources = options
       .fetched_resources
+      .take()
       .map(|resources| {
         resources
           .into_iter()
@@ -273,32 +446,52 @@ impl Renderer {
       })
       .unwrap_or_default();
 
-    let image = render(
-      RenderOptionsBuilder::default()
-        .viewport(Viewport {
-          width: options.width,
-          height: options.height,
-          font_size: DEFAULT_FONT_SIZE,
-          device_pixel_ratio: options
-            .device_pixel_ratio
-            .unwrap_or(DEFAULT_DEVICE_PIXEL_RATIO),
-        })
-        .draw_debug_border(options.draw_debug_border.unwrap_or_default())
-        .fetched_resources(fetched_resources)
-        .node(node)
-        .global(&self.context)
-        .build()
-        .unwrap(),
-    )
-    .unwrap();
+    RenderOptionsBuilder::default()
+      .viewport(Viewport {
+        width: options.width,
+        height: options.height,
+        font_size: DEFAULT_FONT_SIZE,
+        device_pixel_ratio: options
+          .device_pixel_ratio
+          .unwrap_or(DEFAULT_DEVICE_PIXEL_RATIO),
+      })
+      .draw_debug_border(options.draw_debug_border.unwrap_or_default())
+      .fetched_resources(fetched_resources)
+      .node(node)
+      .global(&self.context)
+      .build()
+      .unwrap()
+  }
+
+  fn render_internal(&self, node: NodeKind, mut options: RenderOptions) -> Vec<u8> {
+    let render_options = self.create_render_options(node, &mut options);
+
+    let format = match options.format.unwrap_or(OutputFormat::Png) {
+      OutputFormat::Svg => return render_svg(render_options).unwrap().into_bytes(),
+      OutputFormat::Png => ImageOutputFormat::Png,
+      OutputFormat::Jpeg => ImageOutputFormat::Jpeg,
+      OutputFormat::WebP => ImageOutputFormat::WebP,
+    };
+
+    let metadata = options.metadata.map(takumi::rendering::ImageMetadata::from);
+
+    let image = render(render_options).unwrap();
 
     let mut buffer = Vec::new();
 
-    write_image(
+    write_image_with_options(
       &image,
       &mut buffer,
-      options.format.unwrap_or(ImageOutputFormat::Png),
-      options.quality,
+      format,
+      ImageEncodeOptions {
+        quality: options.quality,
+        webp_compression: if options.lossless.unwrap_or(true) {
+          WebPCompression::Lossless
+        } else {
+          WebPCompression::Lossy
+        },
+        metadata: metadata.as_ref(),
+      },
     )
     .unwrap();
 
@@ -310,7 +503,7 @@ impl Renderer {
     let node: NodeKind = from_value(node.into()).unwrap();
     let options: RenderOptions = from_value(options.into()).unwrap();
 
-    let format = options.format.unwrap_or(ImageOutputFormat::Png);
+    let format = options.format.unwrap_or(OutputFormat::Png);
     let buffer = self.render_internal(node, options);
 
     let mut data_uri = String::new();
@@ -331,38 +524,81 @@ impl Renderer {
   ) -> Vec<u8> {
     let options: RenderAnimationOptions = from_value(options.into()).unwrap();
 
-    let rendered_frames: Vec<AnimationFrame> = frames
-      .into_iter()
-      .map(|frame| {
-        let node: NodeKind = from_value(frame.node.into()).unwrap();
-        let duration_ms = frame.duration_ms;
-
-        let image = render(
-          RenderOptionsBuilder::default()
-            .viewport((options.width, options.height).into())
-            .node(node)
-            .global(&self.context)
-            .draw_debug_border(options.draw_debug_border.unwrap_or_default())
-            .build()
-            .unwrap(),
-        )
-        .unwrap();
-        AnimationFrame::new(image, duration_ms)
-      })
-      .collect();
+    let render_frame = |frame: AnimationFrameSource| {
+      let node: NodeKind = from_value(frame.node.into()).unwrap();
 
-    let mut buffer = Vec::new();
+      let image = render(
+        RenderOptionsBuilder::default()
+          .viewport((options.width, options.height).into())
+          .node(node)
+          .global(&self.context)
+          .draw_debug_border(options.draw_debug_border.unwrap_or_default())
+          .build()
+          .unwrap(),
+      )
+      .unwrap();
+
+      AnimationFrame::new(image, frame.duration_ms)
+    };
+
+    let metadata = options.metadata.map(takumi::rendering::ImageMetadata::from);
+
+    let mut buffer = Cursor::new(Vec::new());
 
+    // Frames are encoded as soon as they are rendered, except for GIF which builds its palette from all of them.
     match options.format.unwrap_or(AnimationOutputFormat::WebP) {
       AnimationOutputFormat::WebP => {
-        encode_animated_webp(&rendered_frames, &mut buffer, true, false, None).unwrap();
+        let mut encoder = WebPAnimationEncoder::new(
+          &mut buffer,
+          options.width,
+          options.height,
+          WebPAnimationOptions {
+            blend: true,
+            quality: (!options.lossless.unwrap_or(true)).then(|| options.quality.unwrap_or(75)),
+            ..Default::default()
+          },
+          metadata.as_ref(),
+        )
+        .unwrap();
+
+        for frame in frames {
+          encoder.push_frame(render_frame(frame)).unwrap();
+        }
+
+        encoder.finish().unwrap();
       }
       AnimationOutputFormat::APng => {
-        encode_animated_png(&rendered_frames, &mut buffer, None).unwrap();
+        let mut encoder = PngAnimationEncoder::new(
+          &mut buffer,
+          options.width,
+          options.height,
+          frames.len() as u32,
+          None,
+          metadata.as_ref(),
+        )
+        .unwrap();
+
+        for frame in frames {
+          encoder.push_frame(render_frame(frame)).unwrap();
+        }
+
+        encoder.finish().unwrap();
+      }
+      AnimationOutputFormat::Gif => {
+        let rendered_frames: Vec<AnimationFrame> = frames.into_iter().map(render_frame).collect();
+
+        encode_animated_gif(
+          &rendered_frames,
+          &mut buffer,
+          None,
+          GifEncodeOptions::default(),
+          metadata.as_ref(),
+        )
+        .unwrap();
       }
     }
 
-    buffer
+    buffer.into_inner()
   }
 }
 
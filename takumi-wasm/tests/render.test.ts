@@ -75,6 +75,19 @@ describe("render", () => {
     expect(result).toBeInstanceOf(Uint8Array);
   });
 
+  test("lossy webp", () => {
+    const result = renderer.render(node, {
+      width: 1200,
+      height: 630,
+      format: "webp",
+      quality: 80,
+      lossless: false,
+    });
+
+    expect(result).toBeInstanceOf(Uint8Array);
+    expect(new TextDecoder().decode(result)).toContain("VP8 ");
+  });
+
   test("png", () => {
     const result = renderer.render(node, {
       width: 1200,
@@ -85,6 +98,21 @@ describe("render", () => {
     expect(result).toBeInstanceOf(Uint8Array);
   });
 
+  test("png with metadata", () => {
+    const result = renderer.render(node, {
+      width: 1200,
+      height: 630,
+      format: "png",
+      metadata: {
+        text: { Author: "Takumi" },
+        iccProfile: "srgb",
+      },
+    });
+
+    expect(result).toBeInstanceOf(Uint8Array);
+    expect(new TextDecoder().decode(result)).toContain("tEXtAuthor\0Takumi");
+  });
+
   test("jpeg 75%", () => {
     const result = renderer.render(node, {
       width: 1200,
@@ -267,5 +295,17 @@ describe("renderAsDataUrl", () => {
 
       expect(result).toBeInstanceOf(Uint8Array);
     });
+
+    test("gif", () => {
+      const frame = new AnimationFrameSource(node, 1000);
+      const result = renderer.renderAnimation([frame], {
+        width: 1200,
+        height: 630,
+        format: "gif",
+      });
+
+      expect(result).toBeInstanceOf(Uint8Array);
+      expect(new TextDecoder().decode(result.subarray(0, 6))).toBe("GIF89a");
+    });
   });
 });
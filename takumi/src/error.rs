@@ -23,6 +23,10 @@ pub enum TakumiError {
   #[error("WebP encoding error: {0}")]
   WebPEncodingError(#[from] image_webp::EncodingError),
 
+  /// Error encoding a GIF image.
+  #[error("GIF encoding error: {0}")]
+  GifEncodingError(#[from] gif::EncodingError),
+
   /// Generic image processing error.
   #[error("Image error: {0}")]
   ImageError(#[from] image::ImageError),
@@ -38,6 +42,11 @@ pub enum TakumiError {
   /// Error during layout computation.
   #[error("Layout error: {0}")]
   LayoutError(#[from] taffy::TaffyError),
+
+  /// Error subsetting a font for embedding in a PDF document.
+  #[cfg(feature = "pdf")]
+  #[error("Font subsetting error: {0}")]
+  FontSubsetError(#[from] subsetter::Error),
 }
 
 /// A specialized Result type for Takumi operations.
@@ -99,7 +99,7 @@ pub(crate) fn create_inline_layout<'c, 'g: 'c, N: Node<N> + 'c>(
 ) -> (InlineLayout, String, Vec<ProcessedInlineSpan<'c, 'g, N>>) {
   let mut spans: Vec<ProcessedInlineSpan<'c, 'g, N>> = Vec::new();
 
-  let (mut layout, text) = global.font_context.tree_builder(style.into(), |builder| {
+  let (mut layout, mut text) = global.font_context.tree_builder(style.into(), |builder| {
     let mut idx = 0;
     let mut index_pos = 0;
 
@@ -163,7 +163,7 @@ pub(crate) fn create_inline_layout<'c, 'g: 'c, N: Node<N> + 'c>(
       .is_some_and(|last_line| last_line.text_range().end < text.len());
 
     if is_overflowing {
-      make_ellipsis_layout(
+      text = make_ellipsis_layout(
         &mut layout,
         &mut spans,
         max_width,
@@ -263,6 +263,7 @@ pub(crate) fn break_lines(
 
 /// Truncates text and inline boxes in the layout and appends an ellipsis character.
 /// This function handles both text spans with their individual styles and inline boxes.
+/// Returns the text of the truncated layout.
 fn make_ellipsis_layout<'c, 'g: 'c, N: Node<N> + 'c>(
   layout: &mut InlineLayout,
   spans: &mut Vec<ProcessedInlineSpan<'c, 'g, N>>,
@@ -270,7 +271,7 @@ fn make_ellipsis_layout<'c, 'g: 'c, N: Node<N> + 'c>(
   max_height: Option<MaxHeight>,
   root_style: &'c SizedFontStyle,
   global: &GlobalContext,
-) {
+) -> String {
   loop {
     let (mut new_layout, text) = global
       .font_context
@@ -296,7 +297,7 @@ fn make_ellipsis_layout<'c, 'g: 'c, N: Node<N> + 'c>(
     // If there are no spans, return the new layout
     if spans.is_empty() {
       *layout = new_layout;
-      return;
+      return text;
     }
 
     // Check if all content (including ellipsis) is visible
@@ -304,13 +305,13 @@ fn make_ellipsis_layout<'c, 'g: 'c, N: Node<N> + 'c>(
       && last_line.text_range().end == text.len()
     {
       *layout = new_layout;
-      return;
+      return text;
     }
 
     // Try to truncate from the last span
     let Some(last_span) = spans.last_mut() else {
       *layout = new_layout;
-      return;
+      return text;
     };
 
     match last_span {
@@ -15,11 +15,14 @@ use crate::{
   layout::{
     Viewport,
     inline::InlineContentKind,
-    style::{Affine, BackgroundClip, BackgroundImage, CssValue, InheritedStyle, Sides, Style},
+    style::{
+      Affine, BackgroundClip, BackgroundImage, BorderImage, CssValue, InheritedStyle, Sides, Style,
+    },
   },
   rendering::{
     BorderProperties, Canvas, RenderContext, SizedShadow, collect_background_image_tiles,
-    create_background_image, draw_background_layers,
+    create_background_image, draw_background_layers, draw_blended_background, draw_border_image,
+    has_background_blend_mode,
   },
   resources::task::FetchTaskCollection,
 };
@@ -186,6 +189,18 @@ pub trait Node<N: Node<N>>: Send + Sync + Clone {
         }));
       };
 
+      if let CssValue::Value(Some(BackgroundImage::Url(url))) = &style.border_image_source {
+        collection.insert(url.clone());
+      };
+
+      if let CssValue::Value(BorderImage {
+        source: Some(BackgroundImage::Url(url)),
+        ..
+      }) = &style.border_image
+      {
+        collection.insert(url.clone());
+      };
+
       if let CssValue::Value(mask) = &style.mask {
         collection.insert_many(mask.iter().filter_map(|background| {
           if let BackgroundImage::Url(url) = &background.image {
@@ -247,7 +262,8 @@ pub trait Node<N: Node<N>>: Send + Sync + Clone {
 
     let border_radius = BorderProperties::from_context(context, layout.size, layout.border);
 
-    for shadow in box_shadow.iter() {
+    // The first shadow is painted on top.
+    for shadow in box_shadow.iter().rev() {
       if shadow.inset {
         continue;
       }
@@ -266,27 +282,25 @@ pub trait Node<N: Node<N>>: Send + Sync + Clone {
         *shadow,
         &context.sizing,
         context.current_color,
+        context.color_space,
         context.opacity,
         layout.size,
       );
 
       border_radius.append_mask_commands(
         &mut paths,
-        layout.size,
+        layout.size
+          + Size {
+            width: shadow.spread_radius * 2.0,
+            height: shadow.spread_radius * 2.0,
+          },
         Point {
           x: -shadow.spread_radius,
           y: -shadow.spread_radius,
         },
       );
 
-      shadow.draw_outset(
-        &mut canvas.image,
-        &mut canvas.mask_memory,
-        canvas.constrains.last(),
-        &paths,
-        context.transform,
-        Fill::EvenOdd.into(),
-      );
+      shadow.draw_outset(canvas, &paths, context.transform, Fill::EvenOdd.into());
     }
 
     Ok(())
@@ -302,7 +316,7 @@ pub trait Node<N: Node<N>>: Send + Sync + Clone {
     if let Some(box_shadow) = context.style.box_shadow.as_ref() {
       let border_radius = BorderProperties::from_context(context, layout.size, layout.border);
 
-      for shadow in box_shadow.iter() {
+      for shadow in box_shadow.iter().rev() {
         if !shadow.inset {
           continue;
         }
@@ -311,6 +325,7 @@ pub trait Node<N: Node<N>>: Send + Sync + Clone {
           *shadow,
           &context.sizing,
           context.current_color,
+          context.color_space,
           context.opacity,
           layout.size,
         );
@@ -327,16 +342,22 @@ pub trait Node<N: Node<N>>: Send + Sync + Clone {
     canvas: &mut Canvas,
     layout: Layout,
   ) -> Result<()> {
+    // The color is the bottom layer of the blended background, drawn with the images.
+    if has_background_blend_mode(&context.style) {
+      return Ok(());
+    }
+
     let mut radius = BorderProperties::from_context(context, layout.size, layout.border);
 
     match context.style.background_clip {
       BackgroundClip::BorderBox => {
         canvas.fill_color(
           layout.size,
-          context
-            .style
-            .background_color
-            .resolve(context.current_color, context.opacity),
+          context.style.background_color.resolve_in(
+            context.color_space,
+            context.current_color,
+            context.opacity,
+          ),
           radius,
           context.transform,
         );
@@ -349,10 +370,11 @@ pub trait Node<N: Node<N>>: Send + Sync + Clone {
             width: layout.size.width - layout.border.left - layout.border.right,
             height: layout.size.height - layout.border.top - layout.border.bottom,
           },
-          context
-            .style
-            .background_color
-            .resolve(context.current_color, context.opacity),
+          context.style.background_color.resolve_in(
+            context.color_space,
+            context.current_color,
+            context.opacity,
+          ),
           radius,
           Affine::translation(layout.border.left, layout.border.top) * context.transform,
         );
@@ -363,10 +385,11 @@ pub trait Node<N: Node<N>>: Send + Sync + Clone {
 
         canvas.fill_color(
           layout.content_box_size(),
-          context
-            .style
-            .background_color
-            .resolve(context.current_color, context.opacity),
+          context.style.background_color.resolve_in(
+            context.color_space,
+            context.current_color,
+            context.opacity,
+          ),
           radius,
           Affine::translation(
             layout.padding.left + layout.border.left,
@@ -387,6 +410,10 @@ pub trait Node<N: Node<N>>: Send + Sync + Clone {
     canvas: &mut Canvas,
     layout: Layout,
   ) -> Result<()> {
+    if has_background_blend_mode(&context.style) {
+      return draw_blended_background(context, canvas, layout);
+    }
+
     let mut border_radius = BorderProperties::from_context(context, layout.size, layout.border);
 
     match context.style.background_clip {
@@ -470,6 +497,11 @@ pub trait Node<N: Node<N>>: Send + Sync + Clone {
     canvas: &mut Canvas,
     layout: Layout,
   ) -> Result<()> {
+    // A border image replaces the border styles once it's loaded.
+    if draw_border_image(context, canvas, layout)? {
+      return Ok(());
+    }
+
     let fill_image = if context.style.background_clip == BackgroundClip::BorderArea {
       create_background_image(
         context,
@@ -8,11 +8,11 @@ use crate::{
   layout::{
     Viewport,
     inline::{
-      InlineContentKind, InlineItem, InlineLayoutStage, create_inline_constraint,
+      InlineContentKind, InlineItem, InlineLayout, InlineLayoutStage, create_inline_constraint,
       create_inline_layout, measure_inline_layout,
     },
     node::Node,
-    style::{InheritedStyle, Style, tw::TailwindValues},
+    style::{InheritedStyle, SizedFontStyle, Style, tw::TailwindValues},
   },
   rendering::{Canvas, MaxHeight, RenderContext, inline_drawing::draw_inline_layout},
 };
@@ -33,6 +33,39 @@ pub struct TextNode {
   pub tw: Option<TailwindValues>,
 }
 
+/// Creates the inline layout of a text content box for drawing.
+pub(crate) fn create_text_inline_layout<N: Node<N>>(
+  text: &str,
+  context: &RenderContext,
+  size: Size<f32>,
+  font_style: &SizedFontStyle,
+) -> (InlineLayout, String) {
+  let max_height = match font_style.parent.line_clamp.as_ref() {
+    Some(clamp) => Some(MaxHeight::HeightAndLines(size.height, clamp.count)),
+    None => Some(MaxHeight::Absolute(size.height)),
+  };
+
+  let inline_text: InlineItem<'_, '_, N> = InlineItem::Text {
+    text: text.into(),
+    context,
+  };
+
+  let (inline_layout, text, _) = create_inline_layout(
+    once(inline_text),
+    Size {
+      width: AvailableSpace::Definite(size.width),
+      height: AvailableSpace::Definite(size.height),
+    },
+    size.width,
+    max_height,
+    font_style,
+    context.global,
+    InlineLayoutStage::Draw,
+  );
+
+  (inline_layout, text)
+}
+
 impl<Nodes: Node<Nodes>> Node<Nodes> for TextNode {
   fn create_inherited_style(
     &mut self,
@@ -77,28 +110,8 @@ impl<Nodes: Node<Nodes>> Node<Nodes> for TextNode {
       return Ok(());
     }
 
-    let max_height = match font_style.parent.line_clamp.as_ref() {
-      Some(clamp) => Some(MaxHeight::HeightAndLines(size.height, clamp.count)),
-      None => Some(MaxHeight::Absolute(size.height)),
-    };
-
-    let inline_text: InlineItem<'_, '_, Nodes> = InlineItem::Text {
-      text: self.text.as_str().into(),
-      context,
-    };
-
-    let (inline_layout, _, _) = create_inline_layout(
-      once(inline_text),
-      Size {
-        width: AvailableSpace::Definite(size.width),
-        height: AvailableSpace::Definite(size.height),
-      },
-      size.width,
-      max_height,
-      &font_style,
-      context.global,
-      InlineLayoutStage::Draw,
-    );
+    let (inline_layout, _) =
+      create_text_inline_layout::<Nodes>(&self.text, context, size, &font_style);
 
     draw_inline_layout(context, canvas, layout, inline_layout, &font_style)?;
 
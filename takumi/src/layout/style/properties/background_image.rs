@@ -4,7 +4,8 @@ use cssparser::{Parser, match_ignore_ascii_case};
 use smallvec::SmallVec;
 
 use crate::layout::style::{
-  FromCss, LinearGradient, NoiseV1, ParseResult, RadialGradient, tw::TailwindPropertyParser,
+  ConicGradient, FromCss, LinearGradient, NoiseV1, ParseResult, RadialGradient,
+  tw::TailwindPropertyParser,
 };
 
 /// Background image variants supported by Takumi.
@@ -13,10 +14,12 @@ pub enum BackgroundImage {
   /// No background image.
   #[default]
   None,
-  /// CSS linear-gradient(...)
+  /// CSS linear-gradient(...) or repeating-linear-gradient(...)
   Linear(LinearGradient),
-  /// CSS radial-gradient(...)
+  /// CSS radial-gradient(...) or repeating-radial-gradient(...)
   Radial(RadialGradient),
+  /// CSS conic-gradient(...) or repeating-conic-gradient(...)
+  Conic(ConicGradient),
   /// Custom noise-v1(...)
   Noise(NoiseV1),
   /// Load external image resource.
@@ -43,8 +46,15 @@ impl<'i> FromCss<'i> for BackgroundImage {
 
     match_ignore_ascii_case! {&function,
       "none" => Ok(BackgroundImage::None),
-      "linear-gradient" => Ok(BackgroundImage::Linear(LinearGradient::from_css(input)?)),
-      "radial-gradient" => Ok(BackgroundImage::Radial(RadialGradient::from_css(input)?)),
+      "linear-gradient" | "repeating-linear-gradient" => {
+        Ok(BackgroundImage::Linear(LinearGradient::from_css(input)?))
+      },
+      "radial-gradient" | "repeating-radial-gradient" => {
+        Ok(BackgroundImage::Radial(RadialGradient::from_css(input)?))
+      },
+      "conic-gradient" | "repeating-conic-gradient" => {
+        Ok(BackgroundImage::Conic(ConicGradient::from_css(input)?))
+      },
       "noise-v1" => Ok(BackgroundImage::Noise(NoiseV1::from_css(input)?)),
       _ => Err(input.new_error_for_next_token()),
     }
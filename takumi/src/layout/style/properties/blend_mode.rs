@@ -0,0 +1,265 @@
+use cssparser::{Parser, Token, match_ignore_ascii_case};
+use smallvec::SmallVec;
+
+use crate::layout::style::{FromCss, ParseResult, tw::TailwindPropertyParser};
+
+/// Defines how an element's color blends with the colors behind it.
+///
+/// Used by the `mix-blend-mode` and `background-blend-mode` properties.
+/// Reference: <https://www.w3.org/TR/compositing-1/#blending>
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+  /// The source color replaces the backdrop color.
+  #[default]
+  Normal,
+  /// Multiplies the backdrop and source colors, which always darkens.
+  Multiply,
+  /// Multiplies the complements of the backdrop and source colors, which always lightens.
+  Screen,
+  /// Multiplies or screens the colors depending on the backdrop color.
+  Overlay,
+  /// Keeps the darker of the backdrop and source colors.
+  Darken,
+  /// Keeps the lighter of the backdrop and source colors.
+  Lighten,
+  /// Brightens the backdrop color to reflect the source color.
+  ColorDodge,
+  /// Darkens the backdrop color to reflect the source color.
+  ColorBurn,
+  /// Multiplies or screens the colors depending on the source color.
+  HardLight,
+  /// Darkens or lightens the colors depending on the source color.
+  SoftLight,
+  /// Subtracts the darker of the two colors from the lighter one.
+  Difference,
+  /// Like `difference`, but with lower contrast.
+  Exclusion,
+  /// The hue of the source color with the saturation and luminosity of the backdrop color.
+  Hue,
+  /// The saturation of the source color with the hue and luminosity of the backdrop color.
+  Saturation,
+  /// The hue and saturation of the source color with the luminosity of the backdrop color.
+  Color,
+  /// The luminosity of the source color with the hue and saturation of the backdrop color.
+  Luminosity,
+}
+
+impl<'i> FromCss<'i> for BlendMode {
+  fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
+    let location = input.current_source_location();
+    let ident = input.expect_ident()?;
+
+    match_ignore_ascii_case! { ident,
+      "normal" => Ok(BlendMode::Normal),
+      "multiply" => Ok(BlendMode::Multiply),
+      "screen" => Ok(BlendMode::Screen),
+      "overlay" => Ok(BlendMode::Overlay),
+      "darken" => Ok(BlendMode::Darken),
+      "lighten" => Ok(BlendMode::Lighten),
+      "color-dodge" => Ok(BlendMode::ColorDodge),
+      "color-burn" => Ok(BlendMode::ColorBurn),
+      "hard-light" => Ok(BlendMode::HardLight),
+      "soft-light" => Ok(BlendMode::SoftLight),
+      "difference" => Ok(BlendMode::Difference),
+      "exclusion" => Ok(BlendMode::Exclusion),
+      "hue" => Ok(BlendMode::Hue),
+      "saturation" => Ok(BlendMode::Saturation),
+      "color" => Ok(BlendMode::Color),
+      "luminosity" => Ok(BlendMode::Luminosity),
+      _ => Err(location.new_unexpected_token_error(Token::Ident(ident.clone()))),
+    }
+  }
+}
+
+impl TailwindPropertyParser for BlendMode {
+  fn parse_tw(token: &str) -> Option<Self> {
+    Self::from_str(token).ok()
+  }
+}
+
+/// A list of blend modes, one per background layer.
+pub type BlendModes = SmallVec<[BlendMode; 4]>;
+
+impl<'i> FromCss<'i> for BlendModes {
+  fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
+    let mut values = SmallVec::new();
+    values.push(BlendMode::from_css(input)?);
+
+    while input.expect_comma().is_ok() {
+      values.push(BlendMode::from_css(input)?);
+    }
+
+    Ok(values)
+  }
+}
+
+impl BlendMode {
+  /// Blends the backdrop and source colors, with channels in the `0.0..=1.0` range.
+  pub(crate) fn blend(self, backdrop: [f32; 3], source: [f32; 3]) -> [f32; 3] {
+    match self {
+      BlendMode::Normal => source,
+      BlendMode::Multiply => separable(backdrop, source, |b, s| b * s),
+      BlendMode::Screen => separable(backdrop, source, screen),
+      BlendMode::Overlay => separable(backdrop, source, |b, s| hard_light(s, b)),
+      BlendMode::Darken => separable(backdrop, source, f32::min),
+      BlendMode::Lighten => separable(backdrop, source, f32::max),
+      BlendMode::ColorDodge => separable(backdrop, source, |b, s| {
+        if b == 0.0 {
+          0.0
+        } else if s >= 1.0 {
+          1.0
+        } else {
+          (b / (1.0 - s)).min(1.0)
+        }
+      }),
+      BlendMode::ColorBurn => separable(backdrop, source, |b, s| {
+        if b >= 1.0 {
+          1.0
+        } else if s <= 0.0 {
+          0.0
+        } else {
+          1.0 - ((1.0 - b) / s).min(1.0)
+        }
+      }),
+      BlendMode::HardLight => separable(backdrop, source, hard_light),
+      BlendMode::SoftLight => separable(backdrop, source, |b, s| {
+        if s <= 0.5 {
+          b - (1.0 - 2.0 * s) * b * (1.0 - b)
+        } else {
+          let d = if b <= 0.25 {
+            ((16.0 * b - 12.0) * b + 4.0) * b
+          } else {
+            b.sqrt()
+          };
+
+          b + (2.0 * s - 1.0) * (d - b)
+        }
+      }),
+      BlendMode::Difference => separable(backdrop, source, |b, s| (b - s).abs()),
+      BlendMode::Exclusion => separable(backdrop, source, |b, s| b + s - 2.0 * b * s),
+      BlendMode::Hue => set_lum(set_sat(source, sat(backdrop)), lum(backdrop)),
+      BlendMode::Saturation => set_lum(set_sat(backdrop, sat(source)), lum(backdrop)),
+      BlendMode::Color => set_lum(source, lum(backdrop)),
+      BlendMode::Luminosity => set_lum(backdrop, lum(source)),
+    }
+  }
+}
+
+fn separable(backdrop: [f32; 3], source: [f32; 3], f: impl Fn(f32, f32) -> f32) -> [f32; 3] {
+  std::array::from_fn(|channel| f(backdrop[channel], source[channel]))
+}
+
+fn screen(backdrop: f32, source: f32) -> f32 {
+  backdrop + source - backdrop * source
+}
+
+fn hard_light(backdrop: f32, source: f32) -> f32 {
+  if source <= 0.5 {
+    backdrop * 2.0 * source
+  } else {
+    screen(backdrop, 2.0 * source - 1.0)
+  }
+}
+
+fn lum([r, g, b]: [f32; 3]) -> f32 {
+  0.3 * r + 0.59 * g + 0.11 * b
+}
+
+fn clip_color(color: [f32; 3]) -> [f32; 3] {
+  let l = lum(color);
+  let n = color[0].min(color[1]).min(color[2]);
+  let x = color[0].max(color[1]).max(color[2]);
+
+  color.map(|c| {
+    let mut c = c;
+
+    if n < 0.0 {
+      c = l + (c - l) * l / (l - n);
+    }
+
+    if x > 1.0 {
+      c = l + (c - l) * (1.0 - l) / (x - l);
+    }
+
+    c
+  })
+}
+
+fn set_lum(color: [f32; 3], l: f32) -> [f32; 3] {
+  let d = l - lum(color);
+
+  clip_color(color.map(|c| c + d))
+}
+
+fn sat([r, g, b]: [f32; 3]) -> f32 {
+  r.max(g).max(b) - r.min(g).min(b)
+}
+
+fn set_sat(color: [f32; 3], s: f32) -> [f32; 3] {
+  let max = color[0].max(color[1]).max(color[2]);
+  let min = color[0].min(color[1]).min(color[2]);
+
+  if max <= min {
+    return [0.0; 3];
+  }
+
+  color.map(|c| (c - min) * s / (max - min))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_blend_mode() {
+    assert_eq!(
+      BlendMode::from_str("color-dodge"),
+      Ok(BlendMode::ColorDodge)
+    );
+    assert_eq!(BlendMode::from_str("Multiply"), Ok(BlendMode::Multiply));
+    assert!(BlendMode::from_str("plus-darker").is_err());
+  }
+
+  #[test]
+  fn test_parse_blend_modes() {
+    assert_eq!(
+      BlendModes::from_str("multiply, screen"),
+      Ok(smallvec::smallvec![BlendMode::Multiply, BlendMode::Screen])
+    );
+  }
+
+  #[test]
+  fn test_separable_blend_modes() {
+    let backdrop = [0.25, 0.5, 1.0];
+    let source = [0.5, 0.5, 0.5];
+
+    assert_eq!(
+      BlendMode::Multiply.blend(backdrop, source),
+      [0.125, 0.25, 0.5]
+    );
+    assert_eq!(
+      BlendMode::Screen.blend(backdrop, source),
+      [0.625, 0.75, 1.0]
+    );
+    assert_eq!(
+      BlendMode::Difference.blend(backdrop, source),
+      [0.25, 0.0, 0.5]
+    );
+    assert_eq!(BlendMode::Darken.blend(backdrop, source), [0.25, 0.5, 0.5]);
+  }
+
+  #[test]
+  fn test_non_separable_blend_modes() {
+    let red = [1.0, 0.0, 0.0];
+    let gray = [0.5, 0.5, 0.5];
+
+    // A gray source has no saturation, so the result keeps the backdrop luminosity without any hue.
+    let saturation = BlendMode::Saturation.blend(red, gray);
+    assert!(saturation.iter().all(|c| (c - lum(red)).abs() < 1e-6));
+
+    // The luminosity of the backdrop is kept when applying the source color.
+    let color = BlendMode::Color.blend(gray, red);
+    assert!((lum(color) - lum(gray)).abs() < 1e-6);
+    assert!(color[0] > color[1] && color[1] == color[2]);
+  }
+}
@@ -2,19 +2,45 @@ use cssparser::{Parser, Token, match_ignore_ascii_case};
 
 use crate::layout::style::{ColorInput, FromCss, ParseResult, properties::Length};
 
-/// Represents border style options (currently only solid is supported).
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Represents the style of a border side.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum BorderStyle {
-  /// Solid border style.
+  /// No border, the width of the side is computed as zero.
+  None,
+  /// Like `none`, the width of the side is computed as zero.
+  Hidden,
+  /// A series of round dots.
+  Dotted,
+  /// A series of square-ended dashes.
+  Dashed,
+  /// A single solid line.
+  #[default]
   Solid,
+  /// Two solid lines, each a third of the width, with a gap between them.
+  Double,
+  /// Looks carved into the surface, with the outer half darker on the top and left sides.
+  Groove,
+  /// Looks extruded from the surface, the opposite of `groove`.
+  Ridge,
+  /// Looks embedded into the surface, with darker top and left sides.
+  Inset,
+  /// Looks embossed from the surface, the opposite of `inset`.
+  Outset,
 }
 
-/// Parsed `border` value.
+impl BorderStyle {
+  /// Whether the side is drawn, otherwise its width is computed as zero.
+  pub(crate) fn is_visible(self) -> bool {
+    !matches!(self, BorderStyle::None | BorderStyle::Hidden)
+  }
+}
+
+/// Parsed `border` or `outline` value.
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Border {
   /// Border width.
   pub width: Option<Length>,
-  /// Border style (currently only solid is supported).
+  /// Border style.
   pub style: Option<BorderStyle>,
   /// Optional border color.
   pub color: Option<ColorInput>,
@@ -61,7 +87,16 @@ impl<'i> FromCss<'i> for BorderStyle {
   fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
     let ident = input.expect_ident()?;
     match_ignore_ascii_case! {ident,
+      "none" => Ok(BorderStyle::None),
+      "hidden" => Ok(BorderStyle::Hidden),
+      "dotted" => Ok(BorderStyle::Dotted),
+      "dashed" => Ok(BorderStyle::Dashed),
       "solid" => Ok(BorderStyle::Solid),
+      "double" => Ok(BorderStyle::Double),
+      "groove" => Ok(BorderStyle::Groove),
+      "ridge" => Ok(BorderStyle::Ridge),
+      "inset" => Ok(BorderStyle::Inset),
+      "outset" => Ok(BorderStyle::Outset),
       _ => {
         let token = Token::Ident(ident.clone());
         Err(input.new_basic_unexpected_token_error(token).into())
@@ -72,7 +107,7 @@ impl<'i> FromCss<'i> for BorderStyle {
 
 #[cfg(test)]
 mod tests {
-  use crate::layout::style::Color;
+  use crate::layout::style::{Color, Sides};
 
   use super::*;
 
@@ -81,9 +116,14 @@ mod tests {
     assert_eq!(BorderStyle::from_str("solid"), Ok(BorderStyle::Solid));
   }
 
+  #[test]
+  fn test_parse_border_style_dashed() {
+    assert_eq!(BorderStyle::from_str("dashed"), Ok(BorderStyle::Dashed));
+  }
+
   #[test]
   fn test_parse_border_style_invalid() {
-    assert!(BorderStyle::from_str("dashed").is_err());
+    assert!(BorderStyle::from_str("wavy").is_err());
   }
 
   #[test]
@@ -208,7 +248,7 @@ mod tests {
 
   #[test]
   fn test_parse_border_invalid_style() {
-    assert!(Border::from_str("2px dashed red").is_err());
+    assert!(Border::from_str("2px wavy red").is_err());
   }
 
   #[test]
@@ -237,4 +277,29 @@ mod tests {
   fn test_border_value_from_invalid_css() {
     assert!(Border::from_str("invalid border").is_err());
   }
+
+  #[test]
+  fn test_parse_border_none() {
+    assert_eq!(
+      Border::from_str("none"),
+      Ok(Border {
+        width: None,
+        style: Some(BorderStyle::None),
+        color: None,
+      })
+    );
+  }
+
+  #[test]
+  fn test_parse_border_style_sides() {
+    assert_eq!(
+      Sides::<BorderStyle>::from_str("dashed double"),
+      Ok(Sides([
+        BorderStyle::Dashed,
+        BorderStyle::Double,
+        BorderStyle::Dashed,
+        BorderStyle::Double,
+      ]))
+    );
+  }
 }
@@ -0,0 +1,257 @@
+use cssparser::{BasicParseErrorKind, Parser, Token, match_ignore_ascii_case};
+
+use crate::layout::style::{BackgroundImage, FromCss, Length, ParseResult, Sides};
+
+/// A value of `border-image-slice`, `border-image-width` or `border-image-outset`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BorderImageLength {
+  /// A multiple of the border width, or pixels of the image for `border-image-slice`.
+  Number(f32),
+  /// A length or percentage, `auto` uses the size of the slice for `border-image-width`.
+  Length(Length),
+}
+
+impl<'i> FromCss<'i> for BorderImageLength {
+  fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
+    if let Ok(number) = input.try_parse(Parser::expect_number) {
+      return Ok(BorderImageLength::Number(number));
+    }
+
+    Ok(BorderImageLength::Length(Length::from_css(input)?))
+  }
+}
+
+/// Parsed `border-image-slice` value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BorderImageSlice {
+  /// Inward offsets from the edges of the image, in pixels of the image or percentages of its size.
+  pub offsets: Sides<BorderImageLength>,
+  /// Whether the middle part of the image is drawn over the padding box.
+  pub fill: bool,
+}
+
+impl Default for BorderImageSlice {
+  fn default() -> Self {
+    Self {
+      offsets: Sides([BorderImageLength::Length(Length::Percentage(100.0)); 4]),
+      fill: false,
+    }
+  }
+}
+
+impl<'i> FromCss<'i> for BorderImageSlice {
+  fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
+    let mut fill = input
+      .try_parse(|input| input.expect_ident_matching("fill"))
+      .is_ok();
+
+    let offsets = Sides::<BorderImageLength>::from_css(input)?;
+
+    // Slices are measured in the image, so only numbers and percentages are allowed.
+    if offsets.0.iter().any(|offset| {
+      matches!(offset, BorderImageLength::Length(length) if !matches!(length, Length::Percentage(_)))
+    }) {
+      return Err(input.new_error(BasicParseErrorKind::QualifiedRuleInvalid));
+    }
+
+    if !fill {
+      fill = input
+        .try_parse(|input| input.expect_ident_matching("fill"))
+        .is_ok();
+    }
+
+    Ok(BorderImageSlice { offsets, fill })
+  }
+}
+
+/// Per-axis repeat style of the edges and middle of a border image.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BorderImageRepeatStyle {
+  /// Stretch the image to fill the area
+  #[default]
+  Stretch,
+  /// Tile the image from the center of the area, clipping the tiles at the ends
+  Repeat,
+  /// Scale the tiles so an integer number fits exactly
+  Round,
+  /// Distribute leftover space evenly around the tiles that fit
+  Space,
+}
+
+impl<'i> FromCss<'i> for BorderImageRepeatStyle {
+  fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
+    let location = input.current_source_location();
+    let ident = input.expect_ident()?;
+
+    match_ignore_ascii_case! {
+      &ident,
+      "stretch" => Ok(BorderImageRepeatStyle::Stretch),
+      "repeat" => Ok(BorderImageRepeatStyle::Repeat),
+      "round" => Ok(BorderImageRepeatStyle::Round),
+      "space" => Ok(BorderImageRepeatStyle::Space),
+      _ => Err(location.new_basic_unexpected_token_error(Token::Ident(ident.clone())).into()),
+    }
+  }
+}
+
+/// Combined repeat for the horizontal and vertical axes.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BorderImageRepeat(pub BorderImageRepeatStyle, pub BorderImageRepeatStyle);
+
+impl<'i> FromCss<'i> for BorderImageRepeat {
+  fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
+    let x = BorderImageRepeatStyle::from_css(input)?;
+    let y = input
+      .try_parse(BorderImageRepeatStyle::from_css)
+      .unwrap_or(x);
+
+    Ok(BorderImageRepeat(x, y))
+  }
+}
+
+/// Parsed `border-image` value.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BorderImage {
+  /// Border image source.
+  pub source: Option<BackgroundImage>,
+  /// Border image slice.
+  pub slice: Option<BorderImageSlice>,
+  /// Border image width.
+  pub width: Option<Sides<BorderImageLength>>,
+  /// Border image outset.
+  pub outset: Option<Sides<BorderImageLength>>,
+  /// Border image repeat.
+  pub repeat: Option<BorderImageRepeat>,
+}
+
+impl<'i> FromCss<'i> for BorderImage {
+  fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
+    let mut border_image = BorderImage::default();
+
+    loop {
+      if input.is_exhausted() {
+        break;
+      }
+
+      if border_image.source.is_none() {
+        if input
+          .try_parse(|input| input.expect_ident_matching("none"))
+          .is_ok()
+        {
+          border_image.source = Some(BackgroundImage::None);
+          continue;
+        }
+
+        if let Ok(source) = input.try_parse(BackgroundImage::from_css) {
+          border_image.source = Some(source);
+          continue;
+        }
+      }
+
+      if border_image.slice.is_none()
+        && let Ok(slice) = input.try_parse(BorderImageSlice::from_css)
+      {
+        border_image.slice = Some(slice);
+
+        // `slice / width`, `slice / width / outset` or `slice / / outset`.
+        if input.try_parse(|input| input.expect_delim('/')).is_ok() {
+          border_image.width = input.try_parse(Sides::from_css).ok();
+
+          if input.try_parse(|input| input.expect_delim('/')).is_ok() {
+            border_image.outset = Some(Sides::from_css(input)?);
+          } else if border_image.width.is_none() {
+            return Err(input.new_error_for_next_token());
+          }
+        }
+
+        continue;
+      }
+
+      if border_image.repeat.is_none()
+        && let Ok(repeat) = input.try_parse(BorderImageRepeat::from_css)
+      {
+        border_image.repeat = Some(repeat);
+        continue;
+      }
+
+      return Err(input.new_error_for_next_token());
+    }
+
+    Ok(border_image)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_border_image_slice() {
+    assert_eq!(
+      BorderImageSlice::from_str("30 20% fill"),
+      Ok(BorderImageSlice {
+        offsets: Sides([
+          BorderImageLength::Number(30.0),
+          BorderImageLength::Length(Length::Percentage(20.0)),
+          BorderImageLength::Number(30.0),
+          BorderImageLength::Length(Length::Percentage(20.0)),
+        ]),
+        fill: true,
+      })
+    );
+  }
+
+  #[test]
+  fn test_parse_border_image_slice_rejects_lengths() {
+    assert!(BorderImageSlice::from_str("10px").is_err());
+  }
+
+  #[test]
+  fn test_parse_border_image_repeat() {
+    assert_eq!(
+      BorderImageRepeat::from_str("round space"),
+      Ok(BorderImageRepeat(
+        BorderImageRepeatStyle::Round,
+        BorderImageRepeatStyle::Space
+      ))
+    );
+  }
+
+  #[test]
+  fn test_parse_border_image_shorthand() {
+    assert_eq!(
+      BorderImage::from_str("url(frame.png) 30 round"),
+      Ok(BorderImage {
+        source: Some(BackgroundImage::Url("frame.png".into())),
+        slice: Some(BorderImageSlice {
+          offsets: Sides([BorderImageLength::Number(30.0); 4]),
+          fill: false,
+        }),
+        repeat: Some(BorderImageRepeat(
+          BorderImageRepeatStyle::Round,
+          BorderImageRepeatStyle::Round
+        )),
+        ..Default::default()
+      })
+    );
+  }
+
+  #[test]
+  fn test_parse_border_image_shorthand_width_outset() -> ParseResult<'static, ()> {
+    assert_eq!(
+      BorderImage::from_str("linear-gradient(red, blue) 10 / 4px / 2"),
+      Ok(BorderImage {
+        source: Some(BackgroundImage::from_str("linear-gradient(red, blue)")?),
+        slice: Some(BorderImageSlice {
+          offsets: Sides([BorderImageLength::Number(10.0); 4]),
+          fill: false,
+        }),
+        width: Some(Sides([BorderImageLength::Length(Length::Px(4.0)); 4])),
+        outset: Some(Sides([BorderImageLength::Number(2.0); 4])),
+        ..Default::default()
+      })
+    );
+
+    Ok(())
+  }
+}
@@ -1,6 +1,6 @@
 use cssparser::{Parser, Token, match_ignore_ascii_case};
 use taffy::{AbsoluteAxis, Point, Rect, Size};
-use zeno::{Fill, PathBuilder, PathData, Placement};
+use zeno::{Command, Fill, PathBuilder, PathData, Placement};
 
 use crate::{
   layout::style::{Axis, Color, FromCss, Length, ParseResult, Sides, SpacePair},
@@ -139,6 +139,17 @@ impl BasicShape {
     size: Size<f32>,
     mask_memory: &'m mut MaskMemory,
   ) -> (&'m [u8], Placement) {
+    let paths = self.to_paths(context, size);
+
+    mask_memory.render(
+      &paths,
+      Some(context.transform),
+      Some(Fill::from(self.fill_rule().unwrap_or(context.style.clip_rule)).into()),
+    )
+  }
+
+  /// Builds the path commands of the shape, relative to the border box.
+  pub(crate) fn to_paths(&self, context: &RenderContext, size: Size<f32>) -> Vec<Command> {
     let mut paths = Vec::new();
 
     match self {
@@ -169,6 +180,7 @@ impl BasicShape {
               )
             })
             .unwrap_or_default(),
+          style: Default::default(),
         };
 
         border.append_mask_commands(
@@ -220,11 +232,7 @@ impl BasicShape {
       }
     }
 
-    mask_memory.render(
-      &paths,
-      Some(context.transform),
-      Some(Fill::from(self.fill_rule().unwrap_or(context.style.clip_rule)).into()),
-    )
+    paths
   }
 }
 
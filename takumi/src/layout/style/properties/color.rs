@@ -1,16 +1,16 @@
 use std::fmt::Display;
 
-use color::{Srgb, parse_color};
+use color::{AlphaColor, ColorSpaceTag, DisplayP3, DynamicColor, HueDirection, Srgb, parse_color};
 use cssparser::{
-  Parser, Token,
+  BasicParseErrorKind, Parser, Token,
   color::{parse_hash_color, parse_named_color},
   match_ignore_ascii_case,
 };
-use image::Rgba;
+use image::{Rgba, RgbaImage};
 
 use crate::{
   layout::style::{FromCss, ParseResult, tw::TailwindPropertyParser},
-  rendering::fast_div_255,
+  rendering::{fast_div_255, linear_to_srgb, srgb_to_linear},
 };
 
 /// Represents a color with 8-bit RGBA components.
@@ -24,6 +24,142 @@ pub enum ColorInput<const DEFAULT_CURRENT_COLOR: bool = true> {
   CurrentColor,
   /// A color value.
   Value(Color),
+  /// A color that may be outside of the sRGB gamut, like `color(display-p3 1 0 0)`.
+  Wide(WideColor),
+}
+
+/// The RGB color space colors are encoded in.
+///
+/// Both spaces share the sRGB transfer function, only their primaries differ.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+  /// The sRGB color space.
+  #[default]
+  Srgb,
+  /// The Display P3 color space, which covers about 50% more colors than sRGB.
+  DisplayP3,
+}
+
+/// Converts linear-light sRGB to linear-light Display P3.
+const SRGB_TO_DISPLAY_P3: [[f32; 3]; 3] = [
+  [0.822_462_1, 0.177_538, 0.0],
+  [0.033_194_2, 0.966_805_8, 0.0],
+  [0.017_082_7, 0.072_397_4, 0.910_519_9],
+];
+
+/// Converts linear-light Display P3 to linear-light sRGB.
+const DISPLAY_P3_TO_SRGB: [[f32; 3]; 3] = [
+  [1.224_940_2, -0.224_940_2, 0.0],
+  [-0.042_056_9, 1.042_056_9, 0.0],
+  [-0.019_637_6, -0.078_636_1, 1.098_273_7],
+];
+
+impl ColorSpace {
+  /// Converts an sRGB color to this color space.
+  pub fn convert_srgb(self, color: Color) -> Color {
+    match self {
+      ColorSpace::Srgb => color,
+      ColorSpace::DisplayP3 => {
+        let [r, g, b, a] = color.0;
+        let [r, g, b] = transform_linear(
+          &SRGB_TO_DISPLAY_P3,
+          [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b)],
+        );
+
+        Color([linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b), a])
+      }
+    }
+  }
+
+  /// Converts every pixel of an sRGB image to this color space.
+  pub(crate) fn convert_srgb_image(self, image: &mut RgbaImage) {
+    if self == ColorSpace::Srgb {
+      return;
+    }
+
+    for pixel in image.pixels_mut() {
+      *pixel = self.convert_srgb(Color(pixel.0)).into();
+    }
+  }
+
+  /// Converts a color encoded in this color space to a color of the `color` crate.
+  pub(crate) fn to_dynamic(self, color: Color) -> DynamicColor {
+    let components = color.0.map(|channel| channel as f32 / 255.0);
+
+    match self {
+      ColorSpace::Srgb => DynamicColor::from_alpha_color(AlphaColor::<Srgb>::new(components)),
+      ColorSpace::DisplayP3 => {
+        DynamicColor::from_alpha_color(AlphaColor::<DisplayP3>::new(components))
+      }
+    }
+  }
+
+  /// Converts a color of the `color` crate to 8-bit channels of this color space,
+  /// clipping the channels outside of its gamut.
+  pub(crate) fn color_from_dynamic(self, color: DynamicColor) -> Color {
+    match self {
+      ColorSpace::Srgb => Color(color.to_alpha_color::<Srgb>().to_rgba8().to_u8_array()),
+      ColorSpace::DisplayP3 => WideColor {
+        color_space: ColorSpace::DisplayP3,
+        components: color.to_alpha_color::<DisplayP3>().components,
+      }
+      .to_color(ColorSpace::DisplayP3),
+    }
+  }
+}
+
+/// Multiplies linear-light RGB channels with a conversion matrix.
+fn transform_linear(matrix: &[[f32; 3]; 3], rgb: [f32; 3]) -> [f32; 3] {
+  matrix.map(|row| row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2])
+}
+
+/// Applies the sRGB transfer function, which Display P3 shares, to a linear-light channel.
+fn encode_transfer(linear: f32) -> f32 {
+  if linear.abs() <= 0.003_130_8 {
+    linear * 12.92
+  } else {
+    linear.signum() * (1.055 * linear.abs().powf(1.0 / 2.4) - 0.055)
+  }
+}
+
+/// Reverses the sRGB transfer function, which Display P3 shares.
+fn decode_transfer(channel: f32) -> f32 {
+  if channel.abs() <= 0.040_45 {
+    channel / 12.92
+  } else {
+    channel.signum() * ((channel.abs() + 0.055) / 1.055).powf(2.4)
+  }
+}
+
+/// A color with floating point components in the color space it was specified in,
+/// so colors outside of the sRGB gamut are kept until they are drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WideColor {
+  /// The color space the components are encoded in.
+  pub color_space: ColorSpace,
+  /// The red, green and blue channels in the `0.0..=1.0` range when inside the gamut of the color space,
+  /// followed by straight alpha.
+  pub components: [f32; 4],
+}
+
+impl WideColor {
+  /// Converts the color to 8-bit channels of `color_space`, clipping the channels outside of its gamut.
+  pub fn to_color(self, color_space: ColorSpace) -> Color {
+    let [r, g, b, a] = self.components;
+
+    let [r, g, b] = if self.color_space == color_space {
+      [r, g, b]
+    } else {
+      let matrix = match color_space {
+        ColorSpace::Srgb => &DISPLAY_P3_TO_SRGB,
+        ColorSpace::DisplayP3 => &SRGB_TO_DISPLAY_P3,
+      };
+
+      transform_linear(matrix, [r, g, b].map(decode_transfer)).map(encode_transfer)
+    };
+
+    Color([r, g, b, a].map(|channel| (channel * 255.0).round().clamp(0.0, 255.0) as u8))
+  }
 }
 
 impl<const DEFAULT_CURRENT_COLOR: bool> Default for ColorInput<DEFAULT_CURRENT_COLOR> {
@@ -37,10 +173,18 @@ impl<const DEFAULT_CURRENT_COLOR: bool> Default for ColorInput<DEFAULT_CURRENT_C
 }
 
 impl<const DEFAULT_CURRENT_COLOR: bool> ColorInput<DEFAULT_CURRENT_COLOR> {
-  /// Resolves the color input to a color.
+  /// Resolves the color input to an sRGB color.
   pub fn resolve(self, current_color: Color, opacity: u8) -> Color {
+    self.resolve_in(ColorSpace::Srgb, current_color, opacity)
+  }
+
+  /// Resolves the color input to a color encoded in `color_space`.
+  ///
+  /// `current_color` is expected to be resolved in `color_space` already.
+  pub fn resolve_in(self, color_space: ColorSpace, current_color: Color, opacity: u8) -> Color {
     match self {
-      ColorInput::Value(color) => color.with_opacity(opacity),
+      ColorInput::Value(color) => color_space.convert_srgb(color).with_opacity(opacity),
+      ColorInput::Wide(color) => color.to_color(color_space).with_opacity(opacity),
       ColorInput::CurrentColor => current_color.with_opacity(opacity),
     }
   }
@@ -300,6 +444,178 @@ impl Color {
   }
 }
 
+/// A color space colors can be mixed in, as in `color-mix(in oklch, ...)` or `linear-gradient(in oklab, ...)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpolationColorSpace {
+  /// The `srgb` color space.
+  Srgb,
+  /// The `srgb-linear` color space.
+  SrgbLinear,
+  /// The `display-p3` color space.
+  DisplayP3,
+  /// The `a98-rgb` color space.
+  A98Rgb,
+  /// The `prophoto-rgb` color space.
+  ProphotoRgb,
+  /// The `rec2020` color space.
+  Rec2020,
+  /// The `lab` color space.
+  Lab,
+  /// The `oklab` color space.
+  Oklab,
+  /// The `xyz-d50` color space.
+  XyzD50,
+  /// The `xyz-d65` color space, also spelled `xyz`.
+  XyzD65,
+  /// The `hsl` color space.
+  Hsl,
+  /// The `hwb` color space.
+  Hwb,
+  /// The `lch` color space.
+  Lch,
+  /// The `oklch` color space.
+  Oklch,
+}
+
+impl InterpolationColorSpace {
+  /// Whether the color space has a hue component, which makes the hue interpolation apply.
+  pub fn is_polar(self) -> bool {
+    matches!(
+      self,
+      InterpolationColorSpace::Hsl
+        | InterpolationColorSpace::Hwb
+        | InterpolationColorSpace::Lch
+        | InterpolationColorSpace::Oklch
+    )
+  }
+
+  /// Returns the tag of the color space in the `color` crate.
+  pub(crate) fn tag(self) -> ColorSpaceTag {
+    match self {
+      InterpolationColorSpace::Srgb => ColorSpaceTag::Srgb,
+      InterpolationColorSpace::SrgbLinear => ColorSpaceTag::LinearSrgb,
+      InterpolationColorSpace::DisplayP3 => ColorSpaceTag::DisplayP3,
+      InterpolationColorSpace::A98Rgb => ColorSpaceTag::A98Rgb,
+      InterpolationColorSpace::ProphotoRgb => ColorSpaceTag::ProphotoRgb,
+      InterpolationColorSpace::Rec2020 => ColorSpaceTag::Rec2020,
+      InterpolationColorSpace::Lab => ColorSpaceTag::Lab,
+      InterpolationColorSpace::Oklab => ColorSpaceTag::Oklab,
+      InterpolationColorSpace::XyzD50 => ColorSpaceTag::XyzD50,
+      InterpolationColorSpace::XyzD65 => ColorSpaceTag::XyzD65,
+      InterpolationColorSpace::Hsl => ColorSpaceTag::Hsl,
+      InterpolationColorSpace::Hwb => ColorSpaceTag::Hwb,
+      InterpolationColorSpace::Lch => ColorSpaceTag::Lch,
+      InterpolationColorSpace::Oklch => ColorSpaceTag::Oklch,
+    }
+  }
+}
+
+impl<'i> FromCss<'i> for InterpolationColorSpace {
+  fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
+    let location = input.current_source_location();
+    let ident = input.expect_ident()?;
+
+    match_ignore_ascii_case! {&ident,
+      "srgb" => Ok(InterpolationColorSpace::Srgb),
+      "srgb-linear" => Ok(InterpolationColorSpace::SrgbLinear),
+      "display-p3" => Ok(InterpolationColorSpace::DisplayP3),
+      "a98-rgb" => Ok(InterpolationColorSpace::A98Rgb),
+      "prophoto-rgb" => Ok(InterpolationColorSpace::ProphotoRgb),
+      "rec2020" => Ok(InterpolationColorSpace::Rec2020),
+      "lab" => Ok(InterpolationColorSpace::Lab),
+      "oklab" => Ok(InterpolationColorSpace::Oklab),
+      "xyz-d50" => Ok(InterpolationColorSpace::XyzD50),
+      "xyz" | "xyz-d65" => Ok(InterpolationColorSpace::XyzD65),
+      "hsl" => Ok(InterpolationColorSpace::Hsl),
+      "hwb" => Ok(InterpolationColorSpace::Hwb),
+      "lch" => Ok(InterpolationColorSpace::Lch),
+      "oklch" => Ok(InterpolationColorSpace::Oklch),
+      _ => Err(location.new_basic_unexpected_token_error(Token::Ident(ident.clone())).into()),
+    }
+  }
+}
+
+/// Which way around the hue wheel hues are interpolated in polar color spaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HueInterpolation {
+  /// Take the shorter arc between the hues.
+  #[default]
+  Shorter,
+  /// Take the longer arc between the hues.
+  Longer,
+  /// Go through increasing hue angles.
+  Increasing,
+  /// Go through decreasing hue angles.
+  Decreasing,
+}
+
+impl HueInterpolation {
+  /// Returns the hue direction in the `color` crate.
+  pub(crate) fn direction(self) -> HueDirection {
+    match self {
+      HueInterpolation::Shorter => HueDirection::Shorter,
+      HueInterpolation::Longer => HueDirection::Longer,
+      HueInterpolation::Increasing => HueDirection::Increasing,
+      HueInterpolation::Decreasing => HueDirection::Decreasing,
+    }
+  }
+}
+
+impl<'i> FromCss<'i> for HueInterpolation {
+  fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
+    let location = input.current_source_location();
+    let ident = input.expect_ident()?.clone();
+
+    let hue = match_ignore_ascii_case! {&ident,
+      "shorter" => HueInterpolation::Shorter,
+      "longer" => HueInterpolation::Longer,
+      "increasing" => HueInterpolation::Increasing,
+      "decreasing" => HueInterpolation::Decreasing,
+      _ => return Err(location.new_basic_unexpected_token_error(Token::Ident(ident)).into()),
+    };
+
+    input.expect_ident_matching("hue")?;
+
+    Ok(hue)
+  }
+}
+
+/// Parsed `in <color-space> [<hue> hue]?` value, the color space and hue interpolation colors are mixed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorInterpolationMethod {
+  /// The color space colors are mixed in.
+  pub color_space: InterpolationColorSpace,
+  /// How hues are mixed, only used by polar color spaces.
+  pub hue: HueInterpolation,
+}
+
+impl<'i> FromCss<'i> for ColorInterpolationMethod {
+  fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
+    input.expect_ident_matching("in")?;
+
+    let color_space = InterpolationColorSpace::from_css(input)?;
+
+    let hue = if color_space.is_polar() {
+      input
+        .try_parse(HueInterpolation::from_css)
+        .unwrap_or_default()
+    } else {
+      HueInterpolation::default()
+    };
+
+    Ok(ColorInterpolationMethod { color_space, hue })
+  }
+}
+
+impl ColorInterpolationMethod {
+  /// Mixes `from` with `amount` of `to`, with premultiplied alpha.
+  pub(crate) fn mix(self, from: DynamicColor, to: DynamicColor, amount: f32) -> DynamicColor {
+    from
+      .interpolate(to, self.color_space.tag(), self.hue.direction())
+      .eval(amount)
+  }
+}
+
 impl<'i, const DEFAULT_CURRENT_COLOR: bool> FromCss<'i> for ColorInput<DEFAULT_CURRENT_COLOR> {
   fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
     if input
@@ -309,14 +625,356 @@ impl<'i, const DEFAULT_CURRENT_COLOR: bool> FromCss<'i> for ColorInput<DEFAULT_C
       return Ok(ColorInput::CurrentColor);
     }
 
+    if let Ok(color) = input.try_parse(WideColor::from_css) {
+      return Ok(ColorInput::Wide(color));
+    }
+
     Ok(ColorInput::Value(Color::from_css(input)?))
   }
 }
 
+impl<'i> FromCss<'i> for WideColor {
+  fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
+    let color = parse_color_function(input)?;
+
+    // `rgb()`, `hsl()` and `hwb()` can only express sRGB colors.
+    if matches!(
+      color.cs,
+      ColorSpaceTag::Srgb | ColorSpaceTag::Hsl | ColorSpaceTag::Hwb
+    ) {
+      return Err(input.new_error(BasicParseErrorKind::QualifiedRuleInvalid));
+    }
+
+    Ok(WideColor {
+      color_space: ColorSpace::DisplayP3,
+      components: color.to_alpha_color::<DisplayP3>().components,
+    })
+  }
+}
+
+/// Parses a color function like `rgb()` or `color()`, in any color space.
+///
+/// `color-mix()` and the relative color syntax, like `oklch(from red l c h)`, are resolved here too.
+fn parse_color_function<'i>(input: &mut Parser<'i, '_>) -> ParseResult<'i, DynamicColor> {
+  let location = input.current_source_location();
+  let position = input.position();
+  let token = input.next()?;
+
+  let Token::Function(name) = token else {
+    return Err(
+      location
+        .new_basic_unexpected_token_error(token.clone())
+        .into(),
+    );
+  };
+
+  // Have to clone to persist token, and allow input to be borrowed
+  let name = name.clone();
+  let token = token.clone();
+
+  input.parse_nested_block(|input| {
+    if name.eq_ignore_ascii_case("color-mix") {
+      return parse_color_mix(input);
+    }
+
+    if input
+      .try_parse(|input| input.expect_ident_matching("from"))
+      .is_ok()
+    {
+      return parse_relative_color(&name, input);
+    }
+
+    while input.next().is_ok() {}
+
+    // Slice from the function name till before the closing parenthesis
+    let body = input.slice_from(position);
+
+    let mut function = body.to_string();
+
+    // Add closing parenthesis
+    function.push(')');
+
+    parse_color(&function).map_err(|_| location.new_basic_unexpected_token_error(token).into())
+  })
+}
+
+/// Parses a color that doesn't depend on `currentColor`,
+/// as an input of `color-mix()` or the origin of a relative color.
+fn parse_dynamic_color<'i>(input: &mut Parser<'i, '_>) -> ParseResult<'i, DynamicColor> {
+  if let Ok(color) = input.try_parse(parse_color_function) {
+    return Ok(color);
+  }
+
+  Ok(ColorSpace::Srgb.to_dynamic(Color::from_css(input)?))
+}
+
+/// Parses the arguments of `color-mix(in <color-space>, <color> <percentage>?, <color> <percentage>?)`.
+fn parse_color_mix<'i>(input: &mut Parser<'i, '_>) -> ParseResult<'i, DynamicColor> {
+  let method = ColorInterpolationMethod::from_css(input)?;
+
+  input.expect_comma()?;
+  let (first, first_percentage) = parse_color_mix_input(input)?;
+
+  input.expect_comma()?;
+  let (second, second_percentage) = parse_color_mix_input(input)?;
+
+  let (first_percentage, second_percentage) = match (first_percentage, second_percentage) {
+    (None, None) => (0.5, 0.5),
+    (Some(first), None) => (first, 1.0 - first),
+    (None, Some(second)) => (1.0 - second, second),
+    (Some(first), Some(second)) => (first, second),
+  };
+
+  let total = first_percentage + second_percentage;
+
+  if total <= 0.0 {
+    return Err(input.new_error(BasicParseErrorKind::QualifiedRuleInvalid));
+  }
+
+  let mut color = method.mix(first, second, second_percentage / total);
+
+  // Percentages adding up to less than 100% make the result partially transparent.
+  if total < 1.0 {
+    color.components[3] *= total;
+  }
+
+  Ok(color)
+}
+
+/// Parses a color of `color-mix()` and its percentage, which can come before or after the color.
+fn parse_color_mix_input<'i>(
+  input: &mut Parser<'i, '_>,
+) -> ParseResult<'i, (DynamicColor, Option<f32>)> {
+  let mut percentage = input.try_parse(Parser::expect_percentage).ok();
+  let color = parse_dynamic_color(input)?;
+
+  if percentage.is_none() {
+    percentage = input.try_parse(Parser::expect_percentage).ok();
+  }
+
+  if percentage.is_some_and(|percentage| !(0.0..=1.0).contains(&percentage)) {
+    return Err(input.new_error(BasicParseErrorKind::QualifiedRuleInvalid));
+  }
+
+  Ok((color, percentage))
+}
+
+/// How the channels of a color function are named and scaled in the relative color syntax.
+struct RelativeChannels {
+  /// The color space the channels are in.
+  color_space: InterpolationColorSpace,
+  /// The keywords referring to the channels of the origin color.
+  names: [&'static str; 3],
+  /// The values `100%` resolves to, `None` for hues which don't take percentages.
+  percentages: [Option<f32>; 3],
+  /// The ratio between the numbers of the syntax and the components of the color space, `255` for `rgb()`.
+  scale: f32,
+}
+
+impl RelativeChannels {
+  /// Looks up the channels of the color function named `name`.
+  fn from_function<'i>(name: &str, input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
+    let (color_space, names, percentages) = match_ignore_ascii_case! {name,
+      "rgb" | "rgba" => {
+        return Ok(RelativeChannels {
+          color_space: InterpolationColorSpace::Srgb,
+          names: ["r", "g", "b"],
+          percentages: [Some(255.0); 3],
+          scale: 255.0,
+        });
+      },
+      "hsl" | "hsla" => (
+        InterpolationColorSpace::Hsl,
+        ["h", "s", "l"],
+        [None, Some(100.0), Some(100.0)],
+      ),
+      "hwb" => (
+        InterpolationColorSpace::Hwb,
+        ["h", "w", "b"],
+        [None, Some(100.0), Some(100.0)],
+      ),
+      "lab" => (
+        InterpolationColorSpace::Lab,
+        ["l", "a", "b"],
+        [Some(100.0), Some(125.0), Some(125.0)],
+      ),
+      "lch" => (
+        InterpolationColorSpace::Lch,
+        ["l", "c", "h"],
+        [Some(100.0), Some(150.0), None],
+      ),
+      "oklab" => (
+        InterpolationColorSpace::Oklab,
+        ["l", "a", "b"],
+        [Some(1.0), Some(0.4), Some(0.4)],
+      ),
+      "oklch" => (
+        InterpolationColorSpace::Oklch,
+        ["l", "c", "h"],
+        [Some(1.0), Some(0.4), None],
+      ),
+      // `color()` takes the color space after the origin color.
+      "color" => match InterpolationColorSpace::from_css(input)? {
+        color_space @ (InterpolationColorSpace::XyzD50 | InterpolationColorSpace::XyzD65) => {
+          (color_space, ["x", "y", "z"], [Some(1.0); 3])
+        }
+        color_space if color_space.is_polar()
+          || matches!(color_space, InterpolationColorSpace::Lab | InterpolationColorSpace::Oklab) =>
+        {
+          return Err(input.new_error(BasicParseErrorKind::QualifiedRuleInvalid));
+        }
+        color_space => (color_space, ["r", "g", "b"], [Some(1.0); 3]),
+      },
+      _ => return Err(input.new_error(BasicParseErrorKind::QualifiedRuleInvalid)),
+    };
+
+    Ok(RelativeChannels {
+      color_space,
+      names,
+      percentages,
+      scale: 1.0,
+    })
+  }
+}
+
+/// Parses the rest of a relative color like `oklch(from red calc(l * 0.8) c h / alpha)`,
+/// after the `from` keyword.
+fn parse_relative_color<'i>(
+  name: &str,
+  input: &mut Parser<'i, '_>,
+) -> ParseResult<'i, DynamicColor> {
+  let origin = parse_dynamic_color(input)?;
+  let channels = RelativeChannels::from_function(name, input)?;
+
+  let mut color = origin.convert(channels.color_space.tag());
+  let [first, second, third, alpha] = color.components.map(|component| {
+    // Missing components, like the hue of a gray, are zero.
+    if component.is_nan() { 0.0 } else { component }
+  });
+
+  let keywords = [
+    (channels.names[0], first * channels.scale),
+    (channels.names[1], second * channels.scale),
+    (channels.names[2], third * channels.scale),
+    ("alpha", alpha),
+  ];
+
+  let mut components = [0.0; 4];
+
+  for (component, percentage) in components.iter_mut().zip(channels.percentages) {
+    *component = parse_relative_channel(input, &keywords, percentage)? / channels.scale;
+  }
+
+  components[3] = if input.try_parse(|input| input.expect_delim('/')).is_ok() {
+    parse_relative_channel(input, &keywords, Some(1.0))?.clamp(0.0, 1.0)
+  } else {
+    alpha
+  };
+
+  color.components = components;
+
+  Ok(color)
+}
+
+/// Parses a channel of a relative color, a number, percentage, angle, channel keyword or `calc()` of them.
+fn parse_relative_channel<'i>(
+  input: &mut Parser<'i, '_>,
+  keywords: &[(&str, f32); 4],
+  percentage: Option<f32>,
+) -> ParseResult<'i, f32> {
+  let location = input.current_source_location();
+  let token = input.next()?.clone();
+
+  let value = match token {
+    Token::Number { value, .. } => Some(value),
+    Token::Percentage { unit_value, .. } => percentage.map(|percentage| unit_value * percentage),
+    // Only hues, which don't take percentages, take angles.
+    Token::Dimension {
+      value, ref unit, ..
+    } if percentage.is_none() => {
+      match_ignore_ascii_case! {unit,
+        "deg" => Some(value),
+        "grad" => Some(value / 400.0 * 360.0),
+        "turn" => Some(value * 360.0),
+        "rad" => Some(value.to_degrees()),
+        _ => None,
+      }
+    }
+    Token::Ident(ref ident) if ident.eq_ignore_ascii_case("none") => Some(0.0),
+    Token::Ident(ref ident) => keywords
+      .iter()
+      .find(|(name, _)| ident.eq_ignore_ascii_case(name))
+      .map(|(_, value)| *value),
+    Token::Function(ref name) if name.eq_ignore_ascii_case("calc") => {
+      return input.parse_nested_block(|input| parse_calc_sum(input, keywords, percentage));
+    }
+    _ => None,
+  };
+
+  value.ok_or_else(|| location.new_basic_unexpected_token_error(token).into())
+}
+
+/// Parses a sum of products inside of `calc()`.
+fn parse_calc_sum<'i>(
+  input: &mut Parser<'i, '_>,
+  keywords: &[(&str, f32); 4],
+  percentage: Option<f32>,
+) -> ParseResult<'i, f32> {
+  let mut value = parse_calc_product(input, keywords, percentage)?;
+
+  loop {
+    if input.try_parse(|input| input.expect_delim('+')).is_ok() {
+      value += parse_calc_product(input, keywords, percentage)?;
+    } else if input.try_parse(|input| input.expect_delim('-')).is_ok() {
+      value -= parse_calc_product(input, keywords, percentage)?;
+    } else {
+      return Ok(value);
+    }
+  }
+}
+
+/// Parses a product of values or parenthesized sums inside of `calc()`.
+fn parse_calc_product<'i>(
+  input: &mut Parser<'i, '_>,
+  keywords: &[(&str, f32); 4],
+  percentage: Option<f32>,
+) -> ParseResult<'i, f32> {
+  let mut value = parse_calc_value(input, keywords, percentage)?;
+
+  loop {
+    if input.try_parse(|input| input.expect_delim('*')).is_ok() {
+      value *= parse_calc_value(input, keywords, percentage)?;
+    } else if input.try_parse(|input| input.expect_delim('/')).is_ok() {
+      let divisor = parse_calc_value(input, keywords, percentage)?;
+
+      if divisor == 0.0 {
+        return Err(input.new_error(BasicParseErrorKind::QualifiedRuleInvalid));
+      }
+
+      value /= divisor;
+    } else {
+      return Ok(value);
+    }
+  }
+}
+
+/// Parses a value or a parenthesized sum inside of `calc()`.
+fn parse_calc_value<'i>(
+  input: &mut Parser<'i, '_>,
+  keywords: &[(&str, f32); 4],
+  percentage: Option<f32>,
+) -> ParseResult<'i, f32> {
+  if input.try_parse(Parser::expect_parenthesis_block).is_ok() {
+    return input.parse_nested_block(|input| parse_calc_sum(input, keywords, percentage));
+  }
+
+  parse_relative_channel(input, keywords, percentage)
+}
+
 impl<'i> FromCss<'i> for Color {
   fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
     let location = input.current_source_location();
-    let position = input.position();
+    let state = input.state();
     let token = input.next()?;
 
     match *token {
@@ -343,24 +1001,10 @@ impl<'i> FromCss<'i> for Color {
         Ok(Color([r, g, b, 255]))
       }
       Token::Function(_) => {
-        // Have to clone to persist token, and allow input to be borrowed
-        let token = token.clone();
-
-        input.parse_nested_block(|input| {
-          while input.next().is_ok() {}
-
-          // Slice from the function name till before the closing parenthesis
-          let body = input.slice_from(position);
+        input.reset(&state);
 
-          let mut function = body.to_string();
-
-          // Add closing parenthesis
-          function.push(')');
-
-          parse_color(&function)
-            .map(|color| Color(color.to_alpha_color::<Srgb>().to_rgba8().to_u8_array()))
-            .map_err(|_| location.new_basic_unexpected_token_error(token).into())
-        })
+        parse_color_function(input)
+          .map(|color| Color(color.to_alpha_color::<Srgb>().to_rgba8().to_u8_array()))
       }
       _ => Err(
         location
@@ -460,4 +1104,116 @@ mod tests {
       Ok(ColorInput::<true>::Value(Color([0, 191, 255, 255])))
     );
   }
+
+  #[test]
+  fn test_parse_display_p3_color_keeps_color_space() {
+    assert_eq!(
+      ColorInput::<true>::from_str("color(display-p3 1 0 0)"),
+      Ok(ColorInput::Wide(WideColor {
+        color_space: ColorSpace::DisplayP3,
+        components: [1.0, 0.0, 0.0, 1.0],
+      }))
+    );
+  }
+
+  #[test]
+  fn test_resolve_display_p3_color() -> ParseResult<'static, ()> {
+    let red = ColorInput::<true>::from_str("color(display-p3 1 0 0)")?;
+
+    assert_eq!(
+      red.resolve_in(ColorSpace::DisplayP3, Color::black(), 255),
+      Color([255, 0, 0, 255])
+    );
+
+    // Outside of the sRGB gamut, so it's clipped to the most saturated red.
+    assert_eq!(red.resolve(Color::black(), 255), Color([255, 0, 0, 255]));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_resolve_srgb_color_in_display_p3() {
+    let red = ColorInput::<true>::Value(Color([255, 0, 0, 255]));
+    let [r, g, b, a] = red.resolve_in(ColorSpace::DisplayP3, Color::black(), 255).0;
+
+    // sRGB red is less saturated than Display P3 red.
+    assert!((232..=236).contains(&r));
+    assert!((50..=56).contains(&g));
+    assert!((33..=40).contains(&b));
+    assert_eq!(a, 255);
+  }
+
+  fn resolve(color: &str) -> ParseResult<'_, [u8; 4]> {
+    ColorInput::<true>::from_str(color).map(|color| color.resolve(Color::black(), 255).0)
+  }
+
+  #[test]
+  fn test_parse_modern_color_functions() {
+    assert_eq!(resolve("hsl(120 100% 50%)"), Ok([0, 255, 0, 255]));
+    assert_eq!(resolve("hwb(0 0% 0%)"), Ok([255, 0, 0, 255]));
+    assert_eq!(resolve("oklch(1 0 0)"), Ok([255, 255, 255, 255]));
+    assert_eq!(resolve("lab(0 0 0)"), Ok([0, 0, 0, 255]));
+  }
+
+  #[test]
+  fn test_parse_color_mix() {
+    assert_eq!(
+      resolve("color-mix(in srgb, red, blue)"),
+      Ok([128, 0, 128, 255])
+    );
+    assert_eq!(
+      resolve("color-mix(in srgb, red 25%, blue)"),
+      Ok([64, 0, 191, 255])
+    );
+  }
+
+  #[test]
+  fn test_parse_color_mix_percentages_below_100_reduce_alpha() {
+    assert_eq!(
+      resolve("color-mix(in srgb, red 20%, blue 20%)"),
+      Ok([128, 0, 128, 102])
+    );
+  }
+
+  #[test]
+  fn test_parse_color_mix_zero_percentages() {
+    assert!(ColorInput::<true>::from_str("color-mix(in srgb, red 0%, blue 0%)").is_err());
+  }
+
+  #[test]
+  fn test_parse_relative_color() {
+    assert_eq!(resolve("rgb(from red r g b / 0.5)"), Ok([255, 0, 0, 128]));
+    assert_eq!(
+      resolve("rgb(from #0000ff calc(r + 255) g b)"),
+      Ok([255, 0, 255, 255])
+    );
+    assert_eq!(
+      resolve("hsl(from red calc(h + 120) s l)"),
+      Ok([0, 255, 0, 255])
+    );
+  }
+
+  #[test]
+  fn test_parse_relative_color_unknown_channel() {
+    assert!(ColorInput::<true>::from_str("rgb(from red x g b)").is_err());
+  }
+
+  #[test]
+  fn test_parse_color_interpolation_method() {
+    assert_eq!(
+      ColorInterpolationMethod::from_str("in oklch longer hue"),
+      Ok(ColorInterpolationMethod {
+        color_space: InterpolationColorSpace::Oklch,
+        hue: HueInterpolation::Longer,
+      })
+    );
+
+    assert_eq!(
+      ColorInterpolationMethod::from_str("in srgb"),
+      Ok(ColorInterpolationMethod {
+        color_space: InterpolationColorSpace::Srgb,
+        hue: HueInterpolation::Shorter,
+      })
+    );
+  }
 }
@@ -0,0 +1,379 @@
+use cssparser::{Parser, Token, match_ignore_ascii_case};
+use smallvec::SmallVec;
+
+use super::gradient_utils::{color_from_stops, repeat_position, resolve_stops_along_axis};
+use crate::{
+  layout::style::{
+    Angle, BackgroundPosition, Color, ColorInput, ColorInterpolationMethod, FromCss, Gradient,
+    GradientStop, Length, ParseResult, ResolvedGradientStop, StopInterpolation, StopPosition,
+  },
+  rendering::RenderContext,
+};
+
+/// Degrees in a full turn, the length of the conic gradient axis.
+const FULL_TURN: f32 = 360.0;
+
+/// Represents a conic gradient.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConicGradient {
+  /// The angle the gradient starts from, clockwise from the top
+  pub from: Angle,
+  /// Center position
+  pub center: BackgroundPosition,
+  /// Gradient stops, positioned in percentages of a full turn
+  pub stops: Vec<GradientStop>,
+  /// The color space the stops are interpolated in, from an `in <color-space>` hint
+  pub interpolation: Option<ColorInterpolationMethod>,
+  /// Whether the stops repeat past the last one, as `repeating-conic-gradient()`
+  pub repeating: bool,
+}
+
+/// Precomputed drawing context for repeated sampling of a `ConicGradient`.
+#[derive(Debug, Clone)]
+pub struct ConicGradientDrawContext {
+  /// Center X coordinate in pixels
+  pub cx: f32,
+  /// Center Y coordinate in pixels
+  pub cy: f32,
+  /// The starting angle in degrees
+  pub from: f32,
+  /// Resolved and ordered color stops (positions in degrees).
+  pub resolved_stops: SmallVec<[ResolvedGradientStop; 4]>,
+  /// How the colors between stops are interpolated.
+  pub interpolation: StopInterpolation,
+}
+
+impl Gradient for ConicGradient {
+  type DrawContext = ConicGradientDrawContext;
+
+  fn at(&self, x: u32, y: u32, ctx: &Self::DrawContext) -> Color {
+    // Fast-paths
+    if ctx.resolved_stops.is_empty() {
+      return Color([0, 0, 0, 0]);
+    }
+    if ctx.resolved_stops.len() == 1 {
+      return ctx.resolved_stops[0].color;
+    }
+
+    let dx = x as f32 - ctx.cx;
+    let dy = y as f32 - ctx.cy;
+
+    // Measured clockwise from the top, starting at the `from` angle.
+    let angle = (dx.atan2(-dy).to_degrees() - ctx.from).rem_euclid(FULL_TURN);
+    let angle = if self.repeating {
+      repeat_position(angle, &ctx.resolved_stops)
+    } else {
+      angle
+    };
+
+    color_from_stops(angle, &ctx.resolved_stops, ctx.interpolation)
+  }
+
+  fn to_draw_context(&self, width: f32, height: f32, context: &RenderContext) -> Self::DrawContext {
+    ConicGradientDrawContext::new(self, width, height, context)
+  }
+}
+
+impl ConicGradient {
+  /// Resolves gradient steps into color stops with positions expressed in degrees.
+  pub(crate) fn resolve_stops_for_turn(
+    &self,
+    context: &RenderContext,
+  ) -> SmallVec<[ResolvedGradientStop; 4]> {
+    resolve_stops_along_axis(&self.stops, FULL_TURN, context)
+  }
+}
+
+impl ConicGradientDrawContext {
+  /// Builds a drawing context from a gradient and a target viewport.
+  pub fn new(gradient: &ConicGradient, width: f32, height: f32, context: &RenderContext) -> Self {
+    ConicGradientDrawContext {
+      cx: Length::from(gradient.center.0.x).to_px(&context.sizing, width),
+      cy: Length::from(gradient.center.0.y).to_px(&context.sizing, height),
+      from: *gradient.from,
+      resolved_stops: gradient.resolve_stops_for_turn(context),
+      interpolation: StopInterpolation::new(gradient.interpolation, context),
+    }
+  }
+}
+
+/// Parses an angle stop position into a percentage of a full turn.
+/// Unlike [`Angle`], the value is kept as is, so `360deg` stays at the end of the turn.
+fn parse_angle_stop_position<'i>(input: &mut Parser<'i, '_>) -> ParseResult<'i, StopPosition> {
+  let location = input.current_source_location();
+  let token = input.next()?;
+
+  let degrees = match token {
+    Token::Dimension { value, unit, .. } => match unit.as_ref() {
+      "deg" => *value,
+      "grad" => *value / 400.0 * FULL_TURN,
+      "turn" => *value * FULL_TURN,
+      "rad" => value.to_degrees(),
+      _ => {
+        return Err(
+          location
+            .new_basic_unexpected_token_error(token.clone())
+            .into(),
+        );
+      }
+    },
+    _ => {
+      return Err(
+        location
+          .new_basic_unexpected_token_error(token.clone())
+          .into(),
+      );
+    }
+  };
+
+  Ok(StopPosition(Length::Percentage(
+    degrees / FULL_TURN * 100.0,
+  )))
+}
+
+/// Parses a stop position of a conic gradient, either an angle or a percentage.
+fn parse_conic_stop_position<'i>(input: &mut Parser<'i, '_>) -> ParseResult<'i, StopPosition> {
+  if let Ok(position) = input.try_parse(parse_angle_stop_position) {
+    return Ok(position);
+  }
+
+  StopPosition::from_css(input)
+}
+
+/// Parses a stop of a conic gradient, the counterpart of [`GradientStop::from_css`] with angle positions.
+fn parse_conic_stop<'i>(input: &mut Parser<'i, '_>) -> ParseResult<'i, GradientStop> {
+  if let Ok(hint) = input.try_parse(parse_conic_stop_position) {
+    return Ok(GradientStop::Hint(hint));
+  }
+
+  let color = ColorInput::from_css(input)?;
+  let hint = input.try_parse(parse_conic_stop_position).ok();
+
+  Ok(GradientStop::ColorHint { color, hint })
+}
+
+impl<'i> FromCss<'i> for ConicGradient {
+  fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, ConicGradient> {
+    let location = input.current_source_location();
+    let function = input.expect_function()?.clone();
+
+    let repeating = match_ignore_ascii_case! {&function,
+      "conic-gradient" => false,
+      "repeating-conic-gradient" => true,
+      _ => return Err(location.new_basic_unexpected_token_error(Token::Function(function)).into()),
+    };
+
+    input.parse_nested_block(|input| {
+      let mut from = Angle::zero();
+      let mut center = BackgroundPosition::default();
+      let mut interpolation = None;
+
+      loop {
+        if input.try_parse(|i| i.expect_ident_matching("from")).is_ok() {
+          from = Angle::from_css(input)?;
+          continue;
+        }
+
+        if input.try_parse(|i| i.expect_ident_matching("at")).is_ok() {
+          center = BackgroundPosition::from_css(input)?;
+          continue;
+        }
+
+        if let Ok(method) = input.try_parse(ColorInterpolationMethod::from_css) {
+          interpolation = Some(method);
+          continue;
+        }
+
+        input.try_parse(Parser::expect_comma).ok();
+
+        break;
+      }
+
+      // Parse at least one stop, comma-separated
+      let mut stops = Vec::new();
+
+      stops.push(parse_conic_stop(input)?);
+
+      while input.try_parse(Parser::expect_comma).is_ok() {
+        stops.push(parse_conic_stop(input)?);
+      }
+
+      Ok(ConicGradient {
+        from,
+        center,
+        stops,
+        interpolation,
+        repeating,
+      })
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::GlobalContext;
+  use crate::layout::style::{PositionComponent, SpacePair};
+
+  #[test]
+  fn test_parse_conic_gradient_basic() {
+    assert_eq!(
+      ConicGradient::from_str("conic-gradient(#ff0000, #0000ff)"),
+      Ok(ConicGradient {
+        from: Angle::zero(),
+        center: BackgroundPosition::default(),
+        stops: vec![
+          GradientStop::ColorHint {
+            color: Color([255, 0, 0, 255]).into(),
+            hint: None,
+          },
+          GradientStop::ColorHint {
+            color: Color([0, 0, 255, 255]).into(),
+            hint: None,
+          },
+        ],
+        interpolation: None,
+        repeating: false,
+      })
+    );
+  }
+
+  #[test]
+  fn test_parse_conic_gradient_from_at() {
+    assert_eq!(
+      ConicGradient::from_str("conic-gradient(from 90deg at 25% 75%, #ff0000, #0000ff)"),
+      Ok(ConicGradient {
+        from: Angle::new(90.0),
+        center: BackgroundPosition(SpacePair::from_pair(
+          PositionComponent::Length(Length::Percentage(25.0)),
+          PositionComponent::Length(Length::Percentage(75.0)),
+        )),
+        stops: vec![
+          GradientStop::ColorHint {
+            color: Color([255, 0, 0, 255]).into(),
+            hint: None,
+          },
+          GradientStop::ColorHint {
+            color: Color([0, 0, 255, 255]).into(),
+            hint: None,
+          },
+        ],
+        interpolation: None,
+        repeating: false,
+      })
+    );
+  }
+
+  #[test]
+  fn test_parse_conic_gradient_angle_stops() {
+    assert_eq!(
+      ConicGradient::from_str("conic-gradient(#ff0000 0deg, #ff0000 0.25turn, #0000ff 25%)"),
+      Ok(ConicGradient {
+        from: Angle::zero(),
+        center: BackgroundPosition::default(),
+        stops: vec![
+          GradientStop::ColorHint {
+            color: Color([255, 0, 0, 255]).into(),
+            hint: Some(StopPosition(Length::Percentage(0.0))),
+          },
+          GradientStop::ColorHint {
+            color: Color([255, 0, 0, 255]).into(),
+            hint: Some(StopPosition(Length::Percentage(25.0))),
+          },
+          GradientStop::ColorHint {
+            color: Color([0, 0, 255, 255]).into(),
+            hint: Some(StopPosition(Length::Percentage(25.0))),
+          },
+        ],
+        interpolation: None,
+        repeating: false,
+      })
+    );
+  }
+
+  #[test]
+  fn test_parse_repeating_conic_gradient() {
+    assert_eq!(
+      ConicGradient::from_str("repeating-conic-gradient(#ff0000 10deg, #0000ff 360deg)"),
+      Ok(ConicGradient {
+        from: Angle::zero(),
+        center: BackgroundPosition::default(),
+        stops: vec![
+          GradientStop::ColorHint {
+            color: Color([255, 0, 0, 255]).into(),
+            hint: Some(StopPosition(Length::Percentage(10.0 / 360.0 * 100.0))),
+          },
+          GradientStop::ColorHint {
+            color: Color([0, 0, 255, 255]).into(),
+            hint: Some(StopPosition(Length::Percentage(100.0))),
+          },
+        ],
+        interpolation: None,
+        repeating: true,
+      })
+    );
+  }
+
+  #[test]
+  fn test_conic_gradient_at_quadrants() -> ParseResult<'static, ()> {
+    let gradient = ConicGradient::from_str(
+      "conic-gradient(#ff0000 90deg, #00ff00 90deg, #00ff00 180deg, #0000ff 180deg, #0000ff 270deg, #000000 270deg)",
+    )?;
+
+    let context = GlobalContext::default();
+    let render_context = RenderContext::new(&context, (100, 100).into(), Default::default());
+    let ctx = gradient.to_draw_context(100.0, 100.0, &render_context);
+
+    // top right, bottom right, bottom left, top left
+    assert_eq!(gradient.at(75, 25, &ctx), Color([255, 0, 0, 255]));
+    assert_eq!(gradient.at(75, 75, &ctx), Color([0, 255, 0, 255]));
+    assert_eq!(gradient.at(25, 75, &ctx), Color([0, 0, 255, 255]));
+    assert_eq!(gradient.at(25, 25, &ctx), Color([0, 0, 0, 255]));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_conic_gradient_at_from_angle() -> ParseResult<'static, ()> {
+    let gradient = ConicGradient::from_str("conic-gradient(from 90deg, #ff0000 50%, #0000ff 50%)")?;
+
+    let context = GlobalContext::default();
+    let render_context = RenderContext::new(&context, (100, 100).into(), Default::default());
+    let ctx = gradient.to_draw_context(100.0, 100.0, &render_context);
+
+    // The first half now spans from the right to the left through the bottom.
+    assert_eq!(gradient.at(50, 90, &ctx), Color([255, 0, 0, 255]));
+    assert_eq!(gradient.at(50, 10, &ctx), Color([0, 0, 255, 255]));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_repeating_conic_gradient_at() -> ParseResult<'static, ()> {
+    let gradient = ConicGradient::from_str(
+      "repeating-conic-gradient(#ff0000 0deg, #ff0000 45deg, #0000ff 45deg, #0000ff 90deg)",
+    )?;
+
+    let context = GlobalContext::default();
+    let render_context = RenderContext::new(&context, (100, 100).into(), Default::default());
+    let ctx = gradient.to_draw_context(100.0, 100.0, &render_context);
+
+    // Each 90deg period is red in its first half and blue in the second one.
+    let sample = |degrees: f32| {
+      let radians = degrees.to_radians();
+
+      gradient.at(
+        (50.0 + radians.sin() * 40.0).round() as u32,
+        (50.0 - radians.cos() * 40.0).round() as u32,
+        &ctx,
+      )
+    };
+
+    assert_eq!(sample(30.0), Color([255, 0, 0, 255]));
+    assert_eq!(sample(60.0), Color([0, 0, 255, 255]));
+    assert_eq!(sample(210.0), Color([255, 0, 0, 255]));
+    assert_eq!(sample(240.0), Color([0, 0, 255, 255]));
+
+    Ok(())
+  }
+}
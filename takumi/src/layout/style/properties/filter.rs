@@ -5,10 +5,13 @@ use taffy::Size;
 
 use crate::{
   layout::style::{
-    Angle, Color, FromCss, Length, ParseResult, PercentageNumber, TextShadow,
+    Angle, Color, ColorSpace, FromCss, Length, ParseResult, PercentageNumber, TextShadow,
     tw::TailwindPropertyParser,
   },
-  rendering::{BlurType, SizedShadow, Sizing, apply_blur, blend_pixel, fast_div_255},
+  rendering::{
+    BlurType, ColorInterpolation, SizedShadow, Sizing, WideImage, apply_blur, apply_wide_blur,
+    blend_premultiplied_pixel, fast_div_255,
+  },
 };
 
 /// Represents a single CSS filter operation
@@ -152,7 +155,9 @@ pub(crate) fn apply_filters<'f, F: Iterator<Item = &'f Filter>>(
   image: &mut RgbaImage,
   sizing: &Sizing,
   current_color: Color,
+  color_space: ColorSpace,
   opacity: u8,
+  color_interpolation: ColorInterpolation,
   filters: F,
 ) {
   // Collect filters and batch consecutive pixel filters
@@ -177,16 +182,27 @@ pub(crate) fn apply_filters<'f, F: Iterator<Item = &'f Filter>>(
             huerotate_in_place(image, *angle as i32);
           }
           Filter::Blur(blur) => {
-            apply_blur(image, blur.to_px(sizing, 1.0), BlurType::Filter);
+            apply_blur(
+              image,
+              blur.to_px(sizing, 1.0),
+              BlurType::Filter,
+              color_interpolation,
+            );
           }
           Filter::DropShadow(drop_shadow) => {
             let size = Size {
               width: image.width() as f32,
               height: image.height() as f32,
             };
-            let shadow =
-              SizedShadow::from_text_shadow(drop_shadow, sizing, current_color, opacity, size);
-            apply_drop_shadow_filter(image, &shadow);
+            let shadow = SizedShadow::from_text_shadow(
+              drop_shadow,
+              sizing,
+              current_color,
+              color_space,
+              opacity,
+              size,
+            );
+            apply_drop_shadow_filter(image, &shadow, color_interpolation);
           }
           _ => unreachable!(),
         }
@@ -209,7 +225,11 @@ pub(crate) fn apply_filters<'f, F: Iterator<Item = &'f Filter>>(
 /// 2. Copy the source alpha channel, filling with shadow color
 /// 3. Apply blur to the shadow
 /// 4. Composite: draw shadow at offset, then draw original on top
-fn apply_drop_shadow_filter(canvas: &mut RgbaImage, shadow: &SizedShadow) {
+fn apply_drop_shadow_filter(
+  canvas: &mut RgbaImage,
+  shadow: &SizedShadow,
+  color_interpolation: ColorInterpolation,
+) {
   let canvas_width = canvas.width();
   let canvas_height = canvas.height();
 
@@ -269,7 +289,12 @@ fn apply_drop_shadow_filter(canvas: &mut RgbaImage, shadow: &SizedShadow) {
   }
 
   // Apply blur to the shadow
-  apply_blur(&mut shadow_image, shadow.blur_radius, BlurType::Shadow);
+  apply_blur(
+    &mut shadow_image,
+    shadow.blur_radius,
+    BlurType::Shadow,
+    color_interpolation,
+  );
 
   // Create the result image
   let mut result = RgbaImage::new(result_width, result_height);
@@ -285,7 +310,7 @@ fn apply_drop_shadow_filter(canvas: &mut RgbaImage, shadow: &SizedShadow) {
       {
         let shadow_pixel = shadow_image.get_pixel(x, y);
         if shadow_pixel.0[3] > 0 {
-          blend_pixel(
+          color_interpolation.blend_pixel(
             result.get_pixel_mut(dest_x as u32, dest_y as u32),
             *shadow_pixel,
           );
@@ -301,7 +326,7 @@ fn apply_drop_shadow_filter(canvas: &mut RgbaImage, shadow: &SizedShadow) {
       let dest_y = (origin_y + y as i32) as u32;
       let src_pixel = *canvas.get_pixel(x, y);
       if src_pixel.0[3] > 0 {
-        blend_pixel(result.get_pixel_mut(dest_x, dest_y), src_pixel);
+        color_interpolation.blend_pixel(result.get_pixel_mut(dest_x, dest_y), src_pixel);
       }
     }
   }
@@ -319,6 +344,178 @@ fn apply_drop_shadow_filter(canvas: &mut RgbaImage, shadow: &SizedShadow) {
   }
 }
 
+/// Applies the filters like [`apply_filters`] to an image with premultiplied channels of `color_interpolation`.
+///
+/// Color filters work on the gamma-encoded colors, without rounding them to 8 bits in between.
+pub(crate) fn apply_wide_filters<'f, F: Iterator<Item = &'f Filter>>(
+  image: &mut WideImage,
+  sizing: &Sizing,
+  current_color: Color,
+  color_space: ColorSpace,
+  opacity: u8,
+  color_interpolation: ColorInterpolation,
+  filters: F,
+) {
+  for filter in filters {
+    match *filter {
+      Filter::Blur(blur) => {
+        apply_wide_blur(image, blur.to_px(sizing, 1.0), BlurType::Filter);
+      }
+      Filter::DropShadow(drop_shadow) => {
+        let size = Size {
+          width: image.width() as f32,
+          height: image.height() as f32,
+        };
+        let shadow = SizedShadow::from_text_shadow(
+          drop_shadow,
+          sizing,
+          current_color,
+          color_space,
+          opacity,
+          size,
+        );
+        apply_wide_drop_shadow_filter(image, &shadow, color_interpolation);
+      }
+      filter => {
+        for pixel in image.pixels_mut() {
+          apply_wide_pixel_filter(pixel, &filter, color_interpolation);
+        }
+      }
+    }
+  }
+}
+
+/// Applies a color filter to a premultiplied pixel, with the formulas of [`apply_single_pixel_filter`].
+fn apply_wide_pixel_filter(
+  pixel: &mut [f32; 4],
+  filter: &Filter,
+  color_interpolation: ColorInterpolation,
+) {
+  let alpha = pixel[3];
+
+  if alpha <= 0.0 {
+    return;
+  }
+
+  let [r, g, b] = [pixel[0], pixel[1], pixel[2]]
+    .map(|channel| color_interpolation.encode_exact(channel / alpha) * 255.0);
+  let luma = r * 0.2126 + g * 0.7152 + b * 0.0722;
+  let mut alpha = alpha * 255.0;
+
+  let rgb = match *filter {
+    Filter::Brightness(PercentageNumber(value)) => [r, g, b].map(|channel| channel * value),
+    Filter::Contrast(PercentageNumber(value)) => {
+      [r, g, b].map(|channel| (channel - 128.0) * value + 128.0)
+    }
+    Filter::Grayscale(PercentageNumber(amount)) => {
+      [r, g, b].map(|channel| channel * (1.0 - amount) + luma * amount)
+    }
+    Filter::Saturate(PercentageNumber(value)) => {
+      [r, g, b].map(|channel| luma * (1.0 - value) + channel * value)
+    }
+    Filter::Invert(PercentageNumber(amount)) => {
+      [r, g, b].map(|channel| channel * (1.0 - amount) + (255.0 - channel) * amount)
+    }
+    Filter::Sepia(PercentageNumber(amount)) => {
+      let sepia = [
+        r * 0.393 + g * 0.769 + b * 0.189,
+        r * 0.349 + g * 0.686 + b * 0.168,
+        r * 0.272 + g * 0.534 + b * 0.131,
+      ];
+
+      [0, 1, 2]
+        .map(|index| [r, g, b][index] * (1.0 - amount) + sepia[index].clamp(0.0, 255.0) * amount)
+    }
+    Filter::HueRotate(angle) => {
+      let (sin, cos) = angle.to_radians().sin_cos();
+
+      [
+        r * (0.213 + cos * 0.787 - sin * 0.213)
+          + g * (0.715 - cos * 0.715 - sin * 0.715)
+          + b * (0.072 - cos * 0.072 + sin * 0.928),
+        r * (0.213 - cos * 0.213 + sin * 0.143)
+          + g * (0.715 + cos * 0.285 + sin * 0.140)
+          + b * (0.072 - cos * 0.072 - sin * 0.283),
+        r * (0.213 - cos * 0.213 - sin * 0.787)
+          + g * (0.715 - cos * 0.715 + sin * 0.715)
+          + b * (0.072 + cos * 0.928 + sin * 0.072),
+      ]
+    }
+    Filter::Opacity(PercentageNumber(value)) => {
+      alpha = (alpha * value).clamp(0.0, 255.0);
+
+      [r, g, b]
+    }
+    Filter::Blur(_) | Filter::DropShadow(_) => return,
+  };
+
+  let alpha = alpha / 255.0;
+
+  for (channel, value) in pixel.iter_mut().zip(rgb) {
+    *channel = color_interpolation.decode_exact(value.clamp(0.0, 255.0) / 255.0) * alpha;
+  }
+
+  pixel[3] = alpha;
+}
+
+/// Applies a drop-shadow filter like [`apply_drop_shadow_filter`] to an image with premultiplied `f32` channels.
+fn apply_wide_drop_shadow_filter(
+  canvas: &mut WideImage,
+  shadow: &SizedShadow,
+  color_interpolation: ColorInterpolation,
+) {
+  let (width, height) = (canvas.width(), canvas.height());
+
+  if width == 0 || height == 0 {
+    return;
+  }
+
+  let blur_padding = (shadow.blur_radius.ceil() as i32).max(0);
+  let color = color_interpolation.premultiply(shadow.color.into());
+
+  // The shadow follows the alpha of the image, with room around it for the blur to spread into.
+  let mut shadow_image = WideImage::from_fn(
+    width + blur_padding as u32 * 2,
+    height + blur_padding as u32 * 2,
+    |x, y| {
+      let source_x = x as i32 - blur_padding;
+      let source_y = y as i32 - blur_padding;
+
+      if source_x < 0 || source_y < 0 || source_x >= width as i32 || source_y >= height as i32 {
+        return [0.0; 4];
+      }
+
+      let alpha = canvas.get_pixel(source_x as u32, source_y as u32)[3];
+
+      color.map(|channel| channel * alpha)
+    },
+  );
+
+  apply_wide_blur(&mut shadow_image, shadow.blur_radius, BlurType::Shadow);
+
+  // The shadow is drawn at its offset below the image, the image keeps its size.
+  for y in 0..height {
+    for x in 0..width {
+      let shadow_x = x as i32 - shadow.offset_x as i32 + blur_padding;
+      let shadow_y = y as i32 - shadow.offset_y as i32 + blur_padding;
+
+      let mut pixel = if shadow_x >= 0
+        && shadow_y >= 0
+        && shadow_x < shadow_image.width() as i32
+        && shadow_y < shadow_image.height() as i32
+      {
+        shadow_image.get_pixel(shadow_x as u32, shadow_y as u32)
+      } else {
+        [0.0; 4]
+      };
+
+      blend_premultiplied_pixel(&mut pixel, canvas.get_pixel(x, y));
+
+      *canvas.get_pixel_mut(x, y) = pixel;
+    }
+  }
+}
+
 impl<'i> FromCss<'i> for Filters {
   fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
     let mut filters = SmallVec::new();
@@ -1,10 +1,15 @@
 use smallvec::SmallVec;
 
-use super::{Color, GradientStop, ResolvedGradientStop};
-use crate::rendering::RenderContext;
-
-/// Interpolates between two colors in RGBA space, if t is 0.0 or 1.0, returns the first or second color.
-pub(crate) fn interpolate_rgba(c1: Color, c2: Color, t: f32) -> Color {
+use super::{Color, GradientStop, ResolvedGradientStop, StopInterpolation};
+use crate::rendering::{ColorInterpolation, RenderContext, linear_to_srgb, srgb_to_linear};
+
+/// Interpolates between two colors in the color space, if t is 0.0 or 1.0, returns the first or second color.
+pub(crate) fn interpolate_rgba(
+  c1: Color,
+  c2: Color,
+  t: f32,
+  color_interpolation: ColorInterpolation,
+) -> Color {
   if t <= f32::EPSILON {
     return c1;
   }
@@ -12,6 +17,10 @@ pub(crate) fn interpolate_rgba(c1: Color, c2: Color, t: f32) -> Color {
     return c2;
   }
 
+  if color_interpolation == ColorInterpolation::LinearRgb {
+    return interpolate_linear_premultiplied(c1, c2, t);
+  }
+
   let mut out = [0u8; 4];
 
   for (i, value) in out.iter_mut().enumerate() {
@@ -21,8 +30,58 @@ pub(crate) fn interpolate_rgba(c1: Color, c2: Color, t: f32) -> Color {
   Color(out)
 }
 
+/// Interpolates in linear light with premultiplied alpha, so a fading stop doesn't tint its neighbor.
+fn interpolate_linear_premultiplied(c1: Color, c2: Color, t: f32) -> Color {
+  let a1 = c1.0[3] as f32 / 255.0;
+  let a2 = c2.0[3] as f32 / 255.0;
+  let alpha = a1 * (1.0 - t) + a2 * t;
+
+  if alpha <= f32::EPSILON {
+    return Color::transparent();
+  }
+
+  let mut out = [0u8; 4];
+
+  for (i, value) in out.iter_mut().take(3).enumerate() {
+    let premultiplied = srgb_to_linear(c1.0[i]) * a1 * (1.0 - t) + srgb_to_linear(c2.0[i]) * a2 * t;
+
+    *value = linear_to_srgb(premultiplied / alpha);
+  }
+
+  out[3] = (alpha * 255.0).round() as u8;
+
+  Color(out)
+}
+
+/// Interpolates between two stop colors, in the color space of the `in` hint of the gradient if it has one.
+fn interpolate_stops(c1: Color, c2: Color, t: f32, interpolation: StopInterpolation) -> Color {
+  match interpolation {
+    StopInterpolation::Channels(color_interpolation) => {
+      interpolate_rgba(c1, c2, t, color_interpolation)
+    }
+    StopInterpolation::Method(method, color_space) => {
+      if t <= f32::EPSILON {
+        return c1;
+      }
+      if t >= 1.0 - f32::EPSILON {
+        return c2;
+      }
+
+      color_space.color_from_dynamic(method.mix(
+        color_space.to_dynamic(c1),
+        color_space.to_dynamic(c2),
+        t,
+      ))
+    }
+  }
+}
+
 /// Returns the color for a pixel-space position along the resolved stops.
-pub(crate) fn color_from_stops(position: f32, resolved_stops: &[ResolvedGradientStop]) -> Color {
+pub(crate) fn color_from_stops(
+  position: f32,
+  resolved_stops: &[ResolvedGradientStop],
+  interpolation: StopInterpolation,
+) -> Color {
   // Find the two stops that bracket the current position.
   // We want the last stop with position <= current position.
   let left_index = resolved_stops
@@ -50,10 +109,31 @@ pub(crate) fn color_from_stops(position: f32, resolved_stops: &[ResolvedGradient
       ((position - left_stop.position) / denom).clamp(0.0, 1.0)
     };
 
-    interpolate_rgba(left_stop.color, right_stop.color, interpolation_position)
+    interpolate_stops(
+      left_stop.color,
+      right_stop.color,
+      interpolation_position,
+      interpolation,
+    )
   }
 }
 
+/// Wraps a position into the span between the first and last resolved stops, for repeating gradients.
+/// A zero-length span resolves to the last stop.
+pub(crate) fn repeat_position(position: f32, resolved_stops: &[ResolvedGradientStop]) -> f32 {
+  let (Some(first), Some(last)) = (resolved_stops.first(), resolved_stops.last()) else {
+    return position;
+  };
+
+  let period = last.position - first.position;
+
+  if period <= f32::EPSILON {
+    return last.position;
+  }
+
+  first.position + (position - first.position).rem_euclid(period)
+}
+
 const UNDEFINED_POSITION: f32 = -1.0;
 
 pub(crate) fn resolve_stops_along_axis(
@@ -78,13 +158,13 @@ pub(crate) fn resolve_stops_along_axis(
         last_position = position;
 
         resolved.push(ResolvedGradientStop {
-          color: color.resolve(context.current_color, context.opacity),
+          color: color.resolve_in(context.color_space, context.current_color, context.opacity),
           position,
         });
       }
       GradientStop::ColorHint { color, hint: None } => {
         resolved.push(ResolvedGradientStop {
-          color: color.resolve(context.current_color, context.opacity),
+          color: color.resolve_in(context.color_space, context.current_color, context.opacity),
           position: UNDEFINED_POSITION,
         });
       }
@@ -95,14 +175,15 @@ pub(crate) fn resolve_stops_along_axis(
 
         let Some(after_color) = stops.get(i + 1).and_then(|stop| match stop {
           GradientStop::ColorHint { color, hint: _ } => {
-            Some(color.resolve(context.current_color, context.opacity))
+            Some(color.resolve_in(context.color_space, context.current_color, context.opacity))
           }
           GradientStop::Hint(_) => None,
         }) else {
           continue;
         };
 
-        let interpolated_color = interpolate_rgba(before.color, after_color, 0.5);
+        let interpolated_color =
+          interpolate_rgba(before.color, after_color, 0.5, context.color_interpolation);
 
         let position = hint
           .0
@@ -323,7 +404,12 @@ mod tests {
     assert_eq!(
       resolved[1],
       ResolvedGradientStop {
-        color: interpolate_rgba(Color([255, 0, 0, 255]), Color([0, 0, 255, 255]), 0.5),
+        color: interpolate_rgba(
+          Color([255, 0, 0, 255]),
+          Color([0, 0, 255, 255]),
+          0.5,
+          ColorInterpolation::Srgb,
+        ),
         position: render_context.sizing.viewport.width.unwrap_or_default() as f32 * 0.1,
       },
     );
@@ -3,10 +3,13 @@ use image::RgbaImage;
 use smallvec::SmallVec;
 use std::ops::{Deref, Neg};
 
-use super::gradient_utils::{color_from_stops, resolve_stops_along_axis};
+use super::gradient_utils::{color_from_stops, repeat_position, resolve_stops_along_axis};
 use crate::{
-  layout::style::{Color, ColorInput, FromCss, Length, ParseResult, tw::TailwindPropertyParser},
-  rendering::RenderContext,
+  layout::style::{
+    Color, ColorInput, ColorInterpolationMethod, ColorSpace, FromCss, Length, ParseResult,
+    tw::TailwindPropertyParser,
+  },
+  rendering::{ColorInterpolation, RenderContext},
 };
 
 /// A trait for gradients that can be sampled at a specific point.
@@ -41,6 +44,10 @@ pub struct LinearGradient {
   pub angle: Angle,
   /// The steps of the gradient.
   pub stops: SmallVec<[GradientStop; 4]>,
+  /// The color space the stops are interpolated in, from an `in <color-space>` hint.
+  pub interpolation: Option<ColorInterpolationMethod>,
+  /// Whether the stops repeat past the last one, as `repeating-linear-gradient()`.
+  pub repeating: bool,
 }
 
 impl Gradient for LinearGradient {
@@ -60,9 +67,13 @@ impl Gradient for LinearGradient {
     let dx = x as f32 - ctx.cx;
     let dy = y as f32 - ctx.cy;
     let projection = dx * ctx.dir_x + dy * ctx.dir_y;
-    let position_px = (projection + ctx.max_extent).clamp(0.0, ctx.axis_length);
+    let position_px = if self.repeating {
+      repeat_position(projection + ctx.max_extent, &ctx.resolved_stops)
+    } else {
+      (projection + ctx.max_extent).clamp(0.0, ctx.axis_length)
+    };
 
-    color_from_stops(position_px, &ctx.resolved_stops)
+    color_from_stops(position_px, &ctx.resolved_stops, ctx.interpolation)
   }
 
   fn to_draw_context(&self, width: f32, height: f32, context: &RenderContext) -> Self::DrawContext {
@@ -103,6 +114,8 @@ pub struct LinearGradientDrawContext {
   pub axis_length: f32,
   /// Resolved and ordered color stops (positions in pixels).
   pub resolved_stops: SmallVec<[ResolvedGradientStop; 4]>,
+  /// How the colors between stops are interpolated.
+  pub interpolation: StopInterpolation,
 }
 
 impl LinearGradientDrawContext {
@@ -128,6 +141,26 @@ impl LinearGradientDrawContext {
       max_extent,
       axis_length,
       resolved_stops,
+      interpolation: StopInterpolation::new(gradient.interpolation, context),
+    }
+  }
+}
+
+/// How the colors between two resolved gradient stops are interpolated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopInterpolation {
+  /// Channel by channel, with the color interpolation of the render.
+  Channels(ColorInterpolation),
+  /// In the color space of an `in <color-space>` hint, with the stop colors encoded in `ColorSpace`.
+  Method(ColorInterpolationMethod, ColorSpace),
+}
+
+impl StopInterpolation {
+  /// Returns the interpolation of a gradient, which follows the render unless the gradient has an `in` hint.
+  pub(crate) fn new(method: Option<ColorInterpolationMethod>, context: &RenderContext) -> Self {
+    match method {
+      Some(method) => StopInterpolation::Method(method, context.color_space),
+      None => StopInterpolation::Channels(context.color_interpolation),
     }
   }
 }
@@ -316,16 +349,29 @@ impl VerticalKeyword {
 
 impl<'i> FromCss<'i> for LinearGradient {
   fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, LinearGradient> {
-    input.expect_function_matching("linear-gradient")?;
+    let location = input.current_source_location();
+    let function = input.expect_function()?.clone();
+
+    let repeating = match_ignore_ascii_case! {&function,
+      "linear-gradient" => false,
+      "repeating-linear-gradient" => true,
+      _ => return Err(location.new_basic_unexpected_token_error(Token::Function(function)).into()),
+    };
 
     input.parse_nested_block(|input| {
-      let angle = if let Ok(angle) = input.try_parse(Angle::from_css) {
+      // The angle and the `in` hint can come in either order.
+      let mut interpolation = input.try_parse(ColorInterpolationMethod::from_css).ok();
+      let angle = input.try_parse(Angle::from_css).ok();
+
+      if interpolation.is_none() {
+        interpolation = input.try_parse(ColorInterpolationMethod::from_css).ok();
+      }
+
+      if angle.is_some() || interpolation.is_some() {
         input.try_parse(Parser::expect_comma).ok();
+      }
 
-        angle
-      } else {
-        Angle::new(180.0)
-      };
+      let angle = angle.unwrap_or(Angle::new(180.0));
 
       let mut stops = SmallVec::new();
 
@@ -335,7 +381,12 @@ impl<'i> FromCss<'i> for LinearGradient {
         stops.push(GradientStop::from_css(input)?);
       }
 
-      Ok(LinearGradient { angle, stops })
+      Ok(LinearGradient {
+        angle,
+        stops,
+        interpolation,
+        repeating,
+      })
     })
   }
 }
@@ -419,7 +470,10 @@ impl<'i> FromCss<'i> for Angle {
 
 #[cfg(test)]
 mod tests {
-  use crate::GlobalContext;
+  use crate::{
+    GlobalContext,
+    layout::style::{HueInterpolation, InterpolationColorSpace},
+  };
   use smallvec::smallvec;
 
   use super::*;
@@ -439,7 +493,9 @@ mod tests {
             color: ColorInput::Value(Color([0, 0, 255, 255])),
             hint: None,
           },
-        ]
+        ],
+        interpolation: None,
+        repeating: false,
       })
     )
   }
@@ -529,7 +585,9 @@ mod tests {
             color: ColorInput::Value(Color([0, 0, 255, 255])),
             hint: None,
           },
-        ]
+        ],
+        interpolation: None,
+        repeating: false,
       })
     )
   }
@@ -549,7 +607,9 @@ mod tests {
             color: ColorInput::Value(Color([0, 0, 255, 255])),
             hint: Some(StopPosition(Length::Percentage(100.0))),
           },
-        ]
+        ],
+        interpolation: None,
+        repeating: false,
       })
     );
   }
@@ -570,7 +630,9 @@ mod tests {
             color: ColorInput::Value(Color([0, 0, 255, 255])),
             hint: None,
           },
-        ]
+        ],
+        interpolation: None,
+        repeating: false,
       })
     );
   }
@@ -584,7 +646,9 @@ mod tests {
         stops: smallvec![GradientStop::ColorHint {
           color: ColorInput::Value(Color([255, 0, 0, 255])),
           hint: None,
-        },]
+        },],
+        interpolation: None,
+        repeating: false,
       })
     );
   }
@@ -605,7 +669,9 @@ mod tests {
             color: ColorInput::Value(Color::from_rgb(0x0000ff)),
             hint: None,
           },
-        ]
+        ],
+        interpolation: None,
+        repeating: false,
       })
     );
   }
@@ -697,7 +763,9 @@ mod tests {
             color: Color([0, 0, 255, 255]).into(),
             hint: None,
           },
-        ]
+        ],
+        interpolation: None,
+        repeating: false,
       })
     );
   }
@@ -716,6 +784,8 @@ mod tests {
           hint: Some(StopPosition(Length::Percentage(100.0))),
         },
       ],
+      interpolation: None,
+      repeating: false,
     };
 
     // Test at the top (should be red)
@@ -753,6 +823,8 @@ mod tests {
           hint: Some(StopPosition(Length::Percentage(100.0))),
         },
       ],
+      interpolation: None,
+      repeating: false,
     };
 
     // Test at the left (should be red)
@@ -775,6 +847,8 @@ mod tests {
         color: Color([255, 0, 0, 255]).into(), // Red
         hint: None,
       }],
+      interpolation: None,
+      repeating: false,
     };
 
     // Should always return the same color
@@ -790,6 +864,8 @@ mod tests {
     let gradient = LinearGradient {
       angle: Angle::new(0.0),
       stops: smallvec![],
+      interpolation: None,
+      repeating: false,
     };
 
     // Should return transparent
@@ -839,6 +915,93 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  fn test_parse_repeating_linear_gradient() {
+    assert_eq!(
+      LinearGradient::from_str("repeating-linear-gradient(45deg, #ff0000 0px, #0000ff 10px)"),
+      Ok(LinearGradient {
+        angle: Angle::new(45.0),
+        stops: smallvec![
+          GradientStop::ColorHint {
+            color: ColorInput::Value(Color([255, 0, 0, 255])),
+            hint: Some(StopPosition(Length::Px(0.0))),
+          },
+          GradientStop::ColorHint {
+            color: ColorInput::Value(Color([0, 0, 255, 255])),
+            hint: Some(StopPosition(Length::Px(10.0))),
+          },
+        ],
+        interpolation: None,
+        repeating: true,
+      })
+    );
+  }
+
+  #[test]
+  fn test_repeating_linear_gradient_stripes() -> ParseResult<'static, ()> {
+    let gradient = LinearGradient::from_str(
+      "repeating-linear-gradient(to right, #ff0000 0px, #ff0000 10px, #0000ff 10px, #0000ff 20px)",
+    )?;
+
+    let context = GlobalContext::default();
+    let dummy_context = RenderContext::new(&context, (40, 40).into(), Default::default());
+    let ctx = gradient.to_draw_context(40.0, 40.0, &dummy_context);
+
+    assert_eq!(gradient.at(5, 0, &ctx), Color([255, 0, 0, 255]));
+    assert_eq!(gradient.at(15, 0, &ctx), Color([0, 0, 255, 255]));
+
+    // the stripes repeat past the last stop
+    assert_eq!(gradient.at(25, 0, &ctx), Color([255, 0, 0, 255]));
+    assert_eq!(gradient.at(35, 0, &ctx), Color([0, 0, 255, 255]));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_linear_gradient_with_interpolation() -> ParseResult<'static, ()> {
+    let gradient = LinearGradient::from_str("linear-gradient(to right in oklch, red, blue)")?;
+
+    assert_eq!(gradient.angle, Angle::new(90.0));
+    assert_eq!(
+      gradient.interpolation,
+      Some(ColorInterpolationMethod {
+        color_space: InterpolationColorSpace::Oklch,
+        hue: HueInterpolation::Shorter,
+      })
+    );
+    assert_eq!(gradient.stops.len(), 2);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_linear_gradient_interpolation_default_angle() -> ParseResult<'static, ()> {
+    let gradient = LinearGradient::from_str("linear-gradient(in oklab, red, blue)")?;
+
+    assert_eq!(gradient.angle, Angle::new(180.0));
+    assert!(gradient.interpolation.is_some());
+    assert_eq!(gradient.stops.len(), 2);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_linear_gradient_at_oklab_interpolation() -> ParseResult<'static, ()> {
+    let gradient = LinearGradient::from_str("linear-gradient(to right in oklab, black, white)")?;
+
+    let context = GlobalContext::default();
+    let dummy_context = RenderContext::new(&context, (100, 1).into(), Default::default());
+    let ctx = gradient.to_draw_context(100.0, 1.0, &dummy_context);
+
+    // the perceptual midpoint is darker than the sRGB one
+    let [r, g, b, a] = gradient.at(50, 0, &ctx).0;
+
+    assert!((95..=103).contains(&r));
+    assert_eq!([g, b, a], [r, r, 255]);
+
+    Ok(())
+  }
+
   #[test]
   fn test_stop_position_parsing_fraction_number() {
     assert_eq!(
@@ -894,6 +1057,8 @@ mod tests {
           hint: Some(StopPosition(Length::Px(100.0))),
         },
       ],
+      interpolation: None,
+      repeating: false,
     };
 
     let context = GlobalContext::default();
@@ -921,6 +1086,8 @@ mod tests {
           hint: Some(StopPosition(Length::Px(0.0))),
         },
       ],
+      interpolation: None,
+      repeating: false,
     };
     let context = GlobalContext::default();
     let ctx = RenderContext::new(&context, (200, 100).into(), Default::default());
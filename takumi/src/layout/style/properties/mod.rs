@@ -9,10 +9,13 @@ mod background_image;
 mod background_position;
 mod background_repeat;
 mod background_size;
+mod blend_mode;
 mod border;
+mod border_image;
 mod box_shadow;
 mod clip_path;
 mod color;
+mod conic_gradient;
 mod filter;
 mod flex;
 mod flex_grow;
@@ -41,6 +44,7 @@ mod text_wrap;
 mod transform;
 mod white_space;
 mod word_break;
+mod z_index;
 
 use std::borrow::Cow;
 
@@ -50,10 +54,13 @@ pub use background_image::*;
 pub use background_position::*;
 pub use background_repeat::*;
 pub use background_size::*;
+pub use blend_mode::*;
 pub use border::*;
+pub use border_image::*;
 pub use box_shadow::*;
 pub use clip_path::*;
 pub use color::*;
+pub use conic_gradient::*;
 use fast_image_resize::ResizeAlg;
 pub use filter::*;
 pub use flex::*;
@@ -62,6 +69,7 @@ pub use font_feature_settings::*;
 pub use font_style::*;
 pub use font_variation_settings::*;
 pub use font_weight::*;
+pub(crate) use gradient_utils::*;
 pub use grid::*;
 pub use length::*;
 pub use line_clamp::*;
@@ -82,6 +90,7 @@ pub use text_wrap::*;
 pub use transform::*;
 pub use white_space::*;
 pub use word_break::*;
+pub use z_index::*;
 
 use cssparser::{ParseError, Parser, ParserInput, Token, match_ignore_ascii_case};
 use image::imageops::FilterType;
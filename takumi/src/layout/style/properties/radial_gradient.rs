@@ -1,11 +1,11 @@
 use cssparser::{Parser, Token, match_ignore_ascii_case};
 use smallvec::SmallVec;
 
-use super::gradient_utils::{color_from_stops, resolve_stops_along_axis};
+use super::gradient_utils::{color_from_stops, repeat_position, resolve_stops_along_axis};
 use crate::{
   layout::style::{
-    BackgroundPosition, Color, FromCss, Gradient, GradientStop, Length, ParseResult,
-    ResolvedGradientStop,
+    BackgroundPosition, Color, ColorInterpolationMethod, FromCss, Gradient, GradientStop, Length,
+    ParseResult, ResolvedGradientStop, StopInterpolation,
   },
   rendering::RenderContext,
 };
@@ -21,6 +21,10 @@ pub struct RadialGradient {
   pub center: BackgroundPosition,
   /// Gradient stops
   pub stops: Vec<GradientStop>,
+  /// The color space the stops are interpolated in, from an `in <color-space>` hint
+  pub interpolation: Option<ColorInterpolationMethod>,
+  /// Whether the stops repeat past the last one, as `repeating-radial-gradient()`
+  pub repeating: bool,
 }
 
 /// Supported shapes for radial gradients
@@ -64,6 +68,8 @@ pub struct RadialGradientDrawContext {
   pub radius_y: f32,
   /// Resolved and ordered color stops.
   pub resolved_stops: SmallVec<[ResolvedGradientStop; 4]>,
+  /// How the colors between stops are interpolated.
+  pub interpolation: StopInterpolation,
 }
 
 impl Gradient for RadialGradient {
@@ -81,8 +87,13 @@ impl Gradient for RadialGradient {
     let dx = (x as f32 - ctx.cx) / ctx.radius_x.max(1e-6);
     let dy = (y as f32 - ctx.cy) / ctx.radius_y.max(1e-6);
     let position = (dx * dx + dy * dy).sqrt() * ctx.radius_x.max(ctx.radius_y);
+    let position = if self.repeating {
+      repeat_position(position, &ctx.resolved_stops)
+    } else {
+      position
+    };
 
-    color_from_stops(position, &ctx.resolved_stops)
+    color_from_stops(position, &ctx.resolved_stops, ctx.interpolation)
   }
 
   fn to_draw_context(&self, width: f32, height: f32, context: &RenderContext) -> Self::DrawContext {
@@ -181,18 +192,27 @@ impl RadialGradientDrawContext {
       radius_x,
       radius_y,
       resolved_stops,
+      interpolation: StopInterpolation::new(gradient.interpolation, context),
     }
   }
 }
 
 impl<'i> FromCss<'i> for RadialGradient {
   fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, RadialGradient> {
-    input.expect_function_matching("radial-gradient")?;
+    let location = input.current_source_location();
+    let function = input.expect_function()?.clone();
+
+    let repeating = match_ignore_ascii_case! {&function,
+      "radial-gradient" => false,
+      "repeating-radial-gradient" => true,
+      _ => return Err(location.new_basic_unexpected_token_error(Token::Function(function)).into()),
+    };
 
     input.parse_nested_block(|input| {
       let mut shape = RadialShape::Ellipse;
       let mut size = RadialSize::FarthestCorner;
       let mut center = BackgroundPosition::default();
+      let mut interpolation = None;
 
       loop {
         if let Ok(s) = input.try_parse(RadialShape::from_css) {
@@ -210,6 +230,11 @@ impl<'i> FromCss<'i> for RadialGradient {
           continue;
         }
 
+        if let Ok(method) = input.try_parse(ColorInterpolationMethod::from_css) {
+          interpolation = Some(method);
+          continue;
+        }
+
         input.try_parse(Parser::expect_comma).ok();
 
         break;
@@ -229,6 +254,8 @@ impl<'i> FromCss<'i> for RadialGradient {
         size,
         center,
         stops,
+        interpolation,
+        repeating,
       })
     })
   }
@@ -290,6 +317,8 @@ mod tests {
             hint: None,
           },
         ],
+        interpolation: None,
+        repeating: false,
       })
     );
   }
@@ -315,6 +344,8 @@ mod tests {
             hint: None,
           },
         ],
+        interpolation: None,
+        repeating: false,
       })
     );
   }
@@ -343,6 +374,8 @@ mod tests {
             hint: None,
           },
         ],
+        interpolation: None,
+        repeating: false,
       })
     );
   }
@@ -371,6 +404,8 @@ mod tests {
             hint: None,
           },
         ],
+        interpolation: None,
+        repeating: false,
       })
     );
   }
@@ -399,6 +434,8 @@ mod tests {
             hint: Some(StopPosition(Length::Percentage(0.0))),
           },
         ],
+        interpolation: None,
+        repeating: false,
       })
     );
   }
@@ -428,10 +465,57 @@ mod tests {
             hint: Some(StopPosition(Length::Percentage(100.0))),
           },
         ],
+        interpolation: None,
+        repeating: false,
+      })
+    );
+  }
+
+  #[test]
+  fn test_parse_repeating_radial_gradient() {
+    let gradient =
+      RadialGradient::from_str("repeating-radial-gradient(circle, #ff0000 0px, #0000ff 10px)");
+
+    assert_eq!(
+      gradient,
+      Ok(RadialGradient {
+        shape: RadialShape::Circle,
+        size: RadialSize::FarthestCorner,
+        center: BackgroundPosition::default(),
+        stops: vec![
+          GradientStop::ColorHint {
+            color: Color([255, 0, 0, 255]).into(),
+            hint: Some(StopPosition(Length::Px(0.0))),
+          },
+          GradientStop::ColorHint {
+            color: Color([0, 0, 255, 255]).into(),
+            hint: Some(StopPosition(Length::Px(10.0))),
+          },
+        ],
+        interpolation: None,
+        repeating: true,
       })
     );
   }
 
+  #[test]
+  fn test_repeating_radial_gradient_rings() -> ParseResult<'static, ()> {
+    let gradient = RadialGradient::from_str(
+      "repeating-radial-gradient(circle, #ff0000 0px, #ff0000 10px, #0000ff 10px, #0000ff 20px)",
+    )?;
+
+    let context = GlobalContext::default();
+    let render_context = RenderContext::new(&context, (100, 100).into(), Default::default());
+    let ctx = gradient.to_draw_context(100.0, 100.0, &render_context);
+
+    assert_eq!(gradient.at(55, 50, &ctx), Color([255, 0, 0, 255]));
+    assert_eq!(gradient.at(65, 50, &ctx), Color([0, 0, 255, 255]));
+    assert_eq!(gradient.at(75, 50, &ctx), Color([255, 0, 0, 255]));
+    assert_eq!(gradient.at(85, 50, &ctx), Color([0, 0, 255, 255]));
+
+    Ok(())
+  }
+
   #[test]
   fn resolve_stops_percentage_and_px_radial() {
     let gradient = RadialGradient {
@@ -452,6 +536,8 @@ mod tests {
           hint: Some(StopPosition(Length::Px(100.0))),
         },
       ],
+      interpolation: None,
+      repeating: false,
     };
 
     let context = GlobalContext::default();
@@ -486,6 +572,8 @@ mod tests {
           hint: Some(StopPosition(Length::Px(0.0))),
         },
       ],
+      interpolation: None,
+      repeating: false,
     };
 
     let context = GlobalContext::default();
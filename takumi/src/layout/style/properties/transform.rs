@@ -1,6 +1,7 @@
 use std::ops::{Mul, MulAssign};
 
 use cssparser::{Parser, Token, match_ignore_ascii_case};
+use serde::Serialize;
 use smallvec::SmallVec;
 use taffy::{Point, Size};
 
@@ -29,7 +30,7 @@ pub enum Transform {
 /// | a c x |
 /// | b d y |
 /// | 0 0 1 |
-#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
 pub struct Affine {
   /// Horizontal scaling / cosine of rotation
   pub a: f32,
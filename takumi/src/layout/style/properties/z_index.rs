@@ -0,0 +1,66 @@
+use cssparser::{Parser, Token, match_ignore_ascii_case};
+
+use crate::layout::style::{FromCss, ParseResult, tw::TailwindPropertyParser};
+
+/// Controls the painting order of an element and its descendants.
+///
+/// An integer value creates a stacking context, painted above (or below, if negative)
+/// siblings with a lower value.
+/// Reference: <https://www.w3.org/TR/CSS2/visuren.html#z-index>
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ZIndex {
+  /// The element is painted in tree order, and doesn't create a stacking context on its own.
+  #[default]
+  Auto,
+  /// The element creates a stacking context painted at this level.
+  Integer(i32),
+}
+
+impl<'i> FromCss<'i> for ZIndex {
+  fn from_css(input: &mut Parser<'i, '_>) -> ParseResult<'i, Self> {
+    if input
+      .try_parse(|input| input.expect_ident_matching("auto"))
+      .is_ok()
+    {
+      return Ok(ZIndex::Auto);
+    }
+
+    let location = input.current_source_location();
+
+    match input.next()? {
+      Token::Number {
+        int_value: Some(value),
+        ..
+      } => Ok(ZIndex::Integer(*value)),
+      token => Err(location.new_unexpected_token_error(token.clone())),
+    }
+  }
+}
+
+impl TailwindPropertyParser for ZIndex {
+  fn parse_tw(token: &str) -> Option<Self> {
+    match_ignore_ascii_case! { token,
+      "auto" => Some(ZIndex::Auto),
+      _ => token.parse::<i32>().ok().map(ZIndex::Integer),
+    }
+  }
+}
+
+impl From<i32> for ZIndex {
+  fn from(value: i32) -> Self {
+    ZIndex::Integer(value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_z_index() {
+    assert_eq!(ZIndex::from_str("auto"), Ok(ZIndex::Auto));
+    assert_eq!(ZIndex::from_str("10"), Ok(ZIndex::Integer(10)));
+    assert_eq!(ZIndex::from_str("-1"), Ok(ZIndex::Integer(-1)));
+    assert!(ZIndex::from_str("1.5").is_err());
+  }
+}
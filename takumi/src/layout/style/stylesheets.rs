@@ -11,7 +11,7 @@ use crate::{
     inline::InlineBrush,
     style::{CssValue, properties::*},
   },
-  rendering::{RenderContext, SizedShadow},
+  rendering::{RenderContext, SizedShadow, has_border_image},
 };
 
 /// Helper macro to define the `Style` struct and `InheritedStyle` struct.
@@ -97,6 +97,7 @@ define_style!(
   flex_wrap: FlexWrap,
   flex_basis: Option<Length>,
   position: Position,
+  z_index: ZIndex,
   rotate: Option<Angle>,
   scale: Option<SpacePair<PercentageNumber>>,
   scale_x: Option<PercentageNumber>,
@@ -129,7 +130,23 @@ define_style!(
   border_right_width: Option<Length>,
   border_bottom_width: Option<Length>,
   border_left_width: Option<Length>,
+  border_style: Option<Sides<BorderStyle>>,
+  border_top_style: Option<BorderStyle>,
+  border_right_style: Option<BorderStyle>,
+  border_bottom_style: Option<BorderStyle>,
+  border_left_style: Option<BorderStyle>,
   border: Border,
+  outline: Border,
+  outline_width: Option<Length>,
+  outline_style: Option<BorderStyle>,
+  outline_color: Option<ColorInput>,
+  outline_offset: Option<Length>,
+  border_image: BorderImage,
+  border_image_source: Option<BackgroundImage>,
+  border_image_slice: Option<BorderImageSlice>,
+  border_image_width: Option<Sides<BorderImageLength>>,
+  border_image_outset: Option<Sides<BorderImageLength>>,
+  border_image_repeat: Option<BorderImageRepeat>,
   object_fit: ObjectFit,
   overflow: SpacePair<Overflow>,
   overflow_x: Option<Overflow>,
@@ -142,6 +159,7 @@ define_style!(
   background_repeat: Option<BackgroundRepeats>,
   background_color: ColorInput<false>,
   background_clip: BackgroundClip,
+  background_blend_mode: BlendModes,
   box_shadow: Option<BoxShadows>,
   grid_auto_columns: Option<GridTrackSizes>,
   grid_auto_rows: Option<GridTrackSizes>,
@@ -157,6 +175,8 @@ define_style!(
   border_color: Option<ColorInput>,
   color: ColorInput where inherit = true,
   filter: Filters,
+  backdrop_filter: Filters,
+  mix_blend_mode: BlendMode,
   font_size: Option<Length> where inherit = true,
   font_family: Option<FontFamily> where inherit = true,
   line_height: LineHeight where inherit = true,
@@ -487,9 +507,10 @@ impl InheritedStyle {
     )
   }
 
+  /// Sides with a `none` or `hidden` style have no width.
   #[inline]
   fn resolved_border_width(&self) -> taffy::Rect<Length> {
-    Self::resolve_rect_with_longhands(
+    let width = Self::resolve_rect_with_longhands(
       self
         .border_width
         .or_else(|| self.border.width.map(Into::into))
@@ -500,6 +521,39 @@ impl InheritedStyle {
       self.border_right_width,
       self.border_bottom_width,
       self.border_left_width,
+    );
+    let style = self.resolved_border_style();
+
+    let visible = |width: Length, style: BorderStyle| {
+      if style.is_visible() {
+        width
+      } else {
+        Length::Px(0.0)
+      }
+    };
+
+    taffy::Rect {
+      top: visible(width.top, style.top),
+      right: visible(width.right, style.right),
+      bottom: visible(width.bottom, style.bottom),
+      left: visible(width.left, style.left),
+    }
+  }
+
+  /// Resolves the style of each border side, sides without a style are solid.
+  #[inline]
+  pub(crate) fn resolved_border_style(&self) -> taffy::Rect<BorderStyle> {
+    Self::resolve_rect_with_longhands(
+      self
+        .border_style
+        .or_else(|| self.border.style.map(Into::into))
+        .unwrap_or(Sides([BorderStyle::Solid; 4])),
+      None,
+      None,
+      self.border_top_style,
+      self.border_right_style,
+      self.border_bottom_style,
+      self.border_left_style,
     )
   }
 
@@ -516,6 +570,78 @@ impl InheritedStyle {
     )
   }
 
+  /// Counts the layers painted by a box without children, which overlap each other.
+  ///
+  /// `has_content` is whether the box paints text or an image on top of its background and border.
+  /// Layers that might be painted are counted, so a count of at most one means they can't overlap.
+  pub(crate) fn painted_layer_count(
+    &self,
+    color_space: ColorSpace,
+    current_color: Color,
+    has_content: bool,
+  ) -> usize {
+    let shadows = self.box_shadow.as_ref().map_or(0, SmallVec::len);
+
+    let background_color = usize::from(
+      self
+        .background_color
+        .resolve_in(color_space, current_color, u8::MAX)
+        .0[3]
+        > 0,
+    );
+
+    let background_images = match self.background_image.as_deref() {
+      Some(images) => images
+        .iter()
+        .filter(|image| !matches!(image, BackgroundImage::None))
+        .count(),
+      None => self
+        .background
+        .iter()
+        .filter(|background| !matches!(background.image, BackgroundImage::None))
+        .count(),
+    };
+
+    let border_width = self.resolved_border_width();
+    let border = usize::from(
+      has_border_image(self)
+        || [
+          border_width.top,
+          border_width.right,
+          border_width.bottom,
+          border_width.left,
+        ]
+        .into_iter()
+        .any(|width| width != Length::Px(0.0)),
+    );
+
+    let outline = usize::from(
+      self
+        .outline_style
+        .or(self.outline.style)
+        .is_some_and(BorderStyle::is_visible),
+    );
+
+    let content = if has_content {
+      let decoration_line = self
+        .text_decoration_line
+        .as_ref()
+        .unwrap_or(&self.text_decoration.line);
+
+      1 + usize::from(
+        self
+          .text_shadow
+          .as_ref()
+          .is_some_and(|shadows| !shadows.is_empty()),
+      ) + usize::from(self.webkit_text_stroke_width.is_some() || self.webkit_text_stroke.is_some())
+        + usize::from(!decoration_line.is_empty())
+    } else {
+      0
+    };
+
+    shadows + background_color + background_images + border + outline + content
+  }
+
   pub(crate) fn to_sized_font_style(&'_ self, context: &RenderContext) -> SizedFontStyle<'_> {
     let line_height = self.line_height.into_parley(&context.sizing);
 
@@ -544,23 +670,26 @@ impl InheritedStyle {
               *shadow,
               &context.sizing,
               context.current_color,
+              context.color_space,
               context.opacity,
               Size::from_length(context.sizing.font_size),
             )
           })
           .collect()
       }),
-      color: self.color.resolve(context.current_color, context.opacity),
+      color: self
+        .color
+        .resolve_in(context.color_space, context.current_color, context.opacity),
       text_stroke_color: self
         .webkit_text_stroke_color
         .or(self.webkit_text_stroke.and_then(|stroke| stroke.color))
         .unwrap_or_default()
-        .resolve(context.current_color, context.opacity),
+        .resolve_in(context.color_space, context.current_color, context.opacity),
       text_decoration_color: self
         .text_decoration_color
         .or(self.text_decoration.color)
         .unwrap_or(ColorInput::CurrentColor)
-        .resolve(context.current_color, context.opacity),
+        .resolve_in(context.color_space, context.current_color, context.opacity),
     }
   }
 
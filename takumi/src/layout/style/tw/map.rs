@@ -68,6 +68,8 @@ pub enum PropertyParser {
   BackgroundClip(fn(BackgroundClip) -> TailwindProperty),
   Blur(fn(TwBlur) -> TailwindProperty),
   Filter(fn(Filters) -> TailwindProperty),
+  BlendMode(fn(BlendMode) -> TailwindProperty),
+  ZIndex(fn(ZIndex) -> TailwindProperty),
 }
 
 impl PropertyParser {
@@ -108,6 +110,8 @@ impl PropertyParser {
       Self::Angle(f) => parse_property(suffix, *f),
       Self::Blur(f) => parse_property(suffix, *f),
       Self::Filter(f) => parse_property(suffix, *f),
+      Self::BlendMode(f) => parse_property(suffix, *f),
+      Self::ZIndex(f) => parse_property(suffix, *f),
     }
   }
 }
@@ -124,6 +128,9 @@ pub static PREFIX_PARSERS: phf::Map<&str, &[PropertyParser]> = phf_map! {
     PropertyParser::BgSize(TailwindProperty::BackgroundSize),
   ],
   "bg-clip" => &[PropertyParser::BackgroundClip(TailwindProperty::BackgroundClip)],
+  "bg-blend" => &[PropertyParser::BlendMode(TailwindProperty::BackgroundBlendMode)],
+  "mix-blend" => &[PropertyParser::BlendMode(TailwindProperty::MixBlendMode)],
+  "z" => &[PropertyParser::ZIndex(TailwindProperty::ZIndex)],
   "bg-size" => &[PropertyParser::BgSize(TailwindProperty::BackgroundSize)],
   "bg-position" => &[PropertyParser::BgPosition(TailwindProperty::BackgroundPosition)],
   "w" => &[PropertyParser::LengthAuto(TailwindProperty::Width)],
@@ -159,6 +166,19 @@ pub static PREFIX_PARSERS: phf::Map<&str, &[PropertyParser]> = phf_map! {
   "border-l" => &[PropertyParser::BorderWidth(TailwindProperty::BorderLeftWidth)],
   "border-x" => &[PropertyParser::BorderWidth(TailwindProperty::BorderXWidth)],
   "border-y" => &[PropertyParser::BorderWidth(TailwindProperty::BorderYWidth)],
+  "outline" => &[
+    PropertyParser::ColorCurrent(TailwindProperty::OutlineColor),
+    PropertyParser::BorderWidth(TailwindProperty::OutlineWidth),
+  ],
+  "outline-offset" => &[PropertyParser::BorderWidth(TailwindProperty::OutlineOffset)],
+  "ring" => &[
+    PropertyParser::ColorCurrent(TailwindProperty::RingColor),
+    PropertyParser::BorderWidth(TailwindProperty::Ring),
+  ],
+  "ring-offset" => &[
+    PropertyParser::ColorCurrent(TailwindProperty::RingOffsetColor),
+    PropertyParser::BorderWidth(TailwindProperty::RingOffset),
+  ],
   "grow" | "flex-grow" => &[PropertyParser::FlexGrow(TailwindProperty::FlexGrow)],
   "shrink" | "flex-shrink" => &[PropertyParser::FlexGrow(TailwindProperty::FlexShrink)],
   "basis" | "flex-basis" => &[PropertyParser::LengthAuto(TailwindProperty::FlexBasis)],
@@ -235,10 +255,33 @@ pub static PREFIX_PARSERS: phf::Map<&str, &[PropertyParser]> = phf_map! {
   "saturate" => &[PropertyParser::Percentage(TailwindProperty::Saturate)],
   "sepia" => &[PropertyParser::Percentage(TailwindProperty::Sepia)],
   "filter" => &[PropertyParser::Filter(TailwindProperty::Filter)],
+  "backdrop-blur" => &[PropertyParser::Blur(TailwindProperty::BackdropBlur)],
+  "backdrop-brightness" => &[PropertyParser::Percentage(TailwindProperty::BackdropBrightness)],
+  "backdrop-contrast" => &[PropertyParser::Percentage(TailwindProperty::BackdropContrast)],
+  "backdrop-grayscale" => &[PropertyParser::Percentage(TailwindProperty::BackdropGrayscale)],
+  "backdrop-hue-rotate" => &[PropertyParser::Angle(TailwindProperty::BackdropHueRotate)],
+  "backdrop-invert" => &[PropertyParser::Percentage(TailwindProperty::BackdropInvert)],
+  "backdrop-opacity" => &[PropertyParser::Percentage(TailwindProperty::BackdropOpacity)],
+  "backdrop-saturate" => &[PropertyParser::Percentage(TailwindProperty::BackdropSaturate)],
+  "backdrop-sepia" => &[PropertyParser::Percentage(TailwindProperty::BackdropSepia)],
+  "backdrop-filter" => &[PropertyParser::Filter(TailwindProperty::BackdropFilter)],
 };
 
 pub static FIXED_PROPERTIES: phf::Map<&str, TailwindProperty> = phf_map! {
   "border" => TailwindProperty::BorderWidth(TwBorderWidth(Length::Px(1.0))),
+  "border-solid" => TailwindProperty::BorderStyle(BorderStyle::Solid),
+  "border-dashed" => TailwindProperty::BorderStyle(BorderStyle::Dashed),
+  "border-dotted" => TailwindProperty::BorderStyle(BorderStyle::Dotted),
+  "border-double" => TailwindProperty::BorderStyle(BorderStyle::Double),
+  "border-hidden" => TailwindProperty::BorderStyle(BorderStyle::Hidden),
+  "border-none" => TailwindProperty::BorderStyle(BorderStyle::None),
+  "outline" => TailwindProperty::OutlineWidth(TwBorderWidth(Length::Px(1.0))),
+  "outline-solid" => TailwindProperty::OutlineStyle(BorderStyle::Solid),
+  "outline-dashed" => TailwindProperty::OutlineStyle(BorderStyle::Dashed),
+  "outline-dotted" => TailwindProperty::OutlineStyle(BorderStyle::Dotted),
+  "outline-double" => TailwindProperty::OutlineStyle(BorderStyle::Double),
+  "outline-none" => TailwindProperty::OutlineStyle(BorderStyle::None),
+  "ring" => TailwindProperty::Ring(TwBorderWidth(Length::Px(1.0))),
   "box-border" => TailwindProperty::BoxSizing(BoxSizing::BorderBox),
   "box-content" => TailwindProperty::BoxSizing(BoxSizing::ContentBox),
   "inline" => TailwindProperty::Display(Display::Inline),
@@ -364,4 +407,7 @@ pub static FIXED_PROPERTIES: phf::Map<&str, TailwindProperty> = phf_map! {
   "grayscale" => TailwindProperty::Grayscale(PercentageNumber(1.0)),
   "invert" => TailwindProperty::Invert(PercentageNumber(1.0)),
   "sepia" => TailwindProperty::Sepia(PercentageNumber(1.0)),
+  "backdrop-grayscale" => TailwindProperty::BackdropGrayscale(PercentageNumber(1.0)),
+  "backdrop-invert" => TailwindProperty::BackdropInvert(PercentageNumber(1.0)),
+  "backdrop-sepia" => TailwindProperty::BackdropSepia(PercentageNumber(1.0)),
 };
@@ -65,9 +65,13 @@ impl TailwindValues {
   }
 
   pub(crate) fn apply(&self, style: &mut Style, viewport: Viewport) {
+    let mut ring = TwRing::default();
+
     for value in self.iter() {
-      value.apply(style, viewport);
+      value.apply(style, &mut ring, viewport);
     }
+
+    ring.apply(style);
   }
 }
 
@@ -94,14 +98,14 @@ pub struct TailwindValue {
 }
 
 impl TailwindValue {
-  pub(crate) fn apply(&self, style: &mut Style, viewport: Viewport) {
+  pub(crate) fn apply(&self, style: &mut Style, ring: &mut TwRing, viewport: Viewport) {
     if let Some(breakpoint) = self.breakpoint
       && !breakpoint.matches(viewport)
     {
       return;
     }
 
-    self.property.apply(style);
+    self.property.apply(style, ring);
   }
 
   /// Parse a tailwind value from a token.
@@ -135,6 +139,69 @@ impl TailwindValue {
   }
 }
 
+/// The ring utilities of an element, like the `--tw-ring-*` variables Tailwind sets.
+///
+/// Tailwind draws rings as spread box shadows, so they're composed with the other shadows
+/// once every value is applied, which keeps the outline free.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct TwRing {
+  width: Option<Length>,
+  color: Option<ColorInput>,
+  offset_width: Option<Length>,
+  offset_color: Option<ColorInput>,
+}
+
+impl TwRing {
+  fn apply(self, style: &mut Style) {
+    let Some(width) = self.width else {
+      return;
+    };
+
+    let spread = |spread_radius, color| BoxShadow {
+      inset: false,
+      offset_x: Length::zero(),
+      offset_y: Length::zero(),
+      blur_radius: Length::zero(),
+      spread_radius,
+      color,
+    };
+
+    let mut shadows = BoxShadows::new();
+
+    // The offset is painted over the ring, which spreads past it.
+    if let Some(offset_width) = self.offset_width {
+      shadows.push(spread(
+        offset_width,
+        self
+          .offset_color
+          .unwrap_or(ColorInput::Value(Color::white())),
+      ));
+    }
+
+    shadows.push(spread(
+      add_lengths(width, self.offset_width.unwrap_or(Length::zero())),
+      self.color.unwrap_or(ColorInput::CurrentColor),
+    ));
+
+    if let CssValue::Value(Some(box_shadow)) = std::mem::take(&mut style.box_shadow) {
+      shadows.extend(box_shadow);
+    }
+
+    style.box_shadow = Some(shadows).into();
+  }
+}
+
+/// Adds two lengths of the same unit, otherwise keeps the first one.
+fn add_lengths(a: Length, b: Length) -> Length {
+  match (a, b) {
+    (a, b) if b == Length::zero() => a,
+    (Length::Px(a), Length::Px(b)) => Length::Px(a + b),
+    (Length::Rem(a), Length::Rem(b)) => Length::Rem(a + b),
+    (Length::Em(a), Length::Em(b)) => Length::Em(a + b),
+    (a, _) => a,
+  }
+}
+
 /// Represents a breakpoint.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Breakpoint(pub(crate) Length);
@@ -325,6 +392,24 @@ pub enum TailwindProperty {
   BorderXWidth(TwBorderWidth),
   /// `border-block-width` property.
   BorderYWidth(TwBorderWidth),
+  /// `border-style` property.
+  BorderStyle(BorderStyle),
+  /// `outline-width` property, solid unless a style is given.
+  OutlineWidth(TwBorderWidth),
+  /// `outline-style` property.
+  OutlineStyle(BorderStyle),
+  /// `outline-color` property.
+  OutlineColor(ColorInput),
+  /// `outline-offset` property.
+  OutlineOffset(TwBorderWidth),
+  /// `ring` width, drawn as a spread `box-shadow`.
+  Ring(TwBorderWidth),
+  /// `ring` color.
+  RingColor(ColorInput),
+  /// `ring-offset` width, the gap between the border box and the ring.
+  RingOffset(TwBorderWidth),
+  /// `ring-offset` color the gap is filled with.
+  RingOffsetColor(ColorInput),
   /// `border-radius` property.
   Rounded(TwRounded),
   /// `border-top-left-radius` property.
@@ -425,6 +510,32 @@ pub enum TailwindProperty {
   Sepia(PercentageNumber),
   /// `filter` property.
   Filter(Filters),
+  /// `backdrop-filter: blur()` property.
+  BackdropBlur(TwBlur),
+  /// `backdrop-filter: brightness()` property.
+  BackdropBrightness(PercentageNumber),
+  /// `backdrop-filter: contrast()` property.
+  BackdropContrast(PercentageNumber),
+  /// `backdrop-filter: grayscale()` property.
+  BackdropGrayscale(PercentageNumber),
+  /// `backdrop-filter: hue-rotate()` property.
+  BackdropHueRotate(Angle),
+  /// `backdrop-filter: invert()` property.
+  BackdropInvert(PercentageNumber),
+  /// `backdrop-filter: opacity()` property.
+  BackdropOpacity(PercentageNumber),
+  /// `backdrop-filter: saturate()` property.
+  BackdropSaturate(PercentageNumber),
+  /// `backdrop-filter: sepia()` property.
+  BackdropSepia(PercentageNumber),
+  /// `backdrop-filter` property.
+  BackdropFilter(Filters),
+  /// `mix-blend-mode` property.
+  MixBlendMode(BlendMode),
+  /// `background-blend-mode` property.
+  BackgroundBlendMode(BlendMode),
+  /// `z-index` property.
+  ZIndex(ZIndex),
 }
 
 /// A trait for parsing tailwind properties.
@@ -468,6 +579,13 @@ impl Neg for TailwindProperty {
       TailwindProperty::Rotate(angle) => TailwindProperty::Rotate(-angle),
       TailwindProperty::LetterSpacing(length) => TailwindProperty::LetterSpacing(-length),
       TailwindProperty::HueRotate(angle) => TailwindProperty::HueRotate(-angle),
+      TailwindProperty::BackdropHueRotate(angle) => TailwindProperty::BackdropHueRotate(-angle),
+      TailwindProperty::ZIndex(ZIndex::Integer(value)) => {
+        TailwindProperty::ZIndex(ZIndex::Integer(-value))
+      }
+      TailwindProperty::OutlineOffset(TwBorderWidth(length)) => {
+        TailwindProperty::OutlineOffset(TwBorderWidth(-length))
+      }
       _ => self,
     }
   }
@@ -516,7 +634,7 @@ impl TailwindProperty {
     None
   }
 
-  pub(crate) fn apply(&self, style: &mut Style) {
+  pub(crate) fn apply(&self, style: &mut Style, ring: &mut TwRing) {
     match *self {
       TailwindProperty::BackgroundClip(background_clip) => {
         style.background_clip = background_clip.into();
@@ -689,6 +807,37 @@ impl TailwindProperty {
         style.border_top_width = Some(tw_border_width.0).into();
         style.border_bottom_width = Some(tw_border_width.0).into();
       }
+      TailwindProperty::BorderStyle(border_style) => {
+        style.border_style = Some(Sides([border_style; 4])).into();
+      }
+      TailwindProperty::OutlineWidth(tw_border_width) => {
+        style.outline_width = Some(tw_border_width.0).into();
+
+        if matches!(style.outline_style, CssValue::Unset) {
+          style.outline_style = Some(BorderStyle::Solid).into();
+        }
+      }
+      TailwindProperty::OutlineStyle(outline_style) => {
+        style.outline_style = Some(outline_style).into();
+      }
+      TailwindProperty::OutlineColor(color_input) => {
+        style.outline_color = Some(color_input).into();
+      }
+      TailwindProperty::OutlineOffset(tw_border_width) => {
+        style.outline_offset = Some(tw_border_width.0).into();
+      }
+      TailwindProperty::Ring(tw_border_width) => {
+        ring.width = Some(tw_border_width.0);
+      }
+      TailwindProperty::RingColor(color_input) => {
+        ring.color = Some(color_input);
+      }
+      TailwindProperty::RingOffset(tw_border_width) => {
+        ring.offset_width = Some(tw_border_width.0);
+      }
+      TailwindProperty::RingOffsetColor(color_input) => {
+        ring.offset_color = Some(color_input);
+      }
       TailwindProperty::Rounded(rounded) => {
         style.border_radius = BorderRadius(Sides([SpacePair::from_single(rounded.0); 4])).into();
       }
@@ -929,6 +1078,45 @@ impl TailwindProperty {
       TailwindProperty::Filter(ref filters) => {
         style.filter = filters.clone().into();
       }
+      TailwindProperty::BackdropBlur(tw_blur) => {
+        style.backdrop_filter = smallvec![Filter::Blur(tw_blur.0)].into();
+      }
+      TailwindProperty::BackdropBrightness(percentage_number) => {
+        style.backdrop_filter = smallvec![Filter::Brightness(percentage_number)].into();
+      }
+      TailwindProperty::BackdropContrast(percentage_number) => {
+        style.backdrop_filter = smallvec![Filter::Contrast(percentage_number)].into();
+      }
+      TailwindProperty::BackdropGrayscale(percentage_number) => {
+        style.backdrop_filter = smallvec![Filter::Grayscale(percentage_number)].into();
+      }
+      TailwindProperty::BackdropHueRotate(angle) => {
+        style.backdrop_filter = smallvec![Filter::HueRotate(angle)].into();
+      }
+      TailwindProperty::BackdropInvert(percentage_number) => {
+        style.backdrop_filter = smallvec![Filter::Invert(percentage_number)].into();
+      }
+      TailwindProperty::BackdropOpacity(percentage_number) => {
+        style.backdrop_filter = smallvec![Filter::Opacity(percentage_number)].into();
+      }
+      TailwindProperty::BackdropSaturate(percentage_number) => {
+        style.backdrop_filter = smallvec![Filter::Saturate(percentage_number)].into();
+      }
+      TailwindProperty::BackdropSepia(percentage_number) => {
+        style.backdrop_filter = smallvec![Filter::Sepia(percentage_number)].into();
+      }
+      TailwindProperty::BackdropFilter(ref filters) => {
+        style.backdrop_filter = filters.clone().into();
+      }
+      TailwindProperty::MixBlendMode(blend_mode) => {
+        style.mix_blend_mode = blend_mode.into();
+      }
+      TailwindProperty::BackgroundBlendMode(blend_mode) => {
+        style.background_blend_mode = smallvec![blend_mode].into();
+      }
+      TailwindProperty::ZIndex(z_index) => {
+        style.z_index = z_index.into();
+      }
     }
   }
 }
@@ -945,6 +1133,50 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_parse_blend_mode() {
+    assert_eq!(
+      TailwindProperty::parse("mix-blend-multiply"),
+      Some(TailwindProperty::MixBlendMode(BlendMode::Multiply))
+    );
+    assert_eq!(
+      TailwindProperty::parse("bg-blend-color-dodge"),
+      Some(TailwindProperty::BackgroundBlendMode(BlendMode::ColorDodge))
+    );
+  }
+
+  #[test]
+  fn test_parse_backdrop_filter() {
+    assert_eq!(
+      TailwindProperty::parse("backdrop-blur-md"),
+      Some(TailwindProperty::BackdropBlur(TwBlur(Length::Px(12.0))))
+    );
+    assert_eq!(
+      TailwindProperty::parse("backdrop-grayscale"),
+      Some(TailwindProperty::BackdropGrayscale(PercentageNumber(1.0)))
+    );
+    assert_eq!(
+      TailwindProperty::parse("backdrop-saturate-150"),
+      Some(TailwindProperty::BackdropSaturate(PercentageNumber(1.5)))
+    );
+  }
+
+  #[test]
+  fn test_parse_z_index() {
+    assert_eq!(
+      TailwindProperty::parse("z-10"),
+      Some(TailwindProperty::ZIndex(ZIndex::Integer(10)))
+    );
+    assert_eq!(
+      TailwindProperty::parse("-z-10"),
+      Some(TailwindProperty::ZIndex(ZIndex::Integer(-10)))
+    );
+    assert_eq!(
+      TailwindProperty::parse("z-auto"),
+      Some(TailwindProperty::ZIndex(ZIndex::Auto))
+    );
+  }
+
   #[test]
   fn test_parse_width() {
     assert_eq!(
@@ -1044,6 +1276,103 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_parse_outline() {
+    assert_eq!(
+      TailwindProperty::parse("outline-offset-2"),
+      Some(TailwindProperty::OutlineOffset(TwBorderWidth(Length::Px(
+        2.0
+      ))))
+    );
+    assert_eq!(
+      TailwindProperty::parse("-outline-offset-2"),
+      Some(TailwindProperty::OutlineOffset(TwBorderWidth(Length::Px(
+        -2.0
+      ))))
+    );
+    assert_eq!(
+      TailwindProperty::parse("outline-dashed"),
+      Some(TailwindProperty::OutlineStyle(BorderStyle::Dashed))
+    );
+  }
+
+  #[test]
+  fn test_parse_ring() {
+    assert_eq!(
+      TailwindProperty::parse("ring"),
+      Some(TailwindProperty::Ring(TwBorderWidth(Length::Px(1.0))))
+    );
+    assert_eq!(
+      TailwindProperty::parse("ring-2"),
+      Some(TailwindProperty::Ring(TwBorderWidth(Length::Px(2.0))))
+    );
+    assert_eq!(
+      TailwindProperty::parse("ring-black"),
+      Some(TailwindProperty::RingColor(ColorInput::Value(
+        Color::black()
+      )))
+    );
+    assert_eq!(
+      TailwindProperty::parse("ring-offset-2"),
+      Some(TailwindProperty::RingOffset(TwBorderWidth(Length::Px(2.0))))
+    );
+    assert_eq!(
+      TailwindProperty::parse("ring-offset-black"),
+      Some(TailwindProperty::RingOffsetColor(ColorInput::Value(
+        Color::black()
+      )))
+    );
+  }
+
+  fn spread(spread_radius: f32, color: ColorInput) -> BoxShadow {
+    BoxShadow {
+      inset: false,
+      offset_x: Length::zero(),
+      offset_y: Length::zero(),
+      blur_radius: Length::zero(),
+      spread_radius: Length::Px(spread_radius),
+      color,
+    }
+  }
+
+  #[test]
+  fn test_ring_is_a_spread_box_shadow() -> Result<(), String> {
+    let mut style = Style::default();
+
+    TailwindValues::from_str("ring-2")?.apply(&mut style, (1000, 1000).into());
+
+    assert_eq!(
+      style.box_shadow,
+      CssValue::Value(Some(smallvec![spread(2.0, ColorInput::CurrentColor)]))
+    );
+    assert_eq!(style.outline_width, CssValue::Unset);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_ring_composes_with_shadows() -> Result<(), String> {
+    let mut style = Style::default();
+
+    TailwindValues::from_str("ring-offset-4 shadow-sm ring-2 ring-black")?
+      .apply(&mut style, (1000, 1000).into());
+
+    let Some(TailwindProperty::Shadow(shadow)) = TailwindProperty::parse("shadow-sm") else {
+      return Err("shadow-sm isn't a shadow".to_owned());
+    };
+
+    assert_eq!(
+      style.box_shadow,
+      CssValue::Value(Some(smallvec![
+        spread(4.0, ColorInput::Value(Color::white())),
+        spread(6.0, ColorInput::Value(Color::black())),
+        shadow,
+      ]))
+    );
+
+    Ok(())
+  }
+
   #[test]
   fn test_parse_col_end() {
     assert_eq!(
@@ -1101,6 +1430,12 @@ mod tests {
       // Borders
       "border",
       "border-t-2",
+      "border-dashed",
+      "border-double",
+      "outline-2",
+      "outline-offset-4",
+      "ring-blue-500",
+      "ring-offset-2",
       "rounded-lg",
       // Transforms
       "rotate-45",
@@ -6,14 +6,14 @@ use crate::{
   Result,
   layout::{
     inline::{
-      InlineItemIterator, InlineLayoutStage, ProcessedInlineSpan, create_inline_constraint,
-      create_inline_layout, measure_inline_layout,
+      InlineItemIterator, InlineLayout, InlineLayoutStage, ProcessedInlineSpan,
+      create_inline_constraint, create_inline_layout, measure_inline_layout,
     },
     node::Node,
-    style::{Display, InheritedStyle},
+    style::{Affine, Display, InheritedStyle, SizedFontStyle},
   },
   rendering::{
-    Canvas, MaxHeight, RenderContext, Sizing,
+    BorderProperties, Canvas, MaxHeight, RenderContext, Sizing,
     inline_drawing::{draw_inline_box, draw_inline_layout},
   },
 };
@@ -38,6 +38,22 @@ impl<'g, N: Node<N>> NodeTree<'g, N> {
     Ok(())
   }
 
+  /// Draws the outline around the border box, over the content of the node.
+  pub(crate) fn draw_outline(&self, canvas: &mut Canvas, layout: Layout) {
+    let Some((outline, outline_box, outset)) =
+      BorderProperties::outline_from_context(&self.context, layout.size)
+    else {
+      return;
+    };
+
+    outline.draw(
+      canvas,
+      outline_box,
+      self.context.transform * Affine::translation(-outset, -outset),
+      None,
+    );
+  }
+
   pub(crate) fn draw_content(&self, canvas: &mut Canvas, layout: Layout) -> Result<()> {
     if let Some(node) = &self.node {
       node.draw_content(&self.context, canvas, layout)?;
@@ -52,6 +68,30 @@ impl<'g, N: Node<N>> NodeTree<'g, N> {
 
     let font_style = self.context.style.to_sized_font_style(&self.context);
 
+    let (inline_layout, _, spans) = self.create_draw_inline_layout(layout, &font_style);
+
+    let boxes = spans.iter().filter_map(|span| match span {
+      ProcessedInlineSpan::Box { node, .. } => Some(node),
+      _ => None,
+    });
+
+    // Draw the inline layout without a callback first
+    let positioned_inline_boxes =
+      draw_inline_layout(&self.context, canvas, layout, inline_layout, &font_style)?;
+
+    // Then handle the inline boxes directly by zipping the node refs with their positioned boxes
+    for (node, positioned) in boxes.zip(positioned_inline_boxes.iter()) {
+      draw_inline_box(positioned, node, canvas, self.context.transform)?;
+    }
+    Ok(())
+  }
+
+  /// Creates the inline layout of the children for drawing, along with the processed spans.
+  pub(crate) fn create_draw_inline_layout<'s>(
+    &'s self,
+    layout: Layout,
+    font_style: &'s SizedFontStyle<'s>,
+  ) -> (InlineLayout, String, Vec<ProcessedInlineSpan<'s, 'g, N>>) {
     let max_height = match font_style.parent.line_clamp.as_ref() {
       Some(clamp) => Some(MaxHeight::HeightAndLines(
         layout.content_box_height(),
@@ -60,7 +100,7 @@ impl<'g, N: Node<N>> NodeTree<'g, N> {
       None => Some(MaxHeight::Absolute(layout.content_box_height())),
     };
 
-    let (inline_layout, _, spans) = create_inline_layout(
+    let (inline_layout, text, spans) = create_inline_layout(
       self.inline_items_iter(),
       Size {
         width: AvailableSpace::Definite(layout.content_box_width()),
@@ -68,25 +108,12 @@ impl<'g, N: Node<N>> NodeTree<'g, N> {
       },
       layout.content_box_width(),
       max_height,
-      &font_style,
+      font_style,
       self.context.global,
       InlineLayoutStage::Draw,
     );
 
-    let boxes = spans.iter().filter_map(|span| match span {
-      ProcessedInlineSpan::Box { node, .. } => Some(node),
-      _ => None,
-    });
-
-    // Draw the inline layout without a callback first
-    let positioned_inline_boxes =
-      draw_inline_layout(&self.context, canvas, layout, inline_layout, &font_style)?;
-
-    // Then handle the inline boxes directly by zipping the node refs with their positioned boxes
-    for (node, positioned) in boxes.zip(positioned_inline_boxes.iter()) {
-      draw_inline_box(positioned, node, canvas, self.context.transform)?;
-    }
-    Ok(())
+    (inline_layout, text, spans)
   }
 
   pub fn is_inline(&self) -> bool {
@@ -123,14 +150,43 @@ impl<'g, N: Node<N>> NodeTree<'g, N> {
 
     // currentColor itself should NOT have opacity applied yet,
     // otherwise it will cause double applying.
-    let current_color = style.color.resolve(parent_context.current_color, 255);
+    let current_color = style.color.resolve_in(
+      parent_context.color_space,
+      parent_context.current_color,
+      255,
+    );
+
+    let children = node.take_children();
 
-    let opacity = (style.opacity.0 * parent_context.opacity as f32) as u8;
+    // Overlapping descendants and layers must not show through each other, so a translucent box
+    // with children or more than one painted layer is composited as a group. Otherwise the opacity
+    // is applied to the colors of its single layer directly. Inline boxes are painted by their parent.
+    let is_opacity_group = style.opacity.0 > 0.0
+      && style.opacity.0 < 1.0
+      && !style.display.is_inline()
+      && (children
+        .as_ref()
+        .is_some_and(|children| !children.is_empty())
+        || style.painted_layer_count(
+          parent_context.color_space,
+          current_color,
+          node.inline_content().is_some(),
+        ) > 1);
+
+    let (opacity, group_opacity) = if is_opacity_group {
+      (parent_context.opacity, (style.opacity.0 * 255.0) as u8)
+    } else {
+      (
+        (style.opacity.0 * parent_context.opacity as f32) as u8,
+        u8::MAX,
+      )
+    };
 
     let mut context = RenderContext {
       style,
       current_color,
       opacity,
+      group_opacity,
       fetched_resources: parent_context.fetched_resources.clone(),
       sizing: Sizing {
         font_size,
@@ -139,7 +195,7 @@ impl<'g, N: Node<N>> NodeTree<'g, N> {
       ..*parent_context
     };
 
-    let children = node.take_children().map(|children| {
+    let children = children.map(|children| {
       children
         .into_iter()
         .map(|child| Self::from_node_impl(&context, child))
@@ -318,6 +374,7 @@ fn flush_inline_group<'g, N: Node<N>>(
       context: RenderContext {
         style: anonymous_box_style.clone(),
         fetched_resources: Default::default(), // anonymous box has nothing to render, so provide an empty map.
+        group_opacity: u8::MAX,
         ..*context
       },
       children: Some(take(inline_group)),
@@ -10,7 +10,7 @@ pub const DEFAULT_LINE_HEIGHT_SCALER: f32 = 1.2;
 pub const DEFAULT_DEVICE_PIXEL_RATIO: f32 = 1.0;
 
 /// The viewport for the image renderer.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Viewport {
   /// The width of the viewport in pixels.
   pub width: Option<u32>,
@@ -79,7 +79,7 @@
 //! - `woff2`: Enable WOFF2 font support.
 //! - `woff`: Enable WOFF font support.
 //! - `svg`: Enable SVG support.
-//! - `rayon`: Enable rayon support.
+//! - `rayon`: Enable rayon support, including tile-parallel rendering of large images.
 //!
 //! # Credits
 //!
@@ -105,6 +105,10 @@ pub mod resources;
 
 pub use error::{Result, TakumiError as Error};
 
+/// Result type of unit tests that bail out with `?` instead of unwrapping.
+#[cfg(test)]
+pub(crate) type TestResult = std::result::Result<(), Box<dyn std::error::Error>>;
+
 pub use image;
 pub use parley;
 pub use taffy;
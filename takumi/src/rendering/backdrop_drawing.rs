@@ -0,0 +1,247 @@
+use image::{GenericImageView, Rgba, RgbaImage};
+use taffy::{Layout, Point, Size};
+
+use crate::{
+  layout::style::{Color, Filter, apply_filters, apply_wide_filters},
+  rendering::{
+    BorderProperties, Canvas, CanvasConstrain, ColorInterpolation, CompositeTarget, CoverageMask,
+    DisplayBackdrop, RenderContext, WideImage, apply_mask_alpha_to_pixel, mask_index_from_coord,
+    overlay_area,
+  },
+};
+
+/// The filtered backdrop of a node, in output coordinates.
+pub(crate) struct FilteredBackdrop {
+  image: RgbaImage,
+  offset: Point<u32>,
+}
+
+/// Records drawing the `backdrop-filter` of the node, which samples what is already painted under its border box.
+///
+/// `is_below_layer` tells whether the node draws into its own layer, so the backdrop is what is below that layer.
+pub(crate) fn draw_filtered_backdrop(
+  context: &RenderContext,
+  canvas: &mut Canvas,
+  layout: Layout,
+  is_below_layer: bool,
+) {
+  let filters = &context.style.backdrop_filter;
+
+  if filters.is_empty() {
+    return;
+  }
+
+  // Blurring pulls in the colors around the border box, so sample a bit more than what is drawn.
+  let padding: f32 = filters
+    .iter()
+    .map(|filter| match filter {
+      Filter::Blur(radius) => radius.to_px(&context.sizing, 1.0).max(0.0) * 3.0,
+      _ => 0.0,
+    })
+    .sum();
+
+  let corners = [
+    Point::ZERO,
+    Point {
+      x: layout.size.width,
+      y: 0.0,
+    },
+    Point {
+      x: 0.0,
+      y: layout.size.height,
+    },
+    Point {
+      x: layout.size.width,
+      y: layout.size.height,
+    },
+  ]
+  .map(|corner| context.transform.transform_point(corner));
+
+  let (min, max) = corners.iter().fold(
+    (
+      Point {
+        x: f32::MAX,
+        y: f32::MAX,
+      },
+      Point {
+        x: f32::MIN,
+        y: f32::MIN,
+      },
+    ),
+    |(min, max), corner| {
+      (
+        Point {
+          x: min.x.min(corner.x),
+          y: min.y.min(corner.y),
+        },
+        Point {
+          x: max.x.max(corner.x),
+          y: max.y.max(corner.y),
+        },
+      )
+    },
+  );
+
+  let size = canvas.size();
+  let (width, height) = (size.width as f32, size.height as f32);
+
+  let left = (min.x - padding).floor().clamp(0.0, width) as u32;
+  let top = (min.y - padding).floor().clamp(0.0, height) as u32;
+  let right = (max.x + padding).ceil().clamp(0.0, width) as u32;
+  let bottom = (max.y + padding).ceil().clamp(0.0, height) as u32;
+
+  if left >= right || top >= bottom {
+    return;
+  }
+
+  let border = BorderProperties::from_context(context, layout.size, layout.border);
+
+  let mut paths = Vec::new();
+  border.append_mask_commands(&mut paths, layout.size, Point::ZERO);
+
+  let (mask, placement) = canvas
+    .mask_memory
+    .render(&paths, Some(context.transform), None);
+  let mask = CoverageMask::new(mask, placement);
+
+  canvas.draw_backdrop(DisplayBackdrop {
+    filters: filters.clone(),
+    sizing: context.sizing,
+    current_color: context.current_color,
+    color_space: context.color_space,
+    opacity: context.opacity,
+    from: Point { x: left, y: top },
+    to: Point {
+      x: right,
+      y: bottom,
+    },
+    mask,
+    is_below_layer,
+  });
+}
+
+impl DisplayBackdrop {
+  /// Samples the backdrop, which is the part of the output starting at `origin`, and applies the filters to it.
+  ///
+  /// Returns `None` if the sampled area is outside of the backdrop.
+  pub(crate) fn filter<I: GenericImageView<Pixel = Rgba<u8>>>(
+    &self,
+    backdrop: &I,
+    origin: Point<u32>,
+    color_interpolation: ColorInterpolation,
+  ) -> Option<FilteredBackdrop> {
+    let (left, top, right, bottom) = self.sampled_area(backdrop.dimensions(), origin)?;
+
+    let mut image = RgbaImage::from_fn(right - left, bottom - top, |x, y| {
+      backdrop.get_pixel(x + left - origin.x, y + top - origin.y)
+    });
+
+    apply_filters(
+      &mut image,
+      &self.sizing,
+      self.current_color,
+      self.color_space,
+      self.opacity,
+      color_interpolation,
+      self.filters.iter(),
+    );
+
+    Some(FilteredBackdrop {
+      image,
+      offset: Point { x: left, y: top },
+    })
+  }
+
+  /// Samples the backdrop like [`DisplayBackdrop::filter`] from an image with premultiplied `f32` channels.
+  pub(crate) fn filter_wide(
+    &self,
+    backdrop: &WideImage,
+    origin: Point<u32>,
+    color_interpolation: ColorInterpolation,
+  ) -> Option<FilteredBackdrop> {
+    let (left, top, right, bottom) =
+      self.sampled_area((backdrop.width(), backdrop.height()), origin)?;
+
+    let mut image = WideImage::from_fn(right - left, bottom - top, |x, y| {
+      backdrop.get_pixel(x + left - origin.x, y + top - origin.y)
+    });
+
+    apply_wide_filters(
+      &mut image,
+      &self.sizing,
+      self.current_color,
+      self.color_space,
+      self.opacity,
+      color_interpolation,
+      self.filters.iter(),
+    );
+
+    Some(FilteredBackdrop {
+      image: image.to_rgba8(color_interpolation),
+      offset: Point { x: left, y: top },
+    })
+  }
+
+  /// The left, top, right and bottom edges of the sampled area in output pixels,
+  /// inside of a backdrop of `size` starting at `origin`.
+  fn sampled_area(&self, size: (u32, u32), origin: Point<u32>) -> Option<(u32, u32, u32, u32)> {
+    let (width, height) = size;
+
+    let left = self.from.x.max(origin.x);
+    let top = self.from.y.max(origin.y);
+    let right = self.to.x.min(origin.x + width);
+    let bottom = self.to.y.min(origin.y + height);
+
+    if left >= right || top >= bottom {
+      return None;
+    }
+
+    Some((left, top, right, bottom))
+  }
+
+  /// Draws the filtered backdrop clipped to the rounded border box of the node.
+  pub(crate) fn draw<C: CompositeTarget>(
+    &self,
+    canvas: &mut C,
+    backdrop: &FilteredBackdrop,
+    origin: Point<u32>,
+    constrain: Option<&CanvasConstrain>,
+    color_interpolation: ColorInterpolation,
+  ) {
+    let placement = self.mask.placement;
+
+    overlay_area(
+      canvas,
+      Point {
+        x: (placement.left - origin.x as i32) as f32,
+        y: (placement.top - origin.y as i32) as f32,
+      },
+      Size {
+        width: placement.width,
+        height: placement.height,
+      },
+      constrain,
+      color_interpolation,
+      |x, y| {
+        let alpha = self.mask.data[mask_index_from_coord(x, y, placement.width)];
+
+        let source_x = placement.left + x as i32 - backdrop.offset.x as i32;
+        let source_y = placement.top + y as i32 - backdrop.offset.y as i32;
+
+        let Some(mut pixel) = u32::try_from(source_x)
+          .ok()
+          .zip(u32::try_from(source_y).ok())
+          .and_then(|(source_x, source_y)| backdrop.image.get_pixel_checked(source_x, source_y))
+          .copied()
+        else {
+          return Color::transparent().into();
+        };
+
+        apply_mask_alpha_to_pixel(&mut pixel, alpha);
+        apply_mask_alpha_to_pixel(&mut pixel, self.opacity);
+
+        pixel
+      },
+    );
+  }
+}
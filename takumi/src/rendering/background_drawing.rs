@@ -1,14 +1,15 @@
-use std::{borrow::Cow, iter::successors};
+use std::{borrow::Cow, iter::successors, sync::Arc};
 
-use image::RgbaImage;
+use image::{Rgba, RgbaImage};
 use smallvec::{SmallVec, smallvec};
-use taffy::{Point, Size};
+use taffy::{Layout, Point, Size};
 
 use crate::{
   Result,
   layout::{node::resolve_image, style::*},
   rendering::{
-    BorderProperties, Canvas, MaskMemory, RenderContext, Sizing, fast_resize, overlay_image,
+    BorderProperties, Canvas, MaskMemory, RenderContext, Sizing, blend_image, fast_resize,
+    overlay_image,
   },
 };
 
@@ -145,14 +146,17 @@ pub(crate) fn render_tile(
     BackgroundImage::None => None,
     BackgroundImage::Linear(gradient) => Some(gradient.to_image(tile_w, tile_h, context)),
     BackgroundImage::Radial(gradient) => Some(gradient.to_image(tile_w, tile_h, context)),
+    BackgroundImage::Conic(gradient) => Some(gradient.to_image(tile_w, tile_h, context)),
     BackgroundImage::Noise(noise) => Some(noise.to_image(tile_w, tile_h, context)),
     BackgroundImage::Url(url) => {
       if let Ok(source) = resolve_image(url, context) {
-        Some(
-          source
-            .render_to_rgba_image(tile_w, tile_h, context.style.image_rendering)?
-            .into_owned(),
-        )
+        let mut image = source
+          .render_to_rgba_image(tile_w, tile_h, context.style.image_rendering)?
+          .into_owned();
+
+        context.color_space.convert_srgb_image(&mut image);
+
+        Some(image)
       } else {
         None
       }
@@ -160,6 +164,65 @@ pub(crate) fn render_tile(
   })
 }
 
+/// Resolved tile size and positions along X and Y for a background-like layer.
+pub(crate) struct LayerTilePlacement {
+  pub(crate) width: u32,
+  pub(crate) height: u32,
+  pub(crate) xs: SmallVec<[i32; 1]>,
+  pub(crate) ys: SmallVec<[i32; 1]>,
+}
+
+/// Resolve the tile size and positions of a background-like layer without rasterizing it.
+/// `background-repeat: round` may change the tile size from the one resolved by `background-size`.
+pub(crate) fn resolve_layer_tile_placement(
+  pos: BackgroundPosition,
+  repeat: BackgroundRepeat,
+  tile_w: u32,
+  tile_h: u32,
+  area_w: u32,
+  area_h: u32,
+  sizing: &Sizing,
+) -> LayerTilePlacement {
+  let (xs, width) = match repeat.0 {
+    BackgroundRepeatStyle::Repeat => {
+      let origin_x = resolve_position_component_x(pos, tile_w, area_w, sizing);
+      (
+        collect_repeat_tile_positions(area_w, tile_w, origin_x),
+        tile_w,
+      )
+    }
+    BackgroundRepeatStyle::NoRepeat => {
+      let origin_x = resolve_position_component_x(pos, tile_w, area_w, sizing);
+      (smallvec![origin_x], tile_w)
+    }
+    BackgroundRepeatStyle::Space => (collect_spaced_tile_positions(area_w, tile_w), tile_w),
+    BackgroundRepeatStyle::Round => collect_stretched_tile_positions(area_w, tile_w),
+  };
+
+  let (ys, height) = match repeat.1 {
+    BackgroundRepeatStyle::Repeat => {
+      let origin_y = resolve_position_component_y(pos, tile_h, area_h, sizing);
+      (
+        collect_repeat_tile_positions(area_h, tile_h, origin_y),
+        tile_h,
+      )
+    }
+    BackgroundRepeatStyle::NoRepeat => {
+      let origin_y = resolve_position_component_y(pos, tile_h, area_h, sizing);
+      (smallvec![origin_y], tile_h)
+    }
+    BackgroundRepeatStyle::Space => (collect_spaced_tile_positions(area_h, tile_h), tile_h),
+    BackgroundRepeatStyle::Round => collect_stretched_tile_positions(area_h, tile_h),
+  };
+
+  LayerTilePlacement {
+    width,
+    height,
+    xs,
+    ys,
+  }
+}
+
 /// Resolve tile image, positions along X and Y for a background-like layer.
 /// Returns (tile_image, tile_w, tile_h, xs, ys).
 pub(crate) fn resolve_layer_tiles(
@@ -172,7 +235,7 @@ pub(crate) fn resolve_layer_tiles(
   context: &RenderContext,
 ) -> Result<Option<ImageTiles>> {
   // Compute tile size
-  let (mut tile_w, mut tile_h) = resolve_background_size(size, (area_w, area_h), image, context);
+  let (tile_w, tile_h) = resolve_background_size(size, (area_w, area_h), image, context);
 
   if tile_w == 0 || tile_h == 0 {
     return Ok(None);
@@ -182,47 +245,28 @@ pub(crate) fn resolve_layer_tiles(
     return Ok(None);
   };
 
-  let xs: SmallVec<[i32; 1]> = match repeat.0 {
-    BackgroundRepeatStyle::Repeat => {
-      let origin_x = resolve_position_component_x(pos, tile_w, area_w, &context.sizing);
-      collect_repeat_tile_positions(area_w, tile_w, origin_x)
-    }
-    BackgroundRepeatStyle::NoRepeat => {
-      let origin_x = resolve_position_component_x(pos, tile_w, area_w, &context.sizing);
-      smallvec![origin_x]
-    }
-    BackgroundRepeatStyle::Space => collect_spaced_tile_positions(area_w, tile_w),
-    BackgroundRepeatStyle::Round => {
-      let (px, new_w) = collect_stretched_tile_positions(area_w, tile_w);
-      if new_w != tile_w {
-        tile_w = new_w;
-        tile_image = fast_resize(&tile_image, tile_w, tile_h, context.style.image_rendering)?;
-      }
-      px
-    }
-  };
+  let placement =
+    resolve_layer_tile_placement(pos, repeat, tile_w, tile_h, area_w, area_h, &context.sizing);
 
-  let ys: SmallVec<[i32; 1]> = match repeat.1 {
-    BackgroundRepeatStyle::Repeat => {
-      let origin_y = resolve_position_component_y(pos, tile_h, area_h, &context.sizing);
-      collect_repeat_tile_positions(area_h, tile_h, origin_y)
-    }
-    BackgroundRepeatStyle::NoRepeat => {
-      let origin_y = resolve_position_component_y(pos, tile_h, area_h, &context.sizing);
-      smallvec![origin_y]
-    }
-    BackgroundRepeatStyle::Space => collect_spaced_tile_positions(area_h, tile_h),
-    BackgroundRepeatStyle::Round => {
-      let (py, new_h) = collect_stretched_tile_positions(area_h, tile_h);
-      if new_h != tile_h {
-        tile_h = new_h;
-        tile_image = fast_resize(&tile_image, tile_w, tile_h, context.style.image_rendering)?;
-      }
-      py
-    }
-  };
+  if placement.width != tile_w {
+    tile_image = fast_resize(
+      &tile_image,
+      placement.width,
+      tile_h,
+      context.style.image_rendering,
+    )?;
+  }
+
+  if placement.height != tile_h {
+    tile_image = fast_resize(
+      &tile_image,
+      placement.width,
+      placement.height,
+      context.style.image_rendering,
+    )?;
+  }
 
-  Ok(Some((tile_image, xs, ys)))
+  Ok(Some((tile_image, placement.xs, placement.ys)))
 }
 
 /// Collects a list of tile positions to place along an axis.
@@ -430,6 +474,7 @@ pub(crate) fn create_mask(
           255,
           None,
           mask_memory,
+          context.color_interpolation,
         );
       }
     }
@@ -438,10 +483,16 @@ pub(crate) fn create_mask(
   Ok(Some(composed.iter().skip(3).step_by(4).copied().collect()))
 }
 
-pub(crate) fn collect_background_image_tiles(
-  context: &RenderContext,
-  border_box: Size<f32>,
-) -> Result<Vec<ImageTiles>> {
+/// The images, positions, sizes and repeats of background layers.
+pub(crate) type BackgroundLayers<'s> = (
+  Cow<'s, [BackgroundImage]>,
+  Cow<'s, [BackgroundPosition]>,
+  Cow<'s, [BackgroundSize]>,
+  Cow<'s, [BackgroundRepeat]>,
+);
+
+/// Resolves the background layers, falling back to the `background` shorthand for unset longhands.
+pub(crate) fn resolve_background_layers<'s>(context: &'s RenderContext) -> BackgroundLayers<'s> {
   let background_image = context
     .style
     .background_image
@@ -458,58 +509,71 @@ pub(crate) fn collect_background_image_tiles(
       )
     });
 
-  resolve_layers_tiles(
-    &background_image,
-    &context
-      .style
-      .background_position
-      .as_deref()
-      .map(Cow::Borrowed)
-      .unwrap_or_else(|| {
-        Cow::Owned(
-          context
-            .style
-            .background
-            .iter()
-            .map(|background| background.position)
-            .collect::<Vec<_>>(),
-        )
-      }),
-    &context
-      .style
-      .background_size
-      .as_deref()
-      .map(Cow::Borrowed)
-      .unwrap_or_else(|| {
-        Cow::Owned(
-          context
-            .style
-            .background
-            .iter()
-            .map(|background| background.size)
-            .collect::<Vec<_>>(),
-        )
-      }),
-    &context
-      .style
-      .background_repeat
-      .as_deref()
-      .map(Cow::Borrowed)
-      .unwrap_or_else(|| {
-        Cow::Owned(
-          context
-            .style
-            .background
-            .iter()
-            .map(|background| background.repeat)
-            .collect::<Vec<_>>(),
-        )
-      }),
-    context,
-    border_box,
+  let background_position = context
+    .style
+    .background_position
+    .as_deref()
+    .map(Cow::Borrowed)
+    .unwrap_or_else(|| {
+      Cow::Owned(
+        context
+          .style
+          .background
+          .iter()
+          .map(|background| background.position)
+          .collect::<Vec<_>>(),
+      )
+    });
+
+  let background_size = context
+    .style
+    .background_size
+    .as_deref()
+    .map(Cow::Borrowed)
+    .unwrap_or_else(|| {
+      Cow::Owned(
+        context
+          .style
+          .background
+          .iter()
+          .map(|background| background.size)
+          .collect::<Vec<_>>(),
+      )
+    });
+
+  let background_repeat = context
+    .style
+    .background_repeat
+    .as_deref()
+    .map(Cow::Borrowed)
+    .unwrap_or_else(|| {
+      Cow::Owned(
+        context
+          .style
+          .background
+          .iter()
+          .map(|background| background.repeat)
+          .collect::<Vec<_>>(),
+      )
+    });
+
+  (
+    background_image,
+    background_position,
+    background_size,
+    background_repeat,
   )
 }
 
+pub(crate) fn collect_background_image_tiles(
+  context: &RenderContext,
+  border_box: Size<f32>,
+) -> Result<Vec<ImageTiles>> {
+  let (images, positions, sizes, repeats) = resolve_background_layers(context);
+
+  resolve_layers_tiles(&images, &positions, &sizes, &repeats, context, border_box)
+}
+
 pub(crate) fn create_background_image(
   context: &RenderContext,
   border_box: Size<f32>,
@@ -557,6 +621,7 @@ pub(crate) fn create_background_image(
           255,
           None,
           mask_memory,
+          context.color_interpolation,
         );
       }
     }
@@ -573,10 +638,13 @@ pub(crate) fn draw_background_layers(
   canvas: &mut Canvas,
 ) {
   for (tile_image, xs, ys) in tiles {
+    // The tile is shared by every repetition of it in the display list.
+    let tile_image = Arc::new(tile_image);
+
     for y in &ys {
       for x in &xs {
         canvas.overlay_image(
-          (&tile_image).into(),
+          Arc::clone(&tile_image).into(),
           radius,
           context.transform * Affine::translation(*x as f32, *y as f32),
           ImageScalingAlgorithm::Auto,
@@ -586,3 +654,142 @@ pub(crate) fn draw_background_layers(
     }
   }
 }
+
+/// Whether any background layer blends with a mode other than `normal`.
+pub(crate) fn has_background_blend_mode(style: &InheritedStyle) -> bool {
+  style
+    .background_blend_mode
+    .iter()
+    .any(|mode| *mode != BlendMode::Normal)
+}
+
+/// Composes the background color and image layers into a single image of `size`,
+/// blending each layer with the layers below it using `background-blend-mode`.
+///
+/// The layers are blended as an isolated group, so they never blend with the content behind the element.
+pub(crate) fn create_blended_background_image(
+  context: &RenderContext,
+  border_box: Size<f32>,
+  size: Size<f32>,
+  offset: Point<f32>,
+  mask_memory: &mut MaskMemory,
+) -> Result<RgbaImage> {
+  let background_color: Rgba<u8> = context
+    .style
+    .background_color
+    .resolve_in(context.color_space, context.current_color, u8::MAX)
+    .into();
+
+  let mut composed = RgbaImage::from_pixel(size.width as u32, size.height as u32, background_color);
+
+  let (images, positions, sizes, repeats) = resolve_background_layers(context);
+  let modes = &context.style.background_blend_mode;
+
+  let last_position = positions.last().copied().unwrap_or_default();
+  let last_size = sizes.last().copied().unwrap_or_default();
+  let last_repeat = repeats.last().copied().unwrap_or_default();
+  let last_mode = modes.last().copied().unwrap_or_default();
+
+  for (i, image) in images.iter().enumerate() {
+    let Some((tile_image, xs, ys)) = resolve_layer_tiles(
+      image,
+      positions.get(i).copied().unwrap_or(last_position),
+      sizes.get(i).copied().unwrap_or(last_size),
+      repeats.get(i).copied().unwrap_or(last_repeat),
+      border_box.width as u32,
+      border_box.height as u32,
+      context,
+    )?
+    else {
+      continue;
+    };
+
+    let mode = modes.get(i).copied().unwrap_or(last_mode);
+
+    // Normal layers are drawn in place, other modes need the whole layer before blending.
+    let mut layer = if mode == BlendMode::Normal {
+      None
+    } else {
+      Some(RgbaImage::new(composed.width(), composed.height()))
+    };
+
+    for y in &ys {
+      for x in &xs {
+        overlay_image(
+          layer.as_mut().unwrap_or(&mut composed),
+          (&tile_image).into(),
+          Default::default(),
+          Affine::translation(*x as f32 - offset.x, *y as f32 - offset.y),
+          context.style.image_rendering,
+          255,
+          None,
+          mask_memory,
+          context.color_interpolation,
+        );
+      }
+    }
+
+    if let Some(layer) = layer {
+      blend_image(&mut composed, &layer, mode, context.color_interpolation);
+    }
+  }
+
+  Ok(composed)
+}
+
+/// Draws the background color and image layers blended with `background-blend-mode`.
+pub(crate) fn draw_blended_background(
+  context: &RenderContext,
+  canvas: &mut Canvas,
+  layout: Layout,
+) -> Result<()> {
+  let mut radius = BorderProperties::from_context(context, layout.size, layout.border);
+
+  let (size, offset) = match context.style.background_clip {
+    BackgroundClip::BorderBox => (layout.size, Point::zero()),
+    BackgroundClip::PaddingBox => {
+      radius.inset_by_border_width();
+
+      (
+        Size {
+          width: layout.size.width - layout.border.left - layout.border.right,
+          height: layout.size.height - layout.border.top - layout.border.bottom,
+        },
+        Point {
+          x: layout.border.left,
+          y: layout.border.top,
+        },
+      )
+    }
+    BackgroundClip::ContentBox => {
+      radius.inset_by_border_width();
+      radius.expand_by(layout.padding.map(|size| -size));
+
+      (
+        layout.content_box_size(),
+        Point {
+          x: layout.padding.left + layout.border.left,
+          y: layout.padding.top + layout.border.top,
+        },
+      )
+    }
+    _ => return Ok(()),
+  };
+
+  if size.width < 1.0 || size.height < 1.0 {
+    return Ok(());
+  }
+
+  let image =
+    create_blended_background_image(context, layout.size, size, offset, &mut canvas.mask_memory)?;
+
+  canvas.overlay_image(
+    image.into(),
+    radius,
+    Affine::translation(offset.x, offset.y) * context.transform,
+    context.style.image_rendering,
+    context.opacity,
+  );
+
+  Ok(())
+}
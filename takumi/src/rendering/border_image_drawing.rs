@@ -0,0 +1,390 @@
+use image::{RgbaImage, imageops::crop_imm};
+use taffy::{Layout, Rect, Size};
+
+use crate::{
+  Result,
+  layout::{node::resolve_image, style::*},
+  rendering::{Canvas, RenderContext, fast_resize, render_tile},
+};
+
+/// Returns the source of the border image, if there's any.
+fn resolve_border_image_source(style: &InheritedStyle) -> Option<&BackgroundImage> {
+  style
+    .border_image_source
+    .as_ref()
+    .or(style.border_image.source.as_ref())
+    .filter(|source| !matches!(source, BackgroundImage::None))
+}
+
+/// Whether the node draws a border image in place of its border.
+pub(crate) fn has_border_image(style: &InheritedStyle) -> bool {
+  resolve_border_image_source(style).is_some()
+}
+
+/// Draws the border image of the node, sliced into nine parts and laid out over the border image area.
+///
+/// Returns `false` when there's no image to draw, so the border styles are drawn instead.
+pub(crate) fn draw_border_image(
+  context: &RenderContext,
+  canvas: &mut Canvas,
+  layout: Layout,
+) -> Result<bool> {
+  let style = &context.style;
+
+  let Some(source) = resolve_border_image_source(style) else {
+    return Ok(false);
+  };
+
+  let slice = style
+    .border_image_slice
+    .or(style.border_image.slice)
+    .unwrap_or_default();
+  let widths = style
+    .border_image_width
+    .or(style.border_image.width)
+    .unwrap_or(Sides([BorderImageLength::Number(1.0); 4]));
+  let outset = style
+    .border_image_outset
+    .or(style.border_image.outset)
+    .unwrap_or(Sides([BorderImageLength::Number(0.0); 4]));
+  let repeat = style
+    .border_image_repeat
+    .or(style.border_image.repeat)
+    .unwrap_or_default();
+
+  // Numbers are multiples of the border width.
+  let resolve_length =
+    |value: BorderImageLength, border: f32, reference: f32, auto: f32| match value {
+      BorderImageLength::Number(number) => number * border,
+      BorderImageLength::Length(Length::Auto) => auto,
+      BorderImageLength::Length(length) => length.to_px(&context.sizing, reference),
+    };
+
+  let outset: Rect<BorderImageLength> = outset.into();
+  let outset = Rect {
+    top: resolve_length(outset.top, layout.border.top, 0.0, 0.0),
+    right: resolve_length(outset.right, layout.border.right, 0.0, 0.0),
+    bottom: resolve_length(outset.bottom, layout.border.bottom, 0.0, 0.0),
+    left: resolve_length(outset.left, layout.border.left, 0.0, 0.0),
+  }
+  .map(|size| size.max(0.0));
+
+  let area = Size {
+    width: (layout.size.width + outset.left + outset.right).round() as u32,
+    height: (layout.size.height + outset.top + outset.bottom).round() as u32,
+  };
+
+  if area.width == 0 || area.height == 0 {
+    return Ok(true);
+  }
+
+  // Gradients have no intrinsic size, so they're sized to the border image area.
+  let image_size = match source {
+    BackgroundImage::Url(url) => {
+      let Ok(image) = resolve_image(url, context) else {
+        return Ok(false);
+      };
+
+      let (width, height) = image.size();
+
+      Size {
+        width: width.round() as u32,
+        height: height.round() as u32,
+      }
+    }
+    _ => area,
+  };
+
+  if image_size.width == 0 || image_size.height == 0 {
+    return Ok(false);
+  }
+
+  let Some(image) = render_tile(source, image_size.width, image_size.height, context)? else {
+    return Ok(false);
+  };
+
+  let slice_offsets: Rect<BorderImageLength> = slice.offsets.into();
+  let resolve_slice = |value: BorderImageLength, size: u32| {
+    (resolve_length(value, 1.0, size as f32, 0.0)
+      .round()
+      .max(0.0) as u32)
+      .min(size)
+  };
+
+  let slices = Rect {
+    top: resolve_slice(slice_offsets.top, image_size.height),
+    right: resolve_slice(slice_offsets.right, image_size.width),
+    bottom: resolve_slice(slice_offsets.bottom, image_size.height),
+    left: resolve_slice(slice_offsets.left, image_size.width),
+  };
+
+  let widths: Rect<BorderImageLength> = widths.into();
+  let mut widths = Rect {
+    top: resolve_length(
+      widths.top,
+      layout.border.top,
+      area.height as f32,
+      slices.top as f32,
+    ),
+    right: resolve_length(
+      widths.right,
+      layout.border.right,
+      area.width as f32,
+      slices.right as f32,
+    ),
+    bottom: resolve_length(
+      widths.bottom,
+      layout.border.bottom,
+      area.height as f32,
+      slices.bottom as f32,
+    ),
+    left: resolve_length(
+      widths.left,
+      layout.border.left,
+      area.width as f32,
+      slices.left as f32,
+    ),
+  }
+  .map(|size| size.max(0.0));
+
+  // Opposite widths that don't fit in the area are scaled down together.
+  let scale = (area.width as f32 / (widths.left + widths.right))
+    .min(area.height as f32 / (widths.top + widths.bottom));
+
+  if scale < 1.0 {
+    widths = widths.map(|size| size * scale);
+  }
+
+  let composed = compose_border_image(
+    &image,
+    slices,
+    widths.map(|size| size.round() as u32),
+    area,
+    repeat,
+    slice.fill,
+    context.style.image_rendering,
+  )?;
+
+  canvas.overlay_image(
+    (&composed).into(),
+    Default::default(),
+    context.transform * Affine::translation(-outset.left, -outset.top),
+    context.style.image_rendering,
+    context.opacity,
+  );
+
+  Ok(true)
+}
+
+/// Lays out the nine parts of the image sliced by `slices` into an image of the border image area,
+/// with the corners and edges sized by `widths`.
+fn compose_border_image(
+  image: &RgbaImage,
+  slices: Rect<u32>,
+  widths: Rect<u32>,
+  area: Size<u32>,
+  repeat: BorderImageRepeat,
+  fill: bool,
+  algorithm: ImageScalingAlgorithm,
+) -> Result<RgbaImage> {
+  let mut composed = RgbaImage::new(area.width, area.height);
+
+  let source_xs = [
+    0,
+    slices.left,
+    image.width().saturating_sub(slices.right).max(slices.left),
+    image.width(),
+  ];
+  let source_ys = [
+    0,
+    slices.top,
+    image.height().saturating_sub(slices.bottom).max(slices.top),
+    image.height(),
+  ];
+  let target_xs = [
+    0,
+    widths.left,
+    area.width.saturating_sub(widths.right).max(widths.left),
+    area.width,
+  ];
+  let target_ys = [
+    0,
+    widths.top,
+    area.height.saturating_sub(widths.bottom).max(widths.top),
+    area.height,
+  ];
+
+  // The ratio between the target and source size of an edge, or `None` when either is empty.
+  let ratio =
+    |target: u32, source: u32| (target > 0 && source > 0).then(|| target as f32 / source as f32);
+
+  let top_ratio = ratio(widths.top, slices.top);
+  let bottom_ratio = ratio(widths.bottom, slices.bottom);
+  let left_ratio = ratio(widths.left, slices.left);
+  let right_ratio = ratio(widths.right, slices.right);
+
+  let rows = source_ys.windows(2).zip(target_ys.windows(2)).enumerate();
+
+  for (row, (source_y, target_y)) in rows {
+    let columns = source_xs.windows(2).zip(target_xs.windows(2)).enumerate();
+
+    for (column, (source_x, target_x)) in columns {
+      if row == 1 && column == 1 && !fill {
+        continue;
+      }
+
+      let source = Rect {
+        left: source_x[0],
+        right: source_x[1],
+        top: source_y[0],
+        bottom: source_y[1],
+      };
+      let target = Rect {
+        left: target_x[0],
+        right: target_x[1],
+        top: target_y[0],
+        bottom: target_y[1],
+      };
+
+      let source_size = Size {
+        width: source.right - source.left,
+        height: source.bottom - source.top,
+      };
+      let target_size = Size {
+        width: target.right - target.left,
+        height: target.bottom - target.top,
+      };
+
+      if source_size.width == 0
+        || source_size.height == 0
+        || target_size.width == 0
+        || target_size.height == 0
+      {
+        continue;
+      }
+
+      // Edges keep the aspect ratio of their slice scaled to the border width,
+      // the middle is scaled like the top and left edges, or the bottom and right ones.
+      let horizontal_ratio = match row {
+        1 => top_ratio.or(bottom_ratio),
+        _ => ratio(target_size.height, source_size.height),
+      };
+      let vertical_ratio = match column {
+        1 => left_ratio.or(right_ratio),
+        _ => ratio(target_size.width, source_size.width),
+      };
+
+      let (tile_width, xs) = if column == 1 {
+        layout_tiles(
+          repeat.0,
+          target_size.width as f32,
+          source_size.width as f32 * horizontal_ratio.unwrap_or(1.0),
+        )
+      } else {
+        (target_size.width as f32, vec![0.0])
+      };
+      let (tile_height, ys) = if row == 1 {
+        layout_tiles(
+          repeat.1,
+          target_size.height as f32,
+          source_size.height as f32 * vertical_ratio.unwrap_or(1.0),
+        )
+      } else {
+        (target_size.height as f32, vec![0.0])
+      };
+
+      // Rounded up, so neighboring tiles overlap instead of leaving gaps.
+      let tile_width = tile_width.ceil().max(1.0) as u32;
+      let tile_height = tile_height.ceil().max(1.0) as u32;
+
+      let part = crop_imm(
+        image,
+        source.left,
+        source.top,
+        source_size.width,
+        source_size.height,
+      )
+      .to_image();
+      let tile = fast_resize(&part, tile_width, tile_height, algorithm)?;
+
+      for &y in &ys {
+        for &x in &xs {
+          blit_clipped(
+            &mut composed,
+            &tile,
+            target.left as i32 + x.round() as i32,
+            target.top as i32 + y.round() as i32,
+            target,
+          );
+        }
+      }
+    }
+  }
+
+  Ok(composed)
+}
+
+/// Lays out tiles of `tile` size along an axis of `area` size,
+/// returning the size of the tiles and their positions.
+fn layout_tiles(repeat: BorderImageRepeatStyle, area: f32, tile: f32) -> (f32, Vec<f32>) {
+  if tile <= 0.0 {
+    return (area, vec![0.0]);
+  }
+
+  match repeat {
+    BorderImageRepeatStyle::Stretch => (area, vec![0.0]),
+    BorderImageRepeatStyle::Repeat => {
+      // Centered in the area, clipped at both ends.
+      let first = (area - tile) / 2.0;
+      let start = first - (first / tile).ceil() * tile;
+      let count = ((area - start) / tile).ceil().max(0.0) as usize;
+
+      (
+        tile,
+        (0..count)
+          .map(|index| start + index as f32 * tile)
+          .collect(),
+      )
+    }
+    BorderImageRepeatStyle::Round => {
+      let count = (area / tile).round().max(1.0);
+      let tile = area / count;
+
+      (
+        tile,
+        (0..count as usize)
+          .map(|index| index as f32 * tile)
+          .collect(),
+      )
+    }
+    BorderImageRepeatStyle::Space => {
+      let count = (area / tile).floor();
+      let gap = (area - count * tile) / (count + 1.0);
+
+      (
+        tile,
+        (0..count as usize)
+          .map(|index| gap + index as f32 * (tile + gap))
+          .collect(),
+      )
+    }
+  }
+}
+
+/// Copies `tile` to `(x, y)` of `target_image`, skipping the pixels outside of `clip`.
+fn blit_clipped(target_image: &mut RgbaImage, tile: &RgbaImage, x: i32, y: i32, clip: Rect<u32>) {
+  let left = x.max(clip.left as i32);
+  let top = y.max(clip.top as i32);
+  let right = (x + tile.width() as i32).min(clip.right as i32);
+  let bottom = (y + tile.height() as i32).min(clip.bottom as i32);
+
+  for target_y in top..bottom {
+    for target_x in left..right {
+      target_image.put_pixel(
+        target_x as u32,
+        target_y as u32,
+        *tile.get_pixel((target_x - x) as u32, (target_y - y) as u32),
+      );
+    }
+  }
+}
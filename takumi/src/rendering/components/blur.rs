@@ -1,6 +1,8 @@
 use image::RgbaImage;
 use wide::u32x4;
 
+use crate::rendering::{ColorInterpolation, WideImage, linear_to_srgb, srgb_to_linear};
+
 /// Specifies the type of blur operation, which affects how the CSS radius is interpreted.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BlurType {
@@ -29,42 +31,186 @@ impl BlurType {
 }
 
 /// Applies a Gaussian approximation using 3-pass Box Blur with SIMD (u32x4).
-pub(crate) fn apply_blur(image: &mut RgbaImage, radius: f32, blur_type: BlurType) {
+pub(crate) fn apply_blur(
+  image: &mut RgbaImage,
+  radius: f32,
+  blur_type: BlurType,
+  color_interpolation: ColorInterpolation,
+) {
+  let Some(box_radius) = box_radius(radius, blur_type) else {
+    return;
+  };
+
+  let (width, height) = image.dimensions();
+  if width == 0 || height == 0 {
+    return;
+  }
+
+  if color_interpolation == ColorInterpolation::LinearRgb {
+    let mut data = to_linear_premultiplied(image);
+
+    box_blur(&mut data, width, height, box_radius);
+
+    return from_linear_premultiplied(image, &data);
+  }
+
+  premultiply_alpha(image);
+
+  box_blur(image, width, height, box_radius);
+
+  unpremultiply_alpha(image);
+}
+
+/// The radius of the box blur passes approximating the Gaussian blur, `None` if it is too small to show.
+fn box_radius(radius: f32, blur_type: BlurType) -> Option<u32> {
   let sigma = blur_type.to_sigma(radius);
   if sigma <= 0.5 {
-    return;
+    return None;
   }
 
-  let box_radius = (((4.0 * sigma * sigma + 1.0).sqrt() - 1.0) * 0.5)
-    .round()
-    .max(1.0) as u32;
+  Some(
+    (((4.0 * sigma * sigma + 1.0).sqrt() - 1.0) * 0.5)
+      .round()
+      .max(1.0) as u32,
+  )
+}
 
-  let (width, height) = image.dimensions();
+/// Applies the same blur as [`apply_blur`] to an image with premultiplied `f32` channels.
+pub(crate) fn apply_wide_blur(image: &mut WideImage, radius: f32, blur_type: BlurType) {
+  let Some(box_radius) = box_radius(radius, blur_type) else {
+    return;
+  };
+
+  let (width, height) = (image.width(), image.height());
   if width == 0 || height == 0 {
     return;
   }
 
-  premultiply_alpha(image);
+  let mut temp_data = vec![[0.0; 4]; image.pixels().len()];
+
+  for _ in 0..3 {
+    wide_box_blur_pass(
+      image.pixels(),
+      &mut temp_data,
+      (height, width),
+      (width as usize, 1),
+      box_radius,
+    );
+    wide_box_blur_pass(
+      &temp_data,
+      image.pixels_mut(),
+      (width, height),
+      (1, width as usize),
+      box_radius,
+    );
+  }
+}
+
+/// Box blurs every line of `src` into `dst`, edge pixels are repeated past the ends.
+///
+/// `size` is the number of lines and their length, `strides` the distance between the first
+/// pixels of lines and between the pixels of a line.
+fn wide_box_blur_pass(
+  src: &[[f32; 4]],
+  dst: &mut [[f32; 4]],
+  (lines, length): (u32, u32),
+  (line_stride, pixel_stride): (usize, usize),
+  radius: u32,
+) {
+  let r = radius as i32;
+  let last = length as i32 - 1;
+  let scale = 1.0 / (2 * r + 1) as f32;
+
+  for line in 0..lines as usize {
+    let at = |index: i32| src[line * line_stride + index.clamp(0, last) as usize * pixel_stride];
+    let mut sum = [0.0; 4];
+
+    for index in -r..=r {
+      add_pixel(&mut sum, at(index), 1.0);
+    }
+
+    for index in 0..=last {
+      dst[line * line_stride + index as usize * pixel_stride] = sum.map(|value| value * scale);
+
+      add_pixel(&mut sum, at(index + r + 1), 1.0);
+      add_pixel(&mut sum, at(index - r), -1.0);
+    }
+  }
+}
+
+#[inline(always)]
+fn add_pixel(sum: &mut [f32; 4], pixel: [f32; 4], sign: f32) {
+  for (sum, value) in sum.iter_mut().zip(pixel) {
+    *sum += value * sign;
+  }
+}
+
+/// A channel type the box blur can run on.
+trait BlurChannel: Copy + Default {
+  /// The shift of the fixed-point division, small enough for the sums times the multiplier to fit into `u32`.
+  const SHIFT: i32;
+
+  fn to_u32(self) -> u32;
+
+  fn from_u32(value: u32) -> Self;
+}
+
+impl BlurChannel for u8 {
+  const SHIFT: i32 = 23;
+
+  #[inline(always)]
+  fn to_u32(self) -> u32 {
+    self as u32
+  }
+
+  #[inline(always)]
+  fn from_u32(value: u32) -> Self {
+    value.min(u8::MAX as u32) as u8
+  }
+}
+
+/// Linear-light channels use 12 bits, so dark colors keep enough precision.
+impl BlurChannel for u16 {
+  const SHIFT: i32 = 19;
+
+  #[inline(always)]
+  fn to_u32(self) -> u32 {
+    self as u32
+  }
+
+  #[inline(always)]
+  fn from_u32(value: u32) -> Self {
+    value.min(LINEAR_MAX as u32) as u16
+  }
+}
+
+/// The value of a fully lit linear-light channel, or a fully opaque alpha.
+const LINEAR_MAX: u16 = 4095;
 
-  let mut temp_data = vec![0u8; (width * height * 4) as usize];
-  let img_data = &mut **image;
+/// 3-pass Box Blur to approximate Gaussian
+fn box_blur<T: BlurChannel>(data: &mut [T], width: u32, height: u32, box_radius: u32) {
+  let mut temp_data = vec![T::default(); (width * height * 4) as usize];
   let stride = width as usize * 4;
 
-  // 3-pass Box Blur to approximate Gaussian
   for _ in 0..3 {
-    box_blur_h(img_data, &mut temp_data, width, height, box_radius, stride);
-    box_blur_v(&temp_data, img_data, width, height, box_radius, stride);
+    box_blur_h(data, &mut temp_data, width, height, box_radius, stride);
+    box_blur_v(&temp_data, data, width, height, box_radius, stride);
   }
-
-  unpremultiply_alpha(image);
 }
 
 /// Horizontal Box Blur Pass
-fn box_blur_h(src: &[u8], dst: &mut [u8], width: u32, height: u32, radius: u32, stride: usize) {
+fn box_blur_h<T: BlurChannel>(
+  src: &[T],
+  dst: &mut [T],
+  width: u32,
+  height: u32,
+  radius: u32,
+  stride: usize,
+) {
   let r = radius as i32;
   let w = width as i32;
   let div = (2 * r + 1) as u32;
-  let (mul_val, shg) = compute_mul_shg(div);
+  let (mul_val, shg) = compute_mul_shg(div, T::SHIFT);
   let mul = u32x4::new([mul_val; 4]);
 
   for y in 0..height {
@@ -90,11 +236,18 @@ fn box_blur_h(src: &[u8], dst: &mut [u8], width: u32, height: u32, radius: u32,
 }
 
 /// Vertical Box Blur Pass
-fn box_blur_v(src: &[u8], dst: &mut [u8], width: u32, height: u32, radius: u32, stride: usize) {
+fn box_blur_v<T: BlurChannel>(
+  src: &[T],
+  dst: &mut [T],
+  width: u32,
+  height: u32,
+  radius: u32,
+  stride: usize,
+) {
   let r = radius as i32;
   let h = height as i32;
   let div = (2 * r + 1) as u32;
-  let (mul_val, shg) = compute_mul_shg(div);
+  let (mul_val, shg) = compute_mul_shg(div, T::SHIFT);
   let mul = u32x4::new([mul_val; 4]);
 
   for x in 0..width {
@@ -119,29 +272,63 @@ fn box_blur_v(src: &[u8], dst: &mut [u8], width: u32, height: u32, radius: u32,
 }
 
 #[inline(always)]
-fn compute_mul_shg(d: u32) -> (u32, i32) {
-  let shg = 23;
+fn compute_mul_shg(d: u32, shg: i32) -> (u32, i32) {
   let mul = ((1u64 << shg) as f64 / d as f64).round() as u32;
   (mul, shg)
 }
 
 #[inline(always)]
-fn load_pixel(buffer: &[u8], offset: usize) -> u32x4 {
+fn load_pixel<T: BlurChannel>(buffer: &[T], offset: usize) -> u32x4 {
   u32x4::new([
-    buffer[offset] as u32,
-    buffer[offset + 1] as u32,
-    buffer[offset + 2] as u32,
-    buffer[offset + 3] as u32,
+    buffer[offset].to_u32(),
+    buffer[offset + 1].to_u32(),
+    buffer[offset + 2].to_u32(),
+    buffer[offset + 3].to_u32(),
   ])
 }
 
 #[inline(always)]
-fn store_pixel(buffer: &mut [u8], offset: usize, pixel: u32x4) {
+fn store_pixel<T: BlurChannel>(buffer: &mut [T], offset: usize, pixel: u32x4) {
   let arr: [u32; 4] = pixel.into();
-  buffer[offset] = arr[0].min(255) as u8;
-  buffer[offset + 1] = arr[1].min(255) as u8;
-  buffer[offset + 2] = arr[2].min(255) as u8;
-  buffer[offset + 3] = arr[3].min(255) as u8;
+  buffer[offset] = T::from_u32(arr[0]);
+  buffer[offset + 1] = T::from_u32(arr[1]);
+  buffer[offset + 2] = T::from_u32(arr[2]);
+  buffer[offset + 3] = T::from_u32(arr[3]);
+}
+
+/// Converts the image to 12-bit linear-light channels with premultiplied alpha.
+fn to_linear_premultiplied(image: &RgbaImage) -> Vec<u16> {
+  let mut data = Vec::with_capacity(image.as_raw().len());
+
+  for pixel in image.pixels() {
+    let alpha = pixel.0[3] as f32 / 255.0;
+
+    for channel in 0..3 {
+      data.push((srgb_to_linear(pixel.0[channel]) * alpha * LINEAR_MAX as f32).round() as u16);
+    }
+
+    data.push((alpha * LINEAR_MAX as f32).round() as u16);
+  }
+
+  data
+}
+
+/// Converts 12-bit linear-light channels with premultiplied alpha back into the image.
+fn from_linear_premultiplied(image: &mut RgbaImage, data: &[u16]) {
+  for (pixel, linear) in image.pixels_mut().zip(data.chunks_exact(4)) {
+    let alpha = linear[3].min(LINEAR_MAX);
+
+    if alpha == 0 {
+      pixel.0 = [0, 0, 0, 0];
+      continue;
+    }
+
+    for (channel, value) in pixel.0.iter_mut().zip(linear).take(3) {
+      *channel = linear_to_srgb(*value as f32 / alpha as f32);
+    }
+
+    pixel.0[3] = (alpha as f32 / LINEAR_MAX as f32 * 255.0).round() as u8;
+  }
 }
 
 fn premultiply_alpha(image: &mut RgbaImage) {
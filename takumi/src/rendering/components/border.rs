@@ -5,13 +5,18 @@ use taffy::{Point, Rect, Size};
 use zeno::{Command, Fill, PathBuilder};
 
 use crate::{
-  layout::style::{Affine, Color, ColorInput, Sides, SpacePair},
+  layout::style::{Affine, BorderStyle, Color, ColorInput, FillRule, Length, Sides, SpacePair},
   rendering::{
-    Canvas, CowImage, RenderContext, apply_mask_alpha_to_pixel, blend_pixel, mask_index_from_coord,
-    overlay_area,
+    Canvas, CowImage, RenderContext, apply_mask_alpha_to_pixel, fast_div_255, mask_index_from_coord,
   },
 };
 
+/// The magic number for the cubic bezier curve
+const KAPPA: f32 = 4.0 / 3.0 * (SQRT_2 - 1.0);
+
+/// The number of line segments a curve is flattened into when placing dashes and dots.
+const CURVE_STEPS: usize = 16;
+
 /// Represents the properties of a border, including corner radii and drawing metadata.
 #[derive(Debug, Clone, Copy, Default)]
 pub(crate) struct BorderProperties {
@@ -21,6 +26,20 @@ pub(crate) struct BorderProperties {
   pub color: Color,
   /// Corner radii: top, right, bottom, left (in pixels)
   pub radius: Sides<SpacePair<f32>>,
+  /// The style of the border on each side
+  pub style: Rect<BorderStyle>,
+}
+
+/// A region of the border filled with a single color.
+pub(crate) struct BorderSegment {
+  /// The outline of the region.
+  pub path: Vec<Command>,
+  /// How `path` is filled, even-odd for the bands between edges and non-zero for dashes and dots.
+  pub fill_rule: FillRule,
+  /// The part of the border box belonging to a single side, the region is limited to.
+  pub clip: Option<Vec<Command>>,
+  /// The color of the region.
+  pub color: Color,
 }
 
 impl BorderProperties {
@@ -34,6 +53,12 @@ impl BorderProperties {
       width: Rect::ZERO,
       color: Color([0, 0, 0, 255]),
       radius: Sides([SpacePair::from_single(0.0); 4]),
+      style: Rect {
+        left: BorderStyle::Solid,
+        right: BorderStyle::Solid,
+        top: BorderStyle::Solid,
+        bottom: BorderStyle::Solid,
+      },
     }
   }
 
@@ -57,11 +82,96 @@ impl BorderProperties {
         .border_color
         .or(context.style.border.color)
         .unwrap_or(ColorInput::CurrentColor)
-        .resolve(context.current_color, context.opacity),
+        .resolve_in(context.color_space, context.current_color, context.opacity),
       radius: Sides([top_left, top_right, bottom_right, bottom_left]),
+      style: context.style.resolved_border_style(),
     }
   }
 
+  /// Creates the outline of the node, a border drawn around the border box grown by the outline offset and width.
+  ///
+  /// Returns the outline with the size of its box and how far the box reaches out of the border box,
+  /// or `None` when there's nothing to draw.
+  pub(crate) fn outline_from_context(
+    context: &RenderContext,
+    border_box: Size<f32>,
+  ) -> Option<(Self, Size<f32>, f32)> {
+    let style = context
+      .style
+      .outline_style
+      .or(context.style.outline.style)
+      .unwrap_or(BorderStyle::None);
+
+    if !style.is_visible() {
+      return None;
+    }
+
+    // The initial `medium` width.
+    let width = context
+      .style
+      .outline_width
+      .or(context.style.outline.width)
+      .unwrap_or(Length::Px(3.0))
+      .to_px(&context.sizing, border_box.width);
+
+    let color = context
+      .style
+      .outline_color
+      .or(context.style.outline.color)
+      .unwrap_or(ColorInput::CurrentColor)
+      .resolve_in(context.color_space, context.current_color, context.opacity);
+
+    if width <= 0.0 || color.0[3] == 0 {
+      return None;
+    }
+
+    let outset = context
+      .style
+      .outline_offset
+      .unwrap_or(Length::Px(0.0))
+      .to_px(&context.sizing, border_box.width)
+      + width;
+
+    let outline_box = Size {
+      width: border_box.width + outset * 2.0,
+      height: border_box.height + outset * 2.0,
+    };
+
+    if outline_box.width <= 0.0 || outline_box.height <= 0.0 {
+      return None;
+    }
+
+    // Rounded corners follow the border radius, square corners stay square.
+    let radius = Self::from_context(context, border_box, Rect::zero())
+      .radius
+      .0
+      .map(|corner| SpacePair {
+        x: grow_radius(corner.x, outset),
+        y: grow_radius(corner.y, outset),
+      });
+
+    Some((
+      Self {
+        width: Rect {
+          top: width,
+          right: width,
+          bottom: width,
+          left: width,
+        },
+        color,
+        radius: Sides(radius),
+        style: Rect {
+          top: style,
+          right: style,
+          bottom: style,
+          left: style,
+        },
+      },
+      outline_box,
+      outset,
+    ))
+  }
+
   /// Returns true if all corner radii are zero.
   #[inline]
   pub fn is_zero(&self) -> bool {
@@ -108,9 +218,6 @@ impl BorderProperties {
   ) {
     path.reserve_exact(BorderProperties::PATH_COMMANDS_AMOUNT);
 
-    // The magic number for the cubic bezier curve
-    const KAPPA: f32 = 4.0 / 3.0 * (SQRT_2 - 1.0);
-
     // Calculate scale factor inline (CSS Overlapping Curves)
     let scale = 1.0f32
       .min(
@@ -231,8 +338,312 @@ impl BorderProperties {
     path.close();
   }
 
+  /// Append the edge paths of the part of the border between `start` and `end`,
+  /// as fractions of the border width measured from the outer edge, to be filled with the even-odd rule.
+  fn append_band_commands(
+    &self,
+    path: &mut Vec<Command>,
+    border_box: Size<f32>,
+    start: f32,
+    end: f32,
+  ) {
+    let (outer, outer_box, outer_offset) = self.inset_by_fraction(border_box, start);
+    let (inner, inner_box, inner_offset) = self.inset_by_fraction(border_box, end);
+
+    outer.append_mask_commands(path, outer_box, outer_offset);
+    inner.append_mask_commands(path, inner_box, inner_offset);
+  }
+
+  /// Shrinks the border box by `fraction` of the border width on every side,
+  /// returning the shrunk border with the size and offset of its box.
+  fn inset_by_fraction(
+    &self,
+    border_box: Size<f32>,
+    fraction: f32,
+  ) -> (Self, Size<f32>, Point<f32>) {
+    let mut inset = *self;
+
+    inset.expand_by(self.width.map(|size| -size * fraction));
+    inset.width = self.width.map(|size| size * (1.0 - fraction));
+
+    (
+      inset,
+      Size {
+        width: border_box.width - (self.width.left + self.width.right) * fraction,
+        height: border_box.height - (self.width.top + self.width.bottom) * fraction,
+      },
+      Point {
+        x: self.width.left * fraction,
+        y: self.width.top * fraction,
+      },
+    )
+  }
+
+  /// Splits the border into regions of a single color, following the style of each side.
+  ///
+  /// When every drawn side looks the same, the regions cover the whole ring.
+  /// Otherwise each side is drawn on its own, limited to its part of the border box.
+  pub(crate) fn segments(&self, border_box: Size<f32>) -> Vec<BorderSegment> {
+    let widths = [
+      self.width.top,
+      self.width.right,
+      self.width.bottom,
+      self.width.left,
+    ];
+    let styles = [
+      self.style.top,
+      self.style.right,
+      self.style.bottom,
+      self.style.left,
+    ];
+
+    let mut drawn = (0..4).filter(|&side| widths[side] > 0.0 && styles[side].is_visible());
+
+    let Some(first) = drawn.next() else {
+      return Vec::new();
+    };
+
+    let style = styles[first];
+    let is_uniform = !matches!(
+      style,
+      BorderStyle::Groove | BorderStyle::Ridge | BorderStyle::Inset | BorderStyle::Outset
+    ) && drawn.all(|side| {
+      styles[side] == style
+        && (!matches!(style, BorderStyle::Dashed | BorderStyle::Dotted)
+          || widths[side] == widths[first])
+    });
+
+    if is_uniform {
+      let sides: Vec<usize> = (0..4)
+        .filter(|&side| widths[side] > 0.0 && styles[side].is_visible())
+        .collect();
+
+      return self.style_segments(
+        border_box,
+        style,
+        widths[first],
+        [self.color; 2],
+        &sides,
+        None,
+      );
+    }
+
+    let mut segments = Vec::new();
+
+    for (side, (&width, &style)) in widths.iter().zip(&styles).enumerate() {
+      if width <= 0.0 || !style.is_visible() {
+        continue;
+      }
+
+      let clip = self.side_clip_commands(border_box, side);
+
+      segments.extend(self.style_segments(
+        border_box,
+        style,
+        width,
+        three_dimensional_colors(self.color, style, side),
+        &[side],
+        Some(clip),
+      ));
+    }
+
+    segments
+  }
+
+  /// Creates the regions of a single border style, with the outer and inner colors of the 3D styles.
+  ///
+  /// Dashes and dots are only laid out along `sides`, the other styles cover the whole ring.
+  fn style_segments(
+    &self,
+    border_box: Size<f32>,
+    style: BorderStyle,
+    width: f32,
+    [outer_color, inner_color]: [Color; 2],
+    sides: &[usize],
+    clip: Option<Vec<Command>>,
+  ) -> Vec<BorderSegment> {
+    let mut path = Vec::new();
+
+    match style {
+      BorderStyle::None | BorderStyle::Hidden => return Vec::new(),
+      BorderStyle::Solid | BorderStyle::Inset | BorderStyle::Outset => {
+        self.append_band_commands(&mut path, border_box, 0.0, 1.0);
+      }
+      BorderStyle::Double => {
+        self.append_band_commands(&mut path, border_box, 0.0, 1.0 / 3.0);
+        self.append_band_commands(&mut path, border_box, 2.0 / 3.0, 1.0);
+      }
+      BorderStyle::Groove | BorderStyle::Ridge => {
+        let mut inner = Vec::new();
+
+        self.append_band_commands(&mut path, border_box, 0.0, 0.5);
+        self.append_band_commands(&mut inner, border_box, 0.5, 1.0);
+
+        return vec![
+          BorderSegment {
+            path,
+            fill_rule: FillRule::EvenOdd,
+            clip: clip.clone(),
+            color: outer_color,
+          },
+          BorderSegment {
+            path: inner,
+            fill_rule: FillRule::EvenOdd,
+            clip,
+            color: inner_color,
+          },
+        ];
+      }
+      BorderStyle::Dashed | BorderStyle::Dotted => {
+        let lines = self.side_center_lines(border_box);
+
+        for &side in sides {
+          append_dash_commands(
+            &mut path,
+            &flatten_path(&lines[side]),
+            width,
+            style == BorderStyle::Dotted,
+          );
+        }
+
+        // Dashes meeting at the corners overlap, which would cancel out with the even-odd rule.
+        return vec![BorderSegment {
+          path,
+          fill_rule: FillRule::NonZero,
+          clip,
+          color: outer_color,
+        }];
+      }
+    }
+
+    vec![BorderSegment {
+      path,
+      fill_rule: FillRule::EvenOdd,
+      clip,
+      color: outer_color,
+    }]
+  }
+
+  /// Returns the line through the middle of each side (top, right, bottom, left), in clockwise order.
+  ///
+  /// Each line runs from the middle of the corner before the side to the middle of the corner after it,
+  /// so the dash patterns of the sides are anchored on the corners.
+  fn side_center_lines(&self, border_box: Size<f32>) -> [Vec<Command>; 4] {
+    let (center, center_box, center_offset) = self.inset_by_fraction(border_box, 0.5);
+    let mut ring = Vec::with_capacity(BorderProperties::PATH_COMMANDS_AMOUNT);
+
+    center.append_mask_commands(&mut ring, center_box, center_offset);
+
+    // The ring starts at the top edge, followed by a corner after each edge:
+    // top, top-right, right, bottom-right, bottom, bottom-left, left, top-left.
+    let corner_halves = |index: usize| {
+      let from = command_end(ring[index - 1]);
+
+      split_corner(from, ring[index])
+    };
+
+    std::array::from_fn(|side| {
+      let edge = side * 2 + 1;
+      let (_, start_half, start) = corner_halves(if side == 0 { 8 } else { edge - 1 });
+      let (end_half, _, _) = corner_halves(edge + 1);
+
+      let mut line = Vec::with_capacity(4);
+
+      line.move_to((start.x, start.y));
+      line.extend(start_half);
+      line.push(ring[edge]);
+      line.extend(end_half);
+
+      line
+    })
+  }
+
+  /// Returns the part of the border box belonging to `side` (top, right, bottom, left),
+  /// split from its neighbors by lines through the outer and inner corners of the border.
+  fn side_clip_commands(&self, border_box: Size<f32>, side: usize) -> Vec<Command> {
+    let Size { width, height } = border_box;
+
+    let outer = [
+      Point { x: 0.0, y: 0.0 },
+      Point { x: width, y: 0.0 },
+      Point {
+        x: width,
+        y: height,
+      },
+      Point { x: 0.0, y: height },
+    ];
+    let inner = [
+      Point {
+        x: self.width.left,
+        y: self.width.top,
+      },
+      Point {
+        x: width - self.width.right,
+        y: self.width.top,
+      },
+      Point {
+        x: width - self.width.right,
+        y: height - self.width.bottom,
+      },
+      Point {
+        x: self.width.left,
+        y: height - self.width.bottom,
+      },
+    ];
+    let toward_center = [(1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)];
+
+    // Extends the line from each outer corner through the inner corner until it reaches the middle of the box.
+    let extended = std::array::from_fn::<_, 4, _>(|corner| {
+      let mut direction = (
+        inner[corner].x - outer[corner].x,
+        inner[corner].y - outer[corner].y,
+      );
+
+      if direction.0 == 0.0 && direction.1 == 0.0 {
+        direction = toward_center[corner];
+      }
+
+      let scale_x = if direction.0 != 0.0 {
+        width / 2.0 / direction.0.abs()
+      } else {
+        f32::INFINITY
+      };
+      let scale_y = if direction.1 != 0.0 {
+        height / 2.0 / direction.1.abs()
+      } else {
+        f32::INFINITY
+      };
+      let scale = scale_x.min(scale_y);
+
+      Point {
+        x: outer[corner].x + direction.0 * scale,
+        y: outer[corner].y + direction.1 * scale,
+      }
+    });
+
+    let next = (side + 1) % 4;
+    let mut path = Vec::with_capacity(5);
+
+    path.move_to((outer[side].x, outer[side].y));
+    path.line_to((outer[next].x, outer[next].y));
+    path.line_to((extended[next].x, extended[next].y));
+    path.line_to((extended[side].x, extended[side].y));
+    path.close();
+
+    path
+  }
+
+  /// Returns true if the border has no width on every side.
+  #[inline]
+  pub fn is_width_zero(&self) -> bool {
+    self.width.left == 0.0
+      && self.width.right == 0.0
+      && self.width.top == 0.0
+      && self.width.bottom == 0.0
+  }
+
   pub(crate) fn draw(
-    mut self,
+    self,
     canvas: &mut Canvas,
     border_box: Size<f32>,
     transform: Affine,
@@ -242,66 +653,387 @@ impl BorderProperties {
       assert_eq!(fill_image.size(), border_box.map(|size| size as u32));
     }
 
-    if self.width.left == 0.0
-      && self.width.right == 0.0
-      && self.width.top == 0.0
-      && self.width.bottom == 0.0
-    {
+    if self.is_width_zero() {
       return;
     }
 
-    let mut paths = Vec::with_capacity(BorderProperties::PATH_COMMANDS_AMOUNT * 2);
+    // The fill image covers the border box, so it's sampled relative to the outer edge.
+    let origin = fill_image.is_some().then(|| {
+      let mut paths = Vec::with_capacity(BorderProperties::PATH_COMMANDS_AMOUNT);
+
+      self.append_mask_commands(&mut paths, border_box, Point::ZERO);
 
-    self.append_mask_commands(&mut paths, border_box, Point::ZERO);
+      canvas.mask_memory.placement(&paths, Some(transform), None)
+    });
 
-    self.inset_by_border_width();
-    self.append_mask_commands(
-      &mut paths,
-      border_box
-        - Size {
-          width: self.width.left + self.width.right,
-          height: self.width.top + self.width.bottom,
+    for segment in self.segments(border_box) {
+      let clip = segment.clip.as_ref().map(|clip| {
+        let (mask, placement) = canvas.mask_memory.render(clip, Some(transform), None);
+
+        (mask.to_vec(), placement)
+      });
+
+      let (mask, placement) = canvas.mask_memory.render(
+        &segment.path,
+        Some(transform),
+        Some(Fill::from(segment.fill_rule).into()),
+      );
+      let mask = mask.to_vec();
+
+      let color_interpolation = canvas.color_interpolation;
+
+      canvas.overlay_area(
+        Point {
+          x: placement.left as f32,
+          y: placement.top as f32,
         },
-      Point {
-        x: self.width.left,
-        y: self.width.top,
-      },
-    );
+        Size {
+          width: placement.width,
+          height: placement.height,
+        },
+        |x, y| {
+          let mut alpha = mask[mask_index_from_coord(x, y, placement.width)];
 
-    let (mask, placement) =
-      canvas
-        .mask_memory
-        .render(&paths, Some(transform), Some(Fill::EvenOdd.into()));
+          if let Some((clip_mask, clip_placement)) = &clip {
+            let clip_x = x as i32 + placement.left - clip_placement.left;
+            let clip_y = y as i32 + placement.top - clip_placement.top;
 
-    overlay_area(
-      &mut canvas.image,
-      Point {
-        x: placement.left as f32,
-        y: placement.top as f32,
-      },
-      Size {
-        width: placement.width,
-        height: placement.height,
-      },
-      canvas.constrains.last(),
-      |x, y| {
-        let alpha = mask[mask_index_from_coord(x, y, placement.width)];
+            let clip_alpha = if clip_x < 0
+              || clip_y < 0
+              || clip_x as u32 >= clip_placement.width
+              || clip_y as u32 >= clip_placement.height
+            {
+              0
+            } else {
+              clip_mask[mask_index_from_coord(clip_x as u32, clip_y as u32, clip_placement.width)]
+            };
 
-        let mut pixel = fill_image
-          .as_ref()
-          .map(|image| {
-            let mut pixel = image.get_pixel(x, y);
+            alpha = fast_div_255(alpha as u16 * clip_alpha as u16);
+          }
 
-            blend_pixel(&mut pixel, self.color.into());
+          let mut pixel = fill_image
+            .as_ref()
+            .zip(origin.as_ref())
+            .and_then(|(image, origin)| {
+              let image_x = x as i32 + placement.left - origin.left;
+              let image_y = y as i32 + placement.top - origin.top;
 
-            pixel
-          })
-          .unwrap_or(self.color.into());
+              if image_x < 0 || image_y < 0 || !image.in_bounds(image_x as u32, image_y as u32) {
+                return None;
+              }
 
-        apply_mask_alpha_to_pixel(&mut pixel, alpha);
+              let mut pixel = image.get_pixel(image_x as u32, image_y as u32);
 
-        pixel
-      },
+              color_interpolation.blend_pixel(&mut pixel, segment.color.into());
+
+              Some(pixel)
+            })
+            .unwrap_or(segment.color.into());
+
+          apply_mask_alpha_to_pixel(&mut pixel, alpha);
+
+          pixel
+        },
+      );
+    }
+  }
+}
+
+fn grow_radius(radius: f32, amount: f32) -> f32 {
+  if radius > 0.0 {
+    (radius + amount).max(0.0)
+  } else {
+    0.0
+  }
+}
+
+/// Returns the outer and inner colors of a side for the 3D border styles, like Chromium does.
+///
+/// `side` is the index of the side in top, right, bottom, left order.
+fn three_dimensional_colors(color: Color, style: BorderStyle, side: usize) -> [Color; 2] {
+  let is_top_left = side == 0 || side == 3;
+  let dark = darken(color);
+
+  match (style, is_top_left) {
+    (BorderStyle::Inset, true) | (BorderStyle::Outset, false) => [dark; 2],
+    (BorderStyle::Groove, true) | (BorderStyle::Ridge, false) => [dark, color],
+    (BorderStyle::Groove, false) | (BorderStyle::Ridge, true) => [color, dark],
+    _ => [color; 2],
+  }
+}
+
+/// Returns the shaded color of the 3D border styles, black is lightened to stay visible.
+fn darken(color: Color) -> Color {
+  let [red, green, blue, alpha] = color.0;
+
+  if red == 0 && green == 0 && blue == 0 {
+    return Color([0x54, 0x54, 0x54, alpha]);
+  }
+
+  let value = red.max(green).max(blue) as f32 / 255.0;
+  let multiplier = ((value - 0.33) / value).max(0.0);
+  let scale = |channel: u8| (channel as f32 * multiplier).round() as u8;
+
+  Color([scale(red), scale(green), scale(blue), alpha])
+}
+
+/// Returns the point a path command ends at.
+fn command_end(command: Command) -> Point<f32> {
+  match command {
+    Command::MoveTo(to)
+    | Command::LineTo(to)
+    | Command::CurveTo(_, _, to)
+    | Command::QuadTo(_, to) => Point { x: to.x, y: to.y },
+    Command::Close => Point::ZERO,
+  }
+}
+
+/// Splits a corner starting at `from` in the middle, returning the commands of both halves
+/// and the point they meet at. A square corner meets at its point, with nothing after it.
+fn split_corner(
+  from: Point<f32>,
+  corner: Command,
+) -> (Option<Command>, Option<Command>, Point<f32>) {
+  let Command::CurveTo(control1, control2, to) = corner else {
+    let point = command_end(corner);
+
+    return (Some(corner), None, point);
+  };
+
+  let mid = |a: Point<f32>, b: Point<f32>| Point {
+    x: (a.x + b.x) / 2.0,
+    y: (a.y + b.y) / 2.0,
+  };
+
+  let [control1, control2, to] = [control1, control2, to].map(|point| Point {
+    x: point.x,
+    y: point.y,
+  });
+
+  // De Casteljau's algorithm at the middle of the curve.
+  let a = mid(from, control1);
+  let b = mid(control1, control2);
+  let c = mid(control2, to);
+  let ab = mid(a, b);
+  let bc = mid(b, c);
+  let middle = mid(ab, bc);
+
+  (
+    Some(Command::CurveTo(
+      (a.x, a.y).into(),
+      (ab.x, ab.y).into(),
+      (middle.x, middle.y).into(),
+    )),
+    Some(Command::CurveTo(
+      (bc.x, bc.y).into(),
+      (c.x, c.y).into(),
+      (to.x, to.y).into(),
+    )),
+    middle,
+  )
+}
+
+/// Flattens a path into a polyline, approximating curves with line segments.
+fn flatten_path(path: &[Command]) -> Vec<Point<f32>> {
+  let mut points: Vec<Point<f32>> = Vec::new();
+  let mut start = Point::ZERO;
+
+  let push = |points: &mut Vec<Point<f32>>, point: Point<f32>| {
+    if points.last() != Some(&point) {
+      points.push(point);
+    }
+  };
+
+  for command in path {
+    let from = points.last().copied().unwrap_or(start);
+
+    match *command {
+      Command::MoveTo(to) => {
+        start = Point { x: to.x, y: to.y };
+        push(&mut points, start);
+      }
+      Command::LineTo(to) => push(&mut points, Point { x: to.x, y: to.y }),
+      Command::CurveTo(control1, control2, to) => {
+        for step in 1..=CURVE_STEPS {
+          let t = step as f32 / CURVE_STEPS as f32;
+          let u = 1.0 - t;
+
+          let [a, b, c, d] = [u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t];
+
+          push(
+            &mut points,
+            Point {
+              x: a * from.x + b * control1.x + c * control2.x + d * to.x,
+              y: a * from.y + b * control1.y + c * control2.y + d * to.y,
+            },
+          );
+        }
+      }
+      Command::QuadTo(control, to) => {
+        for step in 1..=CURVE_STEPS {
+          let t = step as f32 / CURVE_STEPS as f32;
+          let u = 1.0 - t;
+
+          let [a, b, c] = [u * u, 2.0 * u * t, t * t];
+
+          push(
+            &mut points,
+            Point {
+              x: a * from.x + b * control.x + c * to.x,
+              y: a * from.y + b * control.y + c * to.y,
+            },
+          );
+        }
+      }
+      Command::Close => push(&mut points, start),
+    }
+  }
+
+  points
+}
+
+/// Appends dashes, or dots when `dotted`, evenly spread along the open `line`.
+///
+/// Dashes are twice as long as `width` with gaps of `width`, dots are `width` across with gaps of `width`.
+/// The spacing is stretched so the pattern fits the line a whole number of times, with a dash or dot
+/// centered on both ends. Dashes are cut at the ends, so they join the dashes of the neighboring sides.
+fn append_dash_commands(path: &mut Vec<Command>, line: &[Point<f32>], width: f32, dotted: bool) {
+  if line.len() < 2 || width <= 0.0 {
+    return;
+  }
+
+  let mut distances = Vec::with_capacity(line.len());
+  let mut length = 0.0;
+
+  distances.push(0.0);
+
+  for pair in line.windows(2) {
+    length += distance(pair[0], pair[1]);
+    distances.push(length);
+  }
+
+  if length <= 0.0 {
+    return;
+  }
+
+  let count = (length / (width * if dotted { 2.0 } else { 3.0 }))
+    .round()
+    .max(1.0);
+  let step = length / count;
+
+  // Returns the point at `at` along the line.
+  let point_at = |at: f32| {
+    let index = distances
+      .partition_point(|&distance| distance <= at)
+      .clamp(1, line.len() - 1);
+    let (from, to) = (line[index - 1], line[index]);
+    let segment = distances[index] - distances[index - 1];
+    let t = if segment > 0.0 {
+      (at - distances[index - 1]) / segment
+    } else {
+      0.0
+    };
+
+    Point {
+      x: from.x + (to.x - from.x) * t,
+      y: from.y + (to.y - from.y) * t,
+    }
+  };
+
+  for index in 0..=count as usize {
+    let center = index as f32 * step;
+
+    if dotted {
+      append_circle_commands(path, point_at(center), width / 2.0);
+      continue;
+    }
+
+    let start = (center - step / 3.0).max(0.0);
+    let end = (center + step / 3.0).min(length);
+
+    // The vertices within the dash.
+    let mut samples = vec![(start, point_at(start))];
+
+    samples.extend(
+      distances
+        .iter()
+        .zip(line)
+        .map(|(&distance, &point)| (distance, point))
+        .filter(|&(distance, _)| distance > start && distance < end),
     );
+
+    samples.dedup_by(|a, b| distance(a.1, b.1) < f32::EPSILON);
+    samples.push((end, point_at(end)));
+
+    append_offset_polygon(path, &samples, width / 2.0);
   }
 }
+
+/// Appends the outline of `samples` widened by `offset` on both sides.
+fn append_offset_polygon(path: &mut Vec<Command>, samples: &[(f32, Point<f32>)], offset: f32) {
+  let normals = (0..samples.len())
+    .map(|index| {
+      let from = samples[index.saturating_sub(1)].1;
+      let to = samples[(index + 1).min(samples.len() - 1)].1;
+      let length = distance(from, to);
+
+      if length > 0.0 {
+        Point {
+          x: -(to.y - from.y) / length * offset,
+          y: (to.x - from.x) / length * offset,
+        }
+      } else {
+        Point::ZERO
+      }
+    })
+    .collect::<Vec<_>>();
+
+  for (index, ((_, point), normal)) in samples.iter().zip(&normals).enumerate() {
+    let point = (point.x + normal.x, point.y + normal.y);
+
+    if index == 0 {
+      path.move_to(point);
+    } else {
+      path.line_to(point);
+    }
+  }
+
+  for ((_, point), normal) in samples.iter().zip(&normals).rev() {
+    path.line_to((point.x - normal.x, point.y - normal.y));
+  }
+
+  path.close();
+}
+
+/// Appends a circle made of four cubic bezier curves.
+fn append_circle_commands(path: &mut Vec<Command>, center: Point<f32>, radius: f32) {
+  let Point { x, y } = center;
+  let control = radius * KAPPA;
+
+  path.move_to((x + radius, y));
+  path.curve_to(
+    (x + radius, y + control),
+    (x + control, y + radius),
+    (x, y + radius),
+  );
+  path.curve_to(
+    (x - control, y + radius),
+    (x - radius, y + control),
+    (x - radius, y),
+  );
+  path.curve_to(
+    (x - radius, y - control),
+    (x - control, y - radius),
+    (x, y - radius),
+  );
+  path.curve_to(
+    (x + control, y - radius),
+    (x + radius, y - control),
+    (x + radius, y),
+  );
+  path.close();
+}
+
+fn distance(from: Point<f32>, to: Point<f32>) -> f32 {
+  (to.x - from.x).hypot(to.y - from.y)
+}
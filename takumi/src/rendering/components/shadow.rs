@@ -3,10 +3,10 @@ use taffy::{Layout, Point, Size};
 use zeno::{Fill, PathData, Placement};
 
 use crate::{
-  layout::style::{Affine, BoxShadow, Color, ImageScalingAlgorithm, Sides, TextShadow},
+  layout::style::{Affine, BoxShadow, Color, ColorSpace, ImageScalingAlgorithm, Sides, TextShadow},
   rendering::{
-    BlurType, BorderProperties, Canvas, CanvasConstrain, MaskMemory, Sizing, apply_blur, draw_mask,
-    overlay_image,
+    BlurType, BorderProperties, Canvas, ColorInterpolation, CoverageMask, MaskMemory, Sizing,
+    apply_blur, draw_mask,
   },
 };
 
@@ -31,6 +31,7 @@ impl SizedShadow {
     shadow: BoxShadow,
     sizing: &Sizing,
     current_color: Color,
+    color_space: ColorSpace,
     opacity: u8,
     size: Size<f32>,
   ) -> Self {
@@ -39,7 +40,7 @@ impl SizedShadow {
       offset_y: shadow.offset_y.to_px(sizing, size.height),
       blur_radius: shadow.blur_radius.to_px(sizing, size.width),
       spread_radius: shadow.spread_radius.to_px(sizing, size.width).max(0.0),
-      color: shadow.color.resolve(current_color, opacity),
+      color: shadow.color.resolve_in(color_space, current_color, opacity),
     }
   }
 
@@ -48,6 +49,7 @@ impl SizedShadow {
     shadow: TextShadow,
     sizing: &Sizing,
     current_color: Color,
+    color_space: ColorSpace,
     opacity: u8,
     size: Size<f32>,
   ) -> Self {
@@ -57,28 +59,30 @@ impl SizedShadow {
       blur_radius: shadow.blur_radius.to_px(sizing, size.width),
       // Text shadows do not support spread radius; set to 0.
       spread_radius: 0.0,
-      color: shadow.color.resolve(current_color, opacity),
+      color: shadow.color.resolve_in(color_space, current_color, opacity),
     }
   }
 
   /// Draws the outset mask of the shadow.
   pub fn draw_outset<D: PathData>(
     &self,
-    canvas: &mut RgbaImage,
-    mask_memory: &mut MaskMemory,
-    constrain: Option<&CanvasConstrain>,
+    canvas: &mut Canvas,
     paths: D,
     transform: Affine,
     style: zeno::Style,
   ) {
-    let (mask, mut placement) = mask_memory.render(&paths, Some(transform), Some(style));
+    let (mask, mut placement) = canvas
+      .mask_memory
+      .render(&paths, Some(transform), Some(style));
 
     placement.left += self.offset_x as i32;
     placement.top += self.offset_y as i32;
 
+    let mask = CoverageMask::new(mask, placement);
+
     // Fast path: if the blur radius is 0, we can just draw the spread mask
     if self.blur_radius <= 0.0 {
-      return draw_mask(canvas, mask, placement, self.color, constrain);
+      return canvas.fill_mask(mask, self.color);
     }
 
     // Create a new image with the spread mask on, blurred by the blur radius
@@ -90,7 +94,7 @@ impl SizedShadow {
 
     draw_mask(
       &mut image,
-      mask,
+      &mask.data,
       Placement {
         left: blur_padding as i32,
         top: blur_padding as i32,
@@ -99,12 +103,17 @@ impl SizedShadow {
       },
       self.color,
       None,
+      canvas.color_interpolation,
     );
 
-    apply_blur(&mut image, self.blur_radius, BlurType::Shadow);
+    apply_blur(
+      &mut image,
+      self.blur_radius,
+      BlurType::Shadow,
+      canvas.color_interpolation,
+    );
 
-    overlay_image(
-      canvas,
+    canvas.overlay_image(
       image.into(),
       BorderProperties::zero(),
       Affine::translation(
@@ -113,8 +122,6 @@ impl SizedShadow {
       ),
       ImageScalingAlgorithm::Auto,
       255,
-      constrain,
-      mask_memory,
     );
   }
 
@@ -125,7 +132,13 @@ impl SizedShadow {
     canvas: &mut Canvas,
     layout: Layout,
   ) {
-    let image = draw_inset_shadow(self, border_radius, layout.size, &mut canvas.mask_memory);
+    let image = draw_inset_shadow(
+      self,
+      border_radius,
+      layout.size,
+      &mut canvas.mask_memory,
+      canvas.color_interpolation,
+    );
 
     canvas.overlay_image(
       image.into(),
@@ -142,6 +155,7 @@ fn draw_inset_shadow(
   mut border: BorderProperties,
   border_box: Size<f32>,
   mask_memory: &mut MaskMemory,
+  color_interpolation: ColorInterpolation,
 ) -> RgbaImage {
   let mut shadow_image = RgbaImage::from_pixel(
     border_box.width as u32,
@@ -175,9 +189,21 @@ fn draw_inset_shadow(
 
   let (mask, placement) = mask_memory.render(&paths, None, Some(Fill::EvenOdd.into()));
 
-  draw_mask(&mut shadow_image, mask, placement, shadow.color, None);
+  draw_mask(
+    &mut shadow_image,
+    mask,
+    placement,
+    shadow.color,
+    None,
+    color_interpolation,
+  );
 
-  apply_blur(&mut shadow_image, shadow.blur_radius, BlurType::Shadow);
+  apply_blur(
+    &mut shadow_image,
+    shadow.blur_radius,
+    BlurType::Shadow,
+    color_interpolation,
+  );
 
   shadow_image
 }
@@ -0,0 +1,271 @@
+use std::ops::Range;
+
+use parley::PositionedLayoutItem;
+use serde::Serialize;
+use taffy::{Layout, NodeId, Point, TaffyError, TaffyTree};
+
+use crate::{
+  Result,
+  layout::{
+    inline::{InlineContentKind, InlineLayout},
+    node::{Node, create_text_inline_layout},
+    style::Affine,
+    tree::NodeTree,
+  },
+  rendering::{apply_transform, glyph_texts},
+};
+
+/// The computed layout of a node tree, returned by [`compute_layout`](crate::rendering::compute_layout).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutTree {
+  /// The width of the output in pixels.
+  pub width: u32,
+  /// The height of the output in pixels.
+  pub height: u32,
+  /// The layout of the root node.
+  pub root: LayoutNode,
+}
+
+/// A rectangle in the local coordinate space of a node, where the border box starts at the origin.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutRect {
+  /// The left edge.
+  pub x: f32,
+  /// The top edge.
+  pub y: f32,
+  /// The width.
+  pub width: f32,
+  /// The height.
+  pub height: f32,
+}
+
+/// The computed layout of a node.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutNode {
+  /// The border box of the node.
+  pub border_box: LayoutRect,
+  /// The padding box of the node.
+  pub padding_box: LayoutRect,
+  /// The content box of the node.
+  pub content_box: LayoutRect,
+  /// Maps the local coordinate space of the node to the output,
+  /// including the offsets and transforms of the node and its ancestors.
+  pub transform: Affine,
+  /// The lines of text laid out in the content box, if the node contains text.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub lines: Option<Vec<LayoutLine>>,
+  /// The layout of the children, empty if they are laid out as inline content in `lines`.
+  pub children: Vec<LayoutNode>,
+}
+
+/// A line box of laid out text.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutLine {
+  /// The text of the line.
+  pub text: String,
+  /// The line box.
+  pub rect: LayoutRect,
+  /// The vertical position of the baseline.
+  pub baseline: f32,
+  /// The glyph runs of the line, in visual order.
+  pub runs: Vec<LayoutGlyphRun>,
+  /// The inline boxes (e.g. images) placed on the line.
+  pub inline_boxes: Vec<LayoutRect>,
+}
+
+/// Glyphs of a line shaped with the same font and style.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutGlyphRun {
+  /// The text the glyphs represent.
+  pub text: String,
+  /// The font size in pixels.
+  pub font_size: f32,
+  /// The box from the ascent to the descent of the run.
+  pub rect: LayoutRect,
+  /// The vertical position of the baseline.
+  pub baseline: f32,
+  /// The positioned glyphs.
+  pub glyphs: Vec<LayoutGlyph>,
+}
+
+/// A positioned glyph.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutGlyph {
+  /// The glyph id in the font.
+  pub id: u32,
+  /// The horizontal position of the glyph origin.
+  pub x: f32,
+  /// The vertical position of the glyph origin.
+  pub y: f32,
+  /// The horizontal advance.
+  pub advance: f32,
+}
+
+/// Collects the computed layout of the node and its descendants, accumulating transforms like `render_node`.
+pub(crate) fn collect_layout_node<'g, N: Node<N>>(
+  taffy: &TaffyTree<NodeTree<'g, N>>,
+  node_id: NodeId,
+  mut transform: Affine,
+) -> Result<LayoutNode> {
+  let layout = *taffy.layout(node_id)?;
+
+  let Some(node) = taffy.get_node_context(node_id) else {
+    return Err(TaffyError::InvalidInputNode(node_id).into());
+  };
+
+  transform *= Affine::translation(layout.location.x, layout.location.y);
+
+  apply_transform(
+    &mut transform,
+    &node.context.style,
+    layout.size,
+    &node.context.sizing,
+  );
+
+  // Inline children are laid out as the lines of the node, they have no boxes of their own.
+  let children = if node.should_create_inline_layout() {
+    Vec::new()
+  } else {
+    taffy
+      .children(node_id)?
+      .into_iter()
+      .map(|child_id| collect_layout_node(taffy, child_id, transform))
+      .collect::<Result<Vec<_>>>()?
+  };
+
+  let padding_box = LayoutRect {
+    x: layout.border.left,
+    y: layout.border.top,
+    width: layout.size.width - layout.border.left - layout.border.right,
+    height: layout.size.height - layout.border.top - layout.border.bottom,
+  };
+
+  Ok(LayoutNode {
+    border_box: LayoutRect {
+      x: 0.0,
+      y: 0.0,
+      width: layout.size.width,
+      height: layout.size.height,
+    },
+    padding_box,
+    content_box: LayoutRect {
+      x: padding_box.x + layout.padding.left,
+      y: padding_box.y + layout.padding.top,
+      width: layout.content_box_width(),
+      height: layout.content_box_height(),
+    },
+    transform,
+    lines: collect_layout_lines(node, layout),
+    children,
+  })
+}
+
+/// Lays out the text of the node the same way it is drawn, returns `None` if the node has no text.
+fn collect_layout_lines<N: Node<N>>(
+  node: &NodeTree<'_, N>,
+  layout: Layout,
+) -> Option<Vec<LayoutLine>> {
+  let context = &node.context;
+  let font_style = context.style.to_sized_font_style(context);
+
+  let (inline_layout, text) = if node.should_create_inline_layout() {
+    let (inline_layout, text, _) = node.create_draw_inline_layout(layout, &font_style);
+
+    (inline_layout, text)
+  } else if let Some(InlineContentKind::Text(text)) =
+    node.node.as_ref().and_then(Node::inline_content)
+  {
+    create_text_inline_layout::<N>(&text, context, layout.content_box_size(), &font_style)
+  } else {
+    return None;
+  };
+
+  let origin = Point {
+    x: layout.border.left + layout.padding.left,
+    y: layout.border.top + layout.padding.top,
+  };
+
+  Some(collect_lines(&inline_layout, &text, origin))
+}
+
+fn collect_lines(inline_layout: &InlineLayout, text: &str, origin: Point<f32>) -> Vec<LayoutLine> {
+  let mut lines = Vec::new();
+
+  for line in inline_layout.lines() {
+    let metrics = line.metrics();
+
+    let mut runs = Vec::new();
+    let mut inline_boxes = Vec::new();
+
+    // A run is split into multiple glyph runs when the brush changes,
+    // keep track of the glyph each of them starts from.
+    let mut next_glyph_start: Option<(Range<usize>, usize)> = None;
+
+    for item in line.items() {
+      match item {
+        PositionedLayoutItem::GlyphRun(glyph_run) => {
+          let run = glyph_run.run();
+          let run_range = run.text_range();
+          let run_metrics = run.metrics();
+
+          let glyph_start = match &next_glyph_start {
+            Some((range, start)) if *range == run_range => *start,
+            _ => 0,
+          };
+
+          let glyph_texts = glyph_texts(&glyph_run, text, glyph_start);
+
+          next_glyph_start = Some((run_range, glyph_start + glyph_texts.len()));
+
+          runs.push(LayoutGlyphRun {
+            text: glyph_texts.concat(),
+            font_size: run.font_size(),
+            rect: LayoutRect {
+              x: origin.x + glyph_run.offset(),
+              y: origin.y + glyph_run.baseline() - run_metrics.ascent,
+              width: glyph_run.advance(),
+              height: run_metrics.ascent + run_metrics.descent,
+            },
+            baseline: origin.y + glyph_run.baseline(),
+            glyphs: glyph_run
+              .positioned_glyphs()
+              .map(|glyph| LayoutGlyph {
+                id: glyph.id,
+                x: origin.x + glyph.x,
+                y: origin.y + glyph.y,
+                advance: glyph.advance,
+              })
+              .collect(),
+          });
+        }
+        PositionedLayoutItem::InlineBox(inline_box) => inline_boxes.push(LayoutRect {
+          x: origin.x + inline_box.x,
+          y: origin.y + inline_box.y,
+          width: inline_box.width,
+          height: inline_box.height,
+        }),
+      }
+    }
+
+    lines.push(LayoutLine {
+      text: text.get(line.text_range()).unwrap_or_default().to_string(),
+      rect: LayoutRect {
+        x: origin.x + metrics.offset,
+        y: origin.y + metrics.baseline - metrics.ascent - metrics.leading / 2.0,
+        width: metrics.advance,
+        height: metrics.line_height,
+      },
+      baseline: origin.y + metrics.baseline,
+      runs,
+      inline_boxes,
+    });
+  }
+
+  lines
+}
@@ -12,6 +12,7 @@ pub fn draw_debug_border(canvas: &mut Canvas, layout: Layout, transform: Affine)
     width: Sides([1.0; 4]).into(),
     color: Color([255, 0, 0, 255]), // red
     radius: Sides([SpacePair::from_single(0.0); 4]),
+    style: Default::default(),
   }
   .draw(canvas, layout.size, transform, None);
 
@@ -20,6 +21,7 @@ pub fn draw_debug_border(canvas: &mut Canvas, layout: Layout, transform: Affine)
     width: Sides([1.0; 4]).into(),
     color: Color([0, 255, 0, 255]), // green
     radius: Sides([SpacePair::from_single(0.0); 4]),
+    style: Default::default(),
   }
   .draw(
     canvas,
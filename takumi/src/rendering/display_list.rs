@@ -0,0 +1,683 @@
+//! A retained list of drawing commands between layout and painting.
+//!
+//! Painting the laid out tree records [`DisplayCommand`]s instead of touching pixels, and
+//! [`render`](crate::rendering::render) rasterizes the recorded [`DisplayList`]. Paths, borders and
+//! text are recorded as coverage masks in output pixels, so rasterizing a list gives exactly the
+//! image `render` returns. The list no longer depends on the node tree, so it can be inspected,
+//! cached, compared between frames, or rasterized again, also only a part of it.
+//!
+//! SVG and PDF output don't go through the list, they walk the layout tree with the vector
+//! renderer to keep paths and text as vectors.
+
+use std::{fmt, sync::Arc};
+
+use image::{GenericImage, GenericImageView, Rgba, RgbaImage};
+use taffy::{Point, Size};
+use zeno::Placement;
+
+use crate::{
+  Result,
+  layout::{
+    node::Node,
+    style::{
+      Affine, BlendMode, Color, ColorSpace, Filter, Filters, apply_filters, apply_wide_filters,
+    },
+  },
+  rendering::{
+    Canvas, CanvasConstrain, ColorInterpolation, CompositeTarget, FilteredBackdrop, ImagePlacement,
+    RenderOptions, RenderRegion, Rgba16Image, Sizing, WideImage, apply_mask_alpha_to_pixel,
+    blend_image, blend_premultiplied, blend_premultiplied_pixel, create_layout_tree, draw_mask,
+    overlay_area, render_node,
+  },
+};
+
+#[cfg(feature = "rayon")]
+use crate::rendering::{rasterize_tiles, should_rasterize_in_tiles};
+
+/// A drawing command of a [`DisplayList`], in output pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisplayCommand {
+  /// Restricts the following commands to the clip until the matching [`DisplayCommand::PopClip`].
+  ///
+  /// Clips are not intersected, only the innermost one applies.
+  PushClip(DisplayClip),
+  /// Removes the clip added by the last [`DisplayCommand::PushClip`].
+  PopClip,
+  /// Draws the following commands into a transparent layer until the matching [`DisplayCommand::PopLayer`].
+  PushLayer,
+  /// Applies the effects to the layer started by the last [`DisplayCommand::PushLayer`],
+  /// and composites it onto what is below.
+  PopLayer(LayerEffects),
+  /// Fills a rectangle with a color.
+  FillRect {
+    /// The top-left corner, rounded down to whole pixels.
+    offset: Point<f32>,
+    /// The size of the rectangle.
+    size: Size<u32>,
+    /// The fill color.
+    color: Color,
+  },
+  /// Fills the coverage of a path, like a rounded background, a border side or a glyph, with a color.
+  FillPath {
+    /// The coverage of the path.
+    mask: CoverageMask,
+    /// The fill color.
+    color: Color,
+  },
+  /// Draws an image, which also covers gradients, shadows and pixels painted by effects.
+  DrawImage(DisplayImage),
+  /// Draws the backdrop of a node filtered by its `backdrop-filter`.
+  DrawBackdrop(DisplayBackdrop),
+}
+
+/// The anti-aliased coverage of a path, in output pixels.
+#[derive(Clone)]
+pub struct CoverageMask {
+  pub(crate) data: Arc<[u8]>,
+  pub(crate) placement: Placement,
+}
+
+impl CoverageMask {
+  pub(crate) fn new(data: &[u8], placement: Placement) -> Self {
+    Self {
+      data: data.into(),
+      placement,
+    }
+  }
+
+  /// The top-left corner of the mask.
+  pub fn offset(&self) -> Point<i32> {
+    Point {
+      x: self.placement.left,
+      y: self.placement.top,
+    }
+  }
+
+  /// The size of the mask.
+  pub fn size(&self) -> Size<u32> {
+    Size {
+      width: self.placement.width,
+      height: self.placement.height,
+    }
+  }
+
+  /// The coverage of the pixels, row by row.
+  pub fn data(&self) -> &[u8] {
+    &self.data
+  }
+
+  /// Moves the mask for drawing onto the part of the output starting at `origin`.
+  fn translated(&self, origin: Point<u32>) -> Placement {
+    Placement {
+      left: self.placement.left - origin.x as i32,
+      top: self.placement.top - origin.y as i32,
+      ..self.placement
+    }
+  }
+}
+
+impl PartialEq for CoverageMask {
+  fn eq(&self, other: &Self) -> bool {
+    self.offset() == other.offset() && self.size() == other.size() && self.data == other.data
+  }
+}
+
+impl fmt::Debug for CoverageMask {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("CoverageMask")
+      .field("offset", &self.offset())
+      .field("size", &self.size())
+      .finish_non_exhaustive()
+  }
+}
+
+/// A clip of [`DisplayCommand::PushClip`], from `overflow`, `clip-path` or `mask-image`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayClip(pub(crate) CanvasConstrain);
+
+/// The filters and compositing of a layer, applied by [`DisplayCommand::PopLayer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerEffects {
+  pub(crate) filters: Filters,
+  pub(crate) sizing: Sizing,
+  pub(crate) current_color: Color,
+  pub(crate) color_space: ColorSpace,
+  pub(crate) opacity: u8,
+  pub(crate) blend_mode: BlendMode,
+  pub(crate) group_opacity: u8,
+}
+
+impl LayerEffects {
+  /// The filters applied to the layer, in order.
+  pub fn filters(&self) -> &[Filter] {
+    &self.filters
+  }
+
+  /// The blend mode the layer is composited with.
+  pub fn blend_mode(&self) -> BlendMode {
+    self.blend_mode
+  }
+
+  /// The opacity the layer is composited with.
+  pub fn group_opacity(&self) -> u8 {
+    self.group_opacity
+  }
+
+  fn apply<I: GenericImage<Pixel = Rgba<u8>>>(
+    &self,
+    below: &mut I,
+    mut layer: RgbaImage,
+    interpolation: ColorInterpolation,
+  ) {
+    apply_filters(
+      &mut layer,
+      &self.sizing,
+      self.current_color,
+      self.color_space,
+      self.opacity,
+      interpolation,
+      self.filters.iter(),
+    );
+
+    // Clips are already applied to the content of the layer.
+    if self.blend_mode == BlendMode::Normal {
+      return overlay_area(
+        below,
+        Point::ZERO,
+        Size {
+          width: layer.width(),
+          height: layer.height(),
+        },
+        None,
+        interpolation,
+        |x, y| {
+          let mut pixel = *layer.get_pixel(x, y);
+
+          apply_mask_alpha_to_pixel(&mut pixel, self.group_opacity);
+
+          pixel
+        },
+      );
+    }
+
+    if self.group_opacity != u8::MAX {
+      for pixel in layer.pixels_mut() {
+        apply_mask_alpha_to_pixel(pixel, self.group_opacity);
+      }
+    }
+
+    blend_image(below, &layer, self.blend_mode, interpolation);
+  }
+
+  fn apply_wide(
+    &self,
+    below: &mut WideImage,
+    mut layer: WideImage,
+    interpolation: ColorInterpolation,
+  ) {
+    apply_wide_filters(
+      &mut layer,
+      &self.sizing,
+      self.current_color,
+      self.color_space,
+      self.opacity,
+      interpolation,
+      self.filters.iter(),
+    );
+
+    let group_opacity = self.group_opacity as f32 / 255.0;
+
+    for (bottom, top) in below.pixels_mut().iter_mut().zip(layer.pixels()) {
+      let top = top.map(|channel| channel * group_opacity);
+      let (src_a, dst_a) = (top[3], bottom[3]);
+
+      if src_a <= 0.0 {
+        continue;
+      }
+
+      // Blending has no effect where the backdrop is fully transparent, so plain source-over is exact.
+      if self.blend_mode == BlendMode::Normal || dst_a <= 0.0 {
+        blend_premultiplied_pixel(bottom, top);
+        continue;
+      }
+
+      let source = [top[0], top[1], top[2]].map(|channel| channel / src_a);
+      let backdrop = [bottom[0], bottom[1], bottom[2]].map(|channel| channel / dst_a);
+      let [r, g, b] = blend_premultiplied(backdrop, dst_a, source, src_a, self.blend_mode);
+
+      *bottom = [r, g, b, src_a + dst_a * (1.0 - src_a)];
+    }
+  }
+}
+
+/// A target that layers are opened on and composited back onto when replaying a [`DisplayList`].
+pub(crate) trait LayerTarget: CompositeTarget {
+  /// The pixels of the layers opened on the target.
+  type Layer: LayerTarget<Layer = Self::Layer>;
+
+  /// Creates a transparent layer of the size of the target.
+  fn new_layer(&self) -> Self::Layer;
+
+  /// Applies the effects to `layer` and composites it onto the target.
+  fn composite_layer(
+    &mut self,
+    layer: Self::Layer,
+    effects: &LayerEffects,
+    interpolation: ColorInterpolation,
+  );
+
+  /// Samples the backdrop from the target, which is the part of the output starting at `origin`,
+  /// and applies the filters to it.
+  fn filter_backdrop(
+    &self,
+    backdrop: &DisplayBackdrop,
+    origin: Point<u32>,
+    interpolation: ColorInterpolation,
+  ) -> Option<FilteredBackdrop>;
+}
+
+impl<I: GenericImage<Pixel = Rgba<u8>>> LayerTarget for I {
+  type Layer = RgbaImage;
+
+  fn new_layer(&self) -> RgbaImage {
+    let (width, height) = GenericImageView::dimensions(self);
+
+    RgbaImage::new(width, height)
+  }
+
+  fn composite_layer(
+    &mut self,
+    layer: RgbaImage,
+    effects: &LayerEffects,
+    interpolation: ColorInterpolation,
+  ) {
+    effects.apply(self, layer, interpolation);
+  }
+
+  fn filter_backdrop(
+    &self,
+    backdrop: &DisplayBackdrop,
+    origin: Point<u32>,
+    interpolation: ColorInterpolation,
+  ) -> Option<FilteredBackdrop> {
+    backdrop.filter(self, origin, interpolation)
+  }
+}
+
+impl LayerTarget for WideImage {
+  type Layer = WideImage;
+
+  fn new_layer(&self) -> WideImage {
+    WideImage::new(self.width(), self.height())
+  }
+
+  fn composite_layer(
+    &mut self,
+    layer: WideImage,
+    effects: &LayerEffects,
+    interpolation: ColorInterpolation,
+  ) {
+    effects.apply_wide(self, layer, interpolation);
+  }
+
+  fn filter_backdrop(
+    &self,
+    backdrop: &DisplayBackdrop,
+    origin: Point<u32>,
+    interpolation: ColorInterpolation,
+  ) -> Option<FilteredBackdrop> {
+    backdrop.filter_wide(self, origin, interpolation)
+  }
+}
+
+/// An image of [`DisplayCommand::DrawImage`].
+#[derive(Clone, PartialEq)]
+pub struct DisplayImage {
+  pub(crate) image: Arc<RgbaImage>,
+  pub(crate) opacity: u8,
+  pub(crate) placement: ImagePlacement,
+}
+
+impl DisplayImage {
+  /// The pixels of the image.
+  pub fn image(&self) -> &RgbaImage {
+    &self.image
+  }
+
+  /// The opacity the image is drawn with.
+  pub fn opacity(&self) -> u8 {
+    self.opacity
+  }
+}
+
+impl fmt::Debug for DisplayImage {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("DisplayImage")
+      .field("width", &self.image.width())
+      .field("height", &self.image.height())
+      .field("opacity", &self.opacity)
+      .finish_non_exhaustive()
+  }
+}
+
+/// A filtered backdrop of [`DisplayCommand::DrawBackdrop`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayBackdrop {
+  pub(crate) filters: Filters,
+  pub(crate) sizing: Sizing,
+  pub(crate) current_color: Color,
+  pub(crate) color_space: ColorSpace,
+  pub(crate) opacity: u8,
+  /// The sampled area of the backdrop, from the top-left corner (inclusive) to the bottom-right corner (exclusive).
+  pub(crate) from: Point<u32>,
+  pub(crate) to: Point<u32>,
+  /// The rounded border box the filtered backdrop is drawn in.
+  pub(crate) mask: CoverageMask,
+  /// Whether the node draws into its own layer, so the backdrop is what is below that layer.
+  pub(crate) is_below_layer: bool,
+}
+
+impl DisplayBackdrop {
+  /// The filters applied to the backdrop, in order.
+  pub fn filters(&self) -> &[Filter] {
+    &self.filters
+  }
+}
+
+/// The drawing commands of a laid out node tree, returned by [`record_display_list`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayList {
+  pub(crate) size: Size<u32>,
+  pub(crate) color_interpolation: ColorInterpolation,
+  pub(crate) commands: Vec<DisplayCommand>,
+}
+
+impl DisplayList {
+  /// The size of the output in pixels.
+  pub fn size(&self) -> Size<u32> {
+    self.size
+  }
+
+  /// The recorded commands, in painting order.
+  pub fn commands(&self) -> &[DisplayCommand] {
+    &self.commands
+  }
+
+  /// Rasterizes the commands into an image of the output size.
+  pub fn rasterize(&self) -> RgbaImage {
+    self.rasterize_region(Point { x: 0, y: 0 }, self.size)
+  }
+
+  /// Rasterizes the part of the output of `size` starting at `origin`.
+  ///
+  /// With the `rayon` feature, large images are rasterized in tiles in parallel.
+  pub(crate) fn rasterize_region(&self, origin: Point<u32>, size: Size<u32>) -> RgbaImage {
+    #[cfg(feature = "rayon")]
+    if should_rasterize_in_tiles(self, size) {
+      return rasterize_tiles(self, origin, size);
+    }
+
+    if self.is_wide() {
+      let mut image = WideImage::new(size.width, size.height);
+
+      self.replay_onto(&mut image, origin);
+
+      return image.to_rgba8(self.color_interpolation);
+    }
+
+    let mut image = RgbaImage::new(size.width, size.height);
+
+    self.replay_onto(&mut image, origin);
+
+    image
+  }
+
+  /// Rasterizes the commands into an image of the output size with 16 bits per channel.
+  ///
+  /// The commands are always composited in a [`WideImage`], so blending, opacity and filters
+  /// keep the precision 8-bit channels would round away.
+  pub fn rasterize_rgba16(&self) -> Rgba16Image {
+    self.rasterize_region_rgba16(Point { x: 0, y: 0 }, self.size)
+  }
+
+  /// Rasterizes the part of the output of `size` starting at `origin` with 16 bits per channel.
+  pub(crate) fn rasterize_region_rgba16(&self, origin: Point<u32>, size: Size<u32>) -> Rgba16Image {
+    let mut image = WideImage::new(size.width, size.height);
+
+    self.replay_onto(&mut image, origin);
+
+    image.to_rgba16(self.color_interpolation)
+  }
+
+  /// Whether the commands are composited in a [`WideImage`], as linear light needs more than 8 bits
+  /// to keep dark colors apart.
+  fn is_wide(&self) -> bool {
+    self.color_interpolation == ColorInterpolation::LinearRgb
+  }
+
+  /// Replays the commands onto `target`, which is the part of the output starting at `origin`.
+  ///
+  /// Commands are composited onto the existing pixels of `target`. When they are composited
+  /// in a [`WideImage`], `target` is converted to it and back once.
+  pub(crate) fn replay<I: GenericImage<Pixel = Rgba<u8>>>(
+    &self,
+    target: &mut I,
+    origin: Point<u32>,
+  ) {
+    if self.is_wide() {
+      let mut image = WideImage::from_image(target, self.color_interpolation);
+
+      self.replay_onto(&mut image, origin);
+
+      return image.write_into(target, self.color_interpolation);
+    }
+
+    self.replay_onto(target, origin);
+  }
+
+  fn replay_onto<T: LayerTarget>(&self, target: &mut T, origin: Point<u32>) {
+    let mut replay = Replay {
+      target,
+      origin,
+      color_interpolation: self.color_interpolation,
+      layers: Vec::new(),
+      constrains: Vec::new(),
+    };
+
+    for command in &self.commands {
+      replay.apply(command);
+    }
+  }
+}
+
+/// The state of replaying a [`DisplayList`] onto a target.
+struct Replay<'t, T: LayerTarget> {
+  target: &'t mut T,
+  origin: Point<u32>,
+  color_interpolation: ColorInterpolation,
+  /// The open layers, the last one is drawn onto instead of the target.
+  layers: Vec<T::Layer>,
+  /// The open clips, moved to the origin of the target.
+  constrains: Vec<CanvasConstrain>,
+}
+
+impl<T: LayerTarget> Replay<'_, T> {
+  fn apply(&mut self, command: &DisplayCommand) {
+    match command {
+      DisplayCommand::PushClip(clip) => self.constrains.push(clip.0.translated(self.origin)),
+      DisplayCommand::PopClip => {
+        self.constrains.pop();
+      }
+      DisplayCommand::PushLayer => {
+        let layer = self.target.new_layer();
+
+        self.layers.push(layer);
+      }
+      DisplayCommand::PopLayer(effects) => {
+        let Some(layer) = self.layers.pop() else {
+          return;
+        };
+
+        match self.layers.last_mut() {
+          Some(below) => below.composite_layer(layer, effects, self.color_interpolation),
+          None => self
+            .target
+            .composite_layer(layer, effects, self.color_interpolation),
+        }
+      }
+      DisplayCommand::DrawBackdrop(backdrop) => {
+        let depth = self.layers.len() - usize::from(backdrop.is_below_layer);
+
+        let filtered = match depth.checked_sub(1) {
+          Some(index) => {
+            self.layers[index].filter_backdrop(backdrop, self.origin, self.color_interpolation)
+          }
+          None => self
+            .target
+            .filter_backdrop(backdrop, self.origin, self.color_interpolation),
+        };
+
+        if let Some(filtered) = filtered {
+          self.draw(command, Some(&filtered));
+        }
+      }
+      command => self.draw(command, None),
+    }
+  }
+
+  /// Draws onto the innermost open layer, or the target if there is none.
+  fn draw(&mut self, command: &DisplayCommand, backdrop: Option<&FilteredBackdrop>) {
+    let constrain = self.constrains.last();
+
+    match self.layers.last_mut() {
+      Some(layer) => draw_command(
+        layer,
+        command,
+        backdrop,
+        self.origin,
+        constrain,
+        self.color_interpolation,
+      ),
+      None => draw_command(
+        &mut *self.target,
+        command,
+        backdrop,
+        self.origin,
+        constrain,
+        self.color_interpolation,
+      ),
+    }
+  }
+}
+
+/// Draws a command that paints pixels onto `canvas`, which is the part of the output starting at `origin`.
+fn draw_command<C: CompositeTarget>(
+  canvas: &mut C,
+  command: &DisplayCommand,
+  backdrop: Option<&FilteredBackdrop>,
+  origin: Point<u32>,
+  constrain: Option<&CanvasConstrain>,
+  interpolation: ColorInterpolation,
+) {
+  match command {
+    DisplayCommand::FillRect {
+      offset,
+      size,
+      color,
+    } => fill_rect(
+      canvas,
+      *offset,
+      *size,
+      *color,
+      origin,
+      constrain,
+      interpolation,
+    ),
+    DisplayCommand::FillPath { mask, color } => draw_mask(
+      canvas,
+      &mask.data,
+      mask.translated(origin),
+      *color,
+      constrain,
+      interpolation,
+    ),
+    DisplayCommand::DrawImage(image) => image.placement.draw(
+      canvas,
+      &*image.image,
+      image.opacity,
+      origin,
+      constrain,
+      interpolation,
+    ),
+    DisplayCommand::DrawBackdrop(display) => {
+      if let Some(backdrop) = backdrop {
+        display.draw(canvas, backdrop, origin, constrain, interpolation);
+      }
+    }
+    DisplayCommand::PushClip(_)
+    | DisplayCommand::PopClip
+    | DisplayCommand::PushLayer
+    | DisplayCommand::PopLayer(_) => {}
+  }
+}
+
+fn fill_rect<C: CompositeTarget>(
+  canvas: &mut C,
+  offset: Point<f32>,
+  size: Size<u32>,
+  color: Color,
+  origin: Point<u32>,
+  constrain: Option<&CanvasConstrain>,
+  interpolation: ColorInterpolation,
+) {
+  let offset = Point {
+    x: offset.x.floor() - origin.x as f32,
+    y: offset.y.floor() - origin.y as f32,
+  };
+  let color: Rgba<u8> = color.into();
+  let (width, height) = canvas.dimensions();
+
+  // Fast path: an opaque rectangle covering the whole canvas replaces every pixel
+  let covers_canvas = offset.x <= 0.0
+    && offset.y <= 0.0
+    && offset.x + size.width as f32 >= width as f32
+    && offset.y + size.height as f32 >= height as f32;
+
+  if covers_canvas && constrain.is_none() && color.0[3] == u8::MAX {
+    for y in 0..height {
+      for x in 0..width {
+        canvas.put_opaque(x, y, color, interpolation);
+      }
+    }
+
+    return;
+  }
+
+  overlay_area(canvas, offset, size, constrain, interpolation, |_, _| color);
+}
+
+/// Lays out the node tree and records its drawing commands without rasterizing.
+///
+/// [`DisplayList::rasterize`] gives the same image as [`render`](crate::rendering::render) without a region.
+pub fn record_display_list<'g, N: Node<N>>(options: RenderOptions<'g, N>) -> Result<DisplayList> {
+  record_visible_region(options, None)
+}
+
+/// Records the drawing commands, leaving out what is outside of `region` when it is set.
+pub(crate) fn record_visible_region<'g, N: Node<N>>(
+  options: RenderOptions<'g, N>,
+  region: Option<RenderRegion>,
+) -> Result<DisplayList> {
+  let color_interpolation = options.color_interpolation;
+  let (mut taffy, root_node_id, root_size) = create_layout_tree(options)?;
+
+  let mut canvas = Canvas::new(root_size, color_interpolation);
+
+  if let Some(region) = region {
+    canvas.set_visible_region(region);
+  }
+
+  render_node(&mut taffy, root_node_id, &mut canvas, Affine::IDENTITY)?;
+
+  Ok(canvas.into_display_list())
+}
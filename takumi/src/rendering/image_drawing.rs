@@ -240,7 +240,10 @@ pub fn draw_image(
   canvas: &mut Canvas,
   layout: Layout,
 ) -> Result<()> {
-  let (image, offset) = process_image_for_object_fit(image, context, layout.content_box_size())?;
+  let (mut image, offset) =
+    process_image_for_object_fit(image, context, layout.content_box_size())?;
+
+  image.convert_srgb(context.color_space);
 
   // manually apply the border and padding to ensure rotation with origin is applied correctly
   let transform_with_content_offset = context.transform
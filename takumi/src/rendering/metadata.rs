@@ -0,0 +1,538 @@
+use std::borrow::Cow;
+
+use crate::Error::IoError;
+
+/// A color profile embedded into encoded images.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IccProfile {
+  /// The sRGB color space, which is what the renderer produces.
+  ///
+  /// PNG marks it with a `sRGB` chunk, JPEG and WebP embed an sRGB ICC profile,
+  /// and GIF leaves it out since GIF is sRGB by convention.
+  Srgb,
+  /// The Display P3 color space, for output rendered in [`ColorSpace::DisplayP3`].
+  ///
+  /// Its wider gamut spreads colors over more values, so it is best written as 16-bit PNG
+  /// rendered with [`render_rgba16`] and written with [`write_png_rgba16`].
+  ///
+  /// [`render_rgba16`]: crate::rendering::render_rgba16
+  /// [`write_png_rgba16`]: crate::rendering::write_png_rgba16
+  /// [`ColorSpace::DisplayP3`]: crate::layout::style::ColorSpace::DisplayP3
+  DisplayP3,
+  /// The bytes of an ICC profile.
+  Custom(Vec<u8>),
+}
+
+/// Metadata embedded into encoded images, such as provenance text and a color profile.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageMetadata {
+  /// Text entries as keyword and value pairs, like `("Author", "Takumi")`.
+  ///
+  /// PNG stores each entry in a `tEXt` chunk, or an `iTXt` chunk when it is not Latin-1.
+  /// JPEG and WebP store them as XMP, and the `Description`, `Author`, `Copyright` and `Software`
+  /// keywords in EXIF too. GIF stores them as comments.
+  pub text: Vec<(String, String)>,
+  /// The color profile to embed.
+  pub icc_profile: Option<IccProfile>,
+}
+
+/// EXIF tags the standard PNG keywords are written to.
+const EXIF_TEXT_TAGS: [(&str, u16); 4] = [
+  ("Description", 0x010e),
+  ("Software", 0x0131),
+  ("Author", 0x013b),
+  ("Copyright", 0x8298),
+];
+
+/// The EXIF `ASCII` field type.
+const EXIF_ASCII: u16 = 2;
+
+// tag (2 bytes) + type (2 bytes) + count (4 bytes) + value or offset (4 bytes)
+const EXIF_ENTRY_SIZE: usize = 12;
+
+/// Namespace of the XMP properties for keywords without a Dublin Core or XMP equivalent.
+const XMP_TAKUMI_NAMESPACE: &str = "https://takumi.kane.tw/ns/1.0/";
+
+/// Signature of the APP1 segment holding XMP in JPEG.
+const JPEG_XMP_SIGNATURE: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+impl ImageMetadata {
+  /// Returns the bytes of the ICC profile, encoding one for sRGB and Display P3.
+  pub(crate) fn icc_profile_bytes(&self) -> Result<Option<Cow<'_, [u8]>>, crate::Error> {
+    let profile = match &self.icc_profile {
+      None => return Ok(None),
+      Some(IccProfile::Custom(bytes)) => return Ok(Some(Cow::Borrowed(bytes))),
+      Some(IccProfile::Srgb) => moxcms::ColorProfile::new_srgb(),
+      Some(IccProfile::DisplayP3) => moxcms::ColorProfile::new_display_p3(),
+    };
+
+    profile
+      .encode()
+      .map(|bytes| Some(Cow::Owned(bytes)))
+      .map_err(|error| IoError(std::io::Error::other(error.to_string())))
+  }
+
+  /// Returns the value of the text entry with `keyword`, ignoring case.
+  fn text_value(&self, keyword: &str) -> Option<&str> {
+    self
+      .text
+      .iter()
+      .find(|(key, _)| key.eq_ignore_ascii_case(keyword))
+      .map(|(_, value)| value.as_str())
+  }
+
+  /// Returns a little-endian TIFF structure with the text entries that have an EXIF tag,
+  /// or `None` when there are none.
+  pub(crate) fn exif(&self) -> Option<Vec<u8>> {
+    let entries: Vec<(u16, &str)> = EXIF_TEXT_TAGS
+      .iter()
+      .filter_map(|&(keyword, tag)| Some((tag, self.text_value(keyword)?)))
+      .collect();
+
+    if entries.is_empty() {
+      return None;
+    }
+
+    // Values longer than 4 bytes are stored after the IFD.
+    let mut data_offset = 8 + 2 + entries.len() * EXIF_ENTRY_SIZE + 4;
+
+    let mut ifd = Vec::with_capacity(data_offset);
+    let mut data = Vec::new();
+
+    ifd.extend_from_slice(b"II");
+    ifd.extend_from_slice(&42u16.to_le_bytes());
+    ifd.extend_from_slice(&8u32.to_le_bytes());
+    ifd.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+
+    for (tag, value) in entries {
+      let mut bytes = value.as_bytes().to_vec();
+      bytes.push(0);
+
+      ifd.extend_from_slice(&tag.to_le_bytes());
+      ifd.extend_from_slice(&EXIF_ASCII.to_le_bytes());
+      ifd.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+
+      if bytes.len() <= 4 {
+        bytes.resize(4, 0);
+        ifd.extend_from_slice(&bytes);
+        continue;
+      }
+
+      ifd.extend_from_slice(&(data_offset as u32).to_le_bytes());
+
+      // Offsets are kept word aligned.
+      if bytes.len() & 1 == 1 {
+        bytes.push(0);
+      }
+
+      data_offset += bytes.len();
+      data.extend_from_slice(&bytes);
+    }
+
+    // No next IFD.
+    ifd.extend_from_slice(&0u32.to_le_bytes());
+    ifd.extend_from_slice(&data);
+
+    Some(ifd)
+  }
+
+  /// Returns an XMP packet with the text entries, or `None` when there are none.
+  pub(crate) fn xmp(&self) -> Option<Vec<u8>> {
+    if self.text.is_empty() {
+      return None;
+    }
+
+    let mut properties = String::new();
+
+    for (keyword, value) in &self.text {
+      let value = escape_xml(value);
+
+      let property = match keyword.to_ascii_lowercase().as_str() {
+        "title" => format!(
+          "<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{value}</rdf:li></rdf:Alt></dc:title>"
+        ),
+        "description" => format!(
+          "<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{value}</rdf:li></rdf:Alt></dc:description>"
+        ),
+        "author" => format!("<dc:creator><rdf:Seq><rdf:li>{value}</rdf:li></rdf:Seq></dc:creator>"),
+        "copyright" => format!(
+          "<dc:rights><rdf:Alt><rdf:li xml:lang=\"x-default\">{value}</rdf:li></rdf:Alt></dc:rights>"
+        ),
+        "software" => format!("<xmp:CreatorTool>{value}</xmp:CreatorTool>"),
+        _ => {
+          let name = xml_name(keyword);
+          format!("<takumi:{name}>{value}</takumi:{name}>")
+        }
+      };
+
+      properties.push_str("   ");
+      properties.push_str(&property);
+      properties.push('\n');
+    }
+
+    Some(
+      format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n \
+         <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n  \
+         <rdf:Description rdf:about=\"\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\" \
+         xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" \
+         xmlns:takumi=\"{XMP_TAKUMI_NAMESPACE}\">\n\
+         {properties}  \
+         </rdf:Description>\n \
+         </rdf:RDF>\n\
+         </x:xmpmeta>\n\
+         <?xpacket end=\"w\"?>"
+      )
+      .into_bytes(),
+    )
+  }
+}
+
+/// Escapes the characters with a meaning in XML text and attributes.
+fn escape_xml(value: &str) -> Cow<'_, str> {
+  if !value.contains(['&', '<', '>', '"', '\'']) {
+    return Cow::Borrowed(value);
+  }
+
+  let mut escaped = String::with_capacity(value.len() + 16);
+
+  for char in value.chars() {
+    match char {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      _ => escaped.push(char),
+    }
+  }
+
+  Cow::Owned(escaped)
+}
+
+/// Turns a keyword into a valid XML element name, replacing other characters with `_`.
+fn xml_name(keyword: &str) -> String {
+  let mut name: String = keyword
+    .chars()
+    .map(|char| {
+      if char.is_alphanumeric() || matches!(char, '_' | '-' | '.') {
+        char
+      } else {
+        '_'
+      }
+    })
+    .collect();
+
+  if !name.starts_with(|char: char| char.is_alphabetic() || char == '_') {
+    name.insert(0, '_');
+  }
+
+  name
+}
+
+/// Inserts an APP1 segment with the `xmp` packet into a JPEG, after its other application segments.
+pub(crate) fn insert_jpeg_xmp(jpeg: &mut Vec<u8>, xmp: &[u8]) -> Result<(), crate::Error> {
+  // length (2 bytes) + signature + packet
+  let Ok(length) = u16::try_from(2 + JPEG_XMP_SIGNATURE.len() + xmp.len()) else {
+    return Err(IoError(std::io::Error::other(
+      "XMP metadata is too large for a JPEG segment",
+    )));
+  };
+
+  // Skip SOI, then the APPn segments.
+  let mut offset = 2;
+
+  while jpeg.len() >= offset + 4
+    && jpeg[offset] == 0xff
+    && (0xe0..=0xef).contains(&jpeg[offset + 1])
+  {
+    offset += 2 + u16::from_be_bytes([jpeg[offset + 2], jpeg[offset + 3]]) as usize;
+  }
+
+  let mut segment = Vec::with_capacity(2 + length as usize);
+  segment.extend_from_slice(&[0xff, 0xe1]);
+  segment.extend_from_slice(&length.to_be_bytes());
+  segment.extend_from_slice(JPEG_XMP_SIGNATURE);
+  segment.extend_from_slice(xmp);
+
+  let offset = offset.min(jpeg.len());
+  jpeg.splice(offset..offset, segment);
+
+  Ok(())
+}
+
+/// Returns whether `text` can be stored in Latin-1 text chunks.
+pub(crate) fn is_latin1(text: &str) -> bool {
+  text.chars().all(|char| (char as u32) <= 0xff)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+
+  use image::{ImageDecoder, Rgba, RgbaImage, codecs::jpeg::JpegDecoder};
+
+  use super::*;
+  use crate::TestResult;
+  use crate::rendering::{
+    AnimationFrame, ImageEncodeOptions, ImageOutputFormat, PngAnimationOptions,
+    WebPAnimationOptions, WebPCompression, encode_animated_png_with_options,
+    encode_animated_webp_with_options, write_image_with_options,
+  };
+
+  fn metadata() -> ImageMetadata {
+    ImageMetadata {
+      text: vec![
+        ("Author".to_owned(), "Takumi".to_owned()),
+        ("Title".to_owned(), "Café <OG> 画像".to_owned()),
+        ("Creation Time".to_owned(), "2024-01-01".to_owned()),
+      ],
+      icc_profile: Some(IccProfile::Srgb),
+    }
+  }
+
+  fn image() -> RgbaImage {
+    RgbaImage::from_fn(24, 16, |x, y| {
+      Rgba([(x * 10) as u8, (y * 15) as u8, 128, 255])
+    })
+  }
+
+  #[test]
+  fn test_exif() {
+    assert_eq!(ImageMetadata::default().exif(), None);
+
+    let exif = ImageMetadata {
+      text: vec![
+        ("Copyright".to_owned(), "CC0".to_owned()),
+        ("author".to_owned(), "Takumi".to_owned()),
+      ],
+      icc_profile: None,
+    }
+    .exif();
+
+    let mut expected = b"II\x2a\x00\x08\x00\x00\x00\x02\x00".to_vec();
+    // Artist, stored after the IFD since it's longer than 4 bytes.
+    expected.extend_from_slice(&[0x3b, 0x01, 2, 0, 7, 0, 0, 0, 38, 0, 0, 0]);
+    // Copyright, stored inline.
+    expected.extend_from_slice(&[0x98, 0x82, 2, 0, 4, 0, 0, 0]);
+    expected.extend_from_slice(b"CC0\0");
+    expected.extend_from_slice(&[0; 4]);
+    expected.extend_from_slice(b"Takumi\0\0");
+
+    assert_eq!(exif, Some(expected));
+  }
+
+  #[test]
+  fn test_xmp() -> TestResult {
+    let xmp = String::from_utf8(metadata().xmp().ok_or("missing XMP")?)?;
+
+    assert!(xmp.contains("<dc:creator><rdf:Seq><rdf:li>Takumi</rdf:li></rdf:Seq></dc:creator>"));
+    assert!(xmp.contains("Café &lt;OG&gt; 画像"));
+    assert!(xmp.contains("<takumi:Creation_Time>2024-01-01</takumi:Creation_Time>"));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_xml_name() {
+    assert_eq!(xml_name("Creation Time"), "Creation_Time");
+    assert_eq!(xml_name("2x"), "_2x");
+    assert_eq!(xml_name("og:image"), "og_image");
+  }
+
+  #[test]
+  fn test_png_metadata() -> TestResult {
+    let mut png = Vec::new();
+    write_image_with_options(
+      &image(),
+      &mut png,
+      ImageOutputFormat::Png,
+      ImageEncodeOptions {
+        metadata: Some(&metadata()),
+        ..Default::default()
+      },
+    )?;
+
+    let reader = png::Decoder::new(Cursor::new(png)).read_info()?;
+    let info = reader.info();
+
+    assert!(info.srgb.is_some());
+    assert_eq!(info.uncompressed_latin1_text.len(), 2);
+    assert_eq!(info.uncompressed_latin1_text[0].keyword, "Author");
+    assert_eq!(info.uncompressed_latin1_text[0].text, "Takumi");
+    assert_eq!(info.utf8_text.len(), 1);
+    assert_eq!(info.utf8_text[0].get_text()?, "Café <OG> 画像");
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_png_custom_icc_profile() -> TestResult {
+    let metadata = ImageMetadata {
+      text: Vec::new(),
+      icc_profile: Some(IccProfile::Custom(
+        ImageMetadata {
+          text: Vec::new(),
+          icc_profile: Some(IccProfile::Srgb),
+        }
+        .icc_profile_bytes()?
+        .ok_or("missing profile")?
+        .into_owned(),
+      )),
+    };
+
+    let mut png = Vec::new();
+    write_image_with_options(
+      &image(),
+      &mut png,
+      ImageOutputFormat::Png,
+      ImageEncodeOptions {
+        metadata: Some(&metadata),
+        ..Default::default()
+      },
+    )?;
+
+    let reader = png::Decoder::new(Cursor::new(png)).read_info()?;
+
+    assert_eq!(
+      reader.info().icc_profile.as_deref(),
+      metadata.icc_profile_bytes()?.as_deref()
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_jpeg_metadata() -> TestResult {
+    let metadata = metadata();
+
+    let mut jpeg = Vec::new();
+    write_image_with_options(
+      &image(),
+      &mut jpeg,
+      ImageOutputFormat::Jpeg,
+      ImageEncodeOptions {
+        quality: Some(90),
+        metadata: Some(&metadata),
+        ..Default::default()
+      },
+    )?;
+
+    assert!(
+      jpeg
+        .windows(JPEG_XMP_SIGNATURE.len())
+        .any(|window| window == JPEG_XMP_SIGNATURE)
+    );
+
+    let mut decoder = JpegDecoder::new(Cursor::new(&jpeg))?;
+
+    assert_eq!(
+      decoder.icc_profile()?.as_deref(),
+      metadata.icc_profile_bytes()?.as_deref()
+    );
+    assert_eq!(decoder.exif_metadata()?, metadata.exif());
+    assert_eq!(
+      image::load_from_memory(&jpeg)?.to_rgba8().dimensions(),
+      (24, 16)
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_webp_metadata() -> TestResult {
+    let metadata = metadata();
+
+    for webp_compression in [WebPCompression::Lossless, WebPCompression::Lossy] {
+      let mut webp = Vec::new();
+      write_image_with_options(
+        &image(),
+        &mut webp,
+        ImageOutputFormat::WebP,
+        ImageEncodeOptions {
+          webp_compression,
+          metadata: Some(&metadata),
+          ..Default::default()
+        },
+      )?;
+
+      let mut decoder = image_webp::WebPDecoder::new(Cursor::new(&webp))?;
+
+      assert_eq!(
+        decoder.icc_profile()?.as_deref(),
+        metadata.icc_profile_bytes()?.as_deref()
+      );
+      assert_eq!(decoder.exif_metadata()?, metadata.exif());
+      assert_eq!(decoder.xmp_metadata()?, metadata.xmp());
+      assert_eq!(decoder.dimensions(), (24, 16));
+
+      let mut buffer = vec![0; decoder.output_buffer_size().ok_or("image too large")?];
+      decoder.read_image(&mut buffer)?;
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_animated_png_metadata() -> TestResult {
+    let frames = [
+      AnimationFrame::new(image(), 100),
+      AnimationFrame::new(RgbaImage::new(24, 16), 100),
+    ];
+
+    let mut apng = Vec::new();
+    encode_animated_png_with_options(
+      &frames,
+      &mut apng,
+      PngAnimationOptions::default(),
+      Some(&metadata()),
+    )?;
+
+    let reader = png::Decoder::new(Cursor::new(apng)).read_info()?;
+    let info = reader.info();
+
+    assert_eq!(
+      info.animation_control.map(|control| control.num_frames),
+      Some(2)
+    );
+    assert_eq!(info.uncompressed_latin1_text.len(), 2);
+    assert_eq!(info.utf8_text.len(), 1);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_animated_webp_metadata() -> TestResult {
+    let metadata = metadata();
+    let frames = [
+      AnimationFrame::new(image(), 100),
+      AnimationFrame::new(RgbaImage::new(24, 16), 100),
+    ];
+
+    let mut webp = Vec::new();
+    encode_animated_webp_with_options(
+      &frames,
+      &mut webp,
+      WebPAnimationOptions {
+        quality: Some(75),
+        ..Default::default()
+      },
+      Some(&metadata),
+    )?;
+
+    let mut decoder = image_webp::WebPDecoder::new(Cursor::new(&webp))?;
+
+    assert!(decoder.is_animated());
+    assert_eq!(decoder.num_frames(), 2);
+    assert_eq!(decoder.xmp_metadata()?, metadata.xmp());
+    assert_eq!(decoder.exif_metadata()?, metadata.exif());
+    assert_eq!(
+      decoder.icc_profile()?.as_deref(),
+      metadata.icc_profile_bytes()?.as_deref()
+    );
+
+    Ok(())
+  }
+}
@@ -1,41 +1,82 @@
+/// Backdrop filter drawing functions
+mod backdrop_drawing;
 /// Background and color drawing functions
 mod background_drawing;
+/// Border image drawing functions
+mod border_image_drawing;
 /// Canvas operations and image blending
 mod canvas;
 mod components;
+/// Computed layout without rasterizing
+mod computed_layout;
 /// Debug drawing utilities
 mod debug_drawing;
+/// Retained drawing commands between layout and painting
+mod display_list;
 /// Image drawing functions
 mod image_drawing;
 pub(crate) mod inline_drawing;
+/// Metadata embedded into encoded images
+mod metadata;
+/// PDF document output
+#[cfg(feature = "pdf")]
+mod pdf;
 /// Main image renderer and viewport management
 mod render;
+/// Stacking contexts and painting order
+mod stacking_context;
+/// SVG document output
+mod svg;
 /// Text drawing functions
 mod text_drawing;
+/// Tile-parallel rasterization
+#[cfg(feature = "rayon")]
+mod tiles;
+/// Vector drawing of the layout tree
+mod vector;
+/// Lossy VP8 encoding for WebP output
+mod vp8;
+/// Images with premultiplied `f32` channels
+mod wide_image;
 mod write;
 
 use std::{collections::HashMap, sync::Arc};
 
+pub(crate) use backdrop_drawing::*;
 pub(crate) use background_drawing::*;
+pub(crate) use border_image_drawing::*;
+pub use canvas::ColorInterpolation;
 pub(crate) use canvas::*;
 pub(crate) use components::*;
+pub use computed_layout::*;
 pub(crate) use debug_drawing::*;
+pub use display_list::*;
 pub(crate) use image_drawing::*;
+pub use metadata::*;
+#[cfg(feature = "pdf")]
+pub(crate) use pdf::*;
 pub use render::*;
+pub(crate) use stacking_context::*;
+pub use svg::*;
 pub(crate) use text_drawing::*;
+#[cfg(feature = "rayon")]
+pub(crate) use tiles::*;
+pub(crate) use vector::*;
+pub(crate) use vp8::*;
+pub(crate) use wide_image::*;
 pub use write::*;
 
 use crate::{
   GlobalContext,
   layout::{
     Viewport,
-    style::{Affine, Color, InheritedStyle},
+    style::{Affine, Color, ColorSpace, InheritedStyle},
   },
   resources::image::ImageSource,
 };
 
 /// The sizing context used for length value resolving.
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) struct Sizing {
   /// The viewport for the image renderer.
   pub(crate) viewport: Viewport,
@@ -56,10 +97,16 @@ pub struct RenderContext<'g> {
   pub(crate) current_color: Color,
   /// The opacity to apply to all colors.
   pub(crate) opacity: u8,
+  /// The opacity to composite the node and its descendants with as a group, `255` if it isn't a group.
+  pub(crate) group_opacity: u8,
   /// The style after inheritance.
   pub(crate) style: InheritedStyle,
   /// Whether to draw debug borders.
   pub(crate) draw_debug_border: bool,
+  /// The color space to composite, blur and interpolate gradients in.
+  pub(crate) color_interpolation: ColorInterpolation,
+  /// The color space colors are resolved to.
+  pub(crate) color_space: ColorSpace,
   /// The resources fetched externally.
   pub(crate) fetched_resources: HashMap<Arc<str>, Arc<ImageSource>>,
 }
@@ -79,8 +126,11 @@ impl<'g> RenderContext<'g> {
       transform: Affine::IDENTITY,
       current_color: Color::black(),
       opacity: 255,
+      group_opacity: 255,
       style: InheritedStyle::default(),
       draw_debug_border: false,
+      color_interpolation: ColorInterpolation::default(),
+      color_space: ColorSpace::default(),
       fetched_resources,
     }
   }
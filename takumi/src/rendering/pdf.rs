@@ -0,0 +1,1107 @@
+//! PDF document output.
+//!
+//! Pages are drawn by the [`VectorRenderer`], text is written with embedded font subsets
+//! so it stays selectable, and everything else is written as paths, shadings and image XObjects.
+
+use std::{
+  collections::BTreeMap,
+  hash::{Hash, Hasher},
+  mem::take,
+};
+
+use image::RgbaImage;
+use miniz_oxide::deflate::compress_to_vec_zlib;
+use parley::{FontData, Glyph, GlyphRun};
+use pdf_writer::{
+  Content, Filter, Finish, Name, Pdf, Rect, Ref, Str,
+  types::{
+    CidFontType, FontFlags, FunctionShadingType, LineJoinStyle, MaskType, SystemInfo, UnicodeCmap,
+  },
+  writers::Resources,
+};
+use rustc_hash::{FxHashMap, FxHasher};
+use subsetter::{GlyphRemapper, Tag, subset_with_variations};
+use swash::{FontRef, StringId, Style, Variation, tag_from_bytes};
+use taffy::Point;
+use zeno::{Command, Vector};
+
+use crate::{
+  Result,
+  layout::{
+    inline::InlineBrush,
+    node::Node,
+    style::{Affine, Color, ColorSpace, FillRule, ResolvedGradientStop},
+  },
+  rendering::{
+    RenderOptions, VectorPaint, VectorRenderer, VectorSurface, create_layout_tree,
+    strip_alpha_channel,
+  },
+  resources::font::FontError,
+};
+
+/// CSS pixels are 1/96 inch, while PDF points are 1/72 inch.
+const POINTS_PER_PIXEL: f32 = 72.0 / 96.0;
+
+/// Fonts are addressed with the glyph ids of the subset, which is what the subsetter produces.
+const IDENTITY_SYSTEM_INFO: SystemInfo = SystemInfo {
+  registry: Str(b"Adobe"),
+  ordering: Str(b"Identity"),
+  supplement: 0,
+};
+
+/// Identifies a font instance, fonts with different variation coordinates are embedded separately.
+#[derive(PartialEq, Eq, Hash)]
+struct FontKey {
+  blob_id: u64,
+  index: u32,
+  normalized_coords: Vec<i16>,
+}
+
+/// A font used by the document, its subset is written when the document is finished.
+struct PdfFont {
+  name: String,
+  reference: Ref,
+  font: FontData,
+  normalized_coords: Vec<i16>,
+  remapper: GlyphRemapper,
+  /// Advance widths in thousandths of an em, keyed by the glyph id in the subset.
+  widths: BTreeMap<u16, f32>,
+  /// The text each glyph represents, keyed by the glyph id in the subset.
+  texts: BTreeMap<u16, String>,
+}
+
+/// Collects the pages of a PDF document and the resources they use.
+pub(crate) struct PdfDocument {
+  pdf: Pdf,
+  next_ref: i32,
+  catalog_ref: Ref,
+  page_tree_ref: Ref,
+  /// All pages share one resource dictionary, since fonts are only complete after the last page.
+  resources_ref: Ref,
+  page_refs: Vec<Ref>,
+  fonts: Vec<PdfFont>,
+  font_indices: FxHashMap<FontKey, usize>,
+  x_objects: Vec<(String, Ref)>,
+  /// Image XObjects by the hash of their pixels, repeated background tiles are written once.
+  images: FxHashMap<u64, String>,
+  ext_g_states: Vec<(String, Ref)>,
+  alpha_states: FxHashMap<u8, String>,
+  shadings: Vec<(String, Ref)>,
+}
+
+impl Default for PdfDocument {
+  fn default() -> Self {
+    Self {
+      pdf: Pdf::new(),
+      next_ref: 4,
+      catalog_ref: Ref::new(1),
+      page_tree_ref: Ref::new(2),
+      resources_ref: Ref::new(3),
+      page_refs: Vec::new(),
+      fonts: Vec::new(),
+      font_indices: FxHashMap::default(),
+      x_objects: Vec::new(),
+      images: FxHashMap::default(),
+      ext_g_states: Vec::new(),
+      alpha_states: FxHashMap::default(),
+      shadings: Vec::new(),
+    }
+  }
+}
+
+impl PdfDocument {
+  fn alloc(&mut self) -> Ref {
+    let reference = Ref::new(self.next_ref);
+    self.next_ref += 1;
+
+    reference
+  }
+
+  /// Lays out the node and draws it as a new page, sized by the viewport.
+  pub(crate) fn add_page<'g, N: Node<N>>(&mut self, options: RenderOptions<'g, N>) -> Result<()> {
+    let scale = POINTS_PER_PIXEL / options.viewport.device_pixel_ratio;
+
+    // PDF colors are written as device RGB, which viewers treat as sRGB.
+    let (mut taffy, root_node_id, root_size) = create_layout_tree(RenderOptions {
+      color_space: ColorSpace::Srgb,
+      ..options
+    })?;
+
+    let width = root_size.width as f32 * scale;
+    let height = root_size.height as f32 * scale;
+
+    let mut surface = PdfSurface {
+      document: self,
+      content: Content::new(),
+      groups: Vec::new(),
+      bbox: Rect::new(0.0, 0.0, root_size.width as f32, root_size.height as f32),
+    };
+
+    // Flip the y axis, so the page is drawn in the same pixel coordinates as the raster output.
+    surface
+      .content
+      .transform([scale, 0.0, 0.0, -scale, 0.0, height]);
+
+    VectorRenderer::new(&mut surface, root_size).render_node(
+      &mut taffy,
+      root_node_id,
+      Affine::IDENTITY,
+    )?;
+
+    let content = compress(&surface.content.finish());
+
+    let page_ref = self.alloc();
+    let content_ref = self.alloc();
+
+    self
+      .pdf
+      .stream(content_ref, &content)
+      .filter(Filter::FlateDecode);
+
+    let mut page = self.pdf.page(page_ref);
+
+    page
+      .media_box(Rect::new(0.0, 0.0, width, height))
+      .parent(self.page_tree_ref)
+      .contents(content_ref);
+    page.pair(Name(b"Resources"), self.resources_ref);
+    page.finish();
+
+    self.page_refs.push(page_ref);
+
+    Ok(())
+  }
+
+  /// Writes the fonts and the shared resources, and returns the bytes of the document.
+  pub(crate) fn finish(mut self) -> Result<Vec<u8>> {
+    self.pdf.catalog(self.catalog_ref).pages(self.page_tree_ref);
+    self
+      .pdf
+      .pages(self.page_tree_ref)
+      .kids(self.page_refs.iter().copied())
+      .count(self.page_refs.len() as i32);
+
+    let fonts = take(&mut self.fonts);
+
+    for font in &fonts {
+      self.write_font(font)?;
+    }
+
+    let mut resources = self.pdf.indirect(self.resources_ref).start::<Resources>();
+
+    resources.fonts().pairs(
+      fonts
+        .iter()
+        .map(|font| (Name(font.name.as_bytes()), font.reference)),
+    );
+    resources.x_objects().pairs(
+      self
+        .x_objects
+        .iter()
+        .map(|(name, reference)| (Name(name.as_bytes()), *reference)),
+    );
+    resources.ext_g_states().pairs(
+      self
+        .ext_g_states
+        .iter()
+        .map(|(name, reference)| (Name(name.as_bytes()), *reference)),
+    );
+    resources.shadings().pairs(
+      self
+        .shadings
+        .iter()
+        .map(|(name, reference)| (Name(name.as_bytes()), *reference)),
+    );
+    resources.finish();
+
+    Ok(self.pdf.finish())
+  }
+
+  /// Returns the font for the instance, registering it if it's not used yet.
+  fn font(&mut self, font: &FontData, normalized_coords: &[i16]) -> &mut PdfFont {
+    let key = FontKey {
+      blob_id: font.data.id(),
+      index: font.index,
+      normalized_coords: normalized_coords.to_vec(),
+    };
+
+    let index = match self.font_indices.get(&key) {
+      Some(index) => *index,
+      None => {
+        let reference = self.alloc();
+        let index = self.fonts.len();
+
+        self.fonts.push(PdfFont {
+          name: format!("F{index}"),
+          reference,
+          font: font.clone(),
+          normalized_coords: normalized_coords.to_vec(),
+          remapper: GlyphRemapper::new(),
+          widths: BTreeMap::new(),
+          texts: BTreeMap::new(),
+        });
+        self.font_indices.insert(key, index);
+
+        index
+      }
+    };
+
+    &mut self.fonts[index]
+  }
+
+  fn write_font(&mut self, font: &PdfFont) -> Result<()> {
+    let data = font.font.data.as_ref();
+    let font_ref =
+      FontRef::from_index(data, font.font.index as usize).ok_or(FontError::InvalidFontIndex)?;
+
+    let subset = subset_with_variations(
+      data,
+      font.font.index,
+      &user_variations(font_ref, &font.normalized_coords),
+      &font.remapper,
+    )?;
+
+    let is_cff = FontRef::from_index(&subset, 0)
+      .and_then(|subset| subset.table(tag_from_bytes(b"CFF ")))
+      .is_some();
+
+    let metrics = font_ref.metrics(&font.normalized_coords);
+    let to_pdf_units = 1000.0 / metrics.units_per_em as f32;
+
+    let base_font = format!("{}+{}", subset_tag(font), postscript_name(font_ref));
+    let base_font = Name(base_font.as_bytes());
+
+    let cid_font_ref = self.alloc();
+    let descriptor_ref = self.alloc();
+    let font_file_ref = self.alloc();
+    let cmap_ref = self.alloc();
+
+    self
+      .pdf
+      .type0_font(font.reference)
+      .base_font(base_font)
+      .encoding_predefined(Name(b"Identity-H"))
+      .descendant_font(cid_font_ref)
+      .to_unicode(cmap_ref);
+
+    let mut cid_font = self.pdf.cid_font(cid_font_ref);
+
+    cid_font
+      .subtype(if is_cff {
+        CidFontType::Type0
+      } else {
+        CidFontType::Type2
+      })
+      .base_font(base_font)
+      .system_info(IDENTITY_SYSTEM_INFO)
+      .font_descriptor(descriptor_ref)
+      .default_width(0.0);
+
+    if !is_cff {
+      cid_font.cid_to_gid_map_predefined(Name(b"Identity"));
+    }
+
+    let mut widths = cid_font.widths();
+
+    for (glyph, width) in &font.widths {
+      widths.consecutive(*glyph, [*width]);
+    }
+
+    widths.finish();
+    cid_font.finish();
+
+    let attributes = font_ref.attributes();
+
+    let italic_angle = match attributes.style() {
+      Style::Normal => 0.0,
+      Style::Italic => -12.0,
+      Style::Oblique(angle) => -angle.to_degrees(),
+    };
+
+    let mut flags = FontFlags::SYMBOLIC;
+    flags.set(FontFlags::FIXED_PITCH, metrics.is_monospace);
+    flags.set(FontFlags::ITALIC, italic_angle != 0.0);
+
+    let mut descriptor = self.pdf.font_descriptor(descriptor_ref);
+
+    descriptor
+      .name(base_font)
+      .flags(flags)
+      .bbox(font_bbox(font_ref, to_pdf_units))
+      .italic_angle(italic_angle)
+      .ascent(metrics.ascent * to_pdf_units)
+      .descent(-metrics.descent.abs() * to_pdf_units)
+      .cap_height(metrics.cap_height * to_pdf_units)
+      .stem_v(10.0 + 0.244 * (attributes.weight().0 as f32 - 50.0));
+
+    if is_cff {
+      descriptor.font_file3(font_file_ref);
+    } else {
+      descriptor.font_file2(font_file_ref);
+    }
+
+    descriptor.finish();
+
+    let font_file_data = compress(&subset);
+    let mut font_file = self.pdf.stream(font_file_ref, &font_file_data);
+
+    font_file.filter(Filter::FlateDecode);
+
+    if is_cff {
+      font_file.pair(Name(b"Subtype"), Name(b"OpenType"));
+    }
+
+    font_file.finish();
+
+    let mut cmap = UnicodeCmap::new(Name(b"Custom"), IDENTITY_SYSTEM_INFO);
+
+    for (glyph, text) in &font.texts {
+      cmap.pair_with_multiple(*glyph, text.chars());
+    }
+
+    self
+      .pdf
+      .cmap(cmap_ref, &compress(&cmap.finish()))
+      .filter(Filter::FlateDecode);
+
+    Ok(())
+  }
+
+  /// Returns the name of the graphics state setting both fill and stroke alpha.
+  fn alpha_state(&mut self, alpha: u8) -> String {
+    if let Some(name) = self.alpha_states.get(&alpha) {
+      return name.clone();
+    }
+
+    let reference = self.alloc();
+    let name = format!("Gs{}", self.ext_g_states.len());
+
+    self
+      .pdf
+      .ext_graphics(reference)
+      .non_stroking_alpha(alpha as f32 / 255.0)
+      .stroking_alpha(alpha as f32 / 255.0);
+
+    self.ext_g_states.push((name.clone(), reference));
+    self.alpha_states.insert(alpha, name.clone());
+
+    name
+  }
+
+  /// Returns the name of a form XObject drawing the content as an isolated transparency group.
+  ///
+  /// The content is drawn in the coordinates of where the group is painted, with the shared page resources.
+  fn transparency_group(&mut self, content: &[u8], bbox: Rect) -> String {
+    let reference = self.alloc();
+    let data = compress(content);
+
+    let mut form = self.pdf.form_xobject(reference, &data);
+
+    form.filter(Filter::FlateDecode);
+    form.bbox(bbox);
+    form
+      .group()
+      .transparency()
+      .isolated(true)
+      .color_space()
+      .device_rgb();
+    form.pair(Name(b"Resources"), self.resources_ref);
+    form.finish();
+
+    let name = format!("Fm{}", self.x_objects.len());
+
+    self.x_objects.push((name.clone(), reference));
+
+    name
+  }
+
+  /// Returns the name of the image XObject, with its alpha channel as a soft mask.
+  fn image(&mut self, image: &RgbaImage) -> String {
+    let mut hasher = FxHasher::default();
+    image.dimensions().hash(&mut hasher);
+    image.as_raw().hash(&mut hasher);
+    let hash = Hasher::finish(&hasher);
+
+    if let Some(name) = self.images.get(&hash) {
+      return name.clone();
+    }
+
+    let image_ref = self.alloc();
+
+    let mask_ref = if image.pixels().any(|pixel| pixel.0[3] != u8::MAX) {
+      let mask_ref = self.alloc();
+      let alpha: Vec<u8> = image.pixels().map(|pixel| pixel.0[3]).collect();
+      let data = compress(&alpha);
+
+      let mut mask = self.pdf.image_xobject(mask_ref, &data);
+
+      mask.filter(Filter::FlateDecode);
+      mask
+        .width(image.width() as i32)
+        .height(image.height() as i32)
+        .bits_per_component(8);
+      mask.color_space().device_gray();
+      mask.finish();
+
+      Some(mask_ref)
+    } else {
+      None
+    };
+
+    let data = compress(&strip_alpha_channel(image));
+    let mut xobject = self.pdf.image_xobject(image_ref, &data);
+
+    xobject.filter(Filter::FlateDecode);
+    xobject
+      .width(image.width() as i32)
+      .height(image.height() as i32)
+      .bits_per_component(8);
+    xobject.color_space().device_rgb();
+
+    if let Some(mask_ref) = mask_ref {
+      xobject.s_mask(mask_ref);
+    }
+
+    xobject.finish();
+
+    let name = format!("Im{}", self.x_objects.len());
+
+    self.x_objects.push((name.clone(), image_ref));
+    self.images.insert(hash, name.clone());
+
+    name
+  }
+
+  /// Writes the shading of a gradient paint, `alpha` writes the gray shading of its stop alphas instead.
+  fn shading(&mut self, paint: &VectorPaint, alpha: bool) -> Option<Ref> {
+    let (kind, coords, stops) = match paint {
+      VectorPaint::Solid(_) => return None,
+      VectorPaint::LinearGradient { start, end, stops } => (
+        FunctionShadingType::Axial,
+        vec![start.x, start.y, end.x, end.y],
+        stops,
+      ),
+      VectorPaint::RadialGradient { radius, stops, .. } => (
+        FunctionShadingType::Radial,
+        vec![0.0, 0.0, 0.0, 0.0, 0.0, *radius],
+        stops,
+      ),
+    };
+
+    let function_ref = self.gradient_function(stops, alpha);
+    let shading_ref = self.alloc();
+
+    let mut shading = self.pdf.function_shading(shading_ref);
+
+    shading.shading_type(kind);
+
+    if alpha {
+      shading.color_space().device_gray();
+    } else {
+      shading.color_space().device_rgb();
+    }
+
+    shading
+      .function(function_ref)
+      .coords(coords)
+      .extend([true, true]);
+    shading.finish();
+
+    Some(shading_ref)
+  }
+
+  fn gradient_function(&mut self, stops: &[ResolvedGradientStop], alpha: bool) -> Ref {
+    let components = |color: Color| -> Vec<f32> {
+      let [r, g, b, a] = color.0.map(|channel| channel as f32 / 255.0);
+
+      if alpha { vec![a] } else { vec![r, g, b] }
+    };
+
+    let function_ref = self.alloc();
+
+    if stops.len() < 2 {
+      let color = stops
+        .first()
+        .map(|stop| stop.color)
+        .unwrap_or(Color::transparent());
+
+      self
+        .pdf
+        .exponential_function(function_ref)
+        .domain([0.0, 1.0])
+        .c0(components(color))
+        .c1(components(color))
+        .n(1.0);
+
+      return function_ref;
+    }
+
+    let segment_refs: Vec<Ref> = stops
+      .windows(2)
+      .map(|window| {
+        let segment_ref = self.alloc();
+
+        self
+          .pdf
+          .exponential_function(segment_ref)
+          .domain([0.0, 1.0])
+          .c0(components(window[0].color))
+          .c1(components(window[1].color))
+          .n(1.0);
+
+        segment_ref
+      })
+      .collect();
+
+    let mut previous = 0.0f32;
+    let bounds: Vec<f32> = stops[1..stops.len() - 1]
+      .iter()
+      .map(|stop| {
+        previous = stop.position.clamp(previous, 1.0);
+        previous
+      })
+      .collect();
+
+    self
+      .pdf
+      .stitching_function(function_ref)
+      .domain([0.0, 1.0])
+      .functions(segment_refs.iter().copied())
+      .bounds(bounds)
+      .encode(segment_refs.iter().flat_map(|_| [0.0, 1.0]));
+
+    function_ref
+  }
+
+  /// Returns the name of a graphics state masking the painted area with the alpha of the gradient stops.
+  fn gradient_alpha_state(&mut self, paint: &VectorPaint, bbox: Rect) -> Option<String> {
+    let stops = match paint {
+      VectorPaint::Solid(_) => return None,
+      VectorPaint::LinearGradient { stops, .. } | VectorPaint::RadialGradient { stops, .. } => {
+        stops
+      }
+    };
+
+    if stops.iter().all(|stop| stop.color.0[3] == u8::MAX) {
+      return None;
+    }
+
+    let shading_ref = self.shading(paint, true)?;
+    let form_ref = self.alloc();
+    let state_ref = self.alloc();
+
+    let mut content = Content::new();
+
+    if let VectorPaint::RadialGradient { transform, .. } = paint {
+      content.transform(matrix(*transform));
+    }
+
+    content.shading(Name(b"Sh0"));
+
+    let data = content.finish();
+    let mut form = self.pdf.form_xobject(form_ref, &data);
+
+    form.bbox(bbox);
+    form
+      .group()
+      .transparency()
+      .isolated(true)
+      .color_space()
+      .device_gray();
+    form.resources().shadings().pair(Name(b"Sh0"), shading_ref);
+    form.finish();
+
+    self
+      .pdf
+      .ext_graphics(state_ref)
+      .soft_mask()
+      .subtype(MaskType::Luminosity)
+      .group(form_ref);
+
+    let name = format!("Gs{}", self.ext_g_states.len());
+
+    self.ext_g_states.push((name.clone(), state_ref));
+
+    Some(name)
+  }
+}
+
+/// Draws a page of the [`PdfDocument`].
+struct PdfSurface<'d> {
+  document: &'d mut PdfDocument,
+  content: Content,
+  /// The content outside of each open group, with the opacity of the group.
+  groups: Vec<(Content, u8)>,
+  /// The bounds of the page in pixel coordinates.
+  bbox: Rect,
+}
+
+impl PdfSurface<'_> {
+  fn set_fill_color(&mut self, color: Color) {
+    let [r, g, b, a] = color.0;
+
+    if a != u8::MAX {
+      let name = self.document.alpha_state(a);
+      self.content.set_parameters(Name(name.as_bytes()));
+    }
+
+    self
+      .content
+      .set_fill_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+  }
+}
+
+impl VectorSurface for PdfSurface<'_> {
+  fn push_clip(&mut self, path: &[Command], transform: Affine, fill_rule: FillRule) {
+    self.content.save_state();
+
+    write_path(&mut self.content, path, transform);
+
+    match fill_rule {
+      FillRule::NonZero => self.content.clip_nonzero(),
+      FillRule::EvenOdd => self.content.clip_even_odd(),
+    };
+
+    self.content.end_path();
+  }
+
+  fn pop_clip(&mut self) {
+    self.content.restore_state();
+  }
+
+  fn push_group(&mut self, opacity: u8) {
+    let outer = std::mem::replace(&mut self.content, Content::new());
+
+    self.groups.push((outer, opacity));
+  }
+
+  fn pop_group(&mut self) {
+    let Some((outer, opacity)) = self.groups.pop() else {
+      return;
+    };
+
+    let group = std::mem::replace(&mut self.content, outer);
+    let name = self.document.transparency_group(&group.finish(), self.bbox);
+
+    self.content.save_state();
+
+    if opacity != u8::MAX {
+      let alpha_state = self.document.alpha_state(opacity);
+      self.content.set_parameters(Name(alpha_state.as_bytes()));
+    }
+
+    self.content.x_object(Name(name.as_bytes()));
+    self.content.restore_state();
+  }
+
+  fn fill_path(
+    &mut self,
+    path: &[Command],
+    transform: Affine,
+    paint: &VectorPaint,
+    fill_rule: FillRule,
+  ) {
+    if let VectorPaint::Solid(color) = paint {
+      if color.0[3] == 0 {
+        return;
+      }
+
+      self.content.save_state();
+      self.set_fill_color(*color);
+
+      write_path(&mut self.content, path, transform);
+
+      match fill_rule {
+        FillRule::NonZero => self.content.fill_nonzero(),
+        FillRule::EvenOdd => self.content.fill_even_odd(),
+      };
+
+      self.content.restore_state();
+
+      return;
+    }
+
+    let Some(shading_ref) = self.document.shading(paint, false) else {
+      return;
+    };
+
+    let name = format!("Sh{}", self.document.shadings.len());
+    self.document.shadings.push((name.clone(), shading_ref));
+
+    let alpha_state = self.document.gradient_alpha_state(paint, path_bounds(path));
+
+    self.content.save_state();
+
+    write_path(&mut self.content, path, transform);
+
+    match fill_rule {
+      FillRule::NonZero => self.content.clip_nonzero(),
+      FillRule::EvenOdd => self.content.clip_even_odd(),
+    };
+
+    self.content.end_path();
+    self.content.transform(matrix(transform));
+
+    // The soft mask is drawn in the coordinates in effect when it's set.
+    if let Some(alpha_state) = alpha_state {
+      self.content.set_parameters(Name(alpha_state.as_bytes()));
+    }
+
+    if let VectorPaint::RadialGradient { transform, .. } = paint {
+      self.content.transform(matrix(*transform));
+    }
+
+    self.content.shading(Name(name.as_bytes()));
+    self.content.restore_state();
+  }
+
+  fn stroke_path(&mut self, path: &[Command], transform: Affine, color: Color, width: f32) {
+    let [r, g, b, a] = color.0;
+
+    if a == 0 {
+      return;
+    }
+
+    self.content.save_state();
+
+    if a != u8::MAX {
+      let name = self.document.alpha_state(a);
+      self.content.set_parameters(Name(name.as_bytes()));
+    }
+
+    // The path is transformed up front, so the width is not affected by the transform.
+    write_path(&mut self.content, path, transform);
+
+    self
+      .content
+      .set_stroke_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+      .set_line_width(width)
+      .set_line_join(LineJoinStyle::BevelJoin)
+      .stroke();
+
+    self.content.restore_state();
+  }
+
+  fn draw_image(&mut self, image: &RgbaImage, transform: Affine, opacity: u8) -> Result<()> {
+    if opacity == 0 || image.width() == 0 || image.height() == 0 {
+      return Ok(());
+    }
+
+    let name = self.document.image(image);
+
+    self.content.save_state();
+
+    if opacity != u8::MAX {
+      let alpha_state = self.document.alpha_state(opacity);
+      self.content.set_parameters(Name(alpha_state.as_bytes()));
+    }
+
+    let width = image.width() as f32;
+    let height = image.height() as f32;
+
+    // Images are drawn into the unit square, with the first row at the top.
+    self.content.transform(matrix(
+      transform
+        * Affine {
+          a: width,
+          b: 0.0,
+          c: 0.0,
+          d: -height,
+          x: 0.0,
+          y: height,
+        },
+    ));
+    self.content.x_object(Name(name.as_bytes()));
+    self.content.restore_state();
+
+    Ok(())
+  }
+
+  fn fill_glyphs(
+    &mut self,
+    glyph_run: &GlyphRun<'_, InlineBrush>,
+    glyphs: &[(Glyph, &str)],
+    transform: Affine,
+  ) -> Result<bool> {
+    let run = glyph_run.run();
+    let font_data = run.font();
+    let normalized_coords = run.normalized_coords();
+    let font_size = run.font_size();
+
+    let font_ref = FontRef::from_index(font_data.data.as_ref(), font_data.index as usize)
+      .ok_or(FontError::InvalidFontIndex)?;
+
+    let glyph_metrics = font_ref.glyph_metrics(normalized_coords);
+    let to_pdf_units = 1000.0 / glyph_metrics.units_per_em() as f32;
+
+    let font = self.document.font(font_data, normalized_coords);
+    let font_name = font.name.clone();
+
+    // The subset glyph id and the advance in thousandths of an em of each glyph.
+    let subset_glyphs: Vec<(u16, f32)> = glyphs
+      .iter()
+      .map(|(glyph, text)| {
+        let id = font.remapper.remap(glyph.id as u16);
+        let width = glyph_metrics.advance_width(glyph.id as u16) * to_pdf_units;
+
+        font.widths.insert(id, width);
+
+        if !text.is_empty() {
+          font.texts.entry(id).or_insert_with(|| text.to_string());
+        }
+
+        (id, width)
+      })
+      .collect();
+
+    self.content.save_state();
+    self.set_fill_color(glyph_run.style().brush.color);
+    self.content.transform(matrix(transform));
+    self.content.begin_text();
+    self.content.set_font(Name(font_name.as_bytes()), font_size);
+
+    let mut index = 0;
+
+    while index < subset_glyphs.len() {
+      let origin = glyphs[index].0;
+
+      // Flip the text back upright, the page is drawn with the y axis pointing down.
+      self
+        .content
+        .set_text_matrix([1.0, 0.0, 0.0, -1.0, origin.x, origin.y]);
+
+      let mut positioned = self.content.show_positioned();
+      let mut items = positioned.items();
+
+      let mut pen_x = origin.x;
+      let mut codes = Vec::new();
+
+      // Glyphs on the same baseline are shown together, with the difference to their advances as adjustments.
+      while let Some((id, width)) = subset_glyphs.get(index) {
+        let position = glyphs[index].0;
+
+        if (position.y - origin.y).abs() > f32::EPSILON {
+          break;
+        }
+
+        let adjustment = (pen_x - position.x) * 1000.0 / font_size;
+
+        if adjustment.abs() > 0.01 {
+          if !codes.is_empty() {
+            items.show(Str(&codes));
+            codes.clear();
+          }
+
+          items.adjust(adjustment);
+        }
+
+        codes.extend(id.to_be_bytes());
+        pen_x = position.x + width * font_size / 1000.0;
+        index += 1;
+      }
+
+      if !codes.is_empty() {
+        items.show(Str(&codes));
+      }
+
+      items.finish();
+      positioned.finish();
+    }
+
+    self.content.end_text();
+    self.content.restore_state();
+
+    Ok(true)
+  }
+}
+
+fn compress(data: &[u8]) -> Vec<u8> {
+  compress_to_vec_zlib(data, 6)
+}
+
+fn matrix(transform: Affine) -> [f32; 6] {
+  [
+    transform.a,
+    transform.b,
+    transform.c,
+    transform.d,
+    transform.x,
+    transform.y,
+  ]
+}
+
+fn write_path(content: &mut Content, path: &[Command], transform: Affine) {
+  let map = |point: Vector| {
+    transform.transform_point(Point {
+      x: point.x,
+      y: point.y,
+    })
+  };
+
+  let mut start = Vector::ZERO;
+  let mut current = Vector::ZERO;
+
+  for command in path {
+    match *command {
+      Command::MoveTo(point) => {
+        let mapped = map(point);
+        content.move_to(mapped.x, mapped.y);
+        start = point;
+        current = point;
+      }
+      Command::LineTo(point) => {
+        let mapped = map(point);
+        content.line_to(mapped.x, mapped.y);
+        current = point;
+      }
+      Command::CurveTo(control1, control2, point) => {
+        let (control1, control2, mapped) = (map(control1), map(control2), map(point));
+        content.cubic_to(
+          control1.x, control1.y, control2.x, control2.y, mapped.x, mapped.y,
+        );
+        current = point;
+      }
+      Command::QuadTo(control, point) => {
+        // Elevate the quadratic curve to a cubic one
+        let control1 = map(current + (control - current) * (2.0 / 3.0));
+        let control2 = map(point + (control - point) * (2.0 / 3.0));
+        let mapped = map(point);
+        content.cubic_to(
+          control1.x, control1.y, control2.x, control2.y, mapped.x, mapped.y,
+        );
+        current = point;
+      }
+      Command::Close => {
+        content.close_path();
+        current = start;
+      }
+    }
+  }
+}
+
+/// Returns the bounds of all points of the path, including control points.
+fn path_bounds(path: &[Command]) -> Rect {
+  let mut bounds = Rect::new(f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+
+  let points = path.iter().flat_map(|command| match *command {
+    Command::MoveTo(point) | Command::LineTo(point) => vec![point],
+    Command::CurveTo(control1, control2, point) => vec![control1, control2, point],
+    Command::QuadTo(control, point) => vec![control, point],
+    Command::Close => Vec::new(),
+  });
+
+  for point in points {
+    bounds.x1 = bounds.x1.min(point.x);
+    bounds.y1 = bounds.y1.min(point.y);
+    bounds.x2 = bounds.x2.max(point.x);
+    bounds.y2 = bounds.y2.max(point.y);
+  }
+
+  if bounds.x1 > bounds.x2 {
+    return Rect::new(0.0, 0.0, 0.0, 0.0);
+  }
+
+  bounds
+}
+
+/// Converts the normalized coordinates of the instance back to the user space axis values
+/// the subsetter instances the font with.
+fn user_variations(font: FontRef, normalized_coords: &[i16]) -> Vec<(Tag, f32)> {
+  font
+    .variations()
+    .zip(normalized_coords)
+    .map(|(axis, &coord)| {
+      let value = if coord == 0 {
+        axis.default_value()
+      } else {
+        // Normalizing is monotonic (avar included), so the range of values
+        // mapping to the coordinate can be found by bisection.
+        let from = bisect(axis, |normalized| normalized >= coord);
+        let to = bisect(axis, |normalized| normalized > coord);
+
+        (from + to) / 2.0
+      };
+
+      (Tag::new(&axis.tag().to_be_bytes()), value)
+    })
+    .collect()
+}
+
+/// Returns the smallest axis value whose normalized coordinate satisfies the predicate.
+fn bisect(axis: Variation, predicate: impl Fn(i16) -> bool) -> f32 {
+  let mut low = axis.min_value();
+  let mut high = axis.max_value();
+
+  for _ in 0..32 {
+    let middle = (low + high) / 2.0;
+
+    if predicate(axis.normalize(middle)) {
+      high = middle;
+    } else {
+      low = middle;
+    }
+  }
+
+  high
+}
+
+fn postscript_name(font: FontRef) -> String {
+  let name: String = font
+    .localized_strings()
+    .find_by_id(StringId::PostScript, None)
+    .map(|name| name.chars().collect())
+    .unwrap_or_default();
+
+  let name: String = name
+    .chars()
+    .filter(|char| char.is_ascii_alphanumeric() || *char == '-')
+    .collect();
+
+  if name.is_empty() {
+    "Font".to_string()
+  } else {
+    name
+  }
+}
+
+/// Returns the six uppercase letters prefixed to the name of a font subset.
+fn subset_tag(font: &PdfFont) -> String {
+  let mut hasher = FxHasher::default();
+
+  font.font.data.id().hash(&mut hasher);
+  font.font.index.hash(&mut hasher);
+  font.normalized_coords.hash(&mut hasher);
+  font.widths.keys().for_each(|glyph| glyph.hash(&mut hasher));
+
+  let mut hash = Hasher::finish(&hasher);
+
+  (0..6)
+    .map(|_| {
+      let letter = b'A' + (hash % 26) as u8;
+      hash /= 26;
+
+      letter as char
+    })
+    .collect()
+}
+
+/// Reads the bounding box of all glyphs from the `head` table.
+fn font_bbox(font: FontRef, to_pdf_units: f32) -> Rect {
+  let read = |head: &[u8], offset: usize| {
+    head
+      .get(offset..offset + 2)
+      .map(|bytes| i16::from_be_bytes([bytes[0], bytes[1]]) as f32 * to_pdf_units)
+      .unwrap_or_default()
+  };
+
+  let Some(head) = font.table(tag_from_bytes(b"head")) else {
+    return Rect::new(0.0, 0.0, 0.0, 0.0);
+  };
+
+  Rect::new(
+    read(head, 36),
+    read(head, 38),
+    read(head, 40),
+    read(head, 42),
+  )
+}
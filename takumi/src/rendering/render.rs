@@ -1,20 +1,25 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+  collections::HashMap,
+  ops::{Deref, DerefMut},
+  sync::Arc,
+};
 
 use derive_builder::Builder;
-use image::RgbaImage;
-use taffy::{AvailableSpace, NodeId, TaffyError, TaffyTree, geometry::Size};
+use image::{ImageBuffer, Rgba, RgbaImage, imageops::crop};
+use taffy::{AvailableSpace, NodeId, Point, TaffyError, TaffyTree, geometry::Size};
 
 use crate::{
   GlobalContext,
   layout::{
     Viewport,
     node::Node,
-    style::{Affine, Display, ImageScalingAlgorithm, InheritedStyle, SpacePair, apply_filters},
+    style::{Affine, BlendMode, ColorSpace, Display, InheritedStyle, SpacePair},
     tree::NodeTree,
   },
   rendering::{
-    BorderProperties, Canvas, CanvasConstrain, CanvasConstrainResult, Sizing, draw_debug_border,
-    overlay_image,
+    Canvas, CanvasConstrain, CanvasConstrainResult, ColorInterpolation, LayerEffects, LayoutTree,
+    Sizing, StackedNode, collect_layout_node, collect_stacked_nodes, draw_debug_border,
+    draw_filtered_backdrop, is_painted_in_tree_order, record_visible_region, stacking_level,
   },
   resources::image::ImageSource,
 };
@@ -36,14 +41,177 @@ pub struct RenderOptions<'g, N: Node<N>> {
   /// The resources fetched externally.
   #[builder(default)]
   pub(crate) fetched_resources: HashMap<Arc<str>, Arc<ImageSource>>,
+  /// The region of the output to render, the whole output is rendered if not set.
+  #[builder(default, setter(strip_option))]
+  pub(crate) region: Option<RenderRegion>,
+  /// The color space to composite, blur and interpolate gradients in.
+  #[builder(default)]
+  pub(crate) color_interpolation: ColorInterpolation,
+  /// The color space colors and images are converted to, sRGB by default.
+  ///
+  /// Colors outside of sRGB, like `color(display-p3 1 0 0)`, keep their gamut in Display P3.
+  /// Tag the output with [`IccProfile::DisplayP3`](crate::rendering::IccProfile) when writing it.
+  /// Vector output is always sRGB.
+  #[builder(default)]
+  pub(crate) color_space: ColorSpace,
+}
+
+/// A rectangle of the output in pixels, used to render only part of the layout.
+///
+/// Filters are applied to the pixels of the region only,
+/// so blurs near its edges can differ from rendering the whole output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderRegion {
+  /// The left edge of the region.
+  pub x: u32,
+  /// The top edge of the region.
+  pub y: u32,
+  /// The width of the region.
+  pub width: u32,
+  /// The height of the region.
+  pub height: u32,
 }
 
 /// Renders a node to an image.
+///
+/// If a [`RenderRegion`] is set, the image only has the size of the region.
+/// With the `rayon` feature, large images are rendered in tiles in parallel.
 pub fn render<'g, N: Node<N>>(options: RenderOptions<'g, N>) -> Result<RgbaImage, crate::Error> {
+  let region = options.region;
+
+  if region.is_some_and(|region| region.width == 0 || region.height == 0) {
+    return Err(crate::Error::InvalidViewport);
+  }
+
+  let display_list = record_visible_region(options, region)?;
+
+  Ok(match region {
+    Some(region) => display_list.rasterize_region(
+      Point {
+        x: region.x,
+        y: region.y,
+      },
+      Size {
+        width: region.width,
+        height: region.height,
+      },
+    ),
+    None => display_list.rasterize(),
+  })
+}
+
+/// An image with 16 bits per channel and straight alpha.
+pub type Rgba16Image = ImageBuffer<Rgba<u16>, Vec<u16>>;
+
+/// Renders a node to an image with 16 bits per channel.
+///
+/// Drawing is composited in premultiplied `f32` channels and only rounded once at the end, so
+/// gradients, translucent layers, blurs and anti-aliased edges keep more precision than [`render`].
+/// It is meant for 16-bit output with [`write_png_rgba16`](crate::rendering::write_png_rgba16),
+/// especially in [`ColorSpace::DisplayP3`] where colors are spread over a wider gamut.
+/// Unlike [`render`], the image is never rendered in tiles.
+pub fn render_rgba16<'g, N: Node<N>>(
+  options: RenderOptions<'g, N>,
+) -> Result<Rgba16Image, crate::Error> {
+  let region = options.region;
+
+  if region.is_some_and(|region| region.width == 0 || region.height == 0) {
+    return Err(crate::Error::InvalidViewport);
+  }
+
+  let display_list = record_visible_region(options, region)?;
+
+  Ok(match region {
+    Some(region) => display_list.rasterize_region_rgba16(
+      Point {
+        x: region.x,
+        y: region.y,
+      },
+      Size {
+        width: region.width,
+        height: region.height,
+      },
+    ),
+    None => display_list.rasterize_rgba16(),
+  })
+}
+
+/// Renders a node and composites it onto `target` with its top-left corner at `offset`.
+///
+/// The drawing commands are replayed onto `target` directly, so layers blend with and
+/// backdrop filters sample its existing pixels. If a [`RenderRegion`] is set, only that part
+/// of the output is drawn. Pixels outside of `target` are discarded. A raw RGBA buffer can be
+/// used as the target with [`ImageBuffer::from_raw`].
+pub fn render_into<'g, N: Node<N>, C: Deref<Target = [u8]> + DerefMut>(
+  options: RenderOptions<'g, N>,
+  target: &mut ImageBuffer<Rgba<u8>, C>,
+  offset: Point<u32>,
+) -> Result<(), crate::Error> {
+  let region = options.region;
+
+  if region.is_some_and(|region| region.width == 0 || region.height == 0) {
+    return Err(crate::Error::InvalidViewport);
+  }
+
+  // Only the part of the output that lands on the target is recorded.
+  let visible = RenderRegion {
+    x: region.map_or(0, |region| region.x),
+    y: region.map_or(0, |region| region.y),
+    width: region
+      .map_or(u32::MAX, |region| region.width)
+      .min(target.width().saturating_sub(offset.x)),
+    height: region
+      .map_or(u32::MAX, |region| region.height)
+      .min(target.height().saturating_sub(offset.y)),
+  };
+
+  if visible.width == 0 || visible.height == 0 {
+    return Ok(());
+  }
+
+  let display_list = record_visible_region(options, Some(visible))?;
+
+  let mut target = crop(target, offset.x, offset.y, visible.width, visible.height);
+
+  display_list.replay(
+    &mut *target,
+    Point {
+      x: visible.x,
+      y: visible.y,
+    },
+  );
+
+  Ok(())
+}
+
+/// Computes the layout of a node without rasterizing it.
+///
+/// Returns the boxes and accumulated transform of every node, and the lines and glyph runs of text.
+pub fn compute_layout<'g, N: Node<N>>(
+  options: RenderOptions<'g, N>,
+) -> Result<LayoutTree, crate::Error> {
+  let (taffy, root_node_id, root_size) = create_layout_tree(options)?;
+
+  Ok(LayoutTree {
+    width: root_size.width,
+    height: root_size.height,
+    root: collect_layout_node(&taffy, root_node_id, Affine::IDENTITY)?,
+  })
+}
+
+/// The taffy tree with computed layout, its root node and the size of the output.
+pub(crate) type ComputedLayoutTree<'g, N> = (TaffyTree<NodeTree<'g, N>>, NodeId, Size<u32>);
+
+/// Builds the node tree, computes its layout and resolves the size of the output.
+pub(crate) fn create_layout_tree<'g, N: Node<N>>(
+  options: RenderOptions<'g, N>,
+) -> Result<ComputedLayoutTree<'g, N>, crate::Error> {
   let mut taffy = TaffyTree::new();
 
   let render_context = RenderContext {
     draw_debug_border: options.draw_debug_border,
+    color_interpolation: options.color_interpolation,
+    color_space: options.color_space,
     ..RenderContext::new(options.global, options.viewport, options.fetched_resources)
   };
 
@@ -86,14 +254,10 @@ pub fn render<'g, N: Node<N>>(options: RenderOptions<'g, N>) -> Result<RgbaImage
     }
   });
 
-  let mut canvas = Canvas::new(root_size);
-
-  render_node(&mut taffy, root_node_id, &mut canvas, Affine::IDENTITY)?;
-
-  Ok(canvas.into_inner())
+  Ok((taffy, root_node_id, root_size))
 }
 
-fn apply_transform(
+pub(crate) fn apply_transform(
   transform: &mut Affine,
   style: &InheritedStyle,
   border_box: Size<f32>,
@@ -133,13 +297,14 @@ fn apply_transform(
   *transform *= local;
 }
 
-fn render_node<'g, Nodes: Node<Nodes>>(
+pub(crate) fn render_node<'g, Nodes: Node<Nodes>>(
   taffy: &mut TaffyTree<NodeTree<'g, Nodes>>,
   node_id: NodeId,
   canvas: &mut Canvas,
   mut transform: Affine,
 ) -> Result<(), crate::Error> {
   let layout = *taffy.layout(node_id)?;
+  let is_root = taffy.parent(node_id).is_none();
 
   let Some(node) = taffy.get_node_context_mut(node_id) else {
     return Err(TaffyError::InvalidInputNode(node_id).into());
@@ -181,34 +346,67 @@ fn render_node<'g, Nodes: Node<Nodes>>(
   }
 
   let has_constrain = constrain.is_some();
+  let is_overflow_constrained = matches!(
+    constrain,
+    CanvasConstrainResult::Some(CanvasConstrain::Overflow { .. })
+  );
 
-  let should_create_isolated_canvas = !node.context.style.filter.is_empty();
+  let blend_mode = node.context.style.mix_blend_mode;
+  let group_opacity = node.context.group_opacity;
 
-  // If isolated canvas is required, replace the current canvas with a new one.
-  // Make sure to merge the image back!
-  let original_canvas_image = if should_create_isolated_canvas {
-    Some(canvas.replace_new_image())
-  } else {
-    None
-  };
+  // Filters, blend modes and group opacity apply to the node and its descendants as a group.
+  let should_create_isolated_canvas = !node.context.style.filter.is_empty()
+    || blend_mode != BlendMode::Normal
+    || group_opacity != u8::MAX;
 
-  match constrain {
-    CanvasConstrainResult::None => {
-      node.draw_shell(canvas, layout)?;
+  // If isolated canvas is required, draw into a new layer.
+  // Make sure to pop it!
+  if should_create_isolated_canvas {
+    canvas.push_layer();
+  }
+
+  // The root always paints its lifted descendants, even without creating a stacking context on its own.
+  let is_stacking_context = is_root || stacking_level(&node.context.style).is_some();
+  let is_inline_layout = node.should_create_inline_layout();
+
+  // Overflow only clips the descendants, while clip path and mask also apply to the node itself.
+  let overflow_constrain = match constrain {
+    CanvasConstrainResult::Some(constrain @ CanvasConstrain::Overflow { .. }) => Some(constrain),
+    CanvasConstrainResult::Some(constrain) => {
+      canvas.push_constrain(constrain);
+      None
     }
-    CanvasConstrainResult::Some(constrain) => match constrain {
-      CanvasConstrain::ClipPath { .. } | CanvasConstrain::MaskImage { .. } => {
-        canvas.push_constrain(constrain);
-        node.draw_shell(canvas, layout)?;
-      }
-      CanvasConstrain::Overflow { .. } => {
-        node.draw_shell(canvas, layout)?;
-        canvas.push_constrain(constrain);
-      }
-    },
+    CanvasConstrainResult::None => None,
     CanvasConstrainResult::SkipRendering => unreachable!(),
+  };
+
+  // The backdrop is what was painted under the node, before its isolated canvas.
+  draw_filtered_backdrop(&node.context, canvas, layout, should_create_isolated_canvas);
+
+  node.draw_shell(canvas, layout)?;
+
+  if let Some(constrain) = overflow_constrain {
+    canvas.push_constrain(constrain);
+  }
+
+  let stacked_nodes = if is_stacking_context && !is_inline_layout {
+    collect_stacked_nodes(taffy, node_id, transform)?
+  } else {
+    Vec::new()
+  };
+
+  let (negative_nodes, positive_nodes) =
+    stacked_nodes.split_at(stacked_nodes.partition_point(|stacked| stacked.level < 0));
+
+  // Negative levels are painted right above the background of the stacking context.
+  for stacked in negative_nodes {
+    render_stacked_node(taffy, stacked, canvas)?;
   }
 
+  let Some(node) = taffy.get_node_context_mut(node_id) else {
+    return Err(TaffyError::InvalidInputNode(node_id).into());
+  };
+
   node.draw_content(canvas, layout)?;
 
   if node.context.draw_debug_border {
@@ -218,41 +416,98 @@ fn render_node<'g, Nodes: Node<Nodes>>(
   let filters = node.context.style.filter.clone();
   let sizing = node.context.sizing;
   let current_color = node.context.current_color;
+  let color_space = node.context.color_space;
   let opacity = node.context.opacity;
 
-  if node.should_create_inline_layout() {
+  if is_inline_layout {
     node.draw_inline(canvas, layout)?;
   } else {
     for child_id in taffy.children(node_id)? {
-      render_node(taffy, child_id, canvas, transform)?;
+      let Some(child) = taffy.get_node_context(child_id) else {
+        return Err(TaffyError::InvalidInputNode(child_id).into());
+      };
+
+      if is_painted_in_tree_order(&child.context.style) {
+        render_node(taffy, child_id, canvas, transform)?;
+      }
     }
   }
 
-  apply_filters(
-    &mut canvas.image,
-    &sizing,
-    current_color,
-    opacity,
-    filters.iter(),
-  );
+  for stacked in positive_nodes {
+    render_stacked_node(taffy, stacked, canvas)?;
+  }
+
+  // The outline belongs to the node itself, so its own overflow doesn't clip it.
+  if is_overflow_constrained {
+    canvas.pop_constrain();
+  }
 
-  // If there was an isolated canvas, composite the filtered image back into the original canvas
-  if let Some(mut original_canvas_image) = original_canvas_image {
-    overlay_image(
-      &mut original_canvas_image,
-      (&canvas.image).into(),
-      BorderProperties::zero(),
-      Affine::IDENTITY,
-      ImageScalingAlgorithm::Auto,
-      255,
-      None,
+  let Some(node) = taffy.get_node_context(node_id) else {
+    return Err(TaffyError::InvalidInputNode(node_id).into());
+  };
+
+  node.draw_outline(canvas, layout);
+
+  // If there was an isolated canvas, composite the filtered layer back
+  if should_create_isolated_canvas {
+    canvas.pop_layer(LayerEffects {
+      filters,
+      sizing,
+      current_color,
+      color_space,
+      opacity,
+      blend_mode,
+      group_opacity,
+    });
+  }
+
+  if has_constrain && !is_overflow_constrained {
+    canvas.pop_constrain();
+  }
+
+  Ok(())
+}
+
+/// Paints a node lifted out of tree order, under the overflow clips of the ancestors it was lifted out of.
+fn render_stacked_node<'g, Nodes: Node<Nodes>>(
+  taffy: &mut TaffyTree<NodeTree<'g, Nodes>>,
+  stacked: &StackedNode,
+  canvas: &mut Canvas,
+) -> Result<(), crate::Error> {
+  let mut constrain_count = 0;
+  let mut is_visible = true;
+
+  for &ancestor_id in &stacked.ancestors {
+    let layout = *taffy.layout(ancestor_id)?;
+
+    let Some(ancestor) = taffy.get_node_context(ancestor_id) else {
+      return Err(TaffyError::InvalidInputNode(ancestor_id).into());
+    };
+
+    match CanvasConstrain::from_node(
+      &ancestor.context,
+      &ancestor.context.style,
+      layout,
+      ancestor.context.transform,
       &mut canvas.mask_memory,
-    );
+    )? {
+      CanvasConstrainResult::Some(constrain) => {
+        canvas.push_constrain(constrain);
+        constrain_count += 1;
+      }
+      CanvasConstrainResult::None => {}
+      CanvasConstrainResult::SkipRendering => {
+        is_visible = false;
+        break;
+      }
+    }
+  }
 
-    canvas.image = original_canvas_image;
+  if is_visible {
+    render_node(taffy, stacked.node_id, canvas, stacked.transform)?;
   }
 
-  if has_constrain {
+  for _ in 0..constrain_count {
     canvas.pop_constrain();
   }
 
@@ -0,0 +1,130 @@
+use smallvec::SmallVec;
+use taffy::{NodeId, TaffyError, TaffyTree};
+
+use crate::layout::{
+  node::Node,
+  style::{Affine, BackgroundImage, BlendMode, Display, InheritedStyle, SpacePair, ZIndex},
+  tree::NodeTree,
+};
+
+/// Returns the level of the stacking context created by the element, or `None` if it doesn't create one.
+///
+/// Every element here is positioned, so an integer `z-index` always applies.
+/// Reference: <https://developer.mozilla.org/en-US/docs/Web/CSS/CSS_positioned_layout/Stacking_context>
+pub(crate) fn stacking_level(style: &InheritedStyle) -> Option<i32> {
+  if let ZIndex::Integer(level) = style.z_index {
+    return Some(level);
+  }
+
+  let creates_stacking_context = style.opacity.0 < 1.0
+    || style.transform.is_some()
+    || style.rotate.is_some()
+    || style.resolve_scale() != SpacePair::default()
+    || style.resolve_translate() != SpacePair::default()
+    || !style.filter.is_empty()
+    || !style.backdrop_filter.is_empty()
+    || style.mix_blend_mode != BlendMode::Normal
+    || style.clip_path.is_some()
+    || has_mask_image(style);
+
+  creates_stacking_context.then_some(0)
+}
+
+/// Returns whether any mask layer has an image, which makes the mask apply.
+pub(crate) fn has_mask_image(style: &InheritedStyle) -> bool {
+  match style.mask_image.as_deref() {
+    Some(images) => images
+      .iter()
+      .any(|image| !matches!(image, BackgroundImage::None)),
+    None => style
+      .mask
+      .iter()
+      .any(|mask| !matches!(mask.image, BackgroundImage::None)),
+  }
+}
+
+/// A descendant with a non-zero `z-index`, painted by its nearest ancestor stacking context instead of in tree order.
+pub(crate) struct StackedNode {
+  pub(crate) node_id: NodeId,
+  pub(crate) level: i32,
+  /// The transform of the parent node.
+  pub(crate) transform: Affine,
+  /// The nodes between the stacking context and this node, whose overflow clips still apply to it.
+  pub(crate) ancestors: SmallVec<[NodeId; 4]>,
+}
+
+/// Collects the descendants of a stacking context that are lifted out of tree order, sorted by their level.
+///
+/// Descendants that create a stacking context are painted atomically, so their own descendants are not collected.
+pub(crate) fn collect_stacked_nodes<'g, N: Node<N>>(
+  taffy: &mut TaffyTree<NodeTree<'g, N>>,
+  node_id: NodeId,
+  transform: Affine,
+) -> Result<Vec<StackedNode>, TaffyError> {
+  let mut stacked = Vec::new();
+  let mut ancestors = SmallVec::new();
+
+  collect_stacked_nodes_impl(taffy, node_id, transform, &mut ancestors, &mut stacked)?;
+
+  // Nodes with the same level keep their tree order.
+  stacked.sort_by_key(|node| node.level);
+
+  Ok(stacked)
+}
+
+fn collect_stacked_nodes_impl<'g, N: Node<N>>(
+  taffy: &mut TaffyTree<NodeTree<'g, N>>,
+  node_id: NodeId,
+  transform: Affine,
+  ancestors: &mut SmallVec<[NodeId; 4]>,
+  stacked: &mut Vec<StackedNode>,
+) -> Result<(), TaffyError> {
+  let Some(node) = taffy.get_node_context(node_id) else {
+    return Err(TaffyError::InvalidInputNode(node_id));
+  };
+
+  // Children of inline layouts are drawn as part of the text flow.
+  if node.should_create_inline_layout() {
+    return Ok(());
+  }
+
+  for child_id in taffy.children(node_id)? {
+    let location = taffy.layout(child_id)?.location;
+
+    let Some(child) = taffy.get_node_context_mut(child_id) else {
+      return Err(TaffyError::InvalidInputNode(child_id));
+    };
+
+    if child.context.opacity == 0 || child.context.style.display == Display::None {
+      continue;
+    }
+
+    match stacking_level(&child.context.style) {
+      Some(0) => {}
+      Some(level) => stacked.push(StackedNode {
+        node_id: child_id,
+        level,
+        transform,
+        ancestors: ancestors.clone(),
+      }),
+      None => {
+        // Transforms create stacking contexts, so only the offset is left to apply here.
+        let child_transform = transform * Affine::translation(location.x, location.y);
+
+        // Kept for resolving the overflow clip when painting lifted descendants.
+        child.context.transform = child_transform;
+
+        ancestors.push(child_id);
+        collect_stacked_nodes_impl(taffy, child_id, child_transform, ancestors, stacked)?;
+        ancestors.pop();
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Returns whether the node is painted in tree order by its parent, rather than lifted by a stacking context.
+pub(crate) fn is_painted_in_tree_order(style: &InheritedStyle) -> bool {
+  matches!(stacking_level(style), None | Some(0))
+}
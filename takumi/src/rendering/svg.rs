@@ -0,0 +1,338 @@
+use base64::{Engine, prelude::BASE64_STANDARD};
+use image::RgbaImage;
+use taffy::Size;
+use zeno::Command;
+
+use crate::{
+  Result,
+  layout::{
+    node::Node,
+    style::{Affine, Color, FillRule, ResolvedGradientStop},
+  },
+  rendering::{
+    ImageOutputFormat, RenderOptions, VectorPaint, VectorRenderer, VectorSurface,
+    create_layout_tree, write_image,
+  },
+};
+
+/// Renders a node to an SVG document.
+///
+/// Backgrounds, borders, gradients, clip paths and text (as glyph outlines) are emitted as vector elements.
+/// Effects without a vector equivalent, like filters, masks, shadows and image content,
+/// are rasterized and embedded as PNG images.
+pub fn render_svg<'g, N: Node<N>>(options: RenderOptions<'g, N>) -> Result<String> {
+  let (mut taffy, root_node_id, root_size) = create_layout_tree(options)?;
+
+  let mut surface = SvgSurface::default();
+
+  VectorRenderer::new(&mut surface, root_size).render_node(
+    &mut taffy,
+    root_node_id,
+    Affine::IDENTITY,
+  )?;
+
+  Ok(surface.finish(root_size))
+}
+
+/// Collects SVG elements emitted by the [`VectorRenderer`].
+#[derive(Default)]
+struct SvgSurface {
+  /// Gradients and clip paths referenced by the body.
+  defs: String,
+  body: String,
+  next_id: u32,
+}
+
+impl SvgSurface {
+  fn next_id(&mut self, prefix: &str) -> String {
+    self.next_id += 1;
+
+    format!("{prefix}{}", self.next_id)
+  }
+
+  /// Defines the paint if needed, and returns the value for a `fill` attribute with its opacity.
+  fn define_paint(&mut self, paint: &VectorPaint) -> String {
+    match paint {
+      VectorPaint::Solid(color) => color_attributes("fill", *color),
+      VectorPaint::LinearGradient { start, end, stops } => {
+        let id = self.next_id("g");
+
+        self.defs.push_str(&format!(
+          r#"<linearGradient id="{id}" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}">"#,
+          number(start.x),
+          number(start.y),
+          number(end.x),
+          number(end.y),
+        ));
+        push_stops(&mut self.defs, stops);
+        self.defs.push_str("</linearGradient>");
+
+        format!(r#"fill="url(#{id})""#)
+      }
+      VectorPaint::RadialGradient {
+        transform,
+        radius,
+        stops,
+      } => {
+        let id = self.next_id("g");
+
+        self.defs.push_str(&format!(
+          r#"<radialGradient id="{id}" gradientUnits="userSpaceOnUse" cx="0" cy="0" r="{}" gradientTransform="{}">"#,
+          number(*radius),
+          matrix(*transform),
+        ));
+        push_stops(&mut self.defs, stops);
+        self.defs.push_str("</radialGradient>");
+
+        format!(r#"fill="url(#{id})""#)
+      }
+    }
+  }
+
+  fn finish(self, size: Size<u32>) -> String {
+    let mut svg = format!(
+      r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#,
+      width = size.width,
+      height = size.height,
+    );
+
+    if !self.defs.is_empty() {
+      svg.push_str("<defs>");
+      svg.push_str(&self.defs);
+      svg.push_str("</defs>");
+    }
+
+    svg.push_str(&self.body);
+    svg.push_str("</svg>");
+
+    svg
+  }
+}
+
+impl VectorSurface for SvgSurface {
+  fn push_clip(&mut self, path: &[Command], transform: Affine, fill_rule: FillRule) {
+    let id = self.next_id("c");
+
+    self.defs.push_str(&format!(
+      r#"<clipPath id="{id}"><path d="{}"{}{}/></clipPath>"#,
+      path_data(path),
+      transform_attribute(transform),
+      fill_rule_attribute("clip-rule", fill_rule),
+    ));
+
+    self
+      .body
+      .push_str(&format!(r#"<g clip-path="url(#{id})">"#));
+  }
+
+  fn pop_clip(&mut self) {
+    self.body.push_str("</g>");
+  }
+
+  fn push_group(&mut self, opacity: u8) {
+    self.body.push_str(&format!(
+      r#"<g opacity="{}">"#,
+      number(opacity as f32 / 255.0)
+    ));
+  }
+
+  fn pop_group(&mut self) {
+    self.body.push_str("</g>");
+  }
+
+  fn fill_path(
+    &mut self,
+    path: &[Command],
+    transform: Affine,
+    paint: &VectorPaint,
+    fill_rule: FillRule,
+  ) {
+    let fill = self.define_paint(paint);
+
+    self.body.push_str(&format!(
+      r#"<path d="{}"{} {fill}{}/>"#,
+      path_data(path),
+      transform_attribute(transform),
+      fill_rule_attribute("fill-rule", fill_rule),
+    ));
+  }
+
+  fn stroke_path(&mut self, path: &[Command], transform: Affine, color: Color, width: f32) {
+    self.body.push_str(&format!(
+      r#"<path d="{}"{} fill="none" {} stroke-width="{}" stroke-linejoin="bevel" vector-effect="non-scaling-stroke"/>"#,
+      path_data(path),
+      transform_attribute(transform),
+      color_attributes("stroke", color),
+      number(width),
+    ));
+  }
+
+  fn draw_image(&mut self, image: &RgbaImage, transform: Affine, opacity: u8) -> Result<()> {
+    let mut png = Vec::new();
+
+    write_image(image, &mut png, ImageOutputFormat::Png, None)?;
+
+    let opacity = if opacity == u8::MAX {
+      String::new()
+    } else {
+      format!(r#" opacity="{}""#, number(opacity as f32 / 255.0))
+    };
+
+    self.body.push_str(&format!(
+      r#"<image width="{}" height="{}"{}{opacity} href="data:image/png;base64,{}"/>"#,
+      image.width(),
+      image.height(),
+      transform_attribute(transform),
+      BASE64_STANDARD.encode(png),
+    ));
+
+    Ok(())
+  }
+}
+
+fn push_stops(defs: &mut String, stops: &[ResolvedGradientStop]) {
+  for stop in stops {
+    defs.push_str(&format!(
+      r#"<stop offset="{}" {}/>"#,
+      number(stop.position),
+      color_attributes("stop-color", stop.color),
+    ));
+  }
+}
+
+/// Formats the color as `{name}="#rrggbb"`, followed by the `-opacity` attribute if the color is translucent.
+fn color_attributes(name: &str, color: Color) -> String {
+  let [r, g, b, a] = color.0;
+
+  if a == u8::MAX {
+    return format!(r##"{name}="#{r:02x}{g:02x}{b:02x}""##);
+  }
+
+  let opacity_name = match name {
+    "stop-color" => "stop-opacity",
+    "stroke" => "stroke-opacity",
+    _ => "fill-opacity",
+  };
+
+  format!(
+    r##"{name}="#{r:02x}{g:02x}{b:02x}" {opacity_name}="{}""##,
+    number(a as f32 / 255.0)
+  )
+}
+
+fn fill_rule_attribute(name: &str, fill_rule: FillRule) -> String {
+  match fill_rule {
+    FillRule::NonZero => String::new(),
+    FillRule::EvenOdd => format!(r#" {name}="evenodd""#),
+  }
+}
+
+fn transform_attribute(transform: Affine) -> String {
+  if transform.is_identity() {
+    return String::new();
+  }
+
+  format!(r#" transform="{}""#, matrix(transform))
+}
+
+fn matrix(transform: Affine) -> String {
+  format!(
+    "matrix({} {} {} {} {} {})",
+    number(transform.a),
+    number(transform.b),
+    number(transform.c),
+    number(transform.d),
+    number(transform.x),
+    number(transform.y),
+  )
+}
+
+/// Formats the number with at most 3 decimal places, which is more than enough for pixel coordinates.
+fn number(value: f32) -> String {
+  let rounded = (value * 1000.0).round() / 1000.0;
+
+  // Avoid printing `-0`
+  if rounded == 0.0 {
+    return "0".to_string();
+  }
+
+  rounded.to_string()
+}
+
+fn path_data(path: &[Command]) -> String {
+  let mut data = String::new();
+
+  for command in path {
+    match *command {
+      Command::MoveTo(point) => {
+        data.push_str(&format!("M{} {}", number(point.x), number(point.y)));
+      }
+      Command::LineTo(point) => {
+        data.push_str(&format!("L{} {}", number(point.x), number(point.y)));
+      }
+      Command::CurveTo(point1, point2, point3) => {
+        data.push_str(&format!(
+          "C{} {} {} {} {} {}",
+          number(point1.x),
+          number(point1.y),
+          number(point2.x),
+          number(point2.y),
+          number(point3.x),
+          number(point3.y),
+        ));
+      }
+      Command::QuadTo(point1, point2) => {
+        data.push_str(&format!(
+          "Q{} {} {} {}",
+          number(point1.x),
+          number(point1.y),
+          number(point2.x),
+          number(point2.y),
+        ));
+      }
+      Command::Close => data.push('Z'),
+    }
+  }
+
+  data
+}
+
+#[cfg(test)]
+mod tests {
+  use zeno::PathBuilder;
+
+  use super::*;
+
+  #[test]
+  fn test_path_data() {
+    let mut path = Vec::new();
+
+    path.move_to((0.0, 0.0));
+    path.line_to((10.5, -0.0001));
+    path.quad_to((1.0, 2.0), (3.0, 4.0));
+    path.close();
+
+    assert_eq!(path_data(&path), "M0 0L10.5 0Q1 2 3 4Z");
+  }
+
+  #[test]
+  fn test_color_attributes() {
+    assert_eq!(
+      color_attributes("fill", Color([255, 0, 16, 255])),
+      r##"fill="#ff0010""##
+    );
+    assert_eq!(
+      color_attributes("stroke", Color([0, 0, 0, 51])),
+      r##"stroke="#000000" stroke-opacity="0.2""##
+    );
+  }
+
+  #[test]
+  fn test_transform_attribute() {
+    assert_eq!(transform_attribute(Affine::IDENTITY), "");
+    assert_eq!(
+      transform_attribute(Affine::translation(10.0, 20.0)),
+      r#" transform="matrix(1 0 0 1 10 20)""#
+    );
+  }
+}
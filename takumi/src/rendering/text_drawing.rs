@@ -19,13 +19,13 @@ use crate::{
     },
   },
   rendering::{
-    BorderProperties, Canvas, CanvasConstrain, MaskMemory, apply_mask_alpha_to_pixel, blend_pixel,
-    draw_mask, mask_index_from_coord, overlay_area, overlay_image,
+    BorderProperties, Canvas, apply_mask_alpha_to_pixel, draw_mask, mask_index_from_coord,
+    overlay_area,
   },
   resources::font::ResolvedGlyph,
 };
 
-fn invert_y_coordinate(command: Command) -> Command {
+pub(crate) fn invert_y_coordinate(command: Command) -> Command {
   match command {
     Command::MoveTo(point) => Command::MoveTo((point.x, -point.y).into()),
     Command::LineTo(point) => Command::LineTo((point.x, -point.y).into()),
@@ -105,6 +105,7 @@ pub(crate) fn draw_glyph(
           height: bitmap.placement.height,
         },
         None,
+        canvas.color_interpolation,
         |x, y| {
           let alpha = mask[mask_index_from_coord(x, y, bitmap.placement.width)];
 
@@ -161,10 +162,11 @@ pub(crate) fn draw_glyph(
 
       maybe_draw_text_shadow(canvas, style, transform, &paths);
 
+      let color_interpolation = canvas.color_interpolation;
       let (mask, placement) = canvas.mask_memory.render(&paths, Some(transform), None);
+      let mask = mask.to_vec();
 
-      overlay_area(
-        &mut canvas.image,
+      canvas.overlay_area(
         Point {
           x: placement.left as f32,
           y: placement.top as f32,
@@ -173,7 +175,6 @@ pub(crate) fn draw_glyph(
           width: placement.width,
           height: placement.height,
         },
-        canvas.constrains.last(),
         |x, y| {
           let alpha = mask[mask_index_from_coord(x, y, placement.width)];
 
@@ -201,7 +202,7 @@ pub(crate) fn draw_glyph(
             return Color::transparent().into();
           };
 
-          blend_pixel(&mut pixel, text_style.brush.color.into());
+          color_interpolation.blend_pixel(&mut pixel, text_style.brush.color.into());
           apply_mask_alpha_to_pixel(&mut pixel, alpha);
           apply_mask_alpha_to_pixel(&mut pixel, opacity);
 
@@ -219,25 +220,9 @@ pub(crate) fn draw_glyph(
       if outline.is_color()
         && let Some(palette) = palette
       {
-        draw_color_outline_image(
-          &mut canvas.image,
-          &mut canvas.mask_memory,
-          outline,
-          palette,
-          transform,
-          opacity,
-          canvas.constrains.last(),
-        );
+        draw_color_outline_image(canvas, outline, palette, transform, opacity);
       } else {
-        let (mask, placement) = canvas.mask_memory.render(&paths, Some(transform), None);
-
-        draw_mask(
-          &mut canvas.image,
-          mask,
-          placement,
-          text_style.brush.color,
-          canvas.constrains.last(),
-        );
+        canvas.fill_path(&paths, Some(transform), None, text_style.brush.color);
       }
 
       maybe_draw_text_stroke(canvas, style, transform, &paths);
@@ -261,17 +246,11 @@ fn maybe_draw_text_stroke(
   stroke.scale = false;
   stroke.join = Join::Bevel;
 
-  let (stroke_mask, stroke_placement) =
-    canvas
-      .mask_memory
-      .render(paths, Some(transform), Some(stroke.into()));
-
-  draw_mask(
-    &mut canvas.image,
-    stroke_mask,
-    stroke_placement,
+  canvas.fill_path(
+    paths,
+    Some(transform),
+    Some(stroke.into()),
     style.text_stroke_color,
-    canvas.constrains.last(),
   );
 }
 
@@ -286,18 +265,11 @@ fn maybe_draw_text_shadow(
   };
 
   for shadow in shadows.iter() {
-    shadow.draw_outset(
-      &mut canvas.image,
-      &mut canvas.mask_memory,
-      canvas.constrains.last(),
-      paths,
-      transform,
-      Default::default(),
-    );
+    shadow.draw_outset(canvas, paths, transform, Default::default());
   }
 }
 
-fn collect_outline_paths(outline: &Outline) -> Vec<Command> {
+pub(crate) fn collect_outline_paths(outline: &Outline) -> Vec<Command> {
   outline
     .path()
     .commands()
@@ -306,15 +278,12 @@ fn collect_outline_paths(outline: &Outline) -> Vec<Command> {
 }
 
 // https://github.com/dfrg/swash/blob/3d8e6a781c93454dadf97e5c15764ceafab228e0/src/scale/mod.rs#L921
-#[allow(clippy::too_many_arguments)]
 fn draw_color_outline_image(
-  canvas: &mut RgbaImage,
-  mask_memory: &mut MaskMemory,
+  canvas: &mut Canvas,
   outline: &Outline,
   palette: ColorPalette,
   mut transform: Affine,
   opacity: u8,
-  constrain: Option<&CanvasConstrain>,
 ) {
   // Fast path: if the opacity is 255, we can just draw the outline without any blending
   if opacity == 255 {
@@ -333,9 +302,7 @@ fn draw_color_outline_image(
         .map(invert_y_coordinate)
         .collect::<Vec<_>>();
 
-      let (mask, placement) = mask_memory.render(&paths, Some(transform), None);
-
-      draw_mask(canvas, mask, placement, color, constrain);
+      canvas.fill_path(&paths, Some(transform), None, color);
     }
 
     return;
@@ -352,7 +319,7 @@ fn draw_color_outline_image(
     .map(invert_y_coordinate)
     .collect::<Vec<_>>();
 
-  let outer_placement = mask_memory.placement(&paths, Some(transform), None);
+  let outer_placement = canvas.mask_memory.placement(&paths, Some(transform), None);
 
   let mut image = RgbaImage::new(outer_placement.width, outer_placement.height);
 
@@ -371,16 +338,22 @@ fn draw_color_outline_image(
       .map(invert_y_coordinate)
       .collect::<Vec<_>>();
 
-    let (mask, mut placement) = mask_memory.render(&paths, Some(transform), None);
+    let (mask, mut placement) = canvas.mask_memory.render(&paths, Some(transform), None);
 
     placement.left -= outer_placement.left;
     placement.top -= outer_placement.top;
 
-    draw_mask(&mut image, mask, placement, color, constrain);
+    draw_mask(
+      &mut image,
+      mask,
+      placement,
+      color,
+      None,
+      canvas.color_interpolation,
+    );
   }
 
-  overlay_image(
-    canvas,
+  canvas.overlay_image(
     image.into(),
     BorderProperties::default(),
     Affine::translation(
@@ -389,8 +362,6 @@ fn draw_color_outline_image(
     ),
     Default::default(),
     opacity,
-    constrain,
-    mask_memory,
   );
 }
 
@@ -0,0 +1,69 @@
+use image::RgbaImage;
+use rayon::prelude::*;
+use taffy::{Point, Size};
+
+use crate::{
+  layout::style::Filter,
+  rendering::{DisplayCommand, DisplayList},
+};
+
+/// The height of the horizontal strips the canvas is split into.
+const TILE_HEIGHT: u32 = 256;
+
+/// Canvases with fewer pixels are rasterized sequentially, as splitting them costs more than it saves.
+const MIN_TILED_PIXELS: u64 = 2048 * 2048;
+
+/// Whether the canvas is large enough to be split into tiles, and every pixel of a tile
+/// only depends on the tile itself, so the result is identical to rasterizing it at once.
+pub(crate) fn should_rasterize_in_tiles(display_list: &DisplayList, size: Size<u32>) -> bool {
+  if (size.width as u64) * (size.height as u64) < MIN_TILED_PIXELS {
+    return false;
+  }
+
+  !samples_neighbor_pixels(display_list)
+}
+
+/// Blurs and drop shadows of `filter` and `backdrop-filter` read the pixels around them,
+/// which may belong to another tile.
+fn samples_neighbor_pixels(display_list: &DisplayList) -> bool {
+  display_list
+    .commands()
+    .iter()
+    .flat_map(|command| match command {
+      DisplayCommand::PopLayer(effects) => effects.filters(),
+      DisplayCommand::DrawBackdrop(backdrop) => backdrop.filters(),
+      _ => &[],
+    })
+    .any(|filter| matches!(filter, Filter::Blur(_) | Filter::DropShadow(_)))
+}
+
+/// Rasterizes the part of the output of `size` starting at `origin` in horizontal strips in parallel.
+///
+/// The commands are recorded once and shared by every strip, each of them replays them into its own rows.
+pub(crate) fn rasterize_tiles(
+  display_list: &DisplayList,
+  origin: Point<u32>,
+  size: Size<u32>,
+) -> RgbaImage {
+  let mut image = RgbaImage::new(size.width, size.height);
+  let row_len = size.width as usize * 4;
+
+  image
+    .par_chunks_mut(row_len * TILE_HEIGHT as usize)
+    .enumerate()
+    .for_each(|(index, tile)| {
+      let mut canvas = RgbaImage::new(size.width, (tile.len() / row_len) as u32);
+
+      display_list.replay(
+        &mut canvas,
+        Point {
+          x: origin.x,
+          y: origin.y + index as u32 * TILE_HEIGHT,
+        },
+      );
+
+      tile.copy_from_slice(canvas.as_raw());
+    });
+
+  image
+}
@@ -0,0 +1,1201 @@
+//! Vector rendering of the layout tree.
+//!
+//! This module walks the same taffy layout as the raster renderer, but describes the painted
+//! content as paths, gradients and images to a [`VectorSurface`] instead of a [`Canvas`].
+//! Effects without a vector equivalent (filters, masks, shadows and replaced content) are
+//! rasterized and handed to the surface as images.
+
+use image::{
+  ImageError, RgbaImage,
+  error::{DecodingError, ImageFormatHint},
+  imageops::crop_imm,
+};
+use std::ops::Range;
+
+use parley::{Glyph, GlyphRun, PositionedInlineBox, PositionedLayoutItem};
+use smallvec::{SmallVec, smallvec};
+use swash::{ColorPalette, FontRef};
+use taffy::{Layout, NodeId, Point, Size, TaffyError, TaffyTree};
+use zeno::{Command, PathData};
+
+use crate::{
+  Result,
+  layout::{
+    inline::{InlineBrush, InlineContentKind, InlineLayout, ProcessedInlineSpan},
+    node::{Node, create_text_inline_layout},
+    style::{
+      Affine, BackgroundClip, BackgroundImage, Color, Display, FillRule, InheritedStyle,
+      LinearGradient, LinearGradientDrawContext, Overflow, RadialGradient,
+      RadialGradientDrawContext, ResolvedGradientStop, SizedFontStyle, StopInterpolation,
+      TextDecorationLine, color_from_stops,
+    },
+    tree::NodeTree,
+  },
+  rendering::{
+    BorderProperties, Canvas, ColorInterpolation, RenderContext, StackedNode, apply_transform,
+    collect_outline_paths, collect_stacked_nodes, draw_debug_border, has_background_blend_mode,
+    has_border_image, has_mask_image,
+    inline_drawing::{draw_inline_box, draw_inline_layout},
+    invert_y_coordinate, is_painted_in_tree_order, render_node, render_tile,
+    resolve_background_layers, resolve_background_size, resolve_layer_tile_placement,
+    stacking_level,
+  },
+  resources::font::{FontError, ResolvedGlyph},
+};
+
+/// Describes how a vector shape is painted.
+pub(crate) enum VectorPaint {
+  /// A single color.
+  Solid(Color),
+  /// A linear gradient from `start` to `end`, with stop positions in the `0.0..=1.0` range.
+  LinearGradient {
+    start: Point<f32>,
+    end: Point<f32>,
+    stops: SmallVec<[ResolvedGradientStop; 4]>,
+  },
+  /// A circular gradient of `radius` around the origin, mapped to the shape by `transform`.
+  /// Stop positions are in the `0.0..=1.0` range.
+  RadialGradient {
+    transform: Affine,
+    radius: f32,
+    stops: SmallVec<[ResolvedGradientStop; 4]>,
+  },
+}
+
+/// A target for vector drawing operations.
+///
+/// Every operation takes the transform mapping its local coordinates to the output.
+pub(crate) trait VectorSurface {
+  /// Restricts the following operations to the inside of the path until [`VectorSurface::pop_clip`] is called.
+  fn push_clip(&mut self, path: &[Command], transform: Affine, fill_rule: FillRule);
+
+  /// Removes the clip added by the last [`VectorSurface::push_clip`] call.
+  fn pop_clip(&mut self);
+
+  /// Composites the following operations as a single layer with the opacity until [`VectorSurface::pop_group`] is called.
+  fn push_group(&mut self, opacity: u8);
+
+  /// Composites the group started by the last [`VectorSurface::push_group`] call.
+  fn pop_group(&mut self);
+
+  /// Fills the path with the paint.
+  fn fill_path(
+    &mut self,
+    path: &[Command],
+    transform: Affine,
+    paint: &VectorPaint,
+    fill_rule: FillRule,
+  );
+
+  /// Strokes the path with a width that is not affected by the transform.
+  fn stroke_path(&mut self, path: &[Command], transform: Affine, color: Color, width: f32);
+
+  /// Draws the image with its top-left corner at the local origin.
+  fn draw_image(&mut self, image: &RgbaImage, transform: Affine, opacity: u8) -> Result<()>;
+
+  /// Fills the plain outline glyphs of the run as text, which keeps it selectable and searchable in the output.
+  ///
+  /// Each glyph is paired with the text it represents, empty if the glyph is not the first one of its cluster.
+  /// Glyph positions are relative to the content box, which `transform` maps to the output.
+  ///
+  /// Returns `false` if the surface can't draw text, the glyph outlines are filled instead.
+  fn fill_glyphs(
+    &mut self,
+    _glyph_run: &GlyphRun<'_, InlineBrush>,
+    _glyphs: &[(Glyph, &str)],
+    _transform: Affine,
+  ) -> Result<bool> {
+    Ok(false)
+  }
+}
+
+/// Walks the layout tree and draws it onto a [`VectorSurface`].
+pub(crate) struct VectorRenderer<'s, S: VectorSurface> {
+  surface: &'s mut S,
+  /// The size of the output, used for rasterized fallbacks.
+  size: Size<u32>,
+}
+
+impl<'s, S: VectorSurface> VectorRenderer<'s, S> {
+  pub(crate) fn new(surface: &'s mut S, size: Size<u32>) -> Self {
+    Self { surface, size }
+  }
+
+  /// Draws the node and its descendants, mirroring the raster `render_node`.
+  pub(crate) fn render_node<'g, N: Node<N>>(
+    &mut self,
+    taffy: &mut TaffyTree<NodeTree<'g, N>>,
+    node_id: NodeId,
+    mut transform: Affine,
+  ) -> Result<()> {
+    let layout = *taffy.layout(node_id)?;
+    let is_root = taffy.parent(node_id).is_none();
+
+    let Some(node) = taffy.get_node_context_mut(node_id) else {
+      return Err(TaffyError::InvalidInputNode(node_id).into());
+    };
+
+    if node.context.opacity == 0 || node.context.style.display == Display::None {
+      return Ok(());
+    }
+
+    // Filters, masks and blended backgrounds have no vector equivalent, so the whole subtree is rasterized instead.
+    if !node.context.style.filter.is_empty()
+      || has_mask_image(&node.context.style)
+      || has_background_blend_mode(&node.context.style)
+    {
+      return self.draw_rasterized(|canvas| render_node(taffy, node_id, canvas, transform));
+    }
+
+    transform *= Affine::translation(layout.location.x, layout.location.y);
+
+    apply_transform(
+      &mut transform,
+      &node.context.style,
+      layout.size,
+      &node.context.sizing,
+    );
+
+    if !transform.is_invertible() {
+      return Ok(());
+    }
+
+    node.context.transform = transform;
+
+    // Clip path would just clip everything, and takes precedence over overflow like the raster path.
+    let (clip_path, overflow_clip) = if let Some(clip_path) = &node.context.style.clip_path {
+      let fill_rule = clip_path
+        .fill_rule()
+        .unwrap_or(node.context.style.clip_rule);
+
+      (
+        Some((clip_path.to_paths(&node.context, layout.size), fill_rule)),
+        None,
+      )
+    } else {
+      match overflow_clip_paths(&node.context.style, layout) {
+        OverflowClip::None => (None, None),
+        OverflowClip::Clip(paths) => (None, Some(paths)),
+        OverflowClip::Empty => return Ok(()),
+      }
+    };
+
+    let group_opacity = node.context.group_opacity;
+
+    if group_opacity != u8::MAX {
+      self.surface.push_group(group_opacity);
+    }
+
+    let mut clip_count = 0;
+
+    if let Some((paths, fill_rule)) = clip_path {
+      self.surface.push_clip(&paths, transform, fill_rule);
+      clip_count += 1;
+    }
+
+    let is_stacking_context = is_root || stacking_level(&node.context.style).is_some();
+    let is_inline_layout = node.should_create_inline_layout();
+
+    self.draw_shell(node, layout)?;
+
+    let has_overflow_clip = overflow_clip.is_some();
+
+    if let Some(paths) = overflow_clip {
+      self.surface.push_clip(&paths, transform, FillRule::NonZero);
+      clip_count += 1;
+    }
+
+    let stacked_nodes = if is_stacking_context && !is_inline_layout {
+      collect_stacked_nodes(taffy, node_id, transform)?
+    } else {
+      Vec::new()
+    };
+
+    let (negative_nodes, positive_nodes) =
+      stacked_nodes.split_at(stacked_nodes.partition_point(|stacked| stacked.level < 0));
+
+    for stacked in negative_nodes {
+      self.render_stacked_node(taffy, stacked)?;
+    }
+
+    let Some(node) = taffy.get_node_context_mut(node_id) else {
+      return Err(TaffyError::InvalidInputNode(node_id).into());
+    };
+
+    self.draw_content(node, layout)?;
+
+    if node.context.draw_debug_border {
+      self.draw_rasterized(|canvas| {
+        draw_debug_border(canvas, layout, transform);
+        Ok(())
+      })?;
+    }
+
+    if is_inline_layout {
+      self.draw_inline_children(node, layout)?;
+    } else {
+      for child_id in taffy.children(node_id)? {
+        let Some(child) = taffy.get_node_context(child_id) else {
+          return Err(TaffyError::InvalidInputNode(child_id).into());
+        };
+
+        if is_painted_in_tree_order(&child.context.style) {
+          self.render_node(taffy, child_id, transform)?;
+        }
+      }
+    }
+
+    for stacked in positive_nodes {
+      self.render_stacked_node(taffy, stacked)?;
+    }
+
+    // The outline belongs to the node itself, so its own overflow doesn't clip it.
+    if has_overflow_clip {
+      self.surface.pop_clip();
+      clip_count -= 1;
+    }
+
+    let Some(node) = taffy.get_node_context(node_id) else {
+      return Err(TaffyError::InvalidInputNode(node_id).into());
+    };
+
+    if let Some((outline, outline_box, outset)) =
+      BorderProperties::outline_from_context(&node.context, layout.size)
+    {
+      self.fill_border_segments(
+        outline,
+        outline_box,
+        transform * Affine::translation(-outset, -outset),
+      );
+    }
+
+    for _ in 0..clip_count {
+      self.surface.pop_clip();
+    }
+
+    if group_opacity != u8::MAX {
+      self.surface.pop_group();
+    }
+
+    Ok(())
+  }
+
+  /// Draws a node lifted out of tree order, under the overflow clips of the ancestors it was lifted out of.
+  fn render_stacked_node<'g, N: Node<N>>(
+    &mut self,
+    taffy: &mut TaffyTree<NodeTree<'g, N>>,
+    stacked: &StackedNode,
+  ) -> Result<()> {
+    let mut clip_count = 0;
+    let mut is_visible = true;
+
+    for &ancestor_id in &stacked.ancestors {
+      let layout = *taffy.layout(ancestor_id)?;
+
+      let Some(ancestor) = taffy.get_node_context(ancestor_id) else {
+        return Err(TaffyError::InvalidInputNode(ancestor_id).into());
+      };
+
+      match overflow_clip_paths(&ancestor.context.style, layout) {
+        OverflowClip::None => {}
+        OverflowClip::Clip(paths) => {
+          self
+            .surface
+            .push_clip(&paths, ancestor.context.transform, FillRule::NonZero);
+          clip_count += 1;
+        }
+        OverflowClip::Empty => {
+          is_visible = false;
+          break;
+        }
+      }
+    }
+
+    if is_visible {
+      self.render_node(taffy, stacked.node_id, stacked.transform)?;
+    }
+
+    for _ in 0..clip_count {
+      self.surface.pop_clip();
+    }
+
+    Ok(())
+  }
+
+  fn draw_shell<N: Node<N>>(&mut self, node: &NodeTree<'_, N>, layout: Layout) -> Result<()> {
+    let Some(inner) = &node.node else {
+      return Ok(());
+    };
+
+    let context = &node.context;
+
+    let (has_outset_shadow, has_inset_shadow) = context
+      .style
+      .box_shadow
+      .as_ref()
+      .map(|shadows| {
+        (
+          shadows.iter().any(|shadow| !shadow.inset),
+          shadows.iter().any(|shadow| shadow.inset),
+        )
+      })
+      .unwrap_or_default();
+
+    if has_outset_shadow {
+      self.draw_rasterized(|canvas| inner.draw_outset_box_shadow(context, canvas, layout))?;
+    }
+
+    self.draw_background_color(context, layout);
+    self.draw_background_image(context, layout)?;
+
+    if has_inset_shadow {
+      self.draw_rasterized(|canvas| inner.draw_inset_box_shadow(context, canvas, layout))?;
+    }
+
+    // Border images and borders painted with the background have no vector equivalent.
+    if has_border_image(&context.style)
+      || context.style.background_clip == BackgroundClip::BorderArea
+    {
+      return self.draw_rasterized(|canvas| inner.draw_border(context, canvas, layout));
+    }
+
+    let border = BorderProperties::from_context(context, layout.size, layout.border);
+
+    if border.is_width_zero() || border.color.0[3] == 0 {
+      return Ok(());
+    }
+
+    self.fill_border_segments(border, layout.size, context.transform);
+
+    Ok(())
+  }
+
+  /// Fills the regions of a border, clipping each to its side when needed.
+  fn fill_border_segments(
+    &mut self,
+    border: BorderProperties,
+    border_box: Size<f32>,
+    transform: Affine,
+  ) {
+    for segment in border.segments(border_box) {
+      if let Some(clip) = &segment.clip {
+        self.surface.push_clip(clip, transform, FillRule::NonZero);
+      }
+
+      self.surface.fill_path(
+        &segment.path,
+        transform,
+        &VectorPaint::Solid(segment.color),
+        segment.fill_rule,
+      );
+
+      if segment.clip.is_some() {
+        self.surface.pop_clip();
+      }
+    }
+  }
+
+  fn draw_background_color(&mut self, context: &RenderContext, layout: Layout) {
+    let color = context.style.background_color.resolve_in(
+      context.color_space,
+      context.current_color,
+      context.opacity,
+    );
+
+    if color.0[3] == 0 {
+      return;
+    }
+
+    let Some(paths) = background_clip_paths(context, layout) else {
+      return;
+    };
+
+    self.surface.fill_path(
+      &paths,
+      context.transform,
+      &VectorPaint::Solid(color),
+      FillRule::NonZero,
+    );
+  }
+
+  fn draw_background_image(&mut self, context: &RenderContext, layout: Layout) -> Result<()> {
+    let (images, positions, sizes, repeats) = resolve_background_layers(context);
+
+    if images
+      .iter()
+      .all(|image| matches!(image, BackgroundImage::None))
+    {
+      return Ok(());
+    }
+
+    let Some(clip_paths) = background_clip_paths(context, layout) else {
+      return Ok(());
+    };
+
+    let last_position = positions.last().copied().unwrap_or_default();
+    let last_size = sizes.last().copied().unwrap_or_default();
+    let last_repeat = repeats.last().copied().unwrap_or_default();
+
+    let area_w = layout.size.width as u32;
+    let area_h = layout.size.height as u32;
+
+    self
+      .surface
+      .push_clip(&clip_paths, context.transform, FillRule::NonZero);
+
+    for (i, image) in images.iter().enumerate() {
+      let pos = positions.get(i).copied().unwrap_or(last_position);
+      let size = sizes.get(i).copied().unwrap_or(last_size);
+      let repeat = repeats.get(i).copied().unwrap_or(last_repeat);
+
+      let (tile_w, tile_h) = resolve_background_size(size, (area_w, area_h), image, context);
+
+      if tile_w == 0 || tile_h == 0 {
+        continue;
+      }
+
+      let placement =
+        resolve_layer_tile_placement(pos, repeat, tile_w, tile_h, area_w, area_h, &context.sizing);
+
+      let tile_size = Size {
+        width: placement.width as f32,
+        height: placement.height as f32,
+      };
+
+      let paint = match image {
+        BackgroundImage::None => continue,
+        // Repeating and conic gradients, and the ones with an `in` hint have no vector paint,
+        // so they're rasterized below.
+        BackgroundImage::Linear(gradient)
+          if !gradient.repeating && gradient.interpolation.is_none() =>
+        {
+          linear_gradient_paint(gradient, tile_size, context)
+        }
+        BackgroundImage::Radial(gradient)
+          if !gradient.repeating && gradient.interpolation.is_none() =>
+        {
+          radial_gradient_paint(gradient, tile_size, context)
+        }
+        _ => None,
+      };
+
+      if let Some(paint) = paint {
+        let mut paths = Vec::new();
+
+        BorderProperties::default().append_mask_commands(&mut paths, tile_size, Point::ZERO);
+
+        for y in &placement.ys {
+          for x in &placement.xs {
+            self.surface.fill_path(
+              &paths,
+              context.transform * Affine::translation(*x as f32, *y as f32),
+              &paint,
+              FillRule::NonZero,
+            );
+          }
+        }
+
+        continue;
+      }
+
+      let Some(tile_image) = render_tile(image, placement.width, placement.height, context)? else {
+        continue;
+      };
+
+      for y in &placement.ys {
+        for x in &placement.xs {
+          self.surface.draw_image(
+            &tile_image,
+            context.transform * Affine::translation(*x as f32, *y as f32),
+            context.opacity,
+          )?;
+        }
+      }
+    }
+
+    self.surface.pop_clip();
+
+    Ok(())
+  }
+
+  fn draw_content<N: Node<N>>(&mut self, node: &NodeTree<'_, N>, layout: Layout) -> Result<()> {
+    let Some(inner) = &node.node else {
+      return Ok(());
+    };
+
+    let context = &node.context;
+
+    match inner.inline_content() {
+      Some(InlineContentKind::Text(text)) => {
+        let font_style = context.style.to_sized_font_style(context);
+
+        if font_style.font_size == 0.0 {
+          return Ok(());
+        }
+
+        let (inline_layout, text) =
+          create_text_inline_layout::<N>(&text, context, layout.content_box_size(), &font_style);
+
+        self.draw_inline_layout(context, layout, inline_layout, &text, &font_style)?;
+
+        Ok(())
+      }
+      Some(InlineContentKind::Box) => {
+        self.draw_rasterized(|canvas| inner.draw_content(context, canvas, layout))
+      }
+      None => Ok(()),
+    }
+  }
+
+  fn draw_inline_children<N: Node<N>>(
+    &mut self,
+    node: &NodeTree<'_, N>,
+    layout: Layout,
+  ) -> Result<()> {
+    if node.context.opacity == 0 {
+      return Ok(());
+    }
+
+    let font_style = node.context.style.to_sized_font_style(&node.context);
+
+    let (inline_layout, text, spans) = node.create_draw_inline_layout(layout, &font_style);
+
+    let positioned_inline_boxes =
+      self.draw_inline_layout(&node.context, layout, inline_layout, &text, &font_style)?;
+
+    if positioned_inline_boxes.is_empty() {
+      return Ok(());
+    }
+
+    let boxes = spans.iter().filter_map(|span| match span {
+      ProcessedInlineSpan::Box { node, .. } => Some(node),
+      _ => None,
+    });
+
+    // Inline boxes are replaced content, draw them together as a single image.
+    self.draw_rasterized(|canvas| {
+      for (inline_node, positioned) in boxes.zip(positioned_inline_boxes.iter()) {
+        draw_inline_box(positioned, inline_node, canvas, node.context.transform)?;
+      }
+
+      Ok(())
+    })
+  }
+
+  fn draw_inline_layout(
+    &mut self,
+    context: &RenderContext,
+    layout: Layout,
+    inline_layout: InlineLayout,
+    text: &str,
+    font_style: &SizedFontStyle,
+  ) -> Result<Vec<PositionedInlineBox>> {
+    // Glyphs filled with the background have no vector equivalent.
+    if context.style.background_clip == BackgroundClip::Text {
+      let mut positioned_inline_boxes = Vec::new();
+
+      self.draw_rasterized(|canvas| {
+        positioned_inline_boxes =
+          draw_inline_layout(context, canvas, layout, inline_layout, font_style)?;
+
+        Ok(())
+      })?;
+
+      return Ok(positioned_inline_boxes);
+    }
+
+    if font_style.text_shadow.is_some() {
+      self.draw_rasterized(|canvas| {
+        draw_text_shadows(context, canvas, layout, &inline_layout, font_style)
+      })?;
+    }
+
+    let mut positioned_inline_boxes = Vec::new();
+
+    for line in inline_layout.lines() {
+      // A run is split into multiple glyph runs when the brush changes,
+      // keep track of the glyph each of them starts from.
+      let mut next_glyph_start: Option<(Range<usize>, usize)> = None;
+
+      for item in line.items() {
+        match item {
+          PositionedLayoutItem::GlyphRun(glyph_run) => {
+            let run_range = glyph_run.run().text_range();
+
+            let glyph_start = match &next_glyph_start {
+              Some((range, start)) if *range == run_range => *start,
+              _ => 0,
+            };
+
+            let glyph_texts = glyph_texts(&glyph_run, text, glyph_start);
+
+            next_glyph_start = Some((run_range, glyph_start + glyph_texts.len()));
+
+            self.draw_glyph_run(font_style, &glyph_run, &glyph_texts, layout, context)?;
+          }
+          PositionedLayoutItem::InlineBox(inline_box) => positioned_inline_boxes.push(inline_box),
+        }
+      }
+    }
+
+    Ok(positioned_inline_boxes)
+  }
+
+  fn draw_glyph_run(
+    &mut self,
+    style: &SizedFontStyle,
+    glyph_run: &GlyphRun<'_, InlineBrush>,
+    glyph_texts: &[&str],
+    layout: Layout,
+    context: &RenderContext,
+  ) -> Result<()> {
+    let decoration_line = style
+      .parent
+      .text_decoration_line
+      .as_ref()
+      .unwrap_or(&style.parent.text_decoration.line);
+
+    let metrics = glyph_run.run().metrics();
+    let decoration_size = glyph_run.run().font_size() / 18.0;
+
+    if decoration_line.contains(&TextDecorationLine::Underline) {
+      self.draw_decoration(
+        glyph_run,
+        style.text_decoration_color,
+        glyph_run.baseline() - metrics.underline_offset,
+        decoration_size,
+        layout,
+        context.transform,
+      );
+    }
+
+    let brush_color = glyph_run.style().brush.color;
+
+    // Plain outline glyphs are collected and filled at once, so the surface can draw them as text.
+    let mut text_glyphs = Vec::new();
+    let mut text_outlines = Vec::new();
+
+    for_each_glyph(
+      glyph_run,
+      layout,
+      context,
+      |index, glyph, resolved, transform, palette| {
+        match resolved {
+          ResolvedGlyph::Outline(outline) => {
+            let paths = collect_outline_paths(outline);
+
+            if outline.is_color()
+              && let Some(palette) = palette
+            {
+              for i in 0..outline.len() {
+                let Some(layer) = outline.get(i) else {
+                  break;
+                };
+
+                let Some(color) = layer.color_index().map(|index| Color(palette.get(index))) else {
+                  continue;
+                };
+
+                self.surface.fill_path(
+                  &layer
+                    .path()
+                    .commands()
+                    .map(invert_y_coordinate)
+                    .collect::<Vec<_>>(),
+                  transform,
+                  &VectorPaint::Solid(color.with_opacity(context.opacity)),
+                  FillRule::NonZero,
+                );
+              }
+
+              if style.stroke_width > 0.0 {
+                self.surface.stroke_path(
+                  &paths,
+                  transform,
+                  style.text_stroke_color,
+                  style.stroke_width,
+                );
+              }
+            } else {
+              text_glyphs.push((glyph, glyph_texts.get(index).copied().unwrap_or_default()));
+              text_outlines.push((paths, transform));
+            }
+          }
+          ResolvedGlyph::Image(bitmap) => {
+            let image = RgbaImage::from_raw(
+              bitmap.placement.width,
+              bitmap.placement.height,
+              bitmap.data.clone(),
+            )
+            .ok_or(ImageError::Decoding(DecodingError::new(
+              ImageFormatHint::Unknown,
+              "Failed to create image from raw data",
+            )))?;
+
+            self.surface.draw_image(
+              &image,
+              transform
+                * Affine::translation(bitmap.placement.left as f32, -bitmap.placement.top as f32),
+              context.opacity,
+            )?;
+          }
+        }
+
+        Ok(())
+      },
+    )?;
+
+    let content_transform = context.transform
+      * Affine::translation(
+        layout.border.left + layout.padding.left,
+        layout.border.top + layout.padding.top,
+      );
+
+    if text_glyphs.is_empty()
+      || !self
+        .surface
+        .fill_glyphs(glyph_run, &text_glyphs, content_transform)?
+    {
+      for (paths, transform) in &text_outlines {
+        self.surface.fill_path(
+          paths,
+          *transform,
+          &VectorPaint::Solid(brush_color),
+          FillRule::NonZero,
+        );
+      }
+    }
+
+    if style.stroke_width > 0.0 {
+      for (paths, transform) in &text_outlines {
+        self.surface.stroke_path(
+          paths,
+          *transform,
+          style.text_stroke_color,
+          style.stroke_width,
+        );
+      }
+    }
+
+    if decoration_line.contains(&TextDecorationLine::LineThrough) {
+      self.draw_decoration(
+        glyph_run,
+        style.text_decoration_color,
+        glyph_run.baseline() - metrics.strikethrough_offset,
+        decoration_size,
+        layout,
+        context.transform,
+      );
+    }
+
+    if decoration_line.contains(&TextDecorationLine::Overline) {
+      self.draw_decoration(
+        glyph_run,
+        style.text_decoration_color,
+        glyph_run.baseline() - metrics.ascent - metrics.underline_offset,
+        decoration_size,
+        layout,
+        context.transform,
+      );
+    }
+
+    Ok(())
+  }
+
+  fn draw_decoration(
+    &mut self,
+    glyph_run: &GlyphRun<'_, InlineBrush>,
+    color: Color,
+    offset: f32,
+    size: f32,
+    layout: Layout,
+    transform: Affine,
+  ) {
+    if color.0[3] == 0 {
+      return;
+    }
+
+    let mut paths = Vec::new();
+
+    BorderProperties::default().append_mask_commands(
+      &mut paths,
+      Size {
+        width: glyph_run.advance(),
+        height: size,
+      },
+      Point::ZERO,
+    );
+
+    self.surface.fill_path(
+      &paths,
+      transform
+        * Affine::translation(
+          layout.border.left + layout.padding.left + glyph_run.offset(),
+          layout.border.top + layout.padding.top + offset,
+        ),
+      &VectorPaint::Solid(color),
+      FillRule::NonZero,
+    );
+  }
+
+  /// Rasterizes the drawing into a transparent layer of the output size,
+  /// and draws the painted region of it as an image.
+  fn draw_rasterized(&mut self, draw: impl FnOnce(&mut Canvas) -> Result<()>) -> Result<()> {
+    // Vector output is composited in sRGB by its viewers, the fallbacks match that.
+    let mut canvas = Canvas::new(self.size, ColorInterpolation::Srgb);
+
+    draw(&mut canvas)?;
+
+    let image = canvas.into_display_list().rasterize();
+
+    let Some((from, to)) = painted_bounds(&image) else {
+      return Ok(());
+    };
+
+    let cropped = crop_imm(&image, from.x, from.y, to.x - from.x, to.y - from.y).to_image();
+
+    self.surface.draw_image(
+      &cropped,
+      Affine::translation(from.x as f32, from.y as f32),
+      u8::MAX,
+    )
+  }
+}
+
+enum OverflowClip {
+  None,
+  Clip(Vec<Command>),
+  /// The content box is empty along a clipped axis, nothing would be visible.
+  Empty,
+}
+
+/// The distance used in place of an unclipped edge of the overflow clip.
+const UNCLIPPED_EXTENT: f32 = 1.0e6;
+
+fn overflow_clip_paths(style: &InheritedStyle, layout: Layout) -> OverflowClip {
+  let overflow = style.resolve_overflows();
+
+  if !overflow.should_clip_content() {
+    return OverflowClip::None;
+  }
+
+  let clip_x = overflow.x != Overflow::Visible;
+  let clip_y = overflow.y != Overflow::Visible;
+
+  if (clip_x && layout.content_box_width() < f32::EPSILON)
+    || (clip_y && layout.content_box_height() < f32::EPSILON)
+  {
+    return OverflowClip::Empty;
+  }
+
+  let (x, width) = if clip_x {
+    (
+      layout.padding.left + layout.border.left,
+      layout.content_box_width(),
+    )
+  } else {
+    (-UNCLIPPED_EXTENT, UNCLIPPED_EXTENT * 2.0)
+  };
+
+  let (y, height) = if clip_y {
+    (
+      layout.padding.top + layout.border.top,
+      layout.content_box_height(),
+    )
+  } else {
+    (-UNCLIPPED_EXTENT, UNCLIPPED_EXTENT * 2.0)
+  };
+
+  let mut paths = Vec::new();
+
+  BorderProperties::default().append_mask_commands(
+    &mut paths,
+    Size { width, height },
+    Point { x, y },
+  );
+
+  OverflowClip::Clip(paths)
+}
+
+/// Builds the rounded rectangle of the area painted by the background, according to `background-clip`.
+fn background_clip_paths(context: &RenderContext, layout: Layout) -> Option<Vec<Command>> {
+  let mut radius = BorderProperties::from_context(context, layout.size, layout.border);
+
+  let (size, offset) = match context.style.background_clip {
+    BackgroundClip::BorderBox => (layout.size, Point::ZERO),
+    BackgroundClip::PaddingBox => {
+      radius.inset_by_border_width();
+
+      (
+        Size {
+          width: layout.size.width - layout.border.left - layout.border.right,
+          height: layout.size.height - layout.border.top - layout.border.bottom,
+        },
+        Point {
+          x: layout.border.left,
+          y: layout.border.top,
+        },
+      )
+    }
+    BackgroundClip::ContentBox => {
+      radius.inset_by_border_width();
+      radius.expand_by(layout.padding.map(|size| -size));
+
+      (
+        layout.content_box_size(),
+        Point {
+          x: layout.padding.left + layout.border.left,
+          y: layout.padding.top + layout.border.top,
+        },
+      )
+    }
+    BackgroundClip::Text | BackgroundClip::BorderArea => return None,
+  };
+
+  let mut paths = Vec::new();
+
+  radius.append_mask_commands(&mut paths, size, offset);
+
+  Some(paths)
+}
+
+fn linear_gradient_paint(
+  gradient: &LinearGradient,
+  size: Size<f32>,
+  context: &RenderContext,
+) -> Option<VectorPaint> {
+  let draw_context = LinearGradientDrawContext::new(gradient, size.width, size.height, context);
+
+  if draw_context.resolved_stops.is_empty() {
+    return None;
+  }
+
+  Some(VectorPaint::LinearGradient {
+    start: Point {
+      x: draw_context.cx - draw_context.dir_x * draw_context.max_extent,
+      y: draw_context.cy - draw_context.dir_y * draw_context.max_extent,
+    },
+    end: Point {
+      x: draw_context.cx + draw_context.dir_x * draw_context.max_extent,
+      y: draw_context.cy + draw_context.dir_y * draw_context.max_extent,
+    },
+    stops: normalize_gradient_stops(&draw_context.resolved_stops, draw_context.axis_length),
+  })
+}
+
+fn radial_gradient_paint(
+  gradient: &RadialGradient,
+  size: Size<f32>,
+  context: &RenderContext,
+) -> Option<VectorPaint> {
+  let draw_context = RadialGradientDrawContext::new(gradient, size.width, size.height, context);
+
+  let last_position = draw_context.resolved_stops.last()?.position;
+
+  let radius_x = draw_context.radius_x.max(1e-6);
+  let radius_y = draw_context.radius_y.max(1e-6);
+  let radius_scale = radius_x.max(radius_y);
+
+  // Stops past the ending shape keep going outwards, extend the gradient to cover them.
+  let radius = radius_scale.max(last_position);
+
+  Some(VectorPaint::RadialGradient {
+    transform: Affine::translation(draw_context.cx, draw_context.cy)
+      * Affine::scale(radius_x / radius_scale, radius_y / radius_scale),
+    radius,
+    stops: normalize_gradient_stops(&draw_context.resolved_stops, radius),
+  })
+}
+
+/// Converts stop positions in pixels to offsets along `length`.
+/// The colors at both ends are sampled, so stops outside of the gradient line are still represented.
+fn normalize_gradient_stops(
+  stops: &[ResolvedGradientStop],
+  length: f32,
+) -> SmallVec<[ResolvedGradientStop; 4]> {
+  let length = length.max(1e-6);
+  let interpolation = StopInterpolation::Channels(ColorInterpolation::Srgb);
+
+  let mut normalized = smallvec![ResolvedGradientStop {
+    color: color_from_stops(0.0, stops, interpolation),
+    position: 0.0,
+  }];
+
+  normalized.extend(
+    stops
+      .iter()
+      .filter(|stop| stop.position > 0.0 && stop.position < length)
+      .map(|stop| ResolvedGradientStop {
+        color: stop.color,
+        position: stop.position / length,
+      }),
+  );
+
+  normalized.push(ResolvedGradientStop {
+    color: color_from_stops(length, stops, interpolation),
+    position: 1.0,
+  });
+
+  normalized
+}
+
+/// Calls `f` with the index and position of every resolved glyph of the run,
+/// and the transform placing it in the node.
+fn for_each_glyph(
+  glyph_run: &GlyphRun<'_, InlineBrush>,
+  layout: Layout,
+  context: &RenderContext,
+  mut f: impl FnMut(usize, Glyph, &ResolvedGlyph, Affine, Option<ColorPalette>) -> Result<()>,
+) -> Result<()> {
+  let run = glyph_run.run();
+
+  let font = FontRef::from_index(run.font().data.as_ref(), run.font().index as usize)
+    .ok_or(FontError::InvalidFontIndex)?;
+
+  let resolved_glyphs = context.global.font_context.resolve_glyphs(
+    run,
+    font,
+    glyph_run.positioned_glyphs().map(|glyph| glyph.id),
+  );
+
+  let palette = font.color_palettes().next();
+
+  for (index, glyph) in glyph_run.positioned_glyphs().enumerate() {
+    let Some(resolved) = resolved_glyphs.get(&glyph.id) else {
+      continue;
+    };
+
+    f(
+      index,
+      glyph,
+      resolved,
+      context.transform
+        * Affine::translation(
+          layout.border.left + layout.padding.left + glyph.x,
+          layout.border.top + layout.padding.top + glyph.y,
+        ),
+      palette,
+    )?;
+  }
+
+  Ok(())
+}
+
+/// Returns the text each glyph of the glyph run represents, starting from `glyph_start` of its run.
+pub(crate) fn glyph_texts<'t>(
+  glyph_run: &GlyphRun<'_, InlineBrush>,
+  text: &'t str,
+  glyph_start: usize,
+) -> Vec<&'t str> {
+  let run = glyph_run.run();
+
+  let mut ranges: Vec<Range<usize>> = Vec::new();
+  // Ligature components have no glyphs, their text belongs to the ligature glyph,
+  // which comes after them in visual order for right-to-left runs.
+  let mut pending: Option<Range<usize>> = None;
+
+  for cluster in run.visual_clusters() {
+    let mut range = cluster.text_range();
+    let glyph_count = cluster.glyphs().count();
+
+    if glyph_count == 0 {
+      if !run.is_rtl()
+        && let Some(last) = ranges.last_mut()
+      {
+        *last = merge_ranges(last, &range);
+      } else {
+        pending = Some(match pending {
+          Some(pending) => merge_ranges(&pending, &range),
+          None => range,
+        });
+      }
+
+      continue;
+    }
+
+    if let Some(pending) = pending.take() {
+      range = merge_ranges(&pending, &range);
+    }
+
+    ranges.push(range.clone());
+    ranges.extend((1..glyph_count).map(|_| range.end..range.end));
+  }
+
+  ranges
+    .into_iter()
+    .skip(glyph_start)
+    .take(glyph_run.glyphs().count())
+    .map(|range| text.get(range).unwrap_or_default())
+    .collect()
+}
+
+fn merge_ranges(a: &Range<usize>, b: &Range<usize>) -> Range<usize> {
+  a.start.min(b.start)..a.end.max(b.end)
+}
+
+fn draw_text_shadows(
+  context: &RenderContext,
+  canvas: &mut Canvas,
+  layout: Layout,
+  inline_layout: &InlineLayout,
+  font_style: &SizedFontStyle,
+) -> Result<()> {
+  let Some(shadows) = &font_style.text_shadow else {
+    return Ok(());
+  };
+
+  for line in inline_layout.lines() {
+    for item in line.items() {
+      let PositionedLayoutItem::GlyphRun(glyph_run) = item else {
+        continue;
+      };
+
+      for_each_glyph(
+        &glyph_run,
+        layout,
+        context,
+        |_, _, resolved, transform, _| {
+          let ResolvedGlyph::Outline(outline) = resolved else {
+            return Ok(());
+          };
+
+          let paths = collect_outline_paths(outline);
+
+          for shadow in shadows {
+            shadow.draw_outset(canvas, &paths, transform, Default::default());
+          }
+
+          Ok(())
+        },
+      )?;
+    }
+  }
+
+  Ok(())
+}
+
+/// Returns the bounds (inclusive start, exclusive end) of the pixels with non-zero alpha.
+fn painted_bounds(image: &RgbaImage) -> Option<(Point<u32>, Point<u32>)> {
+  let mut from = Point {
+    x: u32::MAX,
+    y: u32::MAX,
+  };
+  let mut to = Point { x: 0, y: 0 };
+
+  for (x, y, pixel) in image.enumerate_pixels() {
+    if pixel.0[3] == 0 {
+      continue;
+    }
+
+    from.x = from.x.min(x);
+    from.y = from.y.min(y);
+    to.x = to.x.max(x + 1);
+    to.y = to.y.max(y + 1);
+  }
+
+  if from.x >= to.x || from.y >= to.y {
+    return None;
+  }
+
+  Some((from, to))
+}
@@ -0,0 +1,1455 @@
+//! Lossy VP8 encoding for WebP output.
+//!
+//! Only key frames are produced. Every macroblock is predicted as a whole (16x16 luma, 8x8 chroma)
+//! with the mode closest to the source, and the token probabilities are adapted to the frame.
+//! The transforms follow libvpx so decoders reconstruct exactly what the encoder predicted from.
+
+use image::RgbaImage;
+
+type TokenProbs = [[[[u8; 11]; 3]; 8]; 4];
+
+type TokenCounts = [[[[[u32; 2]; 11]; 3]; 8]; 4];
+
+/// Coefficient scan order.
+const ZIGZAG: [usize; 16] = [0, 1, 4, 8, 5, 2, 3, 6, 9, 12, 13, 10, 7, 11, 14, 15];
+
+/// Probability band of each coefficient position in scan order.
+const COEFF_BANDS: [usize; 16] = [0, 1, 2, 3, 6, 4, 5, 6, 6, 6, 6, 6, 6, 6, 6, 7];
+
+/// Probabilities of the extra bits of the `DCT_CAT1` to `DCT_CAT6` tokens.
+const DCT_CAT_PROBS: [&[u8]; 6] = [
+  &[159],
+  &[165, 145],
+  &[173, 148, 140],
+  &[176, 155, 140, 135],
+  &[180, 157, 141, 134, 130],
+  &[254, 254, 243, 230, 196, 177, 153, 140, 133, 130, 129],
+];
+
+/// Smallest value of the `DCT_CAT1` to `DCT_CAT6` tokens.
+const DCT_CAT_BASE: [u16; 6] = [5, 7, 11, 19, 35, 67];
+
+/// Largest level a coefficient token can express.
+const MAX_LEVEL: u16 = 2048;
+
+/// Token probability plane of luma blocks whose DC is carried by the Y2 block.
+const PLANE_Y_AFTER_Y2: usize = 0;
+/// Token probability plane of the Y2 block.
+const PLANE_Y2: usize = 1;
+/// Token probability plane of chroma blocks.
+const PLANE_CHROMA: usize = 2;
+
+/// Dequantization factors for DC coefficients by quantizer index (RFC 6386 section 14.1).
+#[rustfmt::skip]
+const DC_QUANT: [u16; 128] = [
+  4, 5, 6, 7, 8, 9, 10, 10, 11, 12, 13, 14, 15, 16, 17, 17,
+  18, 19, 20, 20, 21, 21, 22, 22, 23, 23, 24, 25, 25, 26, 27, 28,
+  29, 30, 31, 32, 33, 34, 35, 36, 37, 37, 38, 39, 40, 41, 42, 43,
+  44, 45, 46, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58,
+  59, 60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 72, 73, 74,
+  75, 76, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 88, 89,
+  91, 93, 95, 96, 98, 100, 101, 102, 104, 106, 108, 110, 112, 114, 116, 118,
+  122, 124, 126, 128, 130, 132, 134, 136, 138, 140, 143, 145, 148, 151, 154, 157,
+];
+
+/// Dequantization factors for AC coefficients by quantizer index (RFC 6386 section 14.1).
+#[rustfmt::skip]
+const AC_QUANT: [u16; 128] = [
+  4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19,
+  20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35,
+  36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51,
+  52, 53, 54, 55, 56, 57, 58, 60, 62, 64, 66, 68, 70, 72, 74, 76,
+  78, 80, 82, 84, 86, 88, 90, 92, 94, 96, 98, 100, 102, 104, 106, 108,
+  110, 112, 114, 116, 119, 122, 125, 128, 131, 134, 137, 140, 143, 146, 149, 152,
+  155, 158, 161, 164, 167, 170, 173, 177, 181, 185, 189, 193, 197, 201, 205, 209,
+  213, 217, 221, 225, 229, 234, 239, 245, 249, 254, 259, 264, 269, 274, 279, 284,
+];
+
+/// Default token probabilities (RFC 6386 section 13.5).
+#[rustfmt::skip]
+const COEFF_PROBS: TokenProbs = [
+  [
+    [
+      [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+      [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+      [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+    ],
+    [
+      [253, 136, 254, 255, 228, 219, 128, 128, 128, 128, 128],
+      [189, 129, 242, 255, 227, 213, 255, 219, 128, 128, 128],
+      [106, 126, 227, 252, 214, 209, 255, 255, 128, 128, 128],
+    ],
+    [
+      [1, 98, 248, 255, 236, 226, 255, 255, 128, 128, 128],
+      [181, 133, 238, 254, 221, 234, 255, 154, 128, 128, 128],
+      [78, 134, 202, 247, 198, 180, 255, 219, 128, 128, 128],
+    ],
+    [
+      [1, 185, 249, 255, 243, 255, 128, 128, 128, 128, 128],
+      [184, 150, 247, 255, 236, 224, 128, 128, 128, 128, 128],
+      [77, 110, 216, 255, 236, 230, 128, 128, 128, 128, 128],
+    ],
+    [
+      [1, 101, 251, 255, 241, 255, 128, 128, 128, 128, 128],
+      [170, 139, 241, 252, 236, 209, 255, 255, 128, 128, 128],
+      [37, 116, 196, 243, 228, 255, 255, 255, 128, 128, 128],
+    ],
+    [
+      [1, 204, 254, 255, 245, 255, 128, 128, 128, 128, 128],
+      [207, 160, 250, 255, 238, 128, 128, 128, 128, 128, 128],
+      [102, 103, 231, 255, 211, 171, 128, 128, 128, 128, 128],
+    ],
+    [
+      [1, 152, 252, 255, 240, 255, 128, 128, 128, 128, 128],
+      [177, 135, 243, 255, 234, 225, 128, 128, 128, 128, 128],
+      [80, 129, 211, 255, 194, 224, 128, 128, 128, 128, 128],
+    ],
+    [
+      [1, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+      [246, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+      [255, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+    ],
+  ],
+  [
+    [
+      [198, 35, 237, 223, 193, 187, 162, 160, 145, 155, 62],
+      [131, 45, 198, 221, 172, 176, 220, 157, 252, 221, 1],
+      [68, 47, 146, 208, 149, 167, 221, 162, 255, 223, 128],
+    ],
+    [
+      [1, 149, 241, 255, 221, 224, 255, 255, 128, 128, 128],
+      [184, 141, 234, 253, 222, 220, 255, 199, 128, 128, 128],
+      [81, 99, 181, 242, 176, 190, 249, 202, 255, 255, 128],
+    ],
+    [
+      [1, 129, 232, 253, 214, 197, 242, 196, 255, 255, 128],
+      [99, 121, 210, 250, 201, 198, 255, 202, 128, 128, 128],
+      [23, 91, 163, 242, 170, 187, 247, 210, 255, 255, 128],
+    ],
+    [
+      [1, 200, 246, 255, 234, 255, 128, 128, 128, 128, 128],
+      [109, 178, 241, 255, 231, 245, 255, 255, 128, 128, 128],
+      [44, 130, 201, 253, 205, 192, 255, 255, 128, 128, 128],
+    ],
+    [
+      [1, 132, 239, 251, 219, 209, 255, 165, 128, 128, 128],
+      [94, 136, 225, 251, 218, 190, 255, 255, 128, 128, 128],
+      [22, 100, 174, 245, 186, 161, 255, 199, 128, 128, 128],
+    ],
+    [
+      [1, 182, 249, 255, 232, 235, 128, 128, 128, 128, 128],
+      [124, 143, 241, 255, 227, 234, 128, 128, 128, 128, 128],
+      [35, 77, 181, 251, 193, 211, 255, 205, 128, 128, 128],
+    ],
+    [
+      [1, 157, 247, 255, 236, 231, 255, 255, 128, 128, 128],
+      [121, 141, 235, 255, 225, 227, 255, 255, 128, 128, 128],
+      [45, 99, 188, 251, 195, 217, 255, 224, 128, 128, 128],
+    ],
+    [
+      [1, 1, 251, 255, 213, 255, 128, 128, 128, 128, 128],
+      [203, 1, 248, 255, 255, 128, 128, 128, 128, 128, 128],
+      [137, 1, 177, 255, 224, 255, 128, 128, 128, 128, 128],
+    ],
+  ],
+  [
+    [
+      [253, 9, 248, 251, 207, 208, 255, 192, 128, 128, 128],
+      [175, 13, 224, 243, 193, 185, 249, 198, 255, 255, 128],
+      [73, 17, 171, 221, 161, 179, 236, 167, 255, 234, 128],
+    ],
+    [
+      [1, 95, 247, 253, 212, 183, 255, 255, 128, 128, 128],
+      [239, 90, 244, 250, 211, 209, 255, 255, 128, 128, 128],
+      [155, 77, 195, 248, 188, 195, 255, 255, 128, 128, 128],
+    ],
+    [
+      [1, 24, 239, 251, 218, 219, 255, 205, 128, 128, 128],
+      [201, 51, 219, 255, 196, 186, 128, 128, 128, 128, 128],
+      [69, 46, 190, 239, 201, 218, 255, 228, 128, 128, 128],
+    ],
+    [
+      [1, 191, 251, 255, 255, 128, 128, 128, 128, 128, 128],
+      [223, 165, 249, 255, 213, 255, 128, 128, 128, 128, 128],
+      [141, 124, 248, 255, 255, 128, 128, 128, 128, 128, 128],
+    ],
+    [
+      [1, 16, 248, 255, 255, 128, 128, 128, 128, 128, 128],
+      [190, 36, 230, 255, 236, 255, 128, 128, 128, 128, 128],
+      [149, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+    ],
+    [
+      [1, 226, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+      [247, 192, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+      [240, 128, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+    ],
+    [
+      [1, 134, 252, 255, 255, 128, 128, 128, 128, 128, 128],
+      [213, 62, 250, 255, 255, 128, 128, 128, 128, 128, 128],
+      [55, 93, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+    ],
+    [
+      [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+      [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+      [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+    ],
+  ],
+  [
+    [
+      [202, 24, 213, 235, 186, 191, 220, 160, 240, 175, 255],
+      [126, 38, 182, 232, 169, 184, 228, 174, 255, 187, 128],
+      [61, 46, 138, 219, 151, 178, 240, 170, 255, 216, 128],
+    ],
+    [
+      [1, 112, 230, 250, 199, 191, 247, 159, 255, 255, 128],
+      [166, 109, 228, 252, 211, 215, 255, 174, 128, 128, 128],
+      [39, 77, 162, 232, 172, 180, 245, 178, 255, 255, 128],
+    ],
+    [
+      [1, 52, 220, 246, 198, 199, 249, 220, 255, 255, 128],
+      [124, 74, 191, 243, 183, 193, 250, 221, 255, 255, 128],
+      [24, 71, 130, 219, 154, 170, 243, 182, 255, 255, 128],
+    ],
+    [
+      [1, 182, 225, 249, 219, 240, 255, 224, 128, 128, 128],
+      [149, 150, 226, 252, 216, 205, 255, 171, 128, 128, 128],
+      [28, 108, 170, 242, 183, 194, 254, 223, 255, 255, 128],
+    ],
+    [
+      [1, 81, 230, 252, 204, 203, 255, 192, 128, 128, 128],
+      [123, 102, 209, 247, 188, 196, 255, 233, 128, 128, 128],
+      [20, 95, 153, 243, 164, 173, 255, 203, 128, 128, 128],
+    ],
+    [
+      [1, 222, 248, 255, 216, 213, 128, 128, 128, 128, 128],
+      [168, 175, 246, 252, 235, 205, 255, 255, 128, 128, 128],
+      [47, 116, 215, 255, 211, 212, 255, 255, 128, 128, 128],
+    ],
+    [
+      [1, 121, 236, 253, 212, 214, 255, 255, 128, 128, 128],
+      [141, 84, 213, 252, 201, 202, 255, 219, 128, 128, 128],
+      [42, 80, 160, 240, 162, 185, 255, 205, 128, 128, 128],
+    ],
+    [
+      [1, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+      [244, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+      [238, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+    ],
+  ],
+];
+
+/// Probabilities that a token probability is updated in the frame header (RFC 6386 section 13.4).
+#[rustfmt::skip]
+const COEFF_UPDATE_PROBS: TokenProbs = [
+  [
+    [
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [176, 246, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [223, 241, 252, 255, 255, 255, 255, 255, 255, 255, 255],
+      [249, 253, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 244, 252, 255, 255, 255, 255, 255, 255, 255, 255],
+      [234, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [253, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 246, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [239, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [254, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 248, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [251, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [251, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [254, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 254, 253, 255, 254, 255, 255, 255, 255, 255, 255],
+      [250, 255, 254, 255, 254, 255, 255, 255, 255, 255, 255],
+      [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+  ],
+  [
+    [
+      [217, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [225, 252, 241, 253, 255, 255, 254, 255, 255, 255, 255],
+      [234, 250, 241, 250, 253, 255, 253, 254, 255, 255, 255],
+    ],
+    [
+      [255, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [223, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [238, 253, 254, 254, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 248, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [249, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 253, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [247, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [252, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [253, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 254, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+      [250, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+  ],
+  [
+    [
+      [186, 251, 250, 255, 255, 255, 255, 255, 255, 255, 255],
+      [234, 251, 244, 254, 255, 255, 255, 255, 255, 255, 255],
+      [251, 251, 243, 253, 254, 255, 254, 255, 255, 255, 255],
+    ],
+    [
+      [255, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [236, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [251, 253, 253, 254, 254, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [254, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [254, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+  ],
+  [
+    [
+      [248, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [250, 254, 252, 254, 255, 255, 255, 255, 255, 255, 255],
+      [248, 254, 249, 253, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 253, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+      [246, 253, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+      [252, 254, 251, 254, 254, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 254, 252, 255, 255, 255, 255, 255, 255, 255, 255],
+      [248, 254, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+      [253, 255, 254, 254, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 251, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [245, 251, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [253, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 251, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+      [252, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 252, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [249, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 255, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+      [250, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+    [
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+      [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+    ],
+  ],
+];
+
+/// Boolean entropy encoder (RFC 6386 section 7.3).
+struct BoolEncoder {
+  output: Vec<u8>,
+  range: u32,
+  bottom: u32,
+  bit_count: u32,
+}
+
+impl BoolEncoder {
+  fn new() -> Self {
+    Self {
+      output: Vec::new(),
+      range: 255,
+      bottom: 0,
+      bit_count: 24,
+    }
+  }
+
+  fn put(&mut self, bit: bool, prob: u8) {
+    let split = 1 + (((self.range - 1) * prob as u32) >> 8);
+
+    if bit {
+      self.bottom = self.bottom.wrapping_add(split);
+      self.range -= split;
+    } else {
+      self.range = split;
+    }
+
+    while self.range < 128 {
+      self.range <<= 1;
+
+      if self.bottom & (1 << 31) != 0 {
+        self.add_one_to_output();
+      }
+
+      self.bottom <<= 1;
+      self.bit_count -= 1;
+
+      if self.bit_count == 0 {
+        self.output.push((self.bottom >> 24) as u8);
+        self.bottom &= (1 << 24) - 1;
+        self.bit_count = 8;
+      }
+    }
+  }
+
+  fn put_flag(&mut self, bit: bool) {
+    self.put(bit, 128);
+  }
+
+  fn put_literal(&mut self, value: u32, bits: u32) {
+    for shift in (0..bits).rev() {
+      self.put_flag((value >> shift) & 1 != 0);
+    }
+  }
+
+  /// Propagates a carry into the bytes already written.
+  fn add_one_to_output(&mut self) {
+    for byte in self.output.iter_mut().rev() {
+      if *byte == u8::MAX {
+        *byte = 0;
+      } else {
+        *byte += 1;
+        return;
+      }
+    }
+  }
+
+  fn finish(mut self) -> Vec<u8> {
+    let mut value = self.bottom;
+
+    if value & (1 << (32 - self.bit_count)) != 0 {
+      self.add_one_to_output();
+    }
+
+    value <<= self.bit_count & 7;
+
+    for _ in 0..self.bit_count >> 3 {
+      value <<= 8;
+    }
+
+    for _ in 0..4 {
+      self.output.push((value >> 24) as u8);
+      value <<= 8;
+    }
+
+    self.output
+  }
+}
+
+/// Receives the bits of the coefficient tokens.
+trait TokenSink {
+  /// A branch of the token tree, identified by `[plane][band][context][node]`.
+  fn put_token(&mut self, slot: [usize; 4], bit: bool);
+
+  /// A bit with a fixed probability (signs and extra bits).
+  fn put_fixed(&mut self, bit: bool, prob: u8);
+}
+
+impl TokenSink for TokenCounts {
+  fn put_token(&mut self, [plane, band, context, node]: [usize; 4], bit: bool) {
+    self[plane][band][context][node][bit as usize] += 1;
+  }
+
+  fn put_fixed(&mut self, _bit: bool, _prob: u8) {}
+}
+
+struct TokenWriter<'a> {
+  encoder: &'a mut BoolEncoder,
+  probs: &'a TokenProbs,
+}
+
+impl TokenSink for TokenWriter<'_> {
+  fn put_token(&mut self, [plane, band, context, node]: [usize; 4], bit: bool) {
+    self
+      .encoder
+      .put(bit, self.probs[plane][band][context][node]);
+  }
+
+  fn put_fixed(&mut self, bit: bool, prob: u8) {
+    self.encoder.put(bit, prob);
+  }
+}
+
+/// Writes the tokens of one block with levels in scan order, returning whether it has non-zero levels.
+fn tokenize_block(
+  sink: &mut impl TokenSink,
+  levels: &[i16; 16],
+  plane: usize,
+  first: usize,
+  context: usize,
+) -> bool {
+  let Some(last) = (first..16).rev().find(|&index| levels[index] != 0) else {
+    sink.put_token([plane, COEFF_BANDS[first], context, 0], false);
+    return false;
+  };
+
+  let mut context = context;
+  let mut after_zero = false;
+
+  for (index, &level) in levels.iter().enumerate().take(last + 1).skip(first) {
+    let slot = |node: usize| [plane, COEFF_BANDS[index], context, node];
+
+    // The end of block can't directly follow a zero, so its branch is left out.
+    if !after_zero {
+      sink.put_token(slot(0), true);
+    }
+
+    let value = level.unsigned_abs();
+
+    if value == 0 {
+      sink.put_token(slot(1), false);
+      context = 0;
+      after_zero = true;
+      continue;
+    }
+
+    sink.put_token(slot(1), true);
+
+    if value == 1 {
+      sink.put_token(slot(2), false);
+    } else {
+      sink.put_token(slot(2), true);
+
+      if value <= 4 {
+        sink.put_token(slot(3), false);
+
+        if value == 2 {
+          sink.put_token(slot(4), false);
+        } else {
+          sink.put_token(slot(4), true);
+          sink.put_token(slot(5), value == 4);
+        }
+      } else {
+        let category = DCT_CAT_BASE
+          .iter()
+          .rposition(|&base| value >= base)
+          .unwrap_or(0);
+
+        sink.put_token(slot(3), true);
+        sink.put_token(slot(6), category >= 2);
+
+        if category < 2 {
+          sink.put_token(slot(7), category == 1);
+        } else {
+          sink.put_token(slot(8), category >= 4);
+          sink.put_token(slot(9 + category / 4), category % 2 == 1);
+        }
+
+        let extra = value - DCT_CAT_BASE[category];
+        let probs = DCT_CAT_PROBS[category];
+
+        for (bit, &prob) in probs.iter().enumerate() {
+          sink.put_fixed((extra >> (probs.len() - 1 - bit)) & 1 != 0, prob);
+        }
+      }
+    }
+
+    sink.put_fixed(level < 0, 128);
+
+    context = if value == 1 { 1 } else { 2 };
+    after_zero = false;
+  }
+
+  if last < 15 {
+    sink.put_token([plane, COEFF_BANDS[last + 1], context, 0], false);
+  }
+
+  true
+}
+
+/// Intra prediction modes of whole 16x16 luma and 8x8 chroma blocks.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PredictionMode {
+  Dc,
+  Vertical,
+  Horizontal,
+  TrueMotion,
+}
+
+impl PredictionMode {
+  const ALL: [PredictionMode; 4] = [
+    PredictionMode::Dc,
+    PredictionMode::Vertical,
+    PredictionMode::Horizontal,
+    PredictionMode::TrueMotion,
+  ];
+
+  /// Writes the mode with the key frame luma tree, after the "not B_PRED" branch.
+  fn write_luma(self, encoder: &mut BoolEncoder) {
+    encoder.put(true, 145);
+
+    match self {
+      PredictionMode::Dc | PredictionMode::Vertical => {
+        encoder.put(false, 156);
+        encoder.put(self == PredictionMode::Vertical, 163);
+      }
+      PredictionMode::Horizontal | PredictionMode::TrueMotion => {
+        encoder.put(true, 156);
+        encoder.put(self == PredictionMode::TrueMotion, 128);
+      }
+    }
+  }
+
+  /// Writes the mode with the key frame chroma tree.
+  fn write_chroma(self, encoder: &mut BoolEncoder) {
+    encoder.put(self != PredictionMode::Dc, 142);
+
+    if self != PredictionMode::Dc {
+      encoder.put(self != PredictionMode::Vertical, 114);
+
+      if self != PredictionMode::Vertical {
+        encoder.put(self == PredictionMode::TrueMotion, 183);
+      }
+    }
+  }
+}
+
+/// A plane of 8-bit samples padded to whole macroblocks.
+struct Plane {
+  data: Vec<u8>,
+  stride: usize,
+}
+
+impl Plane {
+  fn new(stride: usize, height: usize) -> Self {
+    Self {
+      data: vec![0; stride * height],
+      stride,
+    }
+  }
+
+  /// Predicts the `size` x `size` block at (`x`, `y`) from the reconstructed pixels around it.
+  fn predict(&self, x: usize, y: usize, size: usize, mode: PredictionMode) -> [u8; 256] {
+    let mut above = [127u8; 16];
+    let mut left = [129u8; 16];
+
+    if y > 0 {
+      let start = (y - 1) * self.stride + x;
+      above[..size].copy_from_slice(&self.data[start..start + size]);
+    }
+
+    if x > 0 {
+      for (row, value) in left.iter_mut().enumerate().take(size) {
+        *value = self.data[(y + row) * self.stride + x - 1];
+      }
+    }
+
+    let corner = match (x, y) {
+      (_, 0) => 127,
+      (0, _) => 129,
+      _ => self.data[(y - 1) * self.stride + x - 1],
+    };
+
+    let mut prediction = [0u8; 256];
+
+    for row in 0..size {
+      for column in 0..size {
+        prediction[row * size + column] = match mode {
+          PredictionMode::Vertical => above[column],
+          PredictionMode::Horizontal => left[row],
+          PredictionMode::TrueMotion => {
+            (left[row] as i32 + above[column] as i32 - corner as i32).clamp(0, 255) as u8
+          }
+          PredictionMode::Dc => 0,
+        };
+      }
+    }
+
+    if mode == PredictionMode::Dc {
+      let shift = size.trailing_zeros();
+      let sum_above: u32 = above[..size].iter().map(|&value| value as u32).sum();
+      let sum_left: u32 = left[..size].iter().map(|&value| value as u32).sum();
+
+      let dc = match (y > 0, x > 0) {
+        (true, true) => (sum_above + sum_left + size as u32) >> (shift + 1),
+        (true, false) => (sum_above + (size as u32 >> 1)) >> shift,
+        (false, true) => (sum_left + (size as u32 >> 1)) >> shift,
+        (false, false) => 128,
+      };
+
+      prediction[..size * size].fill(dc as u8);
+    }
+
+    prediction
+  }
+
+  /// Sum of squared differences between the `size` x `size` block at (`x`, `y`) and `prediction`.
+  fn distortion(&self, x: usize, y: usize, size: usize, prediction: &[u8; 256]) -> u32 {
+    (0..size)
+      .flat_map(|row| {
+        let start = (y + row) * self.stride + x;
+
+        self.data[start..start + size]
+          .iter()
+          .zip(&prediction[row * size..(row + 1) * size])
+      })
+      .map(|(&a, &b)| (a as i32 - b as i32).pow(2) as u32)
+      .sum()
+  }
+
+  /// Returns the residual of the 4x4 block at (`x`, `y`) against `prediction` of a `size` wide block at (`origin_x`, `origin_y`).
+  fn residual(
+    &self,
+    (origin_x, origin_y): (usize, usize),
+    size: usize,
+    prediction: &[u8; 256],
+    (block_x, block_y): (usize, usize),
+  ) -> [i32; 16] {
+    let mut residual = [0i32; 16];
+
+    for row in 0..4 {
+      for column in 0..4 {
+        let source =
+          self.data[(origin_y + block_y + row) * self.stride + origin_x + block_x + column];
+        let predicted = prediction[(block_y + row) * size + block_x + column];
+
+        residual[row * 4 + column] = source as i32 - predicted as i32;
+      }
+    }
+
+    residual
+  }
+
+  /// Writes `prediction` plus the reconstructed `residual` into the 4x4 block at (`x`, `y`).
+  fn reconstruct(
+    &mut self,
+    (origin_x, origin_y): (usize, usize),
+    size: usize,
+    prediction: &[u8; 256],
+    (block_x, block_y): (usize, usize),
+    residual: &[i32; 16],
+  ) {
+    for row in 0..4 {
+      for column in 0..4 {
+        let predicted = prediction[(block_y + row) * size + block_x + column];
+
+        self.data[(origin_y + block_y + row) * self.stride + origin_x + block_x + column] =
+          (predicted as i32 + residual[row * 4 + column]).clamp(0, 255) as u8;
+      }
+    }
+  }
+}
+
+/// Forward 4x4 DCT of libvpx (`vp8_short_fdct4x4_c`).
+fn forward_dct(input: &[i32; 16]) -> [i32; 16] {
+  let mut temp = [0i32; 16];
+
+  for row in 0..4 {
+    let input = &input[row * 4..row * 4 + 4];
+
+    let a1 = (input[0] + input[3]) * 8;
+    let b1 = (input[1] + input[2]) * 8;
+    let c1 = (input[1] - input[2]) * 8;
+    let d1 = (input[0] - input[3]) * 8;
+
+    temp[row * 4] = a1 + b1;
+    temp[row * 4 + 2] = a1 - b1;
+    temp[row * 4 + 1] = (c1 * 2217 + d1 * 5352 + 14500) >> 12;
+    temp[row * 4 + 3] = (d1 * 2217 - c1 * 5352 + 7500) >> 12;
+  }
+
+  let mut output = [0i32; 16];
+
+  for column in 0..4 {
+    let a1 = temp[column] + temp[12 + column];
+    let b1 = temp[4 + column] + temp[8 + column];
+    let c1 = temp[4 + column] - temp[8 + column];
+    let d1 = temp[column] - temp[12 + column];
+
+    output[column] = (a1 + b1 + 7) >> 4;
+    output[8 + column] = (a1 - b1 + 7) >> 4;
+    output[4 + column] = ((c1 * 2217 + d1 * 5352 + 12000) >> 16) + (d1 != 0) as i32;
+    output[12 + column] = (d1 * 2217 - c1 * 5352 + 51000) >> 16;
+  }
+
+  output
+}
+
+/// Inverse 4x4 DCT (RFC 6386 section 14.3).
+fn inverse_dct(block: &mut [i32; 16]) {
+  const CONST1: i64 = 20091;
+  const CONST2: i64 = 35468;
+
+  for column in 0..4 {
+    let [i0, i1, i2, i3] = [0, 4, 8, 12].map(|offset| block[offset + column] as i64);
+
+    let a1 = i0 + i2;
+    let b1 = i0 - i2;
+    let c1 = ((i1 * CONST2) >> 16) - (i3 + ((i3 * CONST1) >> 16));
+    let d1 = (i1 + ((i1 * CONST1) >> 16)) + ((i3 * CONST2) >> 16);
+
+    block[column] = (a1 + d1) as i32;
+    block[4 + column] = (b1 + c1) as i32;
+    block[8 + column] = (b1 - c1) as i32;
+    block[12 + column] = (a1 - d1) as i32;
+  }
+
+  for row in 0..4 {
+    let [i0, i1, i2, i3] = [0, 1, 2, 3].map(|offset| block[row * 4 + offset] as i64);
+
+    let a1 = i0 + i2;
+    let b1 = i0 - i2;
+    let c1 = ((i1 * CONST2) >> 16) - (i3 + ((i3 * CONST1) >> 16));
+    let d1 = (i1 + ((i1 * CONST1) >> 16)) + ((i3 * CONST2) >> 16);
+
+    block[row * 4] = ((a1 + d1 + 4) >> 3) as i32;
+    block[row * 4 + 1] = ((b1 + c1 + 4) >> 3) as i32;
+    block[row * 4 + 2] = ((b1 - c1 + 4) >> 3) as i32;
+    block[row * 4 + 3] = ((a1 - d1 + 4) >> 3) as i32;
+  }
+}
+
+/// Forward Walsh-Hadamard transform of the luma DC coefficients (`vp8_short_walsh4x4_c`).
+fn forward_wht(input: &[i32; 16]) -> [i32; 16] {
+  let mut temp = [0i32; 16];
+
+  for row in 0..4 {
+    let input = &input[row * 4..row * 4 + 4];
+
+    let a1 = (input[0] + input[2]) * 4;
+    let d1 = (input[1] + input[3]) * 4;
+    let c1 = (input[1] - input[3]) * 4;
+    let b1 = (input[0] - input[2]) * 4;
+
+    temp[row * 4] = a1 + d1 + (a1 != 0) as i32;
+    temp[row * 4 + 1] = b1 + c1;
+    temp[row * 4 + 2] = b1 - c1;
+    temp[row * 4 + 3] = a1 - d1;
+  }
+
+  let mut output = [0i32; 16];
+
+  for column in 0..4 {
+    let a1 = temp[column] + temp[8 + column];
+    let d1 = temp[4 + column] + temp[12 + column];
+    let c1 = temp[4 + column] - temp[12 + column];
+    let b1 = temp[column] - temp[8 + column];
+
+    for (offset, value) in [(0, a1 + d1), (4, b1 + c1), (8, b1 - c1), (12, a1 - d1)] {
+      output[offset + column] = (value + (value < 0) as i32 + 3) >> 3;
+    }
+  }
+
+  output
+}
+
+/// Inverse Walsh-Hadamard transform of the luma DC coefficients (RFC 6386 section 14.3).
+fn inverse_wht(block: &mut [i32; 16]) {
+  for column in 0..4 {
+    let a1 = block[column] + block[12 + column];
+    let b1 = block[4 + column] + block[8 + column];
+    let c1 = block[4 + column] - block[8 + column];
+    let d1 = block[column] - block[12 + column];
+
+    block[column] = a1 + b1;
+    block[4 + column] = c1 + d1;
+    block[8 + column] = a1 - b1;
+    block[12 + column] = d1 - c1;
+  }
+
+  for row in block.chunks_exact_mut(4) {
+    let a1 = row[0] + row[3];
+    let b1 = row[1] + row[2];
+    let c1 = row[1] - row[2];
+    let d1 = row[0] - row[3];
+
+    row[0] = (a1 + b1 + 3) >> 3;
+    row[1] = (c1 + d1 + 3) >> 3;
+    row[2] = (a1 - b1 + 3) >> 3;
+    row[3] = (d1 - c1 + 3) >> 3;
+  }
+}
+
+/// Dequantization factors of one block type.
+#[derive(Clone, Copy)]
+struct Quantizer {
+  dc: i32,
+  ac: i32,
+}
+
+impl Quantizer {
+  /// Quantizes the coefficients from `first` on into `levels` (scan order),
+  /// and returns the dequantized coefficients the decoder will see.
+  fn quantize(&self, coefficients: &[i32; 16], first: usize, levels: &mut [i16; 16]) -> [i32; 16] {
+    let mut dequantized = [0i32; 16];
+
+    for index in first..16 {
+      let position = ZIGZAG[index];
+      let coefficient = coefficients[position];
+
+      let (step, bias) = if position == 0 {
+        (self.dc, self.dc / 2)
+      } else {
+        // A slightly wider dead zone drops noise that costs bits without being visible.
+        (self.ac, self.ac * 3 / 8)
+      };
+
+      let level = ((coefficient.abs() + bias) / step).min(MAX_LEVEL as i32);
+      let level = if coefficient < 0 { -level } else { level };
+
+      levels[index] = level as i16;
+      dequantized[position] = level * step;
+    }
+
+    dequantized
+  }
+}
+
+/// Maps quality (0-100) to a quantizer index the way libwebp does.
+fn quantizer_index(quality: u8) -> usize {
+  let quality = quality.min(100) as f32 / 100.0;
+
+  let linear = if quality < 0.75 {
+    quality * (2.0 / 3.0)
+  } else {
+    2.0 * quality - 1.0
+  };
+
+  (127.0 * (1.0 - linear.cbrt())).round().clamp(0.0, 127.0) as usize
+}
+
+/// The coded data of one macroblock, with levels in scan order.
+struct Macroblock {
+  luma_mode: PredictionMode,
+  chroma_mode: PredictionMode,
+  y2: [i16; 16],
+  y: [[i16; 16]; 16],
+  u: [[i16; 16]; 4],
+  v: [[i16; 16]; 4],
+}
+
+impl Macroblock {
+  fn is_empty(&self) -> bool {
+    [&self.y2]
+      .into_iter()
+      .chain(&self.y)
+      .chain(&self.u)
+      .chain(&self.v)
+      .all(|levels| levels.iter().all(|&level| level == 0))
+  }
+}
+
+/// Converts `image` to padded YUV 4:2:0 planes (BT.601, limited range).
+fn rgba_to_yuv(image: &RgbaImage, mb_width: usize, mb_height: usize) -> [Plane; 3] {
+  let (width, height) = (image.width() as usize, image.height() as usize);
+
+  let rgb = |x: usize, y: usize| {
+    let pixel = image.get_pixel(x.min(width - 1) as u32, y.min(height - 1) as u32);
+    [pixel[0] as i32, pixel[1] as i32, pixel[2] as i32]
+  };
+
+  let mut y_plane = Plane::new(mb_width * 16, mb_height * 16);
+  let mut u_plane = Plane::new(mb_width * 8, mb_height * 8);
+  let mut v_plane = Plane::new(mb_width * 8, mb_height * 8);
+
+  for y in 0..mb_height * 16 {
+    for x in 0..mb_width * 16 {
+      let [r, g, b] = rgb(x, y);
+
+      y_plane.data[y * y_plane.stride + x] =
+        ((16839 * r + 33059 * g + 6420 * b + (1 << 15) + (16 << 16)) >> 16) as u8;
+    }
+  }
+
+  for y in 0..mb_height * 8 {
+    for x in 0..mb_width * 8 {
+      let [r, g, b] = [(0, 0), (1, 0), (0, 1), (1, 1)]
+        .map(|(dx, dy)| rgb(x * 2 + dx, y * 2 + dy))
+        .into_iter()
+        .fold([0; 3], |sum, pixel| {
+          [sum[0] + pixel[0], sum[1] + pixel[1], sum[2] + pixel[2]]
+        });
+
+      let chroma = |value: i32| ((value + (1 << 17) + (128 << 18)) >> 18).clamp(0, 255) as u8;
+
+      u_plane.data[y * u_plane.stride + x] = chroma(-9719 * r - 19081 * g + 28800 * b);
+      v_plane.data[y * v_plane.stride + x] = chroma(28800 * r - 24116 * g - 4684 * b);
+    }
+  }
+
+  [y_plane, u_plane, v_plane]
+}
+
+/// Picks the prediction mode closest to `source`, predicting from the pixels in `reconstructed`.
+fn choose_mode(planes: &[(&Plane, &Plane)], x: usize, y: usize, size: usize) -> PredictionMode {
+  PredictionMode::ALL
+    .into_iter()
+    .min_by_key(|&mode| {
+      planes
+        .iter()
+        .map(|(source, reconstructed)| {
+          source.distortion(x, y, size, &reconstructed.predict(x, y, size, mode))
+        })
+        .sum::<u32>()
+    })
+    .unwrap_or(PredictionMode::Dc)
+}
+
+/// Codes the 8x8 chroma block of one plane, updating `reconstructed`.
+fn encode_chroma(
+  source: &Plane,
+  reconstructed: &mut Plane,
+  origin: (usize, usize),
+  mode: PredictionMode,
+  quantizer: Quantizer,
+) -> [[i16; 16]; 4] {
+  let prediction = reconstructed.predict(origin.0, origin.1, 8, mode);
+  let mut levels = [[0i16; 16]; 4];
+
+  for (index, block_levels) in levels.iter_mut().enumerate() {
+    let block = (index % 2 * 4, index / 2 * 4);
+
+    let coefficients = forward_dct(&source.residual(origin, 8, &prediction, block));
+    let mut residual = quantizer.quantize(&coefficients, 0, block_levels);
+
+    inverse_dct(&mut residual);
+    reconstructed.reconstruct(origin, 8, &prediction, block, &residual);
+  }
+
+  levels
+}
+
+/// Encodes `image` as a VP8 key frame (the payload of a `VP8 ` chunk) with `quality` from 0 to 100.
+///
+/// The alpha channel is ignored. Dimensions must not exceed 16383 pixels.
+pub(crate) fn encode_vp8(image: &RgbaImage, quality: u8) -> Vec<u8> {
+  let mb_width = image.width().div_ceil(16) as usize;
+  let mb_height = image.height().div_ceil(16) as usize;
+
+  let q_index = quantizer_index(quality);
+
+  let y1 = Quantizer {
+    dc: DC_QUANT[q_index] as i32,
+    ac: AC_QUANT[q_index] as i32,
+  };
+  let y2 = Quantizer {
+    dc: DC_QUANT[q_index] as i32 * 2,
+    ac: (AC_QUANT[q_index] as i32 * 155 / 100).max(8),
+  };
+  let uv = Quantizer {
+    dc: (DC_QUANT[q_index] as i32).min(132),
+    ac: AC_QUANT[q_index] as i32,
+  };
+
+  let source = rgba_to_yuv(image, mb_width, mb_height);
+  let mut reconstructed = [
+    Plane::new(mb_width * 16, mb_height * 16),
+    Plane::new(mb_width * 8, mb_height * 8),
+    Plane::new(mb_width * 8, mb_height * 8),
+  ];
+
+  let mut macroblocks = Vec::with_capacity(mb_width * mb_height);
+
+  for mb_y in 0..mb_height {
+    for mb_x in 0..mb_width {
+      let origin = (mb_x * 16, mb_y * 16);
+
+      let luma_mode = choose_mode(&[(&source[0], &reconstructed[0])], origin.0, origin.1, 16);
+      let prediction = reconstructed[0].predict(origin.0, origin.1, 16, luma_mode);
+
+      let mut y_levels = [[0i16; 16]; 16];
+      let mut y_coefficients = [[0i32; 16]; 16];
+      let mut dc_coefficients = [0i32; 16];
+
+      for (index, coefficients) in y_coefficients.iter_mut().enumerate() {
+        let block = (index % 4 * 4, index / 4 * 4);
+
+        *coefficients = forward_dct(&source[0].residual(origin, 16, &prediction, block));
+        dc_coefficients[index] = coefficients[0];
+      }
+
+      let mut y2_levels = [0i16; 16];
+      let mut dc_values = y2.quantize(&forward_wht(&dc_coefficients), 0, &mut y2_levels);
+      inverse_wht(&mut dc_values);
+
+      for (index, (coefficients, levels)) in y_coefficients.iter().zip(&mut y_levels).enumerate() {
+        let block = (index % 4 * 4, index / 4 * 4);
+
+        let mut residual = y1.quantize(coefficients, 1, levels);
+        residual[0] = dc_values[index];
+
+        inverse_dct(&mut residual);
+        reconstructed[0].reconstruct(origin, 16, &prediction, block, &residual);
+      }
+
+      let chroma_origin = (mb_x * 8, mb_y * 8);
+      let [_, reconstructed_u, reconstructed_v] = &mut reconstructed;
+
+      let chroma_mode = choose_mode(
+        &[
+          (&source[1], &*reconstructed_u),
+          (&source[2], &*reconstructed_v),
+        ],
+        chroma_origin.0,
+        chroma_origin.1,
+        8,
+      );
+
+      let u_levels = encode_chroma(&source[1], reconstructed_u, chroma_origin, chroma_mode, uv);
+      let v_levels = encode_chroma(&source[2], reconstructed_v, chroma_origin, chroma_mode, uv);
+
+      macroblocks.push(Macroblock {
+        luma_mode,
+        chroma_mode,
+        y2: y2_levels,
+        y: y_levels,
+        u: u_levels,
+        v: v_levels,
+      });
+    }
+  }
+
+  let mut counts: TokenCounts = [[[[[0; 2]; 11]; 3]; 8]; 4];
+  tokenize_frame(&mut counts, &macroblocks, mb_width);
+
+  let probs = adapt_token_probs(&counts);
+
+  let skipped = macroblocks.iter().filter(|mb| mb.is_empty()).count();
+  let skip_prob = ((macroblocks.len() - skipped) * 255 / macroblocks.len()).clamp(1, 254) as u8;
+
+  // First partition: frame header and macroblock modes.
+  let mut header = BoolEncoder::new();
+
+  header.put_flag(false); // color space
+  header.put_flag(false); // clamping type
+  header.put_flag(false); // segmentation
+  header.put_flag(false); // filter type
+  header.put_literal(loop_filter_level(q_index), 6);
+  header.put_literal(0, 3); // sharpness
+  header.put_flag(false); // loop filter adjustments
+  header.put_literal(0, 2); // one token partition
+  header.put_literal(q_index as u32, 7);
+
+  for _ in 0..5 {
+    header.put_flag(false); // quantizer deltas
+  }
+
+  header.put_flag(false); // refresh entropy probs
+
+  for (plane, bands) in probs.iter().enumerate() {
+    for (band, contexts) in bands.iter().enumerate() {
+      for (context, nodes) in contexts.iter().enumerate() {
+        for (node, &prob) in nodes.iter().enumerate() {
+          let update_prob = COEFF_UPDATE_PROBS[plane][band][context][node];
+          let updated = prob != COEFF_PROBS[plane][band][context][node];
+
+          header.put(updated, update_prob);
+
+          if updated {
+            header.put_literal(prob as u32, 8);
+          }
+        }
+      }
+    }
+  }
+
+  header.put_flag(true); // macroblocks may skip coefficients
+  header.put_literal(skip_prob as u32, 8);
+
+  for macroblock in &macroblocks {
+    header.put(macroblock.is_empty(), skip_prob);
+    macroblock.luma_mode.write_luma(&mut header);
+    macroblock.chroma_mode.write_chroma(&mut header);
+  }
+
+  let first_partition = header.finish();
+
+  let mut tokens = BoolEncoder::new();
+  tokenize_frame(
+    &mut TokenWriter {
+      encoder: &mut tokens,
+      probs: &probs,
+    },
+    &macroblocks,
+    mb_width,
+  );
+
+  let token_partition = tokens.finish();
+
+  let mut output = Vec::with_capacity(10 + first_partition.len() + token_partition.len());
+
+  // Key frame, version 0, shown.
+  let frame_tag = (1 << 4) | ((first_partition.len() as u32) << 5);
+
+  output.extend_from_slice(&frame_tag.to_le_bytes()[..3]);
+  output.extend_from_slice(&[0x9d, 0x01, 0x2a]);
+  output.extend_from_slice(&(image.width() as u16).to_le_bytes());
+  output.extend_from_slice(&(image.height() as u16).to_le_bytes());
+  output.extend_from_slice(&first_partition);
+  output.extend_from_slice(&token_partition);
+
+  output
+}
+
+/// Walks the coefficient tokens of all macroblocks in decoding order, tracking the non-zero contexts.
+fn tokenize_frame(sink: &mut impl TokenSink, macroblocks: &[Macroblock], mb_width: usize) {
+  // Y2, then four luma and two of each chroma columns per macroblock.
+  let mut above = vec![[false; 9]; mb_width];
+
+  for row in macroblocks.chunks(mb_width) {
+    let mut left = [false; 9];
+
+    for (macroblock, above) in row.iter().zip(&mut above) {
+      if macroblock.is_empty() {
+        left = [false; 9];
+        *above = [false; 9];
+        continue;
+      }
+
+      let context = |above: bool, left: bool| above as usize + left as usize;
+
+      let has_levels = tokenize_block(
+        sink,
+        &macroblock.y2,
+        PLANE_Y2,
+        0,
+        context(above[0], left[0]),
+      );
+      above[0] = has_levels;
+      left[0] = has_levels;
+
+      for (index, levels) in macroblock.y.iter().enumerate() {
+        let (x, y) = (1 + index % 4, 1 + index / 4);
+
+        let has_levels = tokenize_block(
+          sink,
+          levels,
+          PLANE_Y_AFTER_Y2,
+          1,
+          context(above[x], left[y]),
+        );
+        above[x] = has_levels;
+        left[y] = has_levels;
+      }
+
+      for (offset, blocks) in [(5, &macroblock.u), (7, &macroblock.v)] {
+        for (index, levels) in blocks.iter().enumerate() {
+          let (x, y) = (offset + index % 2, offset + index / 2);
+
+          let has_levels =
+            tokenize_block(sink, levels, PLANE_CHROMA, 0, context(above[x], left[y]));
+          above[x] = has_levels;
+          left[y] = has_levels;
+        }
+      }
+    }
+  }
+}
+
+/// Cost in bits of coding `counts` with `prob`.
+fn token_cost(counts: [u32; 2], prob: u8) -> f32 {
+  let zero = prob as f32 / 256.0;
+
+  -(counts[0] as f32 * zero.log2() + counts[1] as f32 * (1.0 - zero).log2())
+}
+
+/// Replaces the default token probabilities where the frame statistics save more than the update costs.
+fn adapt_token_probs(counts: &TokenCounts) -> TokenProbs {
+  let mut probs = COEFF_PROBS;
+
+  for (plane, bands) in counts.iter().enumerate() {
+    for (band, contexts) in bands.iter().enumerate() {
+      for (context, nodes) in contexts.iter().enumerate() {
+        for (node, &counts) in nodes.iter().enumerate() {
+          let total = counts[0] + counts[1];
+
+          if total == 0 {
+            continue;
+          }
+
+          let default_prob = COEFF_PROBS[plane][band][context][node];
+          let new_prob =
+            ((counts[0] as u64 * 255 + total as u64 / 2) / total as u64).clamp(1, 255) as u8;
+
+          let update_prob = COEFF_UPDATE_PROBS[plane][band][context][node];
+          let update_cost = 8.0 + token_cost([0, 1], update_prob) - token_cost([1, 0], update_prob);
+
+          if token_cost(counts, default_prob) - token_cost(counts, new_prob) > update_cost {
+            probs[plane][band][context][node] = new_prob;
+          }
+        }
+      }
+    }
+  }
+
+  probs
+}
+
+/// Loop filter strength for the quantizer index, smoothing block edges more as quantization gets coarser.
+fn loop_filter_level(q_index: usize) -> u32 {
+  (AC_QUANT[q_index] as u32 / 4).min(63)
+}
+
+#[cfg(test)]
+mod tests {
+  use image::Rgba;
+
+  use super::*;
+  use crate::TestResult;
+
+  /// Wraps a VP8 bitstream into a simple WebP file.
+  fn wrap_webp(vp8: &[u8]) -> Vec<u8> {
+    let padding = vp8.len() % 2;
+
+    let mut webp = b"RIFF".to_vec();
+    webp.extend_from_slice(&(4 + 8 + vp8.len() as u32 + padding as u32).to_le_bytes());
+    webp.extend_from_slice(b"WEBPVP8 ");
+    webp.extend_from_slice(&(vp8.len() as u32).to_le_bytes());
+    webp.extend_from_slice(vp8);
+    webp.resize(webp.len() + padding, 0);
+
+    webp
+  }
+
+  fn psnr(expected: &RgbaImage, actual: &[u8]) -> f64 {
+    let squared_error: f64 = expected
+      .pixels()
+      .flat_map(|pixel| pixel.0.into_iter().take(3))
+      .zip(actual)
+      .map(|(expected, &actual)| (expected as f64 - actual as f64).powi(2))
+      .sum();
+
+    let mse = squared_error / actual.len() as f64;
+
+    10.0 * (255.0 * 255.0 / mse).log10()
+  }
+
+  #[test]
+  fn test_dct_round_trip() {
+    let input: [i32; 16] = std::array::from_fn(|index| (index as i32 * 37 % 101) - 50);
+
+    let mut block = forward_dct(&input);
+    inverse_dct(&mut block);
+
+    for (actual, expected) in block.iter().zip(input) {
+      assert!((actual - expected).abs() <= 1, "{actual} != {expected}");
+    }
+  }
+
+  #[test]
+  fn test_wht_round_trip() {
+    let input: [i32; 16] = std::array::from_fn(|index| (index as i32 * 53 % 97) * 16 - 700);
+
+    let mut block = forward_wht(&input);
+    inverse_wht(&mut block);
+
+    for (actual, expected) in block.iter().zip(input) {
+      assert!((actual - expected).abs() <= 1, "{actual} != {expected}");
+    }
+  }
+
+  #[test]
+  fn test_quantizer_index() {
+    assert_eq!(quantizer_index(100), 0);
+    assert_eq!(quantizer_index(0), 127);
+
+    for quality in 1..=100 {
+      assert!(quantizer_index(quality) <= quantizer_index(quality - 1));
+    }
+  }
+
+  #[test]
+  fn test_quantize_dequantizes_levels() {
+    let quantizer = Quantizer { dc: 8, ac: 10 };
+    let coefficients: [i32; 16] = std::array::from_fn(|index| index as i32 * 7 - 40);
+
+    let mut levels = [0; 16];
+    let dequantized = quantizer.quantize(&coefficients, 1, &mut levels);
+
+    assert_eq!(levels[0], 0);
+    assert_eq!(dequantized[0], 0);
+
+    for (index, &position) in ZIGZAG.iter().enumerate().skip(1) {
+      assert_eq!(dequantized[position], levels[index] as i32 * quantizer.ac);
+      assert!((dequantized[position] - coefficients[position]).abs() <= quantizer.ac);
+    }
+  }
+
+  #[test]
+  fn test_encode_vp8_decodes() -> TestResult {
+    // Not a multiple of the macroblock size, so the edges are padded.
+    let image = RgbaImage::from_fn(37, 21, |x, y| {
+      Rgba([(x * 6) as u8, (y * 12) as u8, ((x + y) * 4) as u8, 255])
+    });
+
+    for (quality, min_psnr) in [(50, 30.0), (90, 38.0)] {
+      let webp = wrap_webp(&encode_vp8(&image, quality));
+
+      let mut decoder = image_webp::WebPDecoder::new(std::io::Cursor::new(webp))?;
+      assert_eq!(decoder.dimensions(), (37, 21));
+
+      let mut rgb = vec![0; decoder.output_buffer_size().ok_or("image too large")?];
+      decoder.read_image(&mut rgb)?;
+
+      let psnr = psnr(&image, &rgb);
+      assert!(psnr > min_psnr, "PSNR {psnr} at quality {quality}");
+    }
+
+    Ok(())
+  }
+}
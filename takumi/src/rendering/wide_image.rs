@@ -0,0 +1,206 @@
+use image::{GenericImage, GenericImageView, Rgba, RgbaImage};
+
+use crate::rendering::{ColorInterpolation, CompositeTarget, Rgba16Image};
+
+/// An image with premultiplied `f32` channels in the color space of a [`ColorInterpolation`].
+///
+/// It is the working buffer of rasterizing when 8-bit channels would lose precision between draws,
+/// drawn colors are composited into it without rounding and it is only converted once everything is drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WideImage {
+  width: u32,
+  height: u32,
+  pixels: Vec<[f32; 4]>,
+}
+
+impl WideImage {
+  /// Creates a transparent image.
+  pub(crate) fn new(width: u32, height: u32) -> Self {
+    Self {
+      width,
+      height,
+      pixels: vec![[0.0; 4]; width as usize * height as usize],
+    }
+  }
+
+  /// Creates an image with the premultiplied pixels returned by `f`.
+  pub(crate) fn from_fn(width: u32, height: u32, mut f: impl FnMut(u32, u32) -> [f32; 4]) -> Self {
+    let mut pixels = Vec::with_capacity(width as usize * height as usize);
+
+    for y in 0..height {
+      for x in 0..width {
+        pixels.push(f(x, y));
+      }
+    }
+
+    Self {
+      width,
+      height,
+      pixels,
+    }
+  }
+
+  /// Converts an 8-bit image with straight alpha.
+  pub(crate) fn from_image<I: GenericImageView<Pixel = Rgba<u8>>>(
+    image: &I,
+    interpolation: ColorInterpolation,
+  ) -> Self {
+    let (width, height) = image.dimensions();
+
+    Self::from_fn(width, height, |x, y| {
+      interpolation.premultiply(image.get_pixel(x, y))
+    })
+  }
+
+  pub(crate) fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub(crate) fn height(&self) -> u32 {
+    self.height
+  }
+
+  #[inline(always)]
+  fn index(&self, x: u32, y: u32) -> usize {
+    y as usize * self.width as usize + x as usize
+  }
+
+  #[inline(always)]
+  pub(crate) fn get_pixel(&self, x: u32, y: u32) -> [f32; 4] {
+    self.pixels[self.index(x, y)]
+  }
+
+  #[inline(always)]
+  pub(crate) fn get_pixel_mut(&mut self, x: u32, y: u32) -> &mut [f32; 4] {
+    let index = self.index(x, y);
+
+    &mut self.pixels[index]
+  }
+
+  /// The pixels in rows from the top-left corner.
+  pub(crate) fn pixels(&self) -> &[[f32; 4]] {
+    &self.pixels
+  }
+
+  pub(crate) fn pixels_mut(&mut self) -> &mut [[f32; 4]] {
+    &mut self.pixels
+  }
+
+  /// Converts the pixels to 8-bit channels with straight alpha and writes them into `target`.
+  pub(crate) fn write_into<I: GenericImage<Pixel = Rgba<u8>>>(
+    &self,
+    target: &mut I,
+    interpolation: ColorInterpolation,
+  ) {
+    for y in 0..self.height {
+      for x in 0..self.width {
+        target.put_pixel(x, y, interpolation.unpremultiply(self.get_pixel(x, y)));
+      }
+    }
+  }
+
+  /// Converts the pixels to 8-bit channels with straight alpha.
+  pub(crate) fn to_rgba8(&self, interpolation: ColorInterpolation) -> RgbaImage {
+    RgbaImage::from_fn(self.width, self.height, |x, y| {
+      interpolation.unpremultiply(self.get_pixel(x, y))
+    })
+  }
+
+  /// Converts the pixels to 16-bit channels with straight alpha.
+  pub(crate) fn to_rgba16(&self, interpolation: ColorInterpolation) -> Rgba16Image {
+    Rgba16Image::from_fn(self.width, self.height, |x, y| {
+      interpolation.unpremultiply16(self.get_pixel(x, y))
+    })
+  }
+}
+
+/// Composites premultiplied `top` onto premultiplied `bottom` with source-over.
+#[inline(always)]
+pub(crate) fn blend_premultiplied_pixel(bottom: &mut [f32; 4], top: [f32; 4]) {
+  let inverse_alpha = 1.0 - top[3];
+
+  for (bottom, top) in bottom.iter_mut().zip(top) {
+    *bottom = top + *bottom * inverse_alpha;
+  }
+}
+
+impl CompositeTarget for WideImage {
+  fn dimensions(&self) -> (u32, u32) {
+    (self.width, self.height)
+  }
+
+  #[inline(always)]
+  fn blend_pixel(&mut self, x: u32, y: u32, color: Rgba<u8>, interpolation: ColorInterpolation) {
+    if color.0[3] == 0 {
+      return;
+    }
+
+    blend_premultiplied_pixel(self.get_pixel_mut(x, y), interpolation.premultiply(color));
+  }
+
+  #[inline(always)]
+  fn put_opaque(&mut self, x: u32, y: u32, color: Rgba<u8>, interpolation: ColorInterpolation) {
+    *self.get_pixel_mut(x, y) = interpolation.premultiply(color);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use image::Rgba;
+
+  use super::WideImage;
+  use crate::rendering::{ColorInterpolation, CompositeTarget};
+
+  #[test]
+  fn test_converts_back_to_the_same_colors() {
+    for interpolation in [ColorInterpolation::Srgb, ColorInterpolation::LinearRgb] {
+      for value in 0..=u8::MAX {
+        let color = Rgba([value, 255 - value, value / 2, 255]);
+        let mut image = WideImage::new(1, 1);
+
+        image.put_opaque(0, 0, color, interpolation);
+
+        assert_eq!(image.to_rgba8(interpolation).get_pixel(0, 0), &color);
+      }
+    }
+  }
+
+  #[test]
+  fn test_keeps_precision_between_draws() {
+    let interpolation = ColorInterpolation::LinearRgb;
+    let mut image = WideImage::new(1, 1);
+
+    image.put_opaque(0, 0, Rgba([0, 0, 0, 255]), interpolation);
+
+    for _ in 0..16 {
+      image.blend_pixel(0, 0, Rgba([255, 255, 255, 1]), interpolation);
+    }
+
+    let linear = 1.0 - (1.0 - 1.0 / 255.0_f32).powi(16);
+    let expected = (interpolation.encode_exact(linear) * 255.0).round() as u8;
+
+    assert_eq!(
+      image.to_rgba8(interpolation).get_pixel(0, 0),
+      &Rgba([expected, expected, expected, 255])
+    );
+  }
+
+  #[test]
+  fn test_keeps_precision_in_16_bits() {
+    let interpolation = ColorInterpolation::Srgb;
+    let mut image = WideImage::new(1, 1);
+
+    image.put_opaque(0, 0, Rgba([0, 0, 0, 255]), interpolation);
+    image.blend_pixel(0, 0, Rgba([255, 255, 255, 100]), interpolation);
+    image.blend_pixel(0, 0, Rgba([255, 255, 255, 100]), interpolation);
+
+    let value = 1.0 - (1.0 - 100.0 / 255.0_f32).powi(2);
+    let expected = (value * u16::MAX as f32).round() as u16;
+
+    assert_ne!(expected % 257, 0);
+    assert_eq!(
+      image.to_rgba16(interpolation).get_pixel(0, 0),
+      &Rgba([expected, expected, expected, u16::MAX])
+    );
+  }
+}
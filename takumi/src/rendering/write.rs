@@ -1,17 +1,36 @@
-use std::{borrow::Cow, io::Write};
+use std::{
+  borrow::Cow,
+  io::{Seek, SeekFrom, Write},
+};
 
 use rustc_hash::FxHashMap;
 
-use image::{ExtendedColorType, ImageEncoder, ImageFormat, RgbaImage, codecs::jpeg::JpegEncoder};
-use png::{BitDepth, ColorType, Compression, Filter};
+use image::{
+  ExtendedColorType, ImageEncoder, ImageError, ImageFormat, Rgba, RgbaImage,
+  codecs::jpeg::JpegEncoder, imageops,
+};
+use png::{BitDepth, BlendOp, ColorType, Compression, DisposeOp, Filter, SrgbRenderingIntent};
 use serde::Deserialize;
 
 use image_webp::WebPEncoder;
 
+#[cfg(feature = "avif")]
+use image::codecs::avif::AvifEncoder;
+
 #[cfg(feature = "rayon")]
 use rayon::prelude::*;
 
-use crate::{Error::IoError, rendering::quantize_alpha};
+use crate::{
+  Error::IoError,
+  rendering::{
+    IccProfile, ImageMetadata, Rgba16Image, encode_vp8, insert_jpeg_xmp, is_latin1, quantize_alpha,
+  },
+};
+#[cfg(feature = "pdf")]
+use crate::{
+  layout::node::Node,
+  rendering::{PdfDocument, RenderOptions},
+};
 
 /// Output format for rendered images.
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
@@ -26,6 +45,10 @@ pub enum ImageOutputFormat {
 
   /// JPEG image format, lossy and does not support transparency.
   Jpeg,
+
+  /// AVIF image format, gives the smallest lossy output with transparency but is the slowest to encode.
+  #[cfg(feature = "avif")]
+  Avif,
 }
 
 impl ImageOutputFormat {
@@ -35,6 +58,8 @@ impl ImageOutputFormat {
       ImageOutputFormat::WebP => "image/webp",
       ImageOutputFormat::Png => "image/png",
       ImageOutputFormat::Jpeg => "image/jpeg",
+      #[cfg(feature = "avif")]
+      ImageOutputFormat::Avif => "image/avif",
     }
   }
 }
@@ -45,10 +70,33 @@ impl From<ImageOutputFormat> for ImageFormat {
       ImageOutputFormat::WebP => Self::WebP,
       ImageOutputFormat::Png => Self::Png,
       ImageOutputFormat::Jpeg => Self::Jpeg,
+      #[cfg(feature = "avif")]
+      ImageOutputFormat::Avif => Self::Avif,
     }
   }
 }
 
+/// Renders each node as a page of a PDF document, and writes the document to `destination`.
+///
+/// Pages are sized by their viewport, with one pixel being 1/96 inch like in CSS.
+/// Text is embedded with subsetted fonts so it stays selectable,
+/// and effects without a vector equivalent (filters, masks, shadows) are embedded as images.
+#[cfg(feature = "pdf")]
+pub fn write_pdf<'g, N: Node<N>, T: Write>(
+  pages: impl IntoIterator<Item = RenderOptions<'g, N>>,
+  destination: &mut T,
+) -> Result<(), crate::Error> {
+  let mut document = PdfDocument::default();
+
+  for page in pages {
+    document.add_page(page)?;
+  }
+
+  destination.write_all(&document.finish()?)?;
+
+  Ok(())
+}
+
 /// Represents a single frame of an animated image.
 #[derive(Debug, Clone)]
 pub struct AnimationFrame {
@@ -69,7 +117,7 @@ impl AnimationFrame {
 const U24_MAX: u32 = 0xffffff;
 
 // Strip alpha channel into a tightly packed RGB buffer
-fn strip_alpha_channel(image: &RgbaImage) -> Vec<u8> {
+pub(crate) fn strip_alpha_channel(image: &RgbaImage) -> Vec<u8> {
   let raw = image.as_raw();
 
   let pixel_count = raw.len() / 4 * 3;
@@ -97,25 +145,76 @@ fn has_any_alpha_pixel(image: &RgbaImage) -> bool {
   }
 }
 
-/// Palette data for indexed PNG.
-struct PaletteData {
+/// Creates a PNG encoder that writes the color profile and text entries of `metadata`.
+fn png_encoder<W: Write>(
+  destination: W,
+  width: u32,
+  height: u32,
+  metadata: Option<&ImageMetadata>,
+) -> Result<png::Encoder<'static, W>, crate::Error> {
+  let mut info = png::Info::with_size(width, height);
+
+  // sRGB is marked with a `sRGB` chunk instead of a whole profile.
+  if let Some(metadata) = metadata
+    && metadata.icc_profile != Some(IccProfile::Srgb)
+    && let Some(profile) = metadata.icc_profile_bytes()?
+  {
+    info.icc_profile = Some(Cow::Owned(profile.into_owned()));
+  }
+
+  let mut encoder = png::Encoder::with_info(destination, info)?;
+
+  let Some(metadata) = metadata else {
+    return Ok(encoder);
+  };
+
+  if metadata.icc_profile == Some(IccProfile::Srgb) {
+    encoder.set_source_srgb(SrgbRenderingIntent::Perceptual);
+  }
+
+  for (keyword, text) in &metadata.text {
+    if is_latin1(keyword) && is_latin1(text) {
+      encoder.add_text_chunk(keyword.clone(), text.clone())?;
+    } else {
+      encoder.add_itxt_chunk(keyword.clone(), text.clone())?;
+    }
+  }
+
+  Ok(encoder)
+}
+
+/// Palette for indexed PNG.
+struct Palette {
   /// RGB palette, 3 bytes per color.
   palette: Vec<u8>,
   /// Alpha channel for palette entries, 1 byte per color.
   trns: Vec<u8>,
-  /// Indexed pixel data (packed according to bit_depth).
-  indices: Vec<u8>,
+  /// Maps the colors (with quantized alpha) to palette indices.
+  color_map: FxHashMap<[u8; 4], u8>,
   /// Optimal bit depth for this palette (1, 2, 4, or 8).
   bit_depth: BitDepth,
+  /// Quantizes the alpha of the colors before they are looked up.
+  quantize: fn(u8) -> u8,
 }
 
-/// Try to collect a palette from the image.
+/// Quantizes the alpha of APNG frames like [`quantize_alpha`], but keeps opaque pixels opaque
+/// so the frame regions can be blended over the previous frame.
+fn quantize_frame_alpha(alpha: u8) -> u8 {
+  if alpha == u8::MAX {
+    return alpha;
+  }
+
+  quantize_alpha(alpha)
+}
+
+/// Collects the unique colors of the image with their first occurrence position.
 /// Returns None if there are more than MAX_PALETTE_SIZE unique colors.
-fn try_collect_palette(image: &RgbaImage) -> Option<PaletteData> {
-  // Pass 1: Collect unique colors with their first occurrence position
-  // This preserves spatial locality for better PNG filter compression
+fn collect_color_positions(
+  image: &RgbaImage,
+  quantize: fn(u8) -> u8,
+) -> Option<FxHashMap<[u8; 4], usize>> {
   #[cfg(feature = "rayon")]
-  let color_positions: FxHashMap<[u8; 4], usize> = {
+  {
     use rayon::prelude::*;
 
     let map = image
@@ -125,7 +224,7 @@ fn try_collect_palette(image: &RgbaImage) -> Option<PaletteData> {
         || FxHashMap::with_capacity_and_hasher(256, Default::default()),
         |mut acc, (x, y, pixel)| {
           let mut rgba: [u8; 4] = pixel.0;
-          rgba[3] = quantize_alpha(rgba[3]);
+          rgba[3] = quantize(rgba[3]);
 
           // Only insert if not seen before (keeps first occurrence)
           acc
@@ -153,16 +252,16 @@ fn try_collect_palette(image: &RgbaImage) -> Option<PaletteData> {
       return None;
     }
 
-    map
-  };
+    Some(map)
+  }
 
   #[cfg(not(feature = "rayon"))]
-  let color_positions: FxHashMap<[u8; 4], usize> = {
+  {
     let mut map = FxHashMap::with_capacity_and_hasher(256, Default::default());
 
     for (idx, pixel) in image.pixels().enumerate() {
       let mut rgba: [u8; 4] = pixel.0;
-      rgba[3] = quantize_alpha(rgba[3]);
+      rgba[3] = quantize(rgba[3]);
 
       if !map.contains_key(&rgba) {
         if map.len() >= 256 {
@@ -172,11 +271,30 @@ fn try_collect_palette(image: &RgbaImage) -> Option<PaletteData> {
       }
     }
 
-    map
-  };
+    Some(map)
+  }
+}
+
+/// Try to collect a palette shared by all the images, with the alpha of the colors quantized by `quantize`.
+/// Returns None if there are more than MAX_PALETTE_SIZE unique colors.
+fn try_collect_palette(images: &[&RgbaImage], quantize: fn(u8) -> u8) -> Option<Palette> {
+  // Pass 1: Collect unique colors with their first occurrence (image, position)
+  // This preserves spatial locality for better PNG filter compression
+  let mut color_positions: FxHashMap<[u8; 4], (usize, usize)> =
+    FxHashMap::with_capacity_and_hasher(256, Default::default());
+
+  for (image_index, image) in images.iter().enumerate() {
+    for (color, pos) in collect_color_positions(image, quantize)? {
+      color_positions.entry(color).or_insert((image_index, pos));
+    }
+
+    if color_positions.len() > 256 {
+      return None;
+    }
+  }
 
   // Sort colors by first occurrence position (preserves spatial locality)
-  let mut sorted_colors: Vec<([u8; 4], usize)> = color_positions.into_iter().collect();
+  let mut sorted_colors: Vec<([u8; 4], (usize, usize))> = color_positions.into_iter().collect();
   sorted_colors.sort_unstable_by_key(|(_, pos)| *pos);
 
   // Build palette and color map
@@ -194,120 +312,551 @@ fn try_collect_palette(image: &RgbaImage) -> Option<PaletteData> {
     trns.push(rgba[3]);
   }
 
-  // Determine optimal bit depth based on palette size
-  let (bit_depth, bits_per_pixel) = match sorted_colors.len() {
-    0..=2 => (BitDepth::One, 1),
-    3..=4 => (BitDepth::Two, 2),
-    5..=16 => (BitDepth::Four, 4),
-    _ => (BitDepth::Eight, 8),
-  };
+  Some(Palette {
+    palette,
+    trns,
+    color_map,
+    bit_depth: palette_bit_depth(sorted_colors.len()),
+    quantize,
+  })
+}
+
+/// Returns the smallest bit depth that can index `colors` palette entries.
+fn palette_bit_depth(colors: usize) -> BitDepth {
+  match colors {
+    0..=2 => BitDepth::One,
+    3..=4 => BitDepth::Two,
+    5..=16 => BitDepth::Four,
+    _ => BitDepth::Eight,
+  }
+}
+
+impl Palette {
+  /// Whether any palette entry is not fully opaque, in which case a tRNS chunk is needed.
+  fn has_alpha(&self) -> bool {
+    self.trns.iter().any(|&a| a != u8::MAX)
+  }
+
+  /// Sets the color type, bit depth and palette of the PNG.
+  fn configure<W: Write>(&self, encoder: &mut png::Encoder<'_, W>) {
+    encoder.set_color(ColorType::Indexed);
+    encoder.set_depth(self.bit_depth);
+    encoder.set_palette(self.palette.clone());
+
+    if self.has_alpha() {
+      encoder.set_trns(self.trns.clone());
+    }
+  }
+
+  /// Pass 2: Build indices of the image (packed according to bit depth)
+  fn pack_indices(&self, image: &RgbaImage) -> Vec<u8> {
+    let indices: Vec<u8> = image
+      .pixels()
+      .map(|pixel| {
+        let mut rgba: [u8; 4] = pixel.0;
+        rgba[3] = (self.quantize)(rgba[3]);
+
+        self.color_map.get(&rgba).copied().unwrap_or(0)
+      })
+      .collect();
+
+    self.pack(&indices, image.width())
+  }
 
-  // Pass 2: Build indices (packed according to bit depth)
+  /// Packs one index per pixel into rows of `width` pixels according to the bit depth.
+  fn pack(&self, indices: &[u8], width: u32) -> Vec<u8> {
+    let bits_per_pixel = match self.bit_depth {
+      BitDepth::One => 1,
+      BitDepth::Two => 2,
+      BitDepth::Four => 4,
+      _ => 8,
+    };
+
+    if bits_per_pixel == 8 {
+      return indices.to_vec();
+    }
+
+    let width = width as usize;
+    let pixels_per_byte = 8 / bits_per_pixel;
+    let row_bytes = width.div_ceil(pixels_per_byte);
+
+    let mut packed: Vec<u8> = Vec::with_capacity(row_bytes * (indices.len() / width.max(1)));
+
+    for row in indices.chunks_exact(width) {
+      let mut current_byte: u8 = 0;
+      let mut bit_offset = 8 - bits_per_pixel;
+
+      for &idx in row {
+        current_byte |= idx << bit_offset;
+
+        if bit_offset == 0 {
+          packed.push(current_byte);
+          current_byte = 0;
+          bit_offset = 8 - bits_per_pixel;
+        } else {
+          bit_offset -= bits_per_pixel;
+        }
+      }
+
+      // Push remaining byte if row doesn't align to byte boundary
+      if bit_offset != 8 - bits_per_pixel {
+        packed.push(current_byte);
+      }
+    }
+
+    packed
+  }
+
+  /// Reduces the colors of the image to `max_colors` with NeuQuant,
+  /// returning the palette and the packed indices of the pixels.
+  fn quantize(image: &RgbaImage, max_colors: usize, dither: bool) -> (Self, Vec<u8>) {
+    let quantizer = color_quant::NeuQuant::new(NEUQUANT_SAMPLE_FACTOR, max_colors, image.as_raw());
+    let colors = quantizer.color_map_rgba();
+
+    let palette = Self {
+      palette: colors
+        .chunks_exact(4)
+        .flat_map(|color| [color[0], color[1], color[2]])
+        .collect(),
+      trns: colors.chunks_exact(4).map(|color| color[3]).collect(),
+      color_map: FxHashMap::default(),
+      bit_depth: palette_bit_depth(colors.len() / 4),
+      quantize: quantize_alpha,
+    };
+
+    let indices: Vec<u8> = if dither {
+      dither_png_indices(image, &quantizer)
+    } else {
+      image
+        .pixels()
+        .map(|pixel| quantizer.index_of(&pixel.0) as u8)
+        .collect()
+    };
+
+    let packed = palette.pack(&indices, image.width());
+
+    (palette, packed)
+  }
+}
+
+/// Maps the pixels to palette indices, diffusing the quantization error with Floyd-Steinberg dithering.
+fn dither_png_indices(image: &RgbaImage, quantizer: &color_quant::NeuQuant) -> Vec<u8> {
   let width = image.width() as usize;
-  let pixels_per_byte = 8 / bits_per_pixel;
-  let row_bytes = width.div_ceil(pixels_per_byte);
+  let mut indices = Vec::with_capacity(width * image.height() as usize);
 
-  let mut indices: Vec<u8> = Vec::with_capacity(row_bytes * image.height() as usize);
+  // Errors for the current and the next row, padded by one pixel on each side.
+  let mut current_errors = vec![[0.0f32; 4]; width + 2];
+  let mut next_errors = vec![[0.0f32; 4]; width + 2];
 
   for row in image.rows() {
-    let mut current_byte: u8 = 0;
-    let mut bit_offset = 8 - bits_per_pixel;
-
-    for pixel in row {
-      let mut rgba: [u8; 4] = pixel.0;
-      rgba[3] = quantize_alpha(rgba[3]);
+    for (x, pixel) in row.enumerate() {
+      let error = current_errors[x + 1];
+      let color: [f32; 4] =
+        std::array::from_fn(|channel| (pixel[channel] as f32 + error[channel]).clamp(0.0, 255.0));
 
-      let idx = color_map.get(&rgba).copied().unwrap_or(0);
+      let index = quantizer.index_of(&color.map(|value| value.round() as u8));
+      let quantized = quantizer.lookup(index).unwrap_or_default();
 
-      current_byte |= idx << bit_offset;
+      for channel in 0..4 {
+        let error = color[channel] - quantized[channel] as f32;
 
-      if bit_offset == 0 {
-        indices.push(current_byte);
-        current_byte = 0;
-        bit_offset = 8 - bits_per_pixel;
-      } else {
-        bit_offset -= bits_per_pixel;
+        current_errors[x + 2][channel] += error * 7.0 / 16.0;
+        next_errors[x][channel] += error * 3.0 / 16.0;
+        next_errors[x + 1][channel] += error * 5.0 / 16.0;
+        next_errors[x + 2][channel] += error / 16.0;
       }
+
+      indices.push(index as u8);
     }
 
-    // Push remaining byte if row doesn't align to byte boundary
-    if bit_offset != 8 - bits_per_pixel {
-      indices.push(current_byte);
+    std::mem::swap(&mut current_errors, &mut next_errors);
+    next_errors.fill([0.0; 4]);
+  }
+
+  indices
+}
+
+/// How much effort is spent compressing PNG output.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PngCompression {
+  /// The fastest compression, with noticeably larger files.
+  Fastest,
+  /// Fast compression with a decent ratio.
+  #[default]
+  Fast,
+  /// Balances speed and file size.
+  Balanced,
+  /// The smallest files, at a much higher cost.
+  High,
+}
+
+impl From<PngCompression> for Compression {
+  fn from(compression: PngCompression) -> Self {
+    match compression {
+      PngCompression::Fastest => Compression::Fastest,
+      PngCompression::Fast => Compression::Fast,
+      PngCompression::Balanced => Compression::Balanced,
+      PngCompression::High => Compression::High,
     }
   }
+}
 
-  Some(PaletteData {
-    palette,
-    trns,
-    indices,
-    bit_depth,
-  })
+/// The filter applied to the rows of PNG output before compression.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PngFilter {
+  /// Rows are compressed as is.
+  None,
+  /// Each byte is predicted from the pixel to the left.
+  Sub,
+  /// Each byte is predicted from the pixel above.
+  Up,
+  /// Each byte is predicted from the average of the pixels to the left and above.
+  Average,
+  /// Each byte is predicted with the Paeth predictor.
+  Paeth,
+  /// The filter is picked for each row, which is slower but gives smaller files.
+  Adaptive,
+}
+
+impl From<PngFilter> for Filter {
+  fn from(filter: PngFilter) -> Self {
+    match filter {
+      PngFilter::None => Filter::NoFilter,
+      PngFilter::Sub => Filter::Sub,
+      PngFilter::Up => Filter::Up,
+      PngFilter::Average => Filter::Avg,
+      PngFilter::Paeth => Filter::Paeth,
+      PngFilter::Adaptive => Filter::Adaptive,
+    }
+  }
+}
+
+/// Bits per channel of PNG output.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+pub enum PngBitDepth {
+  /// 8 bits per channel, with indexed color when the colors fit in a palette.
+  #[default]
+  #[serde(rename = "8")]
+  Eight,
+  /// 16 bits per channel, never indexed.
+  ///
+  /// [`write_png`] only scales the samples of an 8-bit image up. To keep the precision of
+  /// compositing, render with [`render_rgba16`](crate::rendering::render_rgba16) and write
+  /// the image with [`write_png_rgba16`].
+  #[serde(rename = "16")]
+  Sixteen,
+}
+
+/// Options for [`write_png`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PngEncodeOptions {
+  /// How much effort is spent compressing.
+  pub compression: PngCompression,
+  /// The row filter, picked from the color type and compression when `None`.
+  pub filter: Option<PngFilter>,
+  /// Reduces the image to at most this many colors (2 to 256), so it is always written with indexed color.
+  /// Without it, indexed color is only used when the image has 256 colors or fewer.
+  pub max_colors: Option<u16>,
+  /// Whether to apply Floyd-Steinberg dithering when the colors are reduced.
+  pub dither: bool,
+  /// Bits per channel.
+  pub bit_depth: PngBitDepth,
+}
+
+/// Writes a rendered image to `destination` as PNG.
+///
+/// Images are written with indexed color when their colors fit in a palette,
+/// otherwise as RGB, or RGBA when the image has transparent pixels.
+pub fn write_png<T: Write>(
+  image: &RgbaImage,
+  destination: &mut T,
+  options: PngEncodeOptions,
+  metadata: Option<&ImageMetadata>,
+) -> Result<(), crate::Error> {
+  let mut encoder = png_encoder(destination, image.width(), image.height(), metadata)?;
+  encoder.set_compression(options.compression.into());
+
+  let has_alpha = has_any_alpha_pixel(image);
+  let channels = if has_alpha { 4 } else { 3 };
+
+  if options.bit_depth == PngBitDepth::Sixteen {
+    let image_data: Vec<u8> = image
+      .pixels()
+      .flat_map(|pixel| pixel.0.into_iter().take(channels))
+      .flat_map(|value| (value as u16 * 257).to_be_bytes())
+      .collect();
+
+    return write_png_16_bit(encoder, &image_data, has_alpha, options.filter);
+  }
+
+  let max_colors = options
+    .max_colors
+    .map(|max_colors| max_colors.clamp(2, 256) as usize);
+
+  let indexed = match try_collect_palette(&[image], quantize_alpha) {
+    Some(palette) if max_colors.is_none_or(|max_colors| palette.trns.len() <= max_colors) => {
+      let indices = palette.pack_indices(image);
+
+      Some((palette, indices))
+    }
+    _ => max_colors.map(|max_colors| Palette::quantize(image, max_colors, options.dither)),
+  };
+
+  if let Some((palette, indices)) = indexed {
+    palette.configure(&mut encoder);
+
+    if let Some(filter) = options.filter {
+      encoder.set_filter(filter.into());
+    }
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&indices)?;
+    writer.finish()?;
+
+    return Ok(());
+  }
+
+  let image_data = if has_alpha {
+    Cow::Borrowed(image.as_raw())
+  } else {
+    Cow::Owned(strip_alpha_channel(image))
+  };
+
+  encoder.set_color(if has_alpha {
+    ColorType::Rgba
+  } else {
+    ColorType::Rgb
+  });
+  encoder.set_filter(options.filter.map_or(Filter::Sub, Into::into));
+
+  let mut writer = encoder.write_header()?;
+  writer.write_image_data(&image_data)?;
+  writer.finish()?;
+
+  Ok(())
+}
+
+/// Writes an image rendered with [`render_rgba16`](crate::rendering::render_rgba16) to `destination`
+/// as PNG with 16 bits per channel.
+///
+/// Images are written as RGB, or RGBA when the image has transparent pixels. Only the compression
+/// and filter of `options` are used, as 16-bit images are never indexed.
+pub fn write_png_rgba16<T: Write>(
+  image: &Rgba16Image,
+  destination: &mut T,
+  options: PngEncodeOptions,
+  metadata: Option<&ImageMetadata>,
+) -> Result<(), crate::Error> {
+  let mut encoder = png_encoder(destination, image.width(), image.height(), metadata)?;
+  encoder.set_compression(options.compression.into());
+
+  let has_alpha = image.pixels().any(|pixel| pixel[3] != u16::MAX);
+  let channels = if has_alpha { 4 } else { 3 };
+
+  let image_data: Vec<u8> = image
+    .pixels()
+    .flat_map(|pixel| pixel.0.into_iter().take(channels))
+    .flat_map(u16::to_be_bytes)
+    .collect();
+
+  write_png_16_bit(encoder, &image_data, has_alpha, options.filter)
+}
+
+/// Writes big-endian 16-bit RGB or RGBA samples.
+fn write_png_16_bit<W: Write>(
+  mut encoder: png::Encoder<'static, W>,
+  image_data: &[u8],
+  has_alpha: bool,
+  filter: Option<PngFilter>,
+) -> Result<(), crate::Error> {
+  encoder.set_color(if has_alpha {
+    ColorType::Rgba
+  } else {
+    ColorType::Rgb
+  });
+  encoder.set_depth(BitDepth::Sixteen);
+  encoder.set_filter(filter.map_or(Filter::Sub, Into::into));
+
+  let mut writer = encoder.write_header()?;
+  writer.write_image_data(image_data)?;
+  writer.finish()?;
+
+  Ok(())
+}
+
+/// Options for [`write_avif`].
+#[cfg(feature = "avif")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AvifEncodeOptions {
+  /// The quality from 1 to 100, 100 is visually lossless.
+  pub quality: u8,
+  /// The encoder speed from 1 to 10, slower speeds give smaller files.
+  pub speed: u8,
+}
+
+#[cfg(feature = "avif")]
+impl Default for AvifEncodeOptions {
+  fn default() -> Self {
+    Self {
+      quality: 75,
+      speed: 6,
+    }
+  }
+}
+
+/// Writes a rendered image to `destination` as AVIF.
+///
+/// The alpha channel is only kept when the image has transparent pixels.
+#[cfg(feature = "avif")]
+pub fn write_avif<T: Write>(
+  image: &RgbaImage,
+  destination: &mut T,
+  options: AvifEncodeOptions,
+) -> Result<(), crate::Error> {
+  let encoder = AvifEncoder::new_with_speed_quality(destination, options.speed, options.quality);
+
+  if has_any_alpha_pixel(image) {
+    encoder.write_image(
+      image.as_raw(),
+      image.width(),
+      image.height(),
+      ExtendedColorType::Rgba8,
+    )?;
+  } else {
+    encoder.write_image(
+      &strip_alpha_channel(image),
+      image.width(),
+      image.height(),
+      ExtendedColorType::Rgb8,
+    )?;
+  }
+
+  Ok(())
 }
 
 /// Writes a single rendered image to `destination` using `format`.
+///
+/// `quality` (0-100) applies to JPEG and AVIF. WebP is lossless, use [`write_image_with_options`] for lossy WebP.
 pub fn write_image<T: Write>(
   image: &RgbaImage,
   destination: &mut T,
   format: ImageOutputFormat,
   quality: Option<u8>,
 ) -> Result<(), crate::Error> {
+  write_image_with_options(
+    image,
+    destination,
+    format,
+    ImageEncodeOptions {
+      quality,
+      ..Default::default()
+    },
+  )
+}
+
+/// How WebP output is compressed.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebPCompression {
+  /// Every pixel is kept exactly.
+  #[default]
+  Lossless,
+  /// Colors are compressed with VP8 at the quality of the options, alpha is kept exactly.
+  Lossy,
+}
+
+/// Options for [`write_image_with_options`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ImageEncodeOptions<'m> {
+  /// The quality (0-100) of JPEG, AVIF and lossy WebP output, 75 when not set.
+  pub quality: Option<u8>,
+  /// How WebP output is compressed.
+  pub webp_compression: WebPCompression,
+  /// The metadata embedded into every format except AVIF.
+  pub metadata: Option<&'m ImageMetadata>,
+}
+
+/// Writes a single rendered image to `destination` using `format` and the encoding `options`.
+pub fn write_image_with_options<T: Write>(
+  image: &RgbaImage,
+  destination: &mut T,
+  format: ImageOutputFormat,
+  options: ImageEncodeOptions,
+) -> Result<(), crate::Error> {
+  let ImageEncodeOptions {
+    quality,
+    webp_compression,
+    metadata,
+  } = options;
+
   match format {
     ImageOutputFormat::Jpeg => {
       let rgb = strip_alpha_channel(image);
 
-      let encoder = JpegEncoder::new_with_quality(destination, quality.unwrap_or(75));
-      encoder.write_image(&rgb, image.width(), image.height(), ExtendedColorType::Rgb8)?;
-    }
-    ImageOutputFormat::Png => {
-      let mut encoder = png::Encoder::new(destination, image.width(), image.height());
+      let mut jpeg = Vec::new();
+      let mut encoder = JpegEncoder::new_with_quality(&mut jpeg, quality.unwrap_or(75));
 
-      if let Some(palette) = try_collect_palette(image) {
-        // Use color quantization when there are too many unique colors
-        encoder.set_color(ColorType::Indexed);
-        encoder.set_depth(palette.bit_depth);
-        encoder.set_palette(palette.palette);
+      if let Some(metadata) = metadata {
+        if let Some(profile) = metadata.icc_profile_bytes()? {
+          encoder
+            .set_icc_profile(profile.into_owned())
+            .map_err(ImageError::Unsupported)?;
+        }
 
-        if palette.trns.iter().any(|&a| a != u8::MAX) {
-          encoder.set_trns(palette.trns);
+        if let Some(exif) = metadata.exif() {
+          encoder
+            .set_exif_metadata(exif)
+            .map_err(ImageError::Unsupported)?;
         }
+      }
 
-        encoder.set_compression(Compression::Fast);
+      encoder.write_image(&rgb, image.width(), image.height(), ExtendedColorType::Rgb8)?;
 
-        // For sub-byte depths, up filter works better.
-        encoder.set_filter(match palette.bit_depth {
-          BitDepth::Eight => Filter::Sub,
-          _ => Filter::Up,
-        });
+      // The JPEG encoder has no XMP support, so the segment is added afterwards.
+      if let Some(xmp) = metadata.and_then(ImageMetadata::xmp) {
+        insert_jpeg_xmp(&mut jpeg, &xmp)?;
+      }
 
-        let mut writer = encoder.write_header()?;
-        writer.write_image_data(&palette.indices)?;
-        writer.finish()?;
-      } else {
-        // Final fallback to RGB/RGBA if quantization fails
-        let has_alpha = has_any_alpha_pixel(image);
+      destination.write_all(&jpeg)?;
+    }
+    ImageOutputFormat::Png => {
+      write_png(image, destination, PngEncodeOptions::default(), metadata)?;
+    }
+    #[cfg(feature = "avif")]
+    ImageOutputFormat::Avif => {
+      let defaults = AvifEncodeOptions::default();
+
+      write_avif(
+        image,
+        destination,
+        AvifEncodeOptions {
+          quality: quality.unwrap_or(defaults.quality),
+          ..defaults
+        },
+      )?;
+    }
+    ImageOutputFormat::WebP => {
+      if webp_compression == WebPCompression::Lossy {
+        return write_lossy_webp(image, destination, quality.unwrap_or(75), metadata);
+      }
 
-        let image_data = if has_alpha {
-          Cow::Borrowed(image.as_raw())
-        } else {
-          Cow::Owned(strip_alpha_channel(image))
-        };
+      let mut encoder = WebPEncoder::new(destination);
 
-        encoder.set_color(if has_alpha {
-          ColorType::Rgba
-        } else {
-          ColorType::Rgb
-        });
+      if let Some(metadata) = metadata {
+        if let Some(profile) = metadata.icc_profile_bytes()? {
+          encoder.set_icc_profile(profile.into_owned());
+        }
 
-        encoder.set_compression(Compression::Fast);
-        encoder.set_filter(Filter::Sub);
+        if let Some(exif) = metadata.exif() {
+          encoder.set_exif_metadata(exif);
+        }
 
-        let mut writer = encoder.write_header()?;
-        writer.write_image_data(&image_data)?;
-        writer.finish()?;
+        if let Some(xmp) = metadata.xmp() {
+          encoder.set_xmp_metadata(xmp);
+        }
       }
-    }
-    ImageOutputFormat::WebP => {
-      let encoder = WebPEncoder::new(destination);
 
       let has_alpha = has_any_alpha_pixel(image);
 
@@ -385,173 +934,1472 @@ const VP8X_HEADER_SIZE: u32 = 10;
 // background color (4 bytes) + loop count (2 bytes)
 const ANIM_HEADER_SIZE: u32 = 6;
 
-fn estimate_vp8_payload_size(buf: &[u8]) -> Result<u32, crate::Error> {
-  let (_, len) = vp8_payload_coords(buf)
-    .ok_or_else(|| IoError(std::io::Error::other("VP8/VP8L chunk not found")))?;
+// signature (1 byte) + width, height, alpha hint and version (4 bytes)
+const VP8L_HEADER_SIZE: usize = 5;
 
-  let padding = len & 1;
+const VP8X_ANIMATION_FLAG: u8 = 1 << 1;
+const VP8X_XMP_FLAG: u8 = 1 << 2;
+const VP8X_EXIF_FLAG: u8 = 1 << 3;
+const VP8X_ALPHA_FLAG: u8 = 1 << 4;
+const VP8X_ICC_FLAG: u8 = 1 << 5;
 
-  // ANMF chunk + VP8L chunk
-  Ok(BASE_HEADER_SIZE + ANMF_HEADER_SIZE + BASE_HEADER_SIZE + len as u32 + padding as u32)
-}
+// No pre-processing, no filtering, lossless compression.
+const ALPH_LOSSLESS_HEADER: u8 = 1;
 
-fn estimate_riff_size<'a, I: Iterator<Item = &'a [u8]>>(frames: I) -> Result<u32, crate::Error> {
-  // "WEBP" +  VPX8 chunk + ANIM chunk + [ANMF chunks]
-  let mut size = 4 + BASE_HEADER_SIZE + VP8X_HEADER_SIZE + BASE_HEADER_SIZE + ANIM_HEADER_SIZE;
+// Largest dimension of a VP8 frame (14 bits).
+const VP8_MAX_DIMENSION: u32 = 16383;
 
-  for frame in frames {
-    size += estimate_vp8_payload_size(frame)?;
-  }
+/// Appends a RIFF chunk with its header and padding to `buffer`.
+fn push_riff_chunk(buffer: &mut Vec<u8>, tag: &[u8; 4], payload: &[u8]) {
+  buffer.extend_from_slice(tag);
+  buffer.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+  buffer.extend_from_slice(payload);
 
-  Ok(size)
+  if payload.len() & 1 == 1 {
+    buffer.push(0);
+  }
 }
 
-/// Encode a sequence of RGBA frames into an animated WebP and write to `destination`.
-pub fn encode_animated_webp<W: Write>(
-  frames: &[AnimationFrame],
-  destination: &mut W,
-  blend: bool,
-  dispose: bool,
-  loop_count: Option<u16>,
-) -> Result<(), crate::Error> {
-  assert_ne!(frames.len(), 0);
+/// Encodes `data` losslessly and returns the VP8L bitstream.
+fn encode_vp8l(
+  data: &[u8],
+  width: u32,
+  height: u32,
+  color: image_webp::ColorType,
+) -> Result<Vec<u8>, crate::Error> {
+  let mut buf = Vec::new();
+  WebPEncoder::new(&mut buf).encode(data, width, height, color)?;
+
+  let (start, len) = vp8_payload_coords(&buf)
+    .ok_or_else(|| IoError(std::io::Error::other("VP8/VP8L chunk not found")))?;
 
-  // encode frames losslessly and collect VP8L/VP8 payloads
-  #[cfg(feature = "rayon")]
-  let frames_payloads: Vec<(&AnimationFrame, Vec<u8>)> = frames
-    .par_iter()
-    .map(|frame| {
-      let mut buf = Vec::new();
-      WebPEncoder::new(&mut buf).encode(
-        &frame.image,
-        frame.image.width(),
-        frame.image.height(),
-        image_webp::ColorType::Rgba8,
-      )?;
+  Ok(buf[start..start + len].to_vec())
+}
 
-      Ok((frame, buf))
-    })
-    .collect::<Result<Vec<(&AnimationFrame, Vec<u8>)>, crate::Error>>()?;
+/// Encodes `image` into the chunks describing one WebP frame.
+///
+/// Without `quality` this is a lossless `VP8L` chunk. With `quality` it is a lossy `VP8 ` chunk,
+/// preceded by a losslessly compressed `ALPH` chunk when the image has transparency.
+fn encode_webp_frame_chunks(
+  image: &RgbaImage,
+  quality: Option<u8>,
+) -> Result<Vec<u8>, crate::Error> {
+  let (width, height) = image.dimensions();
+  let mut chunks = Vec::new();
 
-  #[cfg(not(feature = "rayon"))]
-  let frames_payloads: Vec<(&AnimationFrame, Vec<u8>)> = frames
-    .iter()
-    .map(|frame| {
-      let mut buf = Vec::new();
-      WebPEncoder::new(&mut buf)
-        .encode(
-          &frame.image,
-          frame.image.width(),
-          frame.image.height(),
-          image_webp::ColorType::Rgba8,
-        )
-        .map_err(|_| IoError(std::io::Error::other("WebP encode error")))?;
-
-      Ok((frame, buf))
-    })
-    .collect::<Result<Vec<(&AnimationFrame, Vec<u8>)>, crate::Error>>()?;
+  let Some(quality) = quality else {
+    let vp8l = encode_vp8l(image.as_raw(), width, height, image_webp::ColorType::Rgba8)?;
+    push_riff_chunk(&mut chunks, b"VP8L", &vp8l);
 
-  let riff_size = estimate_riff_size(frames_payloads.iter().map(|(_, buf)| buf.as_slice()))?;
+    return Ok(chunks);
+  };
 
-  // RIFF header
-  destination.write_all(b"RIFF")?;
-  destination.write_all(&(riff_size as u32).to_le_bytes())?;
-  destination.write_all(b"WEBP")?;
+  if width > VP8_MAX_DIMENSION || height > VP8_MAX_DIMENSION {
+    return Err(IoError(std::io::Error::other(
+      "Lossy WebP dimensions cannot exceed 16383 pixels",
+    )));
+  }
 
-  // VP8X chunk
-  let vp8x_flags: u8 = (1 << 1) | (1 << 4); // animation + alpha
-  let cw = (frames[0].image.width() - 1).to_le_bytes();
-  let ch = (frames[0].image.height() - 1).to_le_bytes();
+  if has_any_alpha_pixel(image) {
+    let alpha: Vec<u8> = image.pixels().map(|pixel| pixel[3]).collect();
 
-  destination.write_all(b"VP8X")?;
-  destination.write_all(&VP8X_HEADER_SIZE.to_le_bytes())?;
-  destination.write_all(&[vp8x_flags])?;
-  destination.write_all(&[0u8; 3])?;
-  destination.write_all(&cw[..3])?;
-  destination.write_all(&ch[..3])?;
+    // The alpha plane is stored as the green channel of a VP8L image stream without its header.
+    let vp8l = encode_vp8l(&alpha, width, height, image_webp::ColorType::L8)?;
 
-  // ANIM chunk
-  destination.write_all(b"ANIM")?;
-  destination.write_all(&ANIM_HEADER_SIZE.to_le_bytes())?;
-  destination.write_all(&[0u8; 4])?; // bgcolor (4 bytes)
-  destination.write_all(&loop_count.unwrap_or(0).to_le_bytes())?;
+    let mut alph = Vec::with_capacity(1 + vp8l.len() - VP8L_HEADER_SIZE);
+    alph.push(ALPH_LOSSLESS_HEADER);
+    alph.extend_from_slice(&vp8l[VP8L_HEADER_SIZE..]);
+
+    push_riff_chunk(&mut chunks, b"ALPH", &alph);
+  }
 
-  let frame_flags = ((blend as u8) << 1) | (dispose as u8);
+  push_riff_chunk(&mut chunks, b"VP8 ", &encode_vp8(image, quality));
 
-  // ANMF frames
-  for (frame, vp8_data) in frames_payloads.into_iter() {
-    let w_bytes = (frame.image.width() - 1).to_le_bytes();
-    let h_bytes = (frame.image.height() - 1).to_le_bytes();
+  Ok(chunks)
+}
 
-    let (start, len) = vp8_payload_coords(&vp8_data)
-      .ok_or_else(|| IoError(std::io::Error::other("VP8/VP8L chunk not found")))?;
+/// Returns the VP8X chunk payload with `flags` for a canvas of `width` x `height`.
+fn vp8x_payload(flags: u8, width: u32, height: u32) -> Vec<u8> {
+  let mut payload = Vec::with_capacity(VP8X_HEADER_SIZE as usize);
 
-    let vp8_payload = &vp8_data[start..start + len];
+  payload.push(flags);
+  payload.extend_from_slice(&[0u8; 3]);
+  payload.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+  payload.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
 
-    let padding = vp8_payload.len() & 1;
+  payload
+}
 
-    let anmf_size = ANMF_HEADER_SIZE + BASE_HEADER_SIZE + vp8_payload.len() as u32 + padding as u32; // x, y, w, h, duration, flags, payload
+/// The chunks of the extended WebP format holding the metadata.
+#[derive(Default)]
+struct WebPMetadataChunks {
+  /// `ICCP` chunk, written before the image data.
+  iccp: Vec<u8>,
+  /// `EXIF` and `XMP ` chunks, written after the image data.
+  trailing: Vec<u8>,
+  /// VP8X flags announcing the chunks.
+  flags: u8,
+}
 
-    destination.write_all(b"ANMF")?;
-    destination.write_all(&anmf_size.to_le_bytes())?;
+impl WebPMetadataChunks {
+  fn new(metadata: Option<&ImageMetadata>) -> Result<Self, crate::Error> {
+    let mut chunks = Self::default();
 
-    // frame header (16 bytes)
-    destination.write_all(&[0u8; 6])?; // x, y (3 bytes each)
-    destination.write_all(&w_bytes[..3])?; // w (3 bytes)
-    destination.write_all(&h_bytes[..3])?; // h (3 bytes)
-    destination.write_all(&frame.duration_ms.clamp(0, U24_MAX).to_le_bytes()[..3])?; // duration (3 bytes)
-    destination.write_all(&[frame_flags])?; // flags (1 byte)
+    let Some(metadata) = metadata else {
+      return Ok(chunks);
+    };
 
-    // VP8L chunk: VP8L payload
-    destination.write_all(b"VP8L")?;
-    destination.write_all(&(vp8_payload.len() as u32).to_le_bytes())?;
-    destination.write_all(vp8_payload)?;
+    if let Some(profile) = metadata.icc_profile_bytes()? {
+      push_riff_chunk(&mut chunks.iccp, b"ICCP", &profile);
+      chunks.flags |= VP8X_ICC_FLAG;
+    }
 
-    // padding
-    if padding == 1 {
-      destination.write_all(&[0u8])?;
+    if let Some(exif) = metadata.exif() {
+      push_riff_chunk(&mut chunks.trailing, b"EXIF", &exif);
+      chunks.flags |= VP8X_EXIF_FLAG;
     }
-  }
 
-  destination.flush()?;
+    if let Some(xmp) = metadata.xmp() {
+      push_riff_chunk(&mut chunks.trailing, b"XMP ", &xmp);
+      chunks.flags |= VP8X_XMP_FLAG;
+    }
 
-  Ok(())
+    Ok(chunks)
+  }
 }
 
-/// Encode a sequence of RGBA frames into an animated PNG and write to `destination`.
-pub fn encode_animated_png<W: Write>(
-  frames: &[AnimationFrame],
-  destination: &mut W,
-  loop_count: Option<u16>,
+/// Writes a lossy WebP image, using the extended format when an `ALPH` chunk or metadata is needed.
+fn write_lossy_webp<T: Write>(
+  image: &RgbaImage,
+  destination: &mut T,
+  quality: u8,
+  metadata: Option<&ImageMetadata>,
+) -> Result<(), crate::Error> {
+  let chunks = encode_webp_frame_chunks(image, Some(quality))?;
+  let metadata_chunks = WebPMetadataChunks::new(metadata)?;
+
+  let mut flags = metadata_chunks.flags;
+
+  if chunks.starts_with(b"ALPH") {
+    flags |= VP8X_ALPHA_FLAG;
+  }
+
+  let mut body = Vec::with_capacity(
+    4 + (BASE_HEADER_SIZE + VP8X_HEADER_SIZE) as usize
+      + metadata_chunks.iccp.len()
+      + chunks.len()
+      + metadata_chunks.trailing.len(),
+  );
+  body.extend_from_slice(b"WEBP");
+
+  if flags != 0 {
+    push_riff_chunk(
+      &mut body,
+      b"VP8X",
+      &vp8x_payload(flags, image.width(), image.height()),
+    );
+  }
+
+  body.extend_from_slice(&metadata_chunks.iccp);
+  body.extend_from_slice(&chunks);
+  body.extend_from_slice(&metadata_chunks.trailing);
+
+  destination.write_all(b"RIFF")?;
+  destination.write_all(&(body.len() as u32).to_le_bytes())?;
+  destination.write_all(&body)?;
+
+  Ok(())
+}
+
+/// Returns the size of "WEBP" and the chunks before the frames of an animated WebP.
+fn webp_animation_header_size(metadata_chunks: &WebPMetadataChunks) -> u32 {
+  // "WEBP" + VP8X chunk + ICCP chunk + ANIM chunk
+  4 + BASE_HEADER_SIZE
+    + VP8X_HEADER_SIZE
+    + metadata_chunks.iccp.len() as u32
+    + BASE_HEADER_SIZE
+    + ANIM_HEADER_SIZE
+}
+
+/// Writes the RIFF header and the chunks before the frames of an animated WebP.
+fn write_webp_animation_header<W: Write>(
+  destination: &mut W,
+  riff_size: u32,
+  width: u32,
+  height: u32,
+  loop_count: Option<u16>,
+  metadata_chunks: &WebPMetadataChunks,
+) -> Result<(), crate::Error> {
+  // RIFF header
+  destination.write_all(b"RIFF")?;
+  destination.write_all(&riff_size.to_le_bytes())?;
+  destination.write_all(b"WEBP")?;
+
+  // VP8X chunk
+  destination.write_all(b"VP8X")?;
+  destination.write_all(&VP8X_HEADER_SIZE.to_le_bytes())?;
+  destination.write_all(&vp8x_payload(
+    VP8X_ANIMATION_FLAG | VP8X_ALPHA_FLAG | metadata_chunks.flags,
+    width,
+    height,
+  ))?;
+
+  // ICCP chunk
+  destination.write_all(&metadata_chunks.iccp)?;
+
+  // ANIM chunk
+  destination.write_all(b"ANIM")?;
+  destination.write_all(&ANIM_HEADER_SIZE.to_le_bytes())?;
+  destination.write_all(&[0u8; 4])?; // bgcolor (4 bytes)
+  destination.write_all(&loop_count.unwrap_or(0).to_le_bytes())?;
+
+  Ok(())
+}
+
+/// Returns the ANMF flags byte for the blending and disposal methods.
+fn webp_frame_flags(blend: bool, dispose: bool) -> u8 {
+  ((blend as u8) << 1) | (dispose as u8)
+}
+
+/// Writes an ANMF chunk for `frame` holding its encoded `chunks`.
+fn write_anmf_chunk<W: Write>(
+  destination: &mut W,
+  frame: &AnimationFrame,
+  frame_flags: u8,
+  chunks: &[u8],
+) -> Result<(), crate::Error> {
+  let w_bytes = (frame.image.width() - 1).to_le_bytes();
+  let h_bytes = (frame.image.height() - 1).to_le_bytes();
+
+  let anmf_size = ANMF_HEADER_SIZE + chunks.len() as u32; // x, y, w, h, duration, flags, frame chunks
+
+  destination.write_all(b"ANMF")?;
+  destination.write_all(&anmf_size.to_le_bytes())?;
+
+  // frame header (16 bytes)
+  destination.write_all(&[0u8; 6])?; // x, y (3 bytes each)
+  destination.write_all(&w_bytes[..3])?; // w (3 bytes)
+  destination.write_all(&h_bytes[..3])?; // h (3 bytes)
+  destination.write_all(&frame.duration_ms.clamp(0, U24_MAX).to_le_bytes()[..3])?; // duration (3 bytes)
+  destination.write_all(&[frame_flags])?; // flags (1 byte)
+
+  // VP8L chunk, or ALPH and VP8 chunks (already padded)
+  destination.write_all(chunks)?;
+
+  Ok(())
+}
+
+/// Encode a sequence of RGBA frames into a lossless animated WebP and write to `destination`.
+///
+/// Use [`encode_animated_webp_with_options`] for lossy frames and metadata.
+pub fn encode_animated_webp<W: Write>(
+  frames: &[AnimationFrame],
+  destination: &mut W,
+  blend: bool,
+  dispose: bool,
+  loop_count: Option<u16>,
+) -> Result<(), crate::Error> {
+  encode_animated_webp_with_options(
+    frames,
+    destination,
+    WebPAnimationOptions {
+      blend,
+      dispose,
+      loop_count,
+      quality: None,
+    },
+    None,
+  )
+}
+
+/// Encode a sequence of RGBA frames into an animated WebP with the `options` and write to `destination`.
+///
+/// Frames are lossy with the quality of the options, keeping alpha lossless, and lossless without it.
+pub fn encode_animated_webp_with_options<W: Write>(
+  frames: &[AnimationFrame],
+  destination: &mut W,
+  options: WebPAnimationOptions,
+  metadata: Option<&ImageMetadata>,
 ) -> Result<(), crate::Error> {
   assert_ne!(frames.len(), 0);
 
-  let mut encoder = png::Encoder::new(
+  let WebPAnimationOptions {
+    blend,
+    dispose,
+    loop_count,
+    quality,
+  } = options;
+
+  let metadata_chunks = WebPMetadataChunks::new(metadata)?;
+
+  let encode_frame = |frame: &AnimationFrame| encode_webp_frame_chunks(&frame.image, quality);
+
+  #[cfg(feature = "rayon")]
+  let frames_chunks = frames
+    .par_iter()
+    .map(encode_frame)
+    .collect::<Result<Vec<Vec<u8>>, crate::Error>>()?;
+
+  #[cfg(not(feature = "rayon"))]
+  let frames_chunks = frames
+    .iter()
+    .map(encode_frame)
+    .collect::<Result<Vec<Vec<u8>>, crate::Error>>()?;
+
+  // "WEBP" + VP8X chunk + ICCP chunk + ANIM chunk + [ANMF chunks] + EXIF and XMP chunks
+  let riff_size = webp_animation_header_size(&metadata_chunks)
+    + frames_chunks
+      .iter()
+      .map(|chunks| BASE_HEADER_SIZE + ANMF_HEADER_SIZE + chunks.len() as u32)
+      .sum::<u32>()
+    + metadata_chunks.trailing.len() as u32;
+
+  write_webp_animation_header(
     destination,
+    riff_size,
     frames[0].image.width(),
     frames[0].image.height(),
-  );
+    loop_count,
+    &metadata_chunks,
+  )?;
+
+  let frame_flags = webp_frame_flags(blend, dispose);
+
+  // ANMF frames
+  for (frame, chunks) in frames.iter().zip(frames_chunks) {
+    write_anmf_chunk(destination, frame, frame_flags, &chunks)?;
+  }
+
+  // EXIF and XMP chunks
+  destination.write_all(&metadata_chunks.trailing)?;
+
+  destination.flush()?;
+
+  Ok(())
+}
+
+/// Options for [`encode_animated_webp_with_options`] and [`WebPAnimationEncoder`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WebPAnimationOptions {
+  /// The blending method flag of the frames.
+  pub blend: bool,
+  /// Whether frames are cleared to transparent before the next one.
+  pub dispose: bool,
+  /// How many times the animation plays, `None` or 0 loops forever.
+  pub loop_count: Option<u16>,
+  /// The quality (0-100) of lossy frames, frames are lossless without it.
+  pub quality: Option<u8>,
+}
+
+/// Encodes an animated WebP one frame at a time, so frames can be dropped once pushed.
+///
+/// The RIFF size is only known once every frame is written, so [`WebPAnimationEncoder::finish`] seeks back to fill it in.
+/// Use a [`std::io::Cursor`] to encode into memory.
+pub struct WebPAnimationEncoder<W: Write + Seek> {
+  destination: W,
+  /// Stream position of the RIFF header.
+  start: u64,
+  width: u32,
+  height: u32,
+  frame_flags: u8,
+  quality: Option<u8>,
+  /// EXIF and XMP chunks, written after the frames.
+  trailing: Vec<u8>,
+  frame_count: usize,
+}
+
+impl<W: Write + Seek> WebPAnimationEncoder<W> {
+  /// Writes the header of a `width` x `height` animation to `destination`.
+  pub fn new(
+    mut destination: W,
+    width: u32,
+    height: u32,
+    options: WebPAnimationOptions,
+    metadata: Option<&ImageMetadata>,
+  ) -> Result<Self, crate::Error> {
+    let metadata_chunks = WebPMetadataChunks::new(metadata)?;
+    let start = destination.stream_position()?;
+
+    // The RIFF size is filled in by `finish`.
+    write_webp_animation_header(
+      &mut destination,
+      0,
+      width,
+      height,
+      options.loop_count,
+      &metadata_chunks,
+    )?;
+
+    Ok(Self {
+      destination,
+      start,
+      width,
+      height,
+      frame_flags: webp_frame_flags(options.blend, options.dispose),
+      quality: options.quality,
+      trailing: metadata_chunks.trailing,
+      frame_count: 0,
+    })
+  }
+
+  /// Encodes `frame` and writes it to the destination.
+  pub fn push_frame(&mut self, frame: AnimationFrame) -> Result<(), crate::Error> {
+    if frame.image.dimensions() != (self.width, self.height) {
+      return Err(IoError(std::io::Error::other(
+        "All frames must have the same dimensions",
+      )));
+    }
+
+    let chunks = encode_webp_frame_chunks(&frame.image, self.quality)?;
+    write_anmf_chunk(&mut self.destination, &frame, self.frame_flags, &chunks)?;
+
+    self.frame_count += 1;
+
+    Ok(())
+  }
+
+  /// Writes the metadata after the frames and fills in the RIFF size.
+  pub fn finish(mut self) -> Result<(), crate::Error> {
+    if self.frame_count == 0 {
+      return Err(IoError(std::io::Error::other(
+        "Animation must have at least one frame",
+      )));
+    }
+
+    self.destination.write_all(&self.trailing)?;
+
+    let end = self.destination.stream_position()?;
 
-  encoder.set_color(ColorType::Rgba);
-  encoder.set_compression(png::Compression::Fastest);
-  encoder.set_animated(frames.len() as u32, loop_count.unwrap_or(0) as u32)?;
+    // Everything after "RIFF" and the size itself.
+    let Ok(riff_size) = u32::try_from(end - self.start - BASE_HEADER_SIZE as u64) else {
+      return Err(IoError(std::io::Error::other(
+        "Animated WebP cannot exceed 4 GiB",
+      )));
+    };
+
+    self.destination.seek(SeekFrom::Start(self.start + 4))?;
+    self.destination.write_all(&riff_size.to_le_bytes())?;
+    self.destination.seek(SeekFrom::Start(end))?;
+    self.destination.flush()?;
+
+    Ok(())
+  }
+}
+
+/// Returns the bounding box `(left, top, right, bottom)` of the pixels matching `predicate`.
+fn pixel_bounding_box(
+  width: u32,
+  height: u32,
+  predicate: impl Fn(usize) -> bool,
+) -> Option<(u32, u32, u32, u32)> {
+  let mut bounds: Option<(u32, u32, u32, u32)> = None;
+
+  for y in 0..height {
+    for x in 0..width {
+      if !predicate((y * width + x) as usize * 4) {
+        continue;
+      }
+
+      bounds = Some(match bounds {
+        Some((left, top, right, bottom)) => {
+          (left.min(x), top.min(y), right.max(x + 1), bottom.max(y + 1))
+        }
+        None => (x, y, x + 1, y + 1),
+      });
+    }
+  }
+
+  bounds
+}
+
+/// The changed region of an APNG frame.
+struct PngPatch {
+  left: u32,
+  top: u32,
+  image: RgbaImage,
+  blend: BlendOp,
+  duration_ms: u32,
+}
 
-  // Since APNG doesn't support variable frame duration, we use the minimum duration of all frames.
-  let min_duration_ms = frames
+/// Crops `frame` to the region that changed since `previous`, or returns `None` when nothing changed.
+///
+/// When the changed pixels are all opaque, unchanged pixels are left transparent and blended over the previous frame,
+/// otherwise the region replaces what's underneath.
+fn diff_png_frame(previous: &RgbaImage, frame: &AnimationFrame) -> Option<PngPatch> {
+  let pixels = frame.image.as_raw();
+  let previous_pixels = previous.as_raw();
+
+  let (left, top, right, bottom) =
+    pixel_bounding_box(frame.image.width(), frame.image.height(), |offset| {
+      pixels[offset..offset + 4] != previous_pixels[offset..offset + 4]
+    })?;
+
+  let mut image =
+    imageops::crop_imm(&frame.image, left, top, right - left, bottom - top).to_image();
+  let previous_region =
+    imageops::crop_imm(previous, left, top, right - left, bottom - top).to_image();
+
+  // Blending over the previous frame can only keep pixels or paint opaque ones.
+  let can_blend_over = image
+    .pixels()
+    .zip(previous_region.pixels())
+    .all(|(pixel, previous_pixel)| pixel == previous_pixel || pixel[3] == u8::MAX);
+
+  if can_blend_over {
+    for (pixel, previous_pixel) in image.pixels_mut().zip(previous_region.pixels()) {
+      if pixel == previous_pixel {
+        *pixel = Rgba([0; 4]);
+      }
+    }
+  }
+
+  Some(PngPatch {
+    left,
+    top,
+    image,
+    blend: if can_blend_over {
+      BlendOp::Over
+    } else {
+      BlendOp::Source
+    },
+    duration_ms: frame.duration_ms,
+  })
+}
+
+/// Crops every frame after the first to the region that changed since the previous frame.
+///
+/// Frames without changes are merged into the previous one.
+fn diff_png_frames(frames: &[AnimationFrame]) -> Vec<PngPatch> {
+  let mut patches: Vec<PngPatch> = Vec::with_capacity(frames.len());
+  let mut previous: Option<&RgbaImage> = None;
+
+  for frame in frames {
+    let Some(previous_image) = previous.replace(&frame.image) else {
+      patches.push(PngPatch {
+        left: 0,
+        top: 0,
+        image: frame.image.clone(),
+        blend: BlendOp::Source,
+        duration_ms: frame.duration_ms,
+      });
+      continue;
+    };
+
+    match diff_png_frame(previous_image, frame) {
+      Some(patch) => patches.push(patch),
+      None => {
+        if let Some(last) = patches.last_mut() {
+          last.duration_ms = last.duration_ms.saturating_add(frame.duration_ms);
+        }
+      }
+    }
+  }
+
+  patches
+}
+
+/// Writes the frame control of `patch` followed by its `image_data`.
+fn write_png_patch<W: Write>(
+  writer: &mut png::Writer<W>,
+  patch: &PngPatch,
+  image_data: &[u8],
+) -> Result<(), crate::Error> {
+  // The position is reset first, so the new dimension is always within bounds.
+  writer.reset_frame_position()?;
+  writer.set_frame_dimension(patch.image.width(), patch.image.height())?;
+  writer.set_frame_position(patch.left, patch.top)?;
+
+  let (numerator, denominator) = png_frame_delay(patch.duration_ms);
+  writer.set_frame_delay(numerator, denominator)?;
+  writer.set_blend_op(patch.blend)?;
+  writer.set_dispose_op(DisposeOp::None)?;
+
+  writer.write_image_data(image_data)?;
+
+  Ok(())
+}
+
+/// Converts a duration to a `fcTL` delay fraction, falling back to coarser units for long durations.
+fn png_frame_delay(duration_ms: u32) -> (u16, u16) {
+  for (unit_ms, denominator) in [(1, 1000), (10, 100), (1000, 1)] {
+    if let Ok(numerator) = u16::try_from(duration_ms / unit_ms) {
+      return (numerator, denominator);
+    }
+  }
+
+  (u16::MAX, 1)
+}
+
+/// Encode a sequence of RGBA frames into an animated PNG and write to `destination`.
+///
+/// Each frame keeps its own duration, and is cropped to the region that changed since the previous frame.
+/// Frames are written as indexed colors when all of them fit in a 256 colors palette.
+/// Use [`encode_animated_png_with_options`] to embed metadata.
+pub fn encode_animated_png<W: Write>(
+  frames: &[AnimationFrame],
+  destination: &mut W,
+  loop_count: Option<u16>,
+) -> Result<(), crate::Error> {
+  encode_animated_png_with_options(
+    frames,
+    destination,
+    PngAnimationOptions { loop_count },
+    None,
+  )
+}
+
+/// Options for [`encode_animated_png_with_options`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PngAnimationOptions {
+  /// How many times the animation plays, `None` or 0 loops forever.
+  pub loop_count: Option<u16>,
+}
+
+/// Encode a sequence of RGBA frames into an animated PNG with the `options` and `metadata`, and write to `destination`.
+///
+/// Frames are encoded like [`encode_animated_png`].
+pub fn encode_animated_png_with_options<W: Write>(
+  frames: &[AnimationFrame],
+  destination: &mut W,
+  options: PngAnimationOptions,
+  metadata: Option<&ImageMetadata>,
+) -> Result<(), crate::Error> {
+  assert_ne!(frames.len(), 0);
+
+  let PngAnimationOptions { loop_count } = options;
+
+  let width = frames[0].image.width();
+  let height = frames[0].image.height();
+
+  if frames
     .iter()
-    .map(|frame| frame.duration_ms)
-    .min()
-    .unwrap_or(0);
+    .any(|frame| frame.image.dimensions() != (width, height))
+  {
+    return Err(IoError(std::io::Error::other(
+      "All frames must have the same dimensions",
+    )));
+  }
 
-  encoder.set_frame_delay(min_duration_ms.clamp(0, u16::MAX as u32) as u16, 1000)?;
+  let patches = diff_png_frames(frames);
+
+  let palette = try_collect_palette(
+    &patches.iter().map(|patch| &patch.image).collect::<Vec<_>>(),
+    quantize_frame_alpha,
+  );
+  let has_alpha = palette.is_none()
+    && patches
+      .iter()
+      .any(|patch| has_any_alpha_pixel(&patch.image));
+
+  let mut encoder = png_encoder(destination, width, height, metadata)?;
+
+  if let Some(palette) = &palette {
+    palette.configure(&mut encoder);
+  } else {
+    encoder.set_color(if has_alpha {
+      ColorType::Rgba
+    } else {
+      ColorType::Rgb
+    });
+    encoder.set_filter(Filter::Sub);
+  }
+
+  encoder.set_compression(Compression::Fast);
+  encoder.set_animated(patches.len() as u32, loop_count.unwrap_or(0) as u32)?;
 
   let mut writer = encoder.write_header()?;
 
-  for frame in frames {
-    writer.write_image_data(frame.image.as_raw())?;
+  for patch in &patches {
+    let image_data = match &palette {
+      Some(palette) => Cow::Owned(palette.pack_indices(&patch.image)),
+      None if has_alpha => Cow::Borrowed(patch.image.as_raw()),
+      None => Cow::Owned(strip_alpha_channel(&patch.image)),
+    };
+
+    write_png_patch(&mut writer, patch, &image_data)?;
   }
 
   writer.finish()?;
 
   Ok(())
 }
+
+/// Encodes an animated PNG one frame at a time, keeping only the previous frame in memory.
+///
+/// APNG declares the number of frames before the first one, so `frame_count` must match the frames pushed.
+/// Frames are cropped to the region that changed like [`encode_animated_png`],
+/// but always written as RGBA since the colors of the upcoming frames are unknown.
+pub struct PngAnimationEncoder<W: Write> {
+  writer: png::Writer<W>,
+  width: u32,
+  height: u32,
+  previous: Option<RgbaImage>,
+}
+
+impl<W: Write> PngAnimationEncoder<W> {
+  /// Writes the header of a `width` x `height` animation with `frame_count` frames to `destination`.
+  pub fn new(
+    destination: W,
+    width: u32,
+    height: u32,
+    frame_count: u32,
+    loop_count: Option<u16>,
+    metadata: Option<&ImageMetadata>,
+  ) -> Result<Self, crate::Error> {
+    let mut encoder = png_encoder(destination, width, height, metadata)?;
+
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_filter(Filter::Sub);
+    encoder.set_compression(Compression::Fast);
+    encoder.set_animated(frame_count, loop_count.unwrap_or(0) as u32)?;
+    encoder.validate_sequence(true);
+
+    Ok(Self {
+      writer: encoder.write_header()?,
+      width,
+      height,
+      previous: None,
+    })
+  }
+
+  /// Encodes `frame` and writes it to the destination.
+  pub fn push_frame(&mut self, frame: AnimationFrame) -> Result<(), crate::Error> {
+    if frame.image.dimensions() != (self.width, self.height) {
+      return Err(IoError(std::io::Error::other(
+        "All frames must have the same dimensions",
+      )));
+    }
+
+    let patch = match &self.previous {
+      None => PngPatch {
+        left: 0,
+        top: 0,
+        image: frame.image.clone(),
+        blend: BlendOp::Source,
+        duration_ms: frame.duration_ms,
+      },
+      // The frame count is already written, so unchanged frames become a transparent pixel.
+      Some(previous) => diff_png_frame(previous, &frame).unwrap_or_else(|| PngPatch {
+        left: 0,
+        top: 0,
+        image: RgbaImage::new(1, 1),
+        blend: BlendOp::Over,
+        duration_ms: frame.duration_ms,
+      }),
+    };
+
+    write_png_patch(&mut self.writer, &patch, patch.image.as_raw())?;
+
+    self.previous = Some(frame.image);
+
+    Ok(())
+  }
+
+  /// Finishes the animation, failing if fewer frames than declared were pushed.
+  pub fn finish(self) -> Result<(), crate::Error> {
+    self.writer.finish()?;
+
+    Ok(())
+  }
+}
+
+/// How the color palette of an animated GIF is built.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GifPalette {
+  /// A single palette shared by all frames, which keeps colors stable between frames.
+  #[default]
+  Global,
+  /// A palette for each frame, which gives better colors when frames differ a lot.
+  PerFrame,
+}
+
+/// Options for [`encode_animated_gif`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GifEncodeOptions {
+  /// How the color palette is built.
+  pub palette: GifPalette,
+  /// Whether to apply Floyd-Steinberg dithering when the colors don't fit in the palette.
+  pub dither: bool,
+}
+
+/// GIF palettes hold 256 colors, the last one is reserved for transparent pixels.
+const GIF_MAX_COLORS: usize = 255;
+const GIF_TRANSPARENT_INDEX: u8 = 255;
+
+/// Application extension identifier and authentication code of an embedded ICC profile.
+const GIF_ICC_APPLICATION: &[u8] = b"ICCRGBG1012";
+
+/// Sampling factor for NeuQuant, 1 is the slowest and 30 is the fastest.
+const NEUQUANT_SAMPLE_FACTOR: i32 = 10;
+
+/// The changed region of a frame, relative to what the previous frame left on the canvas.
+struct GifPatch {
+  left: u32,
+  top: u32,
+  width: u32,
+  height: u32,
+  /// RGBA pixels of the region, alpha is either 0 (keep what's underneath) or 255.
+  pixels: Vec<u8>,
+  dispose: gif::DisposalMethod,
+  /// Delay in centiseconds.
+  delay: u16,
+}
+
+/// Maps colors to palette indices.
+enum GifQuantizer {
+  /// All the colors fit in the palette.
+  Exact(FxHashMap<[u8; 3], u8>),
+  /// The colors are reduced with NeuQuant.
+  NeuQuant(Box<color_quant::NeuQuant>),
+}
+
+impl GifQuantizer {
+  fn new<'a>(regions: impl Iterator<Item = &'a [u8]> + Clone) -> Self {
+    let mut colors = FxHashMap::with_capacity_and_hasher(GIF_MAX_COLORS, Default::default());
+
+    for pixel in regions.clone().flat_map(|pixels| pixels.chunks_exact(4)) {
+      if pixel[3] == 0 {
+        continue;
+      }
+
+      let next_index = colors.len();
+
+      colors
+        .entry([pixel[0], pixel[1], pixel[2]])
+        .or_insert(next_index as u8);
+
+      if colors.len() > GIF_MAX_COLORS {
+        let samples: Vec<u8> = regions
+          .flat_map(|pixels| pixels.chunks_exact(4))
+          .filter(|pixel| pixel[3] != 0)
+          .flatten()
+          .copied()
+          .collect();
+
+        return Self::NeuQuant(Box::new(color_quant::NeuQuant::new(
+          NEUQUANT_SAMPLE_FACTOR,
+          GIF_MAX_COLORS,
+          &samples,
+        )));
+      }
+    }
+
+    Self::Exact(colors)
+  }
+
+  /// Returns the RGB palette, padded to 256 colors.
+  fn palette(&self) -> Vec<u8> {
+    let mut palette = match self {
+      Self::Exact(colors) => {
+        let mut palette = vec![0; colors.len() * 3];
+
+        for (color, &index) in colors {
+          let offset = index as usize * 3;
+          palette[offset..offset + 3].copy_from_slice(color);
+        }
+
+        palette
+      }
+      Self::NeuQuant(quantizer) => quantizer.color_map_rgb(),
+    };
+
+    palette.resize((GIF_MAX_COLORS + 1) * 3, 0);
+    palette
+  }
+
+  fn index_of(&self, color: [u8; 3]) -> u8 {
+    match self {
+      Self::Exact(colors) => colors.get(&color).copied().unwrap_or(0),
+      Self::NeuQuant(quantizer) => {
+        quantizer.index_of(&[color[0], color[1], color[2], u8::MAX]) as u8
+      }
+    }
+  }
+
+  fn color_of(&self, index: u8) -> [u8; 3] {
+    match self {
+      Self::Exact(_) => [0; 3],
+      Self::NeuQuant(quantizer) => quantizer
+        .lookup(index as usize)
+        .map(|color| [color[0], color[1], color[2]])
+        .unwrap_or_default(),
+    }
+  }
+
+  /// Converts the region to palette indices, diffusing the quantization error if `dither` is set.
+  fn index_pixels(&self, patch: &GifPatch, dither: bool) -> Vec<u8> {
+    // Exact palettes have no error to diffuse.
+    let dither = dither && matches!(self, Self::NeuQuant(_));
+
+    let width = patch.width as usize;
+    let mut indices = Vec::with_capacity(patch.pixels.len() / 4);
+
+    // Errors for the current and the next row, padded by one pixel on each side.
+    let mut current_errors = vec![[0.0f32; 3]; width + 2];
+    let mut next_errors = vec![[0.0f32; 3]; width + 2];
+
+    for row in patch.pixels.chunks_exact(width * 4) {
+      for (x, pixel) in row.chunks_exact(4).enumerate() {
+        if pixel[3] == 0 {
+          indices.push(GIF_TRANSPARENT_INDEX);
+          continue;
+        }
+
+        if !dither {
+          indices.push(self.index_of([pixel[0], pixel[1], pixel[2]]));
+          continue;
+        }
+
+        let error = current_errors[x + 1];
+        let color: [f32; 3] =
+          std::array::from_fn(|channel| (pixel[channel] as f32 + error[channel]).clamp(0.0, 255.0));
+
+        let index = self.index_of(color.map(|value| value.round() as u8));
+        let quantized = self.color_of(index);
+
+        for channel in 0..3 {
+          let error = color[channel] - quantized[channel] as f32;
+
+          current_errors[x + 2][channel] += error * 7.0 / 16.0;
+          next_errors[x][channel] += error * 3.0 / 16.0;
+          next_errors[x + 1][channel] += error * 5.0 / 16.0;
+          next_errors[x + 2][channel] += error / 16.0;
+        }
+
+        indices.push(index);
+      }
+
+      std::mem::swap(&mut current_errors, &mut next_errors);
+      next_errors.fill([0.0; 3]);
+    }
+
+    indices
+  }
+}
+
+/// Snaps the alpha channel to either fully transparent or fully opaque, since GIF only has 1-bit transparency.
+fn snap_gif_alpha(image: &RgbaImage) -> Vec<u8> {
+  let mut pixels = image.as_raw().clone();
+
+  for pixel in pixels.chunks_exact_mut(4) {
+    if pixel[3] < 128 {
+      pixel.copy_from_slice(&[0; 4]);
+    } else {
+      pixel[3] = u8::MAX;
+    }
+  }
+
+  pixels
+}
+
+/// Crops every frame to the region that changed since the previous frame.
+///
+/// Unchanged pixels inside the region are left transparent so the previous frame shows through.
+/// When a pixel turns transparent in the next frame, the region is cleared afterwards with background disposal.
+fn diff_gif_frames(frames: &[AnimationFrame], width: u32, height: u32) -> Vec<GifPatch> {
+  let is_opaque = |pixels: &[u8], offset: usize| pixels[offset + 3] != 0;
+
+  // What decoders show before drawing the next frame.
+  let mut canvas = vec![0u8; (width * height * 4) as usize];
+  let mut patches: Vec<GifPatch> = Vec::with_capacity(frames.len());
+
+  let mut elapsed_ms = 0u64;
+  let mut next_target = frames.first().map(|frame| snap_gif_alpha(&frame.image));
+
+  for (index, frame) in frames.iter().enumerate() {
+    let Some(target) = next_target.take() else {
+      break;
+    };
+
+    next_target = frames
+      .get(index + 1)
+      .map(|frame| snap_gif_alpha(&frame.image));
+
+    // Delays are derived from the rounded timeline, so rounding errors don't accumulate.
+    let start_cs = (elapsed_ms + 5) / 10;
+    elapsed_ms += frame.duration_ms as u64;
+    let delay = ((elapsed_ms + 5) / 10 - start_cs).min(u16::MAX as u64) as u16;
+
+    let changed = pixel_bounding_box(width, height, |offset| {
+      target[offset..offset + 4] != canvas[offset..offset + 4]
+    });
+
+    let cleared = next_target.as_ref().and_then(|next| {
+      pixel_bounding_box(width, height, |offset| {
+        is_opaque(&target, offset) && !is_opaque(next, offset)
+      })
+    });
+
+    let bounds = match (changed, cleared) {
+      (Some(a), Some(b)) => Some((a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3))),
+      (bounds, None) | (None, bounds) => bounds,
+    };
+
+    let (left, top, right, bottom) = match (bounds, patches.last_mut()) {
+      (Some(bounds), _) => bounds,
+      // Nothing changed, extend the previous frame instead.
+      (None, Some(previous)) => {
+        previous.delay = previous.delay.saturating_add(delay);
+        continue;
+      }
+      (None, None) => (0, 0, 1, 1),
+    };
+
+    let mut pixels = Vec::with_capacity(((right - left) * (bottom - top) * 4) as usize);
+
+    for y in top..bottom {
+      let row_start = ((y * width + left) * 4) as usize;
+      let row_end = ((y * width + right) * 4) as usize;
+
+      for (pixel, previous) in target[row_start..row_end]
+        .chunks_exact(4)
+        .zip(canvas[row_start..row_end].chunks_exact(4))
+      {
+        if pixel == previous {
+          pixels.extend_from_slice(&[0; 4]);
+        } else {
+          pixels.extend_from_slice(pixel);
+        }
+      }
+    }
+
+    let dispose = if cleared.is_some() {
+      gif::DisposalMethod::Background
+    } else {
+      gif::DisposalMethod::Keep
+    };
+
+    canvas = target;
+
+    if dispose == gif::DisposalMethod::Background {
+      for y in top..bottom {
+        canvas[((y * width + left) * 4) as usize..((y * width + right) * 4) as usize].fill(0);
+      }
+    }
+
+    patches.push(GifPatch {
+      left,
+      top,
+      width: right - left,
+      height: bottom - top,
+      pixels,
+      dispose,
+      delay,
+    });
+  }
+
+  patches
+}
+
+/// Encode a sequence of RGBA frames into an animated GIF and write to `destination`.
+///
+/// Colors are reduced to 255 per palette, and pixels with less than 50% alpha become transparent.
+/// Each frame is cropped to the region that changed since the previous frame,
+/// and frames without changes are merged into the previous one.
+/// Text entries of `metadata` are written as comments, and a custom ICC profile as an `ICCRGBG1` extension.
+pub fn encode_animated_gif<W: Write>(
+  frames: &[AnimationFrame],
+  destination: &mut W,
+  loop_count: Option<u16>,
+  options: GifEncodeOptions,
+  metadata: Option<&ImageMetadata>,
+) -> Result<(), crate::Error> {
+  assert_ne!(frames.len(), 0);
+
+  let width = frames[0].image.width();
+  let height = frames[0].image.height();
+
+  if frames
+    .iter()
+    .any(|frame| frame.image.dimensions() != (width, height))
+  {
+    return Err(IoError(std::io::Error::other(
+      "All frames must have the same dimensions",
+    )));
+  }
+
+  let (Ok(canvas_width), Ok(canvas_height)) = (u16::try_from(width), u16::try_from(height)) else {
+    return Err(IoError(std::io::Error::other(
+      "GIF dimensions cannot exceed 65535 pixels",
+    )));
+  };
+
+  let patches = diff_gif_frames(frames, width, height);
+
+  let global_quantizer = match options.palette {
+    GifPalette::Global => Some(GifQuantizer::new(
+      patches.iter().map(|patch| patch.pixels.as_slice()),
+    )),
+    GifPalette::PerFrame => None,
+  };
+
+  let encode_patch = |patch: &GifPatch| match &global_quantizer {
+    Some(quantizer) => (quantizer.index_pixels(patch, options.dither), None),
+    None => {
+      let quantizer = GifQuantizer::new(std::iter::once(patch.pixels.as_slice()));
+
+      (
+        quantizer.index_pixels(patch, options.dither),
+        Some(quantizer.palette()),
+      )
+    }
+  };
+
+  #[cfg(feature = "rayon")]
+  let encoded: Vec<(Vec<u8>, Option<Vec<u8>>)> = patches.par_iter().map(encode_patch).collect();
+
+  #[cfg(not(feature = "rayon"))]
+  let encoded: Vec<(Vec<u8>, Option<Vec<u8>>)> = patches.iter().map(encode_patch).collect();
+
+  let global_palette = global_quantizer
+    .as_ref()
+    .map(GifQuantizer::palette)
+    .unwrap_or_default();
+
+  let mut encoder = gif::Encoder::new(destination, canvas_width, canvas_height, &global_palette)?;
+
+  encoder.set_repeat(match loop_count.unwrap_or(0) {
+    0 => gif::Repeat::Infinite,
+    count => gif::Repeat::Finite(count),
+  })?;
+
+  if let Some(metadata) = metadata {
+    if metadata.icc_profile != Some(IccProfile::Srgb)
+      && let Some(profile) = metadata.icc_profile_bytes()?
+    {
+      encoder.write_raw_extension(
+        gif::Extension::Application.into(),
+        &[GIF_ICC_APPLICATION, &profile],
+      )?;
+    }
+
+    for (keyword, text) in &metadata.text {
+      encoder.write_raw_extension(
+        gif::Extension::Comment.into(),
+        &[format!("{keyword}: {text}").as_bytes()],
+      )?;
+    }
+  }
+
+  for (patch, (indices, palette)) in patches.iter().zip(encoded) {
+    encoder.write_frame(&gif::Frame {
+      delay: patch.delay,
+      dispose: patch.dispose,
+      transparent: Some(GIF_TRANSPARENT_INDEX),
+      left: patch.left as u16,
+      top: patch.top as u16,
+      width: patch.width as u16,
+      height: patch.height as u16,
+      palette,
+      buffer: Cow::Owned(indices),
+      ..Default::default()
+    })?;
+  }
+
+  encoder.into_inner()?.flush()?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use image::{Rgba, RgbaImage};
+
+  use super::*;
+  use crate::TestResult;
+
+  fn gradient() -> RgbaImage {
+    RgbaImage::from_fn(64, 32, |x, y| {
+      Rgba([(x * 4) as u8, (y * 8) as u8, ((x + y) * 2) as u8, 255])
+    })
+  }
+
+  fn decode(data: &[u8]) -> Result<(png::OutputInfo, Vec<u8>, Option<usize>), png::DecodingError> {
+    let mut reader = png::Decoder::new(std::io::Cursor::new(data)).read_info()?;
+    let palette_len = reader
+      .info()
+      .palette
+      .as_ref()
+      .map(|palette| palette.len() / 3);
+
+    let mut buffer = vec![0; reader.output_buffer_size().unwrap_or_default()];
+    let info = reader.next_frame(&mut buffer)?;
+
+    Ok((info, buffer, palette_len))
+  }
+
+  #[test]
+  fn test_write_png_default_matches_write_image() -> TestResult {
+    let image = gradient();
+
+    let mut expected = Vec::new();
+    write_image(&image, &mut expected, ImageOutputFormat::Png, None)?;
+
+    let mut output = Vec::new();
+    write_png(&image, &mut output, PngEncodeOptions::default(), None)?;
+
+    assert_eq!(output, expected);
+
+    let (info, _, palette_len) = decode(&output)?;
+    assert_eq!(info.color_type, ColorType::Rgb);
+    assert_eq!(palette_len, None);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_write_png_max_colors() -> TestResult {
+    let image = gradient();
+
+    for dither in [false, true] {
+      let mut output = Vec::new();
+      write_png(
+        &image,
+        &mut output,
+        PngEncodeOptions {
+          compression: PngCompression::High,
+          filter: Some(PngFilter::Paeth),
+          max_colors: Some(16),
+          dither,
+          ..Default::default()
+        },
+        None,
+      )?;
+
+      let (info, _, palette_len) = decode(&output)?;
+      assert_eq!(info.color_type, ColorType::Indexed);
+      assert_eq!(info.bit_depth, BitDepth::Four);
+      assert_eq!(palette_len, Some(16));
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_write_png_max_colors_keeps_exact_palette() -> TestResult {
+    let image = RgbaImage::from_fn(8, 8, |x, _| {
+      if x < 4 {
+        Rgba([255, 0, 0, 255])
+      } else {
+        Rgba([0, 0, 255, 128])
+      }
+    });
+
+    let mut output = Vec::new();
+    write_png(
+      &image,
+      &mut output,
+      PngEncodeOptions {
+        max_colors: Some(16),
+        ..Default::default()
+      },
+      None,
+    )?;
+
+    let (info, _, palette_len) = decode(&output)?;
+    assert_eq!(info.color_type, ColorType::Indexed);
+    assert_eq!(info.bit_depth, BitDepth::One);
+    assert_eq!(palette_len, Some(2));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_encode_animated_png_palette_keeps_opaque_pixels_opaque() -> TestResult {
+    let image = RgbaImage::from_fn(8, 8, |x, _| {
+      if x < 4 {
+        Rgba([255, 0, 0, 255])
+      } else {
+        Rgba([0, 0, 255, 255])
+      }
+    });
+
+    let mut output = Vec::new();
+    encode_animated_png(
+      &[AnimationFrame::new(image.clone(), 100)],
+      &mut output,
+      None,
+    )?;
+
+    let reader = png::Decoder::new(std::io::Cursor::new(&output)).read_info()?;
+    assert_eq!(reader.info().color_type, ColorType::Indexed);
+    assert_eq!(reader.info().trns, None);
+
+    assert_eq!(image::load_from_memory(&output)?.into_rgba8(), image);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_write_png_16_bit() -> TestResult {
+    let mut image = gradient();
+    image.put_pixel(0, 0, Rgba([10, 20, 30, 40]));
+
+    let mut output = Vec::new();
+    write_png(
+      &image,
+      &mut output,
+      PngEncodeOptions {
+        bit_depth: PngBitDepth::Sixteen,
+        ..Default::default()
+      },
+      None,
+    )?;
+
+    let (info, data, _) = decode(&output)?;
+    assert_eq!(info.color_type, ColorType::Rgba);
+    assert_eq!(info.bit_depth, BitDepth::Sixteen);
+
+    let expected: Vec<u8> = image
+      .as_raw()
+      .iter()
+      .flat_map(|&value| (value as u16 * 257).to_be_bytes())
+      .collect();
+    assert_eq!(data, expected);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_write_png_rgba16() -> TestResult {
+    let image = Rgba16Image::from_fn(16, 8, |x, y| {
+      Rgba([x as u16 * 4001, y as u16 * 8009, 1234, u16::MAX])
+    });
+
+    let mut output = Vec::new();
+    write_png_rgba16(&image, &mut output, PngEncodeOptions::default(), None)?;
+
+    let (info, data, _) = decode(&output)?;
+    assert_eq!(info.color_type, ColorType::Rgb);
+    assert_eq!(info.bit_depth, BitDepth::Sixteen);
+
+    let expected: Vec<u8> = image
+      .pixels()
+      .flat_map(|pixel| pixel.0.into_iter().take(3))
+      .flat_map(u16::to_be_bytes)
+      .collect();
+    assert_eq!(data, expected);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_write_lossy_webp_round_trip() -> TestResult {
+    // Opaque on the left, with alpha steps on the right.
+    let image = RgbaImage::from_fn(48, 40, |x, y| {
+      let alpha = if x < 24 {
+        255
+      } else {
+        ((x - 24) * 10 + y) as u8
+      };
+
+      Rgba([(x * 5) as u8, (y * 6) as u8, ((x + y) * 3) as u8, alpha])
+    });
+
+    let mut output = Vec::new();
+    write_image_with_options(
+      &image,
+      &mut output,
+      ImageOutputFormat::WebP,
+      ImageEncodeOptions {
+        quality: Some(90),
+        webp_compression: WebPCompression::Lossy,
+        ..Default::default()
+      },
+    )?;
+
+    let mut decoder = image_webp::WebPDecoder::new(std::io::Cursor::new(output))?;
+    assert!(decoder.is_lossy());
+    assert!(decoder.has_alpha());
+    assert_eq!(decoder.dimensions(), (48, 40));
+
+    let mut data = vec![0; decoder.output_buffer_size().ok_or("image too large")?];
+    decoder.read_image(&mut data)?;
+
+    let decoded = RgbaImage::from_raw(48, 40, data).ok_or("wrong buffer size")?;
+
+    let mut squared_error = 0.0;
+    let mut samples = 0;
+
+    for (expected, actual) in image.pixels().zip(decoded.pixels()) {
+      assert_eq!(expected[3], actual[3]);
+
+      // Colors of translucent pixels are not compared, they are blended with their neighbors.
+      if expected[3] == u8::MAX {
+        for channel in 0..3 {
+          squared_error += (expected[channel] as f64 - actual[channel] as f64).powi(2);
+          samples += 1;
+        }
+      }
+    }
+
+    let psnr = 10.0 * (255.0 * 255.0 / (squared_error / samples as f64)).log10();
+    assert!(psnr > 35.0, "PSNR {psnr}");
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_encode_animated_png_frame_controls() -> TestResult {
+    let background = RgbaImage::from_pixel(64, 32, Rgba([255, 255, 255, 255]));
+
+    let mut moved = background.clone();
+    for (x, y) in (10..14).flat_map(|x| (5..8).map(move |y| (x, y))) {
+      moved.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+    }
+
+    let mut grown = moved.clone();
+    for (x, y) in (40..46).flat_map(|x| (20..22).map(move |y| (x, y))) {
+      grown.put_pixel(x, y, Rgba([0, 0, 255, 255]));
+    }
+
+    let frames = [
+      AnimationFrame::new(background, 100),
+      AnimationFrame::new(moved.clone(), 250),
+      // Unchanged, so its duration is added to the previous frame.
+      AnimationFrame::new(moved, 50),
+      AnimationFrame::new(grown, 70_000),
+    ];
+
+    let mut output = Vec::new();
+    encode_animated_png(&frames, &mut output, None)?;
+
+    let mut reader = png::Decoder::new(std::io::Cursor::new(output)).read_info()?;
+    assert_eq!(
+      reader
+        .info()
+        .animation_control
+        .map(|control| control.num_frames),
+      Some(3)
+    );
+
+    let mut buffer = vec![0; reader.output_buffer_size().unwrap_or_default()];
+    let mut controls = Vec::new();
+
+    for _ in 0..3 {
+      reader.next_frame(&mut buffer)?;
+
+      let control = reader.info().frame_control.ok_or("missing fcTL")?;
+      controls.push((
+        control.x_offset,
+        control.y_offset,
+        control.width,
+        control.height,
+        control.delay_num,
+        control.delay_den,
+      ));
+    }
+
+    assert_eq!(
+      controls,
+      [
+        (0, 0, 64, 32, 100, 1000),
+        (10, 5, 4, 3, 300, 1000),
+        (40, 20, 6, 2, 7000, 100),
+      ]
+    );
+
+    Ok(())
+  }
+}
@@ -0,0 +1,119 @@
+use std::io::Cursor;
+
+use takumi::{
+  image::{ColorType, ImageDecoder, codecs::png::PngDecoder},
+  layout::{
+    node::{ContainerNode, NodeKind},
+    style::{Length::*, *},
+  },
+  rendering::{
+    IccProfile, ImageMetadata, PngEncodeOptions, RenderOptionsBuilder, render, render_rgba16,
+    write_png_rgba16,
+  },
+};
+
+mod test_utils;
+use test_utils::{create_test_context, create_test_viewport};
+
+fn create_poster() -> NodeKind {
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Px(300.0))
+        .height(Px(600.0))
+        .background_image(Some(
+          BackgroundImages::from_str("linear-gradient(to bottom, #ff0000, #0000ff)").unwrap(),
+        ))
+        .display(Display::Flex)
+        .justify_content(JustifyContent::Center)
+        .align_items(AlignItems::Center)
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      ContainerNode {
+        preset: None,
+        tw: None,
+        style: Some(
+          StyleBuilder::default()
+            .width(Px(120.0))
+            .height(Px(120.0))
+            .rotate(Some(Angle::new(30.0)))
+            .background_color(ColorInput::Value(Color([0, 255, 0, 200])))
+            .border_radius(BorderRadius(Sides([SpacePair::from_single(Px(16.0)); 4])))
+            .build()
+            .unwrap(),
+        ),
+        children: None,
+      }
+      .into(),
+    ]),
+  }
+  .into()
+}
+
+#[test]
+fn color_space_rgba16_keeps_precision() {
+  let global = create_test_context();
+
+  let options = || {
+    RenderOptionsBuilder::default()
+      .viewport(create_test_viewport())
+      .node(create_poster())
+      .global(&global)
+      .build()
+      .unwrap()
+  };
+
+  let image = render(options()).unwrap();
+  let wide = render_rgba16(options()).unwrap();
+
+  for (pixel, wide) in image.pixels().zip(wide.pixels()) {
+    for (value, wide) in pixel.0.into_iter().zip(wide.0) {
+      assert!((value as i32 - (wide as f32 / 257.0).round() as i32).abs() <= 1);
+    }
+  }
+
+  assert!(
+    wide
+      .pixels()
+      .any(|pixel| pixel.0.iter().any(|value| value % 257 != 0))
+  );
+}
+
+#[test]
+fn color_space_display_p3_png_output() {
+  let global = create_test_context();
+
+  let image = render_rgba16(
+    RenderOptionsBuilder::default()
+      .viewport(create_test_viewport())
+      .node(create_poster())
+      .global(&global)
+      .color_space(ColorSpace::DisplayP3)
+      .build()
+      .unwrap(),
+  )
+  .unwrap();
+
+  let mut output = Vec::new();
+
+  write_png_rgba16(
+    &image,
+    &mut output,
+    PngEncodeOptions::default(),
+    Some(&ImageMetadata {
+      icc_profile: Some(IccProfile::DisplayP3),
+      ..Default::default()
+    }),
+  )
+  .unwrap();
+
+  let mut decoder = PngDecoder::new(Cursor::new(output)).unwrap();
+
+  // The poster doesn't cover the whole viewport, so the alpha channel is kept.
+  assert_eq!(decoder.color_type(), ColorType::Rgba16);
+  assert!(decoder.icc_profile().unwrap().is_some());
+}
@@ -0,0 +1,121 @@
+use takumi::{
+  GlobalContext,
+  layout::{
+    node::{ContainerNode, NodeKind, TextNode},
+    style::{Length::*, *},
+  },
+  rendering::{DisplayCommand, DisplayList, RenderOptionsBuilder, record_display_list, render},
+};
+
+mod test_utils;
+use test_utils::create_test_context;
+
+fn create_card() -> NodeKind {
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Px(400.0))
+        .height(Px(200.0))
+        .padding(Sides([Px(24.0); 4]))
+        .overflow(SpacePair::from_single(Overflow::Hidden))
+        .opacity(PercentageNumber(0.5))
+        .background_image(Some(
+          BackgroundImages::from_str("linear-gradient(to right, #ff0000, #0000ff)").unwrap(),
+        ))
+        .border_radius(BorderRadius(Sides([SpacePair::from_single(Px(16.0)); 4])))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      TextNode {
+        preset: None,
+        tw: None,
+        style: Some(
+          StyleBuilder::default()
+            .font_size(Some(Px(48.0)))
+            .color(ColorInput::Value(Color::white()))
+            .build()
+            .unwrap(),
+        ),
+        text: "Display list".to_string(),
+      }
+      .into(),
+    ]),
+  }
+  .into()
+}
+
+fn record_card(global: &GlobalContext) -> DisplayList {
+  record_display_list(
+    RenderOptionsBuilder::default()
+      .viewport((400, 200).into())
+      .node(create_card())
+      .global(global)
+      .build()
+      .unwrap(),
+  )
+  .unwrap()
+}
+
+#[test]
+fn display_list_records_commands() {
+  let global = create_test_context();
+  let display_list = record_card(&global);
+
+  assert_eq!(display_list.size().width, 400);
+  assert_eq!(display_list.size().height, 200);
+
+  // Recording doesn't depend on anything but the layout, so it can be compared between frames.
+  assert_eq!(display_list, record_card(&global));
+
+  let count =
+    |f: fn(&DisplayCommand) -> bool| display_list.commands().iter().filter(|c| f(c)).count();
+
+  assert_eq!(
+    count(|c| matches!(c, DisplayCommand::PushClip { .. })),
+    count(|c| matches!(c, DisplayCommand::PopClip))
+  );
+  assert_eq!(count(|c| matches!(c, DisplayCommand::PushLayer)), 1);
+  assert_eq!(
+    count(|c| matches!(c, DisplayCommand::PopLayer(effects) if effects.group_opacity() == 127)),
+    1
+  );
+  assert!(count(|c| matches!(c, DisplayCommand::FillPath { .. })) > 0);
+  assert!(count(|c| matches!(c, DisplayCommand::DrawImage(_))) > 0);
+}
+
+#[test]
+fn display_list_rasterize() {
+  let global = create_test_context();
+  let image = record_card(&global).rasterize();
+
+  assert_eq!(image.dimensions(), (400, 200));
+
+  // The corners are clipped by the border radius, the gradient is composited at half opacity.
+  assert_eq!(image.get_pixel(0, 0).0[3], 0);
+
+  let left = image.get_pixel(4, 100).0;
+
+  assert!(left[0] > left[2]);
+  assert!((120..=136).contains(&left[3]));
+
+  let right = image.get_pixel(395, 100).0;
+
+  assert!(right[2] > right[0]);
+
+  // Rendering goes through the display list, so both give the same pixels.
+  assert_eq!(
+    image,
+    render(
+      RenderOptionsBuilder::default()
+        .viewport((400, 200).into())
+        .node(create_card())
+        .global(&global)
+        .build()
+        .unwrap(),
+    )
+    .unwrap()
+  );
+}
@@ -1,14 +1,20 @@
 use smallvec::smallvec;
 use std::f32::consts::PI;
-use takumi::layout::{
-  node::{ContainerNode, NodeKind, TextNode},
-  style::{Length::*, *},
+use takumi::{
+  layout::{
+    node::{ContainerNode, NodeKind, TextNode},
+    style::{Length::*, *},
+  },
+  rendering::{GifEncodeOptions, GifPalette, WebPAnimationOptions},
 };
 
 mod test_utils;
 use test_utils::run_webp_animation_test;
 
-use crate::test_utils::run_png_animation_test;
+use crate::test_utils::{
+  run_gif_animation_test, run_png_animation_test, run_streaming_png_animation_test,
+  run_streaming_webp_animation_test,
+};
 
 fn create_bouncing_text_nodes() -> Vec<(NodeKind, u32)> {
   const FPS: u32 = 30;
@@ -85,6 +91,32 @@ fn fixtures_animation_bouncing_text_webp() {
     true,
     false,
     None,
+    None,
+  );
+}
+
+#[test]
+fn fixtures_animation_bouncing_text_webp_lossy() {
+  run_webp_animation_test(
+    create_bouncing_text_nodes(),
+    "tests/fixtures/animation_bouncing_text_lossy.webp",
+    true,
+    false,
+    None,
+    Some(75),
+  );
+}
+
+#[test]
+fn fixtures_animation_bouncing_text_webp_streaming() {
+  run_streaming_webp_animation_test(
+    create_bouncing_text_nodes(),
+    "tests/fixtures/animation_bouncing_text_streaming.webp",
+    WebPAnimationOptions {
+      blend: true,
+      quality: Some(75),
+      ..Default::default()
+    },
   );
 }
 
@@ -96,3 +128,34 @@ fn fixtures_animation_bouncing_text_png() {
     None,
   );
 }
+
+#[test]
+fn fixtures_animation_bouncing_text_png_streaming() {
+  run_streaming_png_animation_test(
+    create_bouncing_text_nodes(),
+    "tests/fixtures/animation_bouncing_text_streaming.png",
+  );
+}
+
+#[test]
+fn fixtures_animation_bouncing_text_gif() {
+  run_gif_animation_test(
+    create_bouncing_text_nodes(),
+    "tests/fixtures/animation_bouncing_text.gif",
+    None,
+    GifEncodeOptions::default(),
+  );
+}
+
+#[test]
+fn fixtures_animation_bouncing_text_gif_per_frame_dither() {
+  run_gif_animation_test(
+    create_bouncing_text_nodes(),
+    "tests/fixtures/animation_bouncing_text_per_frame_dither.gif",
+    None,
+    GifEncodeOptions {
+      palette: GifPalette::PerFrame,
+      dither: true,
+    },
+  );
+}
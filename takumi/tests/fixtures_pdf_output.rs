@@ -0,0 +1,117 @@
+use takumi::layout::{
+  node::{ContainerNode, ImageNode, NodeKind, TextNode},
+  style::{Length::*, *},
+};
+
+mod test_utils;
+use test_utils::run_pdf_output_test;
+
+fn create_page(children: Vec<NodeKind>) -> NodeKind {
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Percentage(100.0))
+        .height(Percentage(100.0))
+        .display(Display::Flex)
+        .flex_direction(FlexDirection::Column)
+        .justify_content(JustifyContent::Center)
+        .align_items(AlignItems::Center)
+        .gap(SpacePair::from_single(Px(24.0)))
+        .background_color(ColorInput::Value(Color([240, 240, 240, 255])))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(children),
+  }
+  .into()
+}
+
+#[test]
+fn fixtures_pdf_output_pages() {
+  let card = ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Px(600.0))
+        .height(Px(300.0))
+        .display(Display::Flex)
+        .flex_direction(FlexDirection::Column)
+        .justify_content(JustifyContent::Center)
+        .align_items(AlignItems::Center)
+        .padding(Sides([Px(24.0); 4]))
+        .border_width(Some(Sides([Px(4.0); 4])))
+        .border_color(Some(ColorInput::Value(Color([255, 255, 255, 128]))))
+        .border_radius(BorderRadius(Sides([SpacePair::from_single(Px(24.0)); 4])))
+        .background_image(Some(
+          BackgroundImages::from_str(
+            "linear-gradient(135deg, #2d3748 0%, #1a202c 100%), radial-gradient(circle at 80% 20%, rgba(255, 255, 255, 0.3) 0%, transparent 40%)",
+          )
+          .unwrap(),
+        ))
+        .box_shadow(Some(BoxShadows::from_str("0 8px 24px rgba(0, 0, 0, 0.5)").unwrap()))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      TextNode {
+        preset: None,
+        tw: None,
+        style: Some(
+          StyleBuilder::default()
+            .font_size(Some(Px(48.0)))
+            .font_weight(FontWeight::from(700.0))
+            .color(ColorInput::Value(Color::white()))
+            .text_decoration_line(Some(TextDecorationLines::from_str("underline").unwrap()))
+            .build()
+            .unwrap(),
+        ),
+        text: "Document output 🎉".to_string(),
+      }
+      .into(),
+      ImageNode {
+        preset: None,
+        tw: None,
+        style: Some(
+          StyleBuilder::default()
+            .width(Px(64.0))
+            .height(Px(64.0))
+            .border_radius(BorderRadius(Sides([SpacePair::from_single(Percentage(50.0)); 4])))
+            .build()
+            .unwrap(),
+        ),
+        width: None,
+        height: None,
+        src: "assets/images/yeecord.png".into(),
+      }
+      .into(),
+    ]),
+  };
+
+  let paragraph = TextNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Px(800.0))
+        .font_size(Some(Px(32.0)))
+        .font_family(Some(FontFamily::from("Archivo")))
+        .color(ColorInput::Value(Color([17, 24, 39, 255])))
+        .line_clamp(Some(3.into()))
+        .text_overflow(TextOverflow::Ellipsis)
+        .build()
+        .unwrap(),
+    ),
+    text: "Text in PDF documents is kept as real text with embedded font subsets, so it stays selectable and searchable. The fine ligatures and variable font weights are preserved, and long paragraphs are still clamped to the requested number of lines with an ellipsis at the end.".to_string(),
+  };
+
+  run_pdf_output_test(
+    vec![
+      create_page(vec![card.into()]),
+      create_page(vec![paragraph.into()]),
+    ],
+    "tests/fixtures/pdf_output_pages.pdf",
+  );
+}
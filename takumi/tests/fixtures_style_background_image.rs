@@ -1,12 +1,15 @@
-use takumi::layout::{
-  node::{ContainerNode, NodeKind},
-  style::{Length::*, *},
+use takumi::{
+  layout::{
+    node::{ContainerNode, NodeKind},
+    style::{Length::*, *},
+  },
+  rendering::ColorInterpolation,
 };
 
 use smallvec::smallvec;
 
 mod test_utils;
-use test_utils::run_style_width_test;
+use test_utils::{run_style_width_test, run_style_width_test_with};
 
 fn create_container(background_images: BackgroundImages) -> ContainerNode<NodeKind> {
   ContainerNode {
@@ -307,6 +310,64 @@ fn test_background_image_dotted_pattern() {
   );
 }
 
+#[test]
+fn test_style_background_image_conic_gradient() {
+  let background_images = BackgroundImages::from_str(
+    "conic-gradient(from 45deg at 50% 50%, #ff3b30, #ffcc00, #34c759, #007aff, #ff3b30)",
+  )
+  .unwrap();
+
+  let container = create_container(background_images);
+
+  run_style_width_test(
+    container.into(),
+    "tests/fixtures/style_background_image_conic_gradient.png",
+  );
+}
+
+#[test]
+fn test_style_background_image_conic_gradient_progress() {
+  let background_images =
+    BackgroundImages::from_str("conic-gradient(#34c759 0.7turn, #e5e5ea 0.7turn)").unwrap();
+
+  let container = create_container(background_images);
+
+  run_style_width_test(
+    container.into(),
+    "tests/fixtures/style_background_image_conic_gradient_progress.png",
+  );
+}
+
+#[test]
+fn test_style_background_image_repeating_linear_gradient() {
+  let background_images = BackgroundImages::from_str(
+    "repeating-linear-gradient(45deg, #252525 0px, #252525 10px, #fcd50e 10px, #fcd50e 20px)",
+  )
+  .unwrap();
+
+  let container = create_container(background_images);
+
+  run_style_width_test(
+    container.into(),
+    "tests/fixtures/style_background_image_repeating_linear_gradient.png",
+  );
+}
+
+#[test]
+fn test_style_background_image_repeating_radial_gradient() {
+  let background_images = BackgroundImages::from_str(
+    "repeating-radial-gradient(circle, #007aff 0px, #007aff 8px, #f5f5f5 8px, #f5f5f5 16px)",
+  )
+  .unwrap();
+
+  let container = create_container(background_images);
+
+  run_style_width_test(
+    container.into(),
+    "tests/fixtures/style_background_image_repeating_radial_gradient.png",
+  );
+}
+
 #[test]
 fn test_background_size_contain() {
   let images = BackgroundImages::from_str("url(assets/images/yeecord.png)").unwrap();
@@ -338,3 +399,61 @@ fn test_background_size_cover() {
     "tests/fixtures/style_background_size_cover.png",
   );
 }
+
+#[test]
+fn test_style_background_image_linear_rgb() {
+  let band = |style: Style| -> NodeKind {
+    ContainerNode {
+      preset: None,
+      tw: None,
+      style: Some(style),
+      children: None,
+    }
+    .into()
+  };
+
+  let container = ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Percentage(100.0))
+        .height(Percentage(100.0))
+        .flex_direction(FlexDirection::Column)
+        .background_color(ColorInput::Value(Color::white()))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      band(
+        StyleBuilder::default()
+          .width(Percentage(100.0))
+          .height(Percentage(50.0))
+          .background_image(Some(
+            BackgroundImages::from_str("linear-gradient(to right, #ff0000, #00ff00, #0000ff)")
+              .unwrap(),
+          ))
+          .build()
+          .unwrap(),
+      ),
+      band(
+        StyleBuilder::default()
+          .width(Percentage(100.0))
+          .height(Percentage(50.0))
+          .background_image(Some(
+            BackgroundImages::from_str("linear-gradient(to right, #00000000, #000000)").unwrap(),
+          ))
+          .build()
+          .unwrap(),
+      ),
+    ]),
+  };
+
+  run_style_width_test_with(
+    container.into(),
+    "tests/fixtures/style_background_image_linear_rgb.png",
+    |options| {
+      options.color_interpolation(ColorInterpolation::LinearRgb);
+    },
+  );
+}
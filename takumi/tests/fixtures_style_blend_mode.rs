@@ -0,0 +1,198 @@
+use takumi::{
+  layout::{
+    node::{ContainerNode, ImageNode, NodeKind, TextNode},
+    style::{Length::*, *},
+  },
+  rendering::ColorInterpolation,
+};
+
+mod test_utils;
+use test_utils::{run_style_width_test, run_style_width_test_with};
+
+const BLEND_MODES: &[&str] = &[
+  "normal",
+  "multiply",
+  "screen",
+  "overlay",
+  "darken",
+  "lighten",
+  "color-dodge",
+  "color-burn",
+  "hard-light",
+  "soft-light",
+  "difference",
+  "exclusion",
+  "hue",
+  "saturation",
+  "color",
+  "luminosity",
+];
+
+fn create_blend_grid(cards: Vec<NodeKind>) -> NodeKind {
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Percentage(100.0))
+        .height(Percentage(100.0))
+        .display(Display::Grid)
+        .grid_template_columns(GridTemplateComponents::from_str("repeat(8, 1fr)").ok())
+        .gap(SpacePair::from_single(Px(12.0)))
+        .padding(Sides([Px(16.0); 4]))
+        .background_color(ColorInput::Value(Color([255, 255, 255, 255])))
+        .font_size(Some(Px(16.0)))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(cards),
+  }
+  .into()
+}
+
+fn create_label(text: &str) -> NodeKind {
+  TextNode {
+    preset: None,
+    tw: None,
+    style: None,
+    text: text.to_string(),
+  }
+  .into()
+}
+
+fn create_mix_blend_card(mode: &str) -> NodeKind {
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .flex_direction(FlexDirection::Column)
+        .align_items(AlignItems::Center)
+        .gap(SpacePair::from_single(Px(8.0)))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      ContainerNode {
+        preset: None,
+        tw: None,
+        style: Some(
+          StyleBuilder::default()
+            .width(Px(120.0))
+            .height(Px(120.0))
+            .background_image(Some(
+              BackgroundImages::from_str("linear-gradient(135deg, #f97316, #0ea5e9)").unwrap(),
+            ))
+            .build()
+            .unwrap(),
+        ),
+        children: Some(vec![
+          ImageNode {
+            preset: None,
+            tw: None,
+            src: "assets/images/yeecord.png".into(),
+            style: Some(
+              StyleBuilder::default()
+                .width(Px(120.0))
+                .height(Px(120.0))
+                .mix_blend_mode(BlendMode::from_str(mode).unwrap())
+                .build()
+                .unwrap(),
+            ),
+            width: None,
+            height: None,
+          }
+          .into(),
+        ]),
+      }
+      .into(),
+      create_label(mode),
+    ]),
+  }
+  .into()
+}
+
+fn create_background_blend_card(mode: &str) -> NodeKind {
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .flex_direction(FlexDirection::Column)
+        .align_items(AlignItems::Center)
+        .gap(SpacePair::from_single(Px(8.0)))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      ContainerNode {
+        preset: None,
+        tw: None,
+        style: Some(
+          StyleBuilder::default()
+            .width(Px(120.0))
+            .height(Px(120.0))
+            .border_radius(BorderRadius(Sides(
+              [SpacePair::from_single(Px(16.0)); 4],
+            )))
+            .background_color(ColorInput::Value(Color([250, 204, 21, 255])))
+            .background_image(Some(
+              BackgroundImages::from_str(
+                "radial-gradient(circle, #ffffff, #000000), linear-gradient(90deg, #ef4444, #3b82f6)",
+              )
+              .unwrap(),
+            ))
+            .background_blend_mode(BlendModes::from_str(mode).unwrap())
+            .build()
+            .unwrap(),
+        ),
+        children: None,
+      }
+      .into(),
+      create_label(mode),
+    ]),
+  }
+  .into()
+}
+
+#[test]
+fn test_style_mix_blend_mode() {
+  let cards = BLEND_MODES
+    .iter()
+    .map(|mode| create_mix_blend_card(mode))
+    .collect();
+
+  run_style_width_test(
+    create_blend_grid(cards),
+    "tests/fixtures/style_mix_blend_mode.png",
+  );
+}
+
+#[test]
+fn test_style_background_blend_mode() {
+  let cards = BLEND_MODES
+    .iter()
+    .map(|mode| create_background_blend_card(mode))
+    .collect();
+
+  run_style_width_test(
+    create_blend_grid(cards),
+    "tests/fixtures/style_background_blend_mode.png",
+  );
+}
+
+#[test]
+fn test_style_mix_blend_mode_linear_rgb() {
+  let cards = BLEND_MODES
+    .iter()
+    .map(|mode| create_mix_blend_card(mode))
+    .collect();
+
+  run_style_width_test_with(
+    create_blend_grid(cards),
+    "tests/fixtures/style_mix_blend_mode_linear_rgb.png",
+    |options| {
+      options.color_interpolation(ColorInterpolation::LinearRgb);
+    },
+  );
+}
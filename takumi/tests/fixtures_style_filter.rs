@@ -1,10 +1,13 @@
-use takumi::layout::{
-  node::{ContainerNode, ImageNode, NodeKind, TextNode},
-  style::{Length::*, *},
+use takumi::{
+  layout::{
+    node::{ContainerNode, ImageNode, NodeKind, TextNode},
+    style::{Length::*, *},
+  },
+  rendering::ColorInterpolation,
 };
 
 mod test_utils;
-use test_utils::run_style_width_test;
+use test_utils::{run_style_width_test, run_style_width_test_with};
 
 /// Helper function to create a filter test container with labeled images.
 /// All sizes are in pixels for simplicity.
@@ -135,6 +138,25 @@ fn test_style_filter_combined() {
   run_style_width_test(container, "tests/fixtures/style_filter_combined.png");
 }
 
+#[test]
+fn test_style_filter_combined_linear_rgb() {
+  let combined_filters = [
+    "blur(3px) grayscale(50%)",
+    "drop-shadow(5px 5px 10px black) brightness(120%)",
+    "blur(2px) drop-shadow(3px 3px 5px red)",
+    "saturate(150%) blur(1px)",
+  ];
+
+  let container = create_filter_test_container(&combined_filters, 16.0, 140.0, 16.0);
+  run_style_width_test_with(
+    container,
+    "tests/fixtures/style_filter_combined_linear_rgb.png",
+    |options| {
+      options.color_interpolation(ColorInterpolation::LinearRgb);
+    },
+  );
+}
+
 #[test]
 fn test_style_filter_sepia() {
   let sepia_values = ["sepia(0%)", "sepia(50%)", "sepia(75%)", "sepia(100%)"];
@@ -142,3 +164,86 @@ fn test_style_filter_sepia() {
   let container = create_filter_test_container(&sepia_values, 16.0, 150.0, 24.0);
   run_style_width_test(container, "tests/fixtures/style_filter_sepia.png");
 }
+
+fn create_frosted_panel(backdrop_filter: &str, left_px: f32) -> NodeKind {
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .position(Position::Absolute)
+        .top(Some(Px(160.0)))
+        .left(Some(Px(left_px)))
+        .width(Px(320.0))
+        .height(Px(240.0))
+        .display(Display::Flex)
+        .align_items(AlignItems::Center)
+        .justify_content(JustifyContent::Center)
+        .border_radius(BorderRadius(Sides([SpacePair::from_single(Px(32.0)); 4])))
+        .border_width(Some(Sides([Px(1.0); 4])))
+        .border_color(Some(ColorInput::Value(Color([255, 255, 255, 128]))))
+        .background_color(ColorInput::Value(Color([255, 255, 255, 48])))
+        .backdrop_filter(Filters::from_str(backdrop_filter).unwrap())
+        .font_size(Some(Px(24.0)))
+        .color(ColorInput::Value(Color([255, 255, 255, 255])))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      TextNode {
+        preset: None,
+        tw: None,
+        style: None,
+        text: backdrop_filter.to_string(),
+      }
+      .into(),
+    ]),
+  }
+  .into()
+}
+
+#[test]
+fn test_style_backdrop_filter() {
+  let container = ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Percentage(100.0))
+        .height(Percentage(100.0))
+        .background_image(Some(
+          BackgroundImages::from_str(
+            "linear-gradient(90deg, #0ea5e9 25%, #f97316 25%, #f97316 50%, #22c55e 50%, #22c55e 75%, #a855f7 75%)",
+          )
+          .unwrap(),
+        ))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      ImageNode {
+        preset: None,
+        tw: None,
+        src: "assets/images/yeecord.png".into(),
+        style: Some(
+          StyleBuilder::default()
+            .position(Position::Absolute)
+            .top(Some(Px(40.0)))
+            .left(Some(Px(440.0)))
+            .width(Px(320.0))
+            .height(Px(320.0))
+            .build()
+            .unwrap(),
+        ),
+        width: None,
+        height: None,
+      }
+      .into(),
+      create_frosted_panel("blur(16px)", 80.0),
+      create_frosted_panel("blur(8px) saturate(1.8)", 440.0),
+      create_frosted_panel("grayscale(1) brightness(0.6)", 800.0),
+    ]),
+  };
+
+  run_style_width_test(container.into(), "tests/fixtures/style_backdrop_filter.png");
+}
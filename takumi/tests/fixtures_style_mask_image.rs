@@ -212,3 +212,31 @@ fn test_style_mask_image_corner_fade() {
     "tests/fixtures/style_mask_image_corner_fade.png",
   );
 }
+
+#[test]
+fn test_style_mask_image_conic_gradient() {
+  let mask_image =
+    BackgroundImages::from_str("conic-gradient(black 0.75turn, transparent 0.75turn)").unwrap();
+
+  let container = create_container_with_mask(mask_image, Color([88, 86, 214, 255]));
+
+  run_style_width_test(
+    container.into(),
+    "tests/fixtures/style_mask_image_conic_gradient.png",
+  );
+}
+
+#[test]
+fn test_style_mask_image_repeating_linear_gradient() {
+  let mask_image = BackgroundImages::from_str(
+    "repeating-linear-gradient(90deg, black 0px, black 20px, transparent 20px, transparent 40px)",
+  )
+  .unwrap();
+
+  let container = create_container_with_mask(mask_image, Color([255, 20, 147, 255]));
+
+  run_style_width_test(
+    container.into(),
+    "tests/fixtures/style_mask_image_repeating_linear_gradient.png",
+  );
+}
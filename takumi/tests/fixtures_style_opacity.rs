@@ -136,3 +136,121 @@ fn test_style_opacity_image_with_text() {
     "tests/fixtures/style_opacity_image_with_text.png",
   );
 }
+
+fn create_overlapping_circles(opacity: f32) -> NodeKind {
+  let circle = |color: Color, left: f32| -> NodeKind {
+    ContainerNode {
+      preset: None,
+      tw: None,
+      style: Some(
+        StyleBuilder::default()
+          .position(Position::Absolute)
+          .top(Some(Length::Px(0.0)))
+          .left(Some(Length::Px(left)))
+          .width(Length::Px(200.0))
+          .height(Length::Px(200.0))
+          .border_radius(BorderRadius(Sides(
+            [SpacePair::from_single(Length::Px(100.0)); 4],
+          )))
+          .background_color(ColorInput::Value(color))
+          .build()
+          .unwrap(),
+      ),
+      children: None,
+    }
+    .into()
+  };
+
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Length::Px(320.0))
+        .height(Length::Px(200.0))
+        .opacity(PercentageNumber(opacity))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      circle(Color([239, 68, 68, 255]), 0.0),
+      circle(Color([59, 130, 246, 255]), 120.0),
+    ]),
+  }
+  .into()
+}
+
+#[test]
+fn test_style_opacity_group() {
+  let container = ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Length::Percentage(100.0))
+        .height(Length::Percentage(100.0))
+        .justify_content(JustifyContent::Center)
+        .align_items(AlignItems::Center)
+        .background_color(ColorInput::Value(Color([255, 255, 255, 255])))
+        .gap(SpacePair::from_single(Length::Rem(4.0)))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      // The circles are composited as a group, so the red one doesn't show through the blue one.
+      create_overlapping_circles(0.5),
+      create_overlapping_circles(1.0),
+    ]),
+  };
+
+  run_style_width_test(container.into(), "tests/fixtures/style_opacity_group.png");
+}
+
+fn create_bordered_leaf(opacity: f32) -> NodeKind {
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Length::Px(200.0))
+        .height(Length::Px(200.0))
+        .opacity(PercentageNumber(opacity))
+        .border_width(Some(Sides([Length::Px(24.0); 4])))
+        .border_color(Some(ColorInput::Value(Color([59, 130, 246, 160]))))
+        .background_color(ColorInput::Value(Color([239, 68, 68, 255])))
+        .build()
+        .unwrap(),
+    ),
+    children: None,
+  }
+  .into()
+}
+
+#[test]
+fn test_style_opacity_group_leaf_border() {
+  let container = ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Length::Percentage(100.0))
+        .height(Length::Percentage(100.0))
+        .justify_content(JustifyContent::Center)
+        .align_items(AlignItems::Center)
+        .background_color(ColorInput::Value(Color([255, 255, 255, 255])))
+        .gap(SpacePair::from_single(Length::Rem(4.0)))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      // The background under the translucent border must match the opaque leaf, faded as a whole.
+      create_bordered_leaf(0.5),
+      create_bordered_leaf(1.0),
+    ]),
+  };
+
+  run_style_width_test(
+    container.into(),
+    "tests/fixtures/style_opacity_group_leaf_border.png",
+  );
+}
@@ -1,12 +1,12 @@
 use serde_json::{from_value, json};
 use smallvec::smallvec;
 use takumi::layout::{
-  node::{ContainerNode, ImageNode, TextNode},
+  node::{ContainerNode, ImageNode, NodeKind, TextNode},
   style::{Length::*, *},
 };
 
 mod test_utils;
-use test_utils::run_style_width_test;
+use test_utils::{run_style_width_test, run_style_width_test_with};
 
 #[test]
 fn test_style_background_color() {
@@ -30,6 +30,65 @@ fn test_style_background_color() {
   );
 }
 
+/// Swatches of sRGB colors next to the Display P3 colors with the same channels.
+fn create_wide_color_swatches() -> NodeKind {
+  let swatch = |color: &str| -> NodeKind {
+    ContainerNode {
+      preset: None,
+      tw: None,
+      style: Some(
+        StyleBuilder::default()
+          .flex_grow(Some(FlexGrow(1.0)))
+          .height(Percentage(100.0))
+          .background_color(ColorInput::from_str(color).unwrap())
+          .build()
+          .unwrap(),
+      ),
+      children: None,
+    }
+    .into()
+  };
+
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Percentage(100.0))
+        .height(Percentage(100.0))
+        .display(Display::Flex)
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      swatch("#ff0000"),
+      swatch("color(display-p3 1 0 0)"),
+      swatch("#00ff00"),
+      swatch("color(display-p3 0 1 0)"),
+    ]),
+  }
+  .into()
+}
+
+#[test]
+fn test_style_background_color_wide_gamut_srgb() {
+  run_style_width_test(
+    create_wide_color_swatches(),
+    "tests/fixtures/style_background_color_wide_gamut_srgb.png",
+  );
+}
+
+#[test]
+fn test_style_background_color_wide_gamut_display_p3() {
+  run_style_width_test_with(
+    create_wide_color_swatches(),
+    "tests/fixtures/style_background_color_wide_gamut_display_p3.png",
+    |options| {
+      options.color_space(ColorSpace::DisplayP3);
+    },
+  );
+}
+
 #[test]
 fn test_style_border_radius() {
   let container = ContainerNode {
@@ -444,3 +503,205 @@ fn test_style_border_width_on_image_node() {
     "tests/fixtures/style_border_width_on_image_node.png",
   );
 }
+
+fn create_border_style_grid(boxes: Vec<serde_json::Value>) -> NodeKind {
+  from_value(json!({
+    "type": "container",
+    "style": {
+      "width": "100%",
+      "height": "100%",
+      "display": "flex",
+      "flexWrap": "wrap",
+      "gap": 24,
+      "padding": 24,
+      "backgroundColor": "white"
+    },
+    "children": boxes
+  }))
+  .unwrap()
+}
+
+#[test]
+fn test_style_border_style() {
+  let boxes = [
+    "solid", "dashed", "dotted", "double", "groove", "ridge", "inset", "outset", "none",
+  ]
+  .into_iter()
+  .flat_map(|style| {
+    [0, 32].map(|radius| {
+      json!({
+        "type": "container",
+        "style": {
+          "width": 160,
+          "height": 100,
+          "borderWidth": 12,
+          "borderStyle": style,
+          "borderColor": "#d9480f",
+          "borderRadius": radius
+        }
+      })
+    })
+  })
+  .collect();
+
+  run_style_width_test(
+    create_border_style_grid(boxes),
+    "tests/fixtures/style_border_style.png",
+  );
+}
+
+#[test]
+fn test_style_border_style_per_side() {
+  let boxes = vec![
+    json!({
+      "type": "container",
+      "style": {
+        "width": 260,
+        "height": 160,
+        "borderWidth": "4px 8px 12px 16px",
+        "borderStyle": "dashed dotted double solid",
+        "borderColor": "#1c7ed6"
+      }
+    }),
+    json!({
+      "type": "container",
+      "style": {
+        "width": 260,
+        "height": 160,
+        "borderWidth": "6px 18px",
+        "borderStyle": "dashed",
+        "borderColor": "#2b8a3e",
+        "borderRadius": "48px 8px"
+      }
+    }),
+    json!({
+      "type": "container",
+      "style": {
+        "width": 260,
+        "height": 160,
+        "borderTopWidth": 10,
+        "borderTopStyle": "dotted",
+        "borderColor": "#862e9c"
+      }
+    }),
+    json!({
+      "type": "container",
+      "style": {
+        "width": 260,
+        "height": 160,
+        "borderWidth": 14,
+        "borderStyle": "dashed",
+        "borderColor": "rgba(0, 0, 0, 0.5)",
+        "borderRadius": 40
+      }
+    }),
+  ];
+
+  run_style_width_test(
+    create_border_style_grid(boxes),
+    "tests/fixtures/style_border_style_per_side.png",
+  );
+}
+
+#[test]
+fn test_style_outline() {
+  let boxes = [
+    ("4px solid red", 2, 0),
+    ("4px solid red", -6, 0),
+    // Without a style the outline isn't drawn.
+    ("4px red", 2, 0),
+    ("6px dashed #1c7ed6", 4, 24),
+    ("8px double #2b8a3e", 0, 16),
+    ("4px dotted #862e9c", 8, 50),
+  ]
+  .into_iter()
+  .map(|(outline, offset, radius)| {
+    json!({
+      "type": "container",
+      "style": {
+        "width": 140,
+        "height": 100,
+        "margin": 16,
+        "overflow": "hidden",
+        "backgroundColor": "#4c6ef5",
+        "borderRadius": radius,
+        "outline": outline,
+        "outlineOffset": offset
+      }
+    })
+  })
+  .collect();
+
+  run_style_width_test(
+    create_border_style_grid(boxes),
+    "tests/fixtures/style_outline.png",
+  );
+}
+
+#[test]
+fn test_style_ring() {
+  let boxes = [
+    "ring-2 ring-blue-500 ring-offset-2",
+    "ring",
+    "ring-4 ring-red-500 rounded-2xl",
+    "ring-2 ring-green-600 ring-offset-4 ring-offset-slate-300 rounded-full",
+    // Rings are composed with the other shadows.
+    "shadow-xl ring-2 ring-black/20 rounded-lg",
+    // The outline is drawn outside of the ring.
+    "ring-4 ring-amber-400 outline-2 outline-red-500 outline-offset-8",
+  ]
+  .into_iter()
+  .map(|tw| {
+    json!({
+      "type": "container",
+      "tw": tw,
+      "style": {
+        "width": 140,
+        "height": 100,
+        "margin": 16,
+        "backgroundColor": "#4c6ef5"
+      }
+    })
+  })
+  .collect();
+
+  run_style_width_test(
+    create_border_style_grid(boxes),
+    "tests/fixtures/style_ring.png",
+  );
+}
+
+#[test]
+fn test_style_border_image() {
+  let boxes = [
+    "url(assets/images/yeecord.png) 40",
+    // The middle part is only drawn with `fill`.
+    "url(assets/images/yeecord.png) 40 fill",
+    "url(assets/images/yeecord.png) 40 round",
+    "url(assets/images/yeecord.png) 40 / 20px space",
+    "linear-gradient(#e03131, #1c7ed6) 10",
+    "linear-gradient(45deg, #f08c00 50%, #212529 50%) 20 / 20px repeat",
+    // Outset extends the image past the border box.
+    "linear-gradient(#2b8a3e, #2b8a3e) 10 / 1 / 10px",
+  ]
+  .into_iter()
+  .map(|border_image| {
+    json!({
+      "type": "container",
+      "style": {
+        "width": 160,
+        "height": 120,
+        "margin": 12,
+        "borderWidth": 20,
+        "borderImage": border_image,
+        "backgroundColor": "#f1f3f5"
+      }
+    })
+  })
+  .collect();
+
+  run_style_width_test(
+    create_border_style_grid(boxes),
+    "tests/fixtures/style_border_image.png",
+  );
+}
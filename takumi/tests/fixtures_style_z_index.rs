@@ -0,0 +1,132 @@
+use takumi::layout::{
+  node::{ContainerNode, NodeKind},
+  style::{Length::*, *},
+};
+
+mod test_utils;
+use test_utils::run_style_width_test;
+
+fn create_box(style: StyleBuilder) -> NodeKind {
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(style.build().unwrap()),
+    children: None,
+  }
+  .into()
+}
+
+fn create_badge(color: Color, z_index: ZIndex, inset: Sides<Length>) -> NodeKind {
+  create_box(
+    StyleBuilder::default()
+      .position(Position::Absolute)
+      .inset(inset)
+      .width(Px(80.0))
+      .height(Px(80.0))
+      .border_radius(BorderRadius(Sides([SpacePair::from_single(Px(40.0)); 4])))
+      .background_color(ColorInput::Value(color))
+      .z_index(z_index)
+      .clone(),
+  )
+}
+
+fn create_card(z_index: ZIndex, children: Vec<NodeKind>) -> NodeKind {
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Px(320.0))
+        .height(Px(240.0))
+        .display(Display::Flex)
+        .gap(SpacePair::from_single(Px(16.0)))
+        .padding(Sides([Px(24.0); 4]))
+        .background_color(ColorInput::Value(Color([226, 232, 240, 255])))
+        .z_index(z_index)
+        .build()
+        .unwrap(),
+    ),
+    children: Some(children),
+  }
+  .into()
+}
+
+fn create_tile(color: Color) -> NodeKind {
+  create_box(
+    StyleBuilder::default()
+      .width(Px(120.0))
+      .height(Px(120.0))
+      .background_color(ColorInput::Value(color))
+      .clone(),
+  )
+}
+
+#[test]
+fn test_style_z_index() {
+  let red = Color([239, 68, 68, 255]);
+  let blue = Color([59, 130, 246, 255]);
+  let green = Color([34, 197, 94, 255]);
+  let purple = Color([168, 85, 247, 255]);
+
+  let container = ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Percentage(100.0))
+        .height(Percentage(100.0))
+        .display(Display::Flex)
+        .justify_content(JustifyContent::SpaceEvenly)
+        .align_items(AlignItems::Center)
+        .background_color(ColorInput::Value(Color([255, 255, 255, 255])))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      // The badge comes first in tree order, but is lifted above the tiles.
+      // The card doesn't create a stacking context, so the negative badge is painted below its background.
+      create_card(
+        ZIndex::Auto,
+        vec![
+          create_badge(
+            red,
+            ZIndex::Integer(10),
+            Sides([Px(-24.0), Px(-24.0), Auto, Auto]),
+          ),
+          create_badge(
+            purple,
+            ZIndex::Integer(-1),
+            Sides([Auto, Auto, Px(-40.0), Px(-40.0)]),
+          ),
+          create_tile(blue),
+          create_tile(green),
+        ],
+      ),
+      // The card creates a stacking context, so the negative badge stays above its background but below the tiles.
+      create_card(
+        ZIndex::Integer(0),
+        vec![
+          create_tile(blue),
+          create_tile(green),
+          create_badge(
+            red,
+            ZIndex::Integer(2),
+            Sides([Px(80.0), Auto, Auto, Px(72.0)]),
+          ),
+          create_badge(
+            purple,
+            ZIndex::Integer(-1),
+            Sides([Px(-20.0), Auto, Auto, Px(-20.0)]),
+          ),
+          create_badge(
+            blue,
+            ZIndex::Integer(1),
+            Sides([Px(120.0), Auto, Auto, Px(112.0)]),
+          ),
+        ],
+      ),
+    ]),
+  };
+
+  run_style_width_test(container.into(), "tests/fixtures/style_z_index.png");
+}
@@ -0,0 +1,169 @@
+use takumi::layout::{
+  node::{ContainerNode, ImageNode, NodeKind, TextNode},
+  style::{Length::*, *},
+};
+
+mod test_utils;
+use test_utils::run_svg_output_test;
+
+fn create_card(children: Vec<NodeKind>) -> ContainerNode<NodeKind> {
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Px(600.0))
+        .height(Px(300.0))
+        .display(Display::Flex)
+        .flex_direction(FlexDirection::Column)
+        .justify_content(JustifyContent::Center)
+        .align_items(AlignItems::Center)
+        .padding(Sides([Px(24.0); 4]))
+        .border_width(Some(Sides([Px(4.0); 4])))
+        .border_color(Some(ColorInput::Value(Color([255, 255, 255, 128]))))
+        .border_radius(BorderRadius(Sides([SpacePair::from_single(Px(24.0)); 4])))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(children),
+  }
+}
+
+#[test]
+fn fixtures_svg_output_card() {
+  let card = ContainerNode {
+    style: Some(
+      StyleBuilder::default()
+        .width(Px(600.0))
+        .height(Px(300.0))
+        .display(Display::Flex)
+        .flex_direction(FlexDirection::Column)
+        .justify_content(JustifyContent::Center)
+        .align_items(AlignItems::Center)
+        .padding(Sides([Px(24.0); 4]))
+        .border_width(Some(Sides([Px(4.0); 4])))
+        .border_color(Some(ColorInput::Value(Color([255, 255, 255, 128]))))
+        .border_radius(BorderRadius(Sides([SpacePair::from_single(Px(24.0)); 4])))
+        .background_image(Some(
+          BackgroundImages::from_str(
+            "linear-gradient(135deg, #2d3748 0%, #1a202c 100%), radial-gradient(circle at 80% 20%, rgba(255, 255, 255, 0.3) 0%, transparent 40%)",
+          )
+          .unwrap(),
+        ))
+        .box_shadow(Some(BoxShadows::from_str("0 8px 24px rgba(0, 0, 0, 0.5)").unwrap()))
+        .build()
+        .unwrap(),
+    ),
+    ..create_card(vec![
+      TextNode {
+        preset: None,
+        tw: None,
+        style: Some(
+          StyleBuilder::default()
+            .font_size(Some(Px(48.0)))
+            .font_weight(FontWeight::from(700.0))
+            .color(ColorInput::Value(Color::white()))
+            .text_decoration_line(Some(TextDecorationLines::from_str("underline").unwrap()))
+            .build()
+            .unwrap(),
+        ),
+        text: "Vector output 🎉".to_string(),
+      }
+      .into(),
+      ImageNode {
+        preset: None,
+        tw: None,
+        style: Some(
+          StyleBuilder::default()
+            .width(Px(64.0))
+            .height(Px(64.0))
+            .border_radius(BorderRadius(Sides([SpacePair::from_single(Percentage(50.0)); 4])))
+            .build()
+            .unwrap(),
+        ),
+        width: None,
+        height: None,
+        src: "assets/images/yeecord.png".into(),
+      }
+      .into(),
+    ])
+  };
+
+  let container = ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Percentage(100.0))
+        .height(Percentage(100.0))
+        .display(Display::Flex)
+        .justify_content(JustifyContent::Center)
+        .align_items(AlignItems::Center)
+        .background_color(ColorInput::Value(Color([240, 240, 240, 255])))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![card.into()]),
+  };
+
+  run_svg_output_test(container.into(), "tests/fixtures/svg_output_card.svg");
+}
+
+#[test]
+fn fixtures_svg_output_clip_path_transform() {
+  let card = ContainerNode {
+    style: Some(
+      StyleBuilder::default()
+        .width(Px(600.0))
+        .height(Px(300.0))
+        .display(Display::Flex)
+        .justify_content(JustifyContent::Center)
+        .align_items(AlignItems::Center)
+        .background_color(ColorInput::Value(Color([59, 130, 246, 255])))
+        .clip_path(Some(
+          BasicShape::from_str("polygon(50% 0%, 100% 100%, 0% 100%)").unwrap(),
+        ))
+        .rotate(Some(Angle::new(15.0)))
+        .build()
+        .unwrap(),
+    ),
+    ..create_card(vec![
+      TextNode {
+        preset: None,
+        tw: None,
+        style: Some(
+          StyleBuilder::default()
+            .font_size(Some(Px(64.0)))
+            .color(ColorInput::Value(Color::white()))
+            .webkit_text_stroke_width(Some(Px(2.0)))
+            .webkit_text_stroke_color(Some(ColorInput::Value(Color([0, 0, 0, 255]))))
+            .build()
+            .unwrap(),
+        ),
+        text: "Clipped".to_string(),
+      }
+      .into(),
+    ])
+  };
+
+  let container = ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Percentage(100.0))
+        .height(Percentage(100.0))
+        .display(Display::Flex)
+        .justify_content(JustifyContent::Center)
+        .align_items(AlignItems::Center)
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![card.into()]),
+  };
+
+  run_svg_output_test(
+    container.into(),
+    "tests/fixtures/svg_output_clip_path_transform.svg",
+  );
+}
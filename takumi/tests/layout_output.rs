@@ -0,0 +1,150 @@
+use takumi::layout::{
+  node::{ContainerNode, TextNode},
+  style::{Length::*, *},
+};
+
+mod test_utils;
+use test_utils::compute_test_layout;
+
+#[test]
+fn layout_output_boxes_and_transform() {
+  let container = ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Px(400.0))
+        .height(Px(200.0))
+        .padding(Sides([Px(20.0); 4]))
+        .border_width(Some(Sides([Px(4.0); 4])))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      ContainerNode {
+        preset: None,
+        tw: None,
+        style: Some(
+          StyleBuilder::default()
+            .width(Px(100.0))
+            .height(Px(50.0))
+            .translate(Some(SpacePair::from_pair(Px(10.0), Px(20.0))))
+            .build()
+            .unwrap(),
+        ),
+        children: None,
+      }
+      .into(),
+    ]),
+  };
+
+  let layout = compute_test_layout(container.into());
+  let root = &layout.root;
+
+  assert_eq!(root.border_box.width, 400.0);
+  assert_eq!(root.border_box.height, 200.0);
+  assert_eq!(root.padding_box.x, 4.0);
+  assert_eq!(root.padding_box.width, 392.0);
+  assert_eq!(root.content_box.x, 24.0);
+  assert_eq!(root.content_box.width, 352.0);
+  assert!(root.lines.is_none());
+
+  let child = &root.children[0];
+
+  assert_eq!(child.border_box.width, 100.0);
+  assert_eq!(child.border_box.height, 50.0);
+  assert_eq!(child.transform.x, 34.0);
+  assert_eq!(child.transform.y, 44.0);
+
+  let json = serde_json::to_value(&layout).unwrap();
+
+  assert_eq!(json["root"]["children"][0]["borderBox"]["width"], 100.0);
+  assert_eq!(json["root"]["children"][0]["transform"]["x"], 34.0);
+}
+
+#[test]
+fn layout_output_text_lines() {
+  let text = "The quick brown fox jumps over the lazy dog";
+
+  let node = TextNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Px(200.0))
+        .font_size(Some(Px(24.0)))
+        .build()
+        .unwrap(),
+    ),
+    text: text.to_string(),
+  };
+
+  let layout = compute_test_layout(node.into());
+  let lines = layout.root.lines.as_ref().unwrap();
+
+  assert!(lines.len() > 1);
+  assert_eq!(
+    lines
+      .iter()
+      .map(|line| line.text.as_str())
+      .collect::<String>(),
+    text
+  );
+
+  for line in lines {
+    assert!(line.rect.width <= 200.0);
+    assert!(!line.runs.is_empty());
+
+    for run in &line.runs {
+      assert_eq!(run.font_size, 24.0);
+      assert!(!run.glyphs.is_empty());
+      assert!(line.text.contains(run.text.trim()));
+    }
+  }
+}
+
+#[test]
+fn layout_output_inline_children_are_lines() {
+  let inline = StyleBuilder::default()
+    .display(Display::Inline)
+    .build()
+    .unwrap();
+
+  let container = ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .display(Display::Block)
+        .width(Px(400.0))
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      TextNode {
+        preset: None,
+        tw: None,
+        style: Some(inline.clone()),
+        text: "Hello".to_string(),
+      }
+      .into(),
+      TextNode {
+        preset: None,
+        tw: None,
+        style: Some(inline),
+        text: "world".to_string(),
+      }
+      .into(),
+    ]),
+  };
+
+  let layout = compute_test_layout(container.into());
+  let root = &layout.root;
+
+  assert!(root.children.is_empty());
+
+  let lines = root.lines.as_ref().unwrap();
+
+  assert!(lines.iter().any(|line| line.text.contains("Hello")));
+  assert!(lines.iter().any(|line| line.text.contains("world")));
+}
@@ -0,0 +1,259 @@
+use takumi::{
+  Error, GlobalContext,
+  image::{ImageBuffer, Rgba, RgbaImage, imageops::crop_imm},
+  layout::{
+    node::{ContainerNode, NodeKind, TextNode},
+    style::{Length::*, *},
+  },
+  rendering::{RenderOptionsBuilder, RenderRegion, render, render_into},
+  taffy::Point,
+};
+
+mod test_utils;
+use test_utils::{create_test_context, create_test_viewport};
+
+fn create_poster() -> NodeKind {
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Px(300.0))
+        .height(Px(600.0))
+        .background_image(Some(
+          BackgroundImages::from_str("linear-gradient(to bottom, #ff0000, #0000ff)").unwrap(),
+        ))
+        .display(Display::Flex)
+        .justify_content(JustifyContent::Center)
+        .align_items(AlignItems::Center)
+        .build()
+        .unwrap(),
+    ),
+    children: Some(vec![
+      ContainerNode {
+        preset: None,
+        tw: None,
+        style: Some(
+          StyleBuilder::default()
+            .width(Px(120.0))
+            .height(Px(120.0))
+            .rotate(Some(Angle::new(30.0)))
+            .background_color(ColorInput::Value(Color([0, 255, 0, 200])))
+            .border_radius(BorderRadius(Sides([SpacePair::from_single(Px(16.0)); 4])))
+            .build()
+            .unwrap(),
+        ),
+        children: None,
+      }
+      .into(),
+    ]),
+  }
+  .into()
+}
+
+fn render_poster(global: &GlobalContext, region: Option<RenderRegion>) -> RgbaImage {
+  let mut builder = RenderOptionsBuilder::default();
+
+  builder
+    .viewport(create_test_viewport())
+    .node(create_poster())
+    .global(global);
+
+  if let Some(region) = region {
+    builder.region(region);
+  }
+
+  render(builder.build().unwrap()).unwrap()
+}
+
+#[test]
+fn render_region_matches_full_render() {
+  let global = create_test_context();
+  let full = render_poster(&global, None);
+
+  for y in (0..600).step_by(200) {
+    let region = RenderRegion {
+      x: 50,
+      y,
+      width: 200,
+      height: 200,
+    };
+
+    let tile = render_poster(&global, Some(region));
+
+    assert_eq!(tile.dimensions(), (200, 200));
+    assert_eq!(tile, crop_imm(&full, 50, y, 200, 200).to_image());
+  }
+}
+
+#[test]
+fn render_empty_region_is_rejected() {
+  let global = create_test_context();
+  let options = RenderOptionsBuilder::default()
+    .viewport(create_test_viewport())
+    .node(create_poster())
+    .global(&global)
+    .region(RenderRegion {
+      x: 0,
+      y: 0,
+      width: 0,
+      height: 200,
+    })
+    .build()
+    .unwrap();
+
+  assert!(matches!(render(options), Err(Error::InvalidViewport)));
+}
+
+#[test]
+fn render_into_raw_buffer_at_offset() {
+  let global = create_test_context();
+  let full = render_poster(&global, None);
+
+  let mut buffer = vec![0; 1300 * 730 * 4];
+  let mut target = ImageBuffer::<Rgba<u8>, _>::from_raw(1300, 730, buffer.as_mut_slice()).unwrap();
+
+  render_into(
+    RenderOptionsBuilder::default()
+      .viewport(create_test_viewport())
+      .node(create_poster())
+      .global(&global)
+      .build()
+      .unwrap(),
+    &mut target,
+    Point { x: 50, y: 50 },
+  )
+  .unwrap();
+
+  assert_eq!(*target.get_pixel(10, 10), Rgba([0, 0, 0, 0]));
+
+  let target = RgbaImage::from_raw(1300, 730, target.into_raw().to_vec()).unwrap();
+
+  assert_eq!(crop_imm(&target, 50, 50, 1200, 630).to_image(), full);
+}
+
+#[test]
+fn render_into_region_clipped_by_target() {
+  let global = create_test_context();
+  let full = render_poster(&global, None);
+
+  let mut target = RgbaImage::new(150, 150);
+
+  render_into(
+    RenderOptionsBuilder::default()
+      .viewport(create_test_viewport())
+      .node(create_poster())
+      .global(&global)
+      .region(RenderRegion {
+        x: 50,
+        y: 200,
+        width: 200,
+        height: 200,
+      })
+      .build()
+      .unwrap(),
+    &mut target,
+    Point { x: 50, y: 50 },
+  )
+  .unwrap();
+
+  assert_eq!(*target.get_pixel(10, 10), Rgba([0, 0, 0, 0]));
+  assert_eq!(
+    crop_imm(&target, 50, 50, 100, 100).to_image(),
+    crop_imm(&full, 50, 200, 100, 100).to_image()
+  );
+}
+
+fn create_large_poster() -> NodeKind {
+  let card = |index: usize| -> NodeKind {
+    ContainerNode {
+      preset: None,
+      tw: None,
+      style: Some(
+        StyleBuilder::default()
+          .width(Px(900.0))
+          .height(Px(500.0))
+          .padding(Sides([Px(32.0); 4]))
+          .overflow(SpacePair::from_single(Overflow::Hidden))
+          .opacity(PercentageNumber(0.8))
+          .rotate(Some(Angle::new(index as f32 * 5.0 - 10.0)))
+          .background_image(Some(
+            BackgroundImages::from_str("linear-gradient(135deg, #2d3748 0%, #1a202c 100%)")
+              .unwrap(),
+          ))
+          .border_radius(BorderRadius(Sides([SpacePair::from_single(Px(24.0)); 4])))
+          .build()
+          .unwrap(),
+      ),
+      children: Some(vec![
+        TextNode {
+          preset: None,
+          tw: None,
+          style: Some(
+            StyleBuilder::default()
+              .font_size(Some(Px(96.0)))
+              .color(ColorInput::Value(Color::white()))
+              .build()
+              .unwrap(),
+          ),
+          text: format!("Tile {index} renders the same across strip edges"),
+        }
+        .into(),
+      ]),
+    }
+    .into()
+  };
+
+  ContainerNode {
+    preset: None,
+    tw: None,
+    style: Some(
+      StyleBuilder::default()
+        .width(Px(1200.0))
+        .height(Px(3600.0))
+        .display(Display::Flex)
+        .flex_direction(FlexDirection::Column)
+        .justify_content(JustifyContent::SpaceAround)
+        .align_items(AlignItems::Center)
+        .background_color(ColorInput::Value(Color::white()))
+        .build()
+        .unwrap(),
+    ),
+    children: Some((0..5).map(card).collect()),
+  }
+  .into()
+}
+
+#[test]
+fn render_large_canvas_matches_smaller_regions() {
+  let global = create_test_context();
+
+  let render_large_poster = |region: Option<RenderRegion>| {
+    let mut builder = RenderOptionsBuilder::default();
+
+    builder
+      .viewport((1200, 3600).into())
+      .node(create_large_poster())
+      .global(&global);
+
+    if let Some(region) = region {
+      builder.region(region);
+    }
+
+    render(builder.build().unwrap()).unwrap()
+  };
+
+  // The whole canvas is large enough to be rendered in tiles, while each half is rendered at once.
+  let full = render_large_poster(None);
+
+  for y in [0, 1800] {
+    let half = render_large_poster(Some(RenderRegion {
+      x: 0,
+      y,
+      width: 1200,
+      height: 1800,
+    }));
+
+    assert_eq!(half, crop_imm(&full, 0, y, 1200, 1800).to_image());
+  }
+}
@@ -1,19 +1,21 @@
 use std::{
   fs::File,
-  io::Read,
+  io::{BufReader, Read},
   path::{Path, PathBuf},
   sync::Arc,
 };
 
-use image::load_from_memory;
+use image::{AnimationDecoder, codecs::png::PngDecoder, load_from_memory};
 use parley::{GenericFamily, fontique::FontInfoOverride};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use takumi::{
   GlobalContext,
   layout::{Viewport, node::NodeKind},
   rendering::{
-    AnimationFrame, ImageOutputFormat, RenderOptionsBuilder, encode_animated_png,
-    encode_animated_webp, render, write_image,
+    AnimationFrame, GifEncodeOptions, ImageOutputFormat, LayoutTree, PngAnimationEncoder,
+    RenderOptionsBuilder, WebPAnimationEncoder, WebPAnimationOptions, compute_layout,
+    encode_animated_gif, encode_animated_png, encode_animated_webp_with_options, render,
+    render_svg, write_image,
   },
   resources::image::{ImageSource, parse_svg_str},
 };
@@ -48,7 +50,7 @@ const TEST_FONTS: &[(&str, &str, GenericFamily)] = &[
   ),
 ];
 
-fn create_test_context() -> GlobalContext {
+pub fn create_test_context() -> GlobalContext {
   let mut context = GlobalContext::default();
 
   let mut yeecord_image_data = Vec::new();
@@ -105,10 +107,41 @@ pub fn create_test_viewport() -> Viewport {
 /// Helper function to run style width tests
 #[allow(dead_code)]
 pub fn run_style_width_test(node: NodeKind, fixture_path: &str) {
+  run_style_width_test_with(node, fixture_path, |_| {});
+}
+
+/// Helper function to run style width tests with extra render options set by `configure`
+#[allow(dead_code)]
+pub fn run_style_width_test_with(
+  node: NodeKind,
+  fixture_path: &str,
+  configure: impl FnOnce(&mut RenderOptionsBuilder<'_, NodeKind>),
+) {
   let context = create_test_context();
   let viewport = create_test_viewport();
 
-  let image = render(
+  let mut builder = RenderOptionsBuilder::default();
+
+  builder.viewport(viewport).node(node).global(&context);
+
+  configure(&mut builder);
+
+  let image = render(builder.build().unwrap()).unwrap();
+
+  let path = Path::new(fixture_path);
+
+  let mut file = File::create(path).unwrap();
+
+  write_image(&image, &mut file, ImageOutputFormat::Png, Some(75)).unwrap();
+}
+
+/// Helper function to run SVG output tests, the output is checked to be a parsable SVG document.
+#[allow(dead_code)]
+pub fn run_svg_output_test(node: NodeKind, fixture_path: &str) {
+  let context = create_test_context();
+  let viewport = create_test_viewport();
+
+  let svg = render_svg(
     RenderOptionsBuilder::default()
       .viewport(viewport)
       .node(node)
@@ -118,11 +151,53 @@ pub fn run_style_width_test(node: NodeKind, fixture_path: &str) {
   )
   .unwrap();
 
-  let path = Path::new(fixture_path);
+  parse_svg_str(&svg).unwrap();
 
-  let mut file = File::create(path).unwrap();
+  std::fs::write(fixture_path, svg).unwrap();
+}
 
-  write_image(&image, &mut file, ImageOutputFormat::Png, Some(75)).unwrap();
+/// Helper function to compute the layout of a node in the test viewport.
+#[allow(dead_code)]
+pub fn compute_test_layout(node: NodeKind) -> LayoutTree {
+  let context = create_test_context();
+  let viewport = create_test_viewport();
+
+  compute_layout(
+    RenderOptionsBuilder::default()
+      .viewport(viewport)
+      .node(node)
+      .global(&context)
+      .build()
+      .unwrap(),
+  )
+  .unwrap()
+}
+
+/// Helper function to run PDF output tests, each node is rendered as a separate page.
+#[cfg(feature = "pdf")]
+#[allow(dead_code)]
+pub fn run_pdf_output_test(pages: Vec<NodeKind>, fixture_path: &str) {
+  let context = create_test_context();
+  let viewport = create_test_viewport();
+
+  let mut pdf = Vec::new();
+
+  takumi::rendering::write_pdf(
+    pages.into_iter().map(|node| {
+      RenderOptionsBuilder::default()
+        .viewport(viewport)
+        .node(node)
+        .global(&context)
+        .build()
+        .unwrap()
+    }),
+    &mut pdf,
+  )
+  .unwrap();
+
+  assert!(pdf.starts_with(b"%PDF-"));
+
+  std::fs::write(fixture_path, pdf).unwrap();
 }
 
 #[allow(dead_code)]
@@ -132,6 +207,7 @@ pub fn run_webp_animation_test(
   blend: bool,
   dispose: bool,
   loop_count: Option<u16>,
+  quality: Option<u8>,
 ) {
   assert_ne!(nodes.len(), 0);
 
@@ -157,7 +233,130 @@ pub fn run_webp_animation_test(
     .collect();
 
   let mut out = File::create(fixture_path).unwrap();
-  encode_animated_webp(&frames, &mut out, blend, dispose, loop_count).unwrap();
+  encode_animated_webp_with_options(
+    &frames,
+    &mut out,
+    WebPAnimationOptions {
+      blend,
+      dispose,
+      loop_count,
+      quality,
+    },
+    None,
+  )
+  .unwrap();
+}
+
+/// Renders the frames one by one and pushes them to a [`WebPAnimationEncoder`],
+/// checking the output matches [`encode_animated_webp_with_options`].
+#[allow(dead_code)]
+pub fn run_streaming_webp_animation_test(
+  nodes: Vec<(NodeKind, u32)>,
+  fixture_path: &str,
+  options: WebPAnimationOptions,
+) {
+  assert_ne!(nodes.len(), 0);
+
+  let context = create_test_context();
+  let viewport = create_test_viewport();
+
+  let frames: Vec<_> = nodes
+    .into_iter()
+    .map(|(node, duration_ms)| {
+      AnimationFrame::new(
+        render(
+          RenderOptionsBuilder::default()
+            .viewport(viewport)
+            .node(node)
+            .global(&context)
+            .build()
+            .unwrap(),
+        )
+        .unwrap(),
+        duration_ms,
+      )
+    })
+    .collect();
+
+  let mut expected = Vec::new();
+  encode_animated_webp_with_options(&frames, &mut expected, options, None).unwrap();
+
+  let (width, height) = frames[0].image.dimensions();
+  let mut encoder = WebPAnimationEncoder::new(
+    File::create(fixture_path).unwrap(),
+    width,
+    height,
+    options,
+    None,
+  )
+  .unwrap();
+
+  for frame in frames {
+    encoder.push_frame(frame).unwrap();
+  }
+
+  encoder.finish().unwrap();
+
+  assert_eq!(std::fs::read(fixture_path).unwrap(), expected);
+}
+
+/// Renders the frames one by one and pushes them to a [`PngAnimationEncoder`],
+/// checking every frame decodes back to what was rendered.
+#[allow(dead_code)]
+pub fn run_streaming_png_animation_test(nodes: Vec<(NodeKind, u32)>, fixture_path: &str) {
+  assert_ne!(nodes.len(), 0);
+
+  let context = create_test_context();
+  let viewport = create_test_viewport();
+
+  let mut encoder: Option<PngAnimationEncoder<File>> = None;
+  let mut rendered = Vec::with_capacity(nodes.len());
+  let frame_count = nodes.len() as u32;
+
+  for (node, duration_ms) in nodes {
+    let image = render(
+      RenderOptionsBuilder::default()
+        .viewport(viewport)
+        .node(node)
+        .global(&context)
+        .build()
+        .unwrap(),
+    )
+    .unwrap();
+
+    let encoder = encoder.get_or_insert_with(|| {
+      PngAnimationEncoder::new(
+        File::create(fixture_path).unwrap(),
+        image.width(),
+        image.height(),
+        frame_count,
+        None,
+        None,
+      )
+      .unwrap()
+    });
+
+    rendered.push(image.clone());
+    encoder
+      .push_frame(AnimationFrame::new(image, duration_ms))
+      .unwrap();
+  }
+
+  encoder.unwrap().finish().unwrap();
+
+  let decoder = PngDecoder::new(BufReader::new(File::open(fixture_path).unwrap())).unwrap();
+  let decoded = decoder
+    .apng()
+    .unwrap()
+    .into_frames()
+    .collect_frames()
+    .unwrap();
+
+  assert_eq!(decoded.len(), rendered.len());
+
+  for (frame, image) in decoded.iter().zip(&rendered) {
+    assert_eq!(frame.buffer(), image);
+  }
 }
 
 #[allow(dead_code)]
@@ -192,3 +391,37 @@ pub fn run_png_animation_test(
   let mut out = File::create(fixture_path).unwrap();
   encode_animated_png(&frames, &mut out, loop_count).unwrap();
 }
+
+#[allow(dead_code)]
+pub fn run_gif_animation_test(
+  nodes: Vec<(NodeKind, u32)>,
+  fixture_path: &str,
+  loop_count: Option<u16>,
+  options: GifEncodeOptions,
+) {
+  assert_ne!(nodes.len(), 0);
+
+  let context = create_test_context();
+  let viewport = create_test_viewport();
+
+  let frames: Vec<_> = nodes
+    .into_par_iter()
+    .map(|(node, duration_ms)| {
+      AnimationFrame::new(
+        render(
+          RenderOptionsBuilder::default()
+            .viewport(viewport)
+            .node(node)
+            .global(&context)
+            .build()
+            .unwrap(),
+        )
+        .unwrap(),
+        duration_ms,
+      )
+    })
+    .collect();
+
+  let mut out = File::create(fixture_path).unwrap();
+  encode_animated_gif(&frames, &mut out, loop_count, options, None).unwrap();
+}